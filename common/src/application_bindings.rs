@@ -1,68 +1,410 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct ApplicationBindings {
     #[serde(flatten)]
-    pub profiles: HashMap<String, InteractionProfileBindings>,
+    pub profiles: BTreeMap<String, InteractionProfileBindings>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct InteractionProfileBindings {
     #[serde(flatten)]
-    pub action_sets: HashMap<String, ActionSetBindings>,
+    pub action_sets: BTreeMap<String, ActionSetBindings>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct ActionSetBindings {
     #[serde(flatten)]
-    pub actions: HashMap<String, ActionBindings>,
+    pub actions: BTreeMap<String, ActionBindings>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+impl ApplicationBindings {
+    ///Lays `overrides` over these bindings, every action it mentions takes its bindings (or unbinding) from it and everything else is kept
+    pub fn merge(&mut self, overrides: ApplicationBindings) {
+        for (profile_name, profile_bindings) in overrides.profiles {
+            let profile = self.profiles.entry(profile_name).or_default();
+            for (set_name, set_bindings) in profile_bindings.action_sets {
+                profile.action_sets.entry(set_name).or_default().actions.extend(set_bindings.actions);
+            }
+        }
+    }
+}
+
+///The sources bound to an action
+///
+///An action mapped to `null` (or `{ "unbound": true }`) is unbound, it never fires no matter which interaction profile is in use
+#[derive(Debug, Clone, Default)]
 pub struct ActionBindings {
-    pub bindings: Vec<String>,
+    pub bindings: Vec<Binding>,
+    pub unbound: bool,
+}
+
+impl ActionBindings {
+    pub fn unbound() -> ActionBindings {
+        ActionBindings {
+            bindings: Vec::new(),
+            unbound: true,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct ActionBindingsObject {
+    #[serde(default)]
+    bindings: Vec<Binding>,
+    ///For formats without null, only ever read
+    #[serde(default, skip_serializing)]
+    unbound: bool,
 }
 
+impl Serialize for ActionBindings {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+        if self.unbound {
+            serializer.serialize_none()
+        } else {
+            ActionBindingsObject {
+                bindings: self.bindings.clone(),
+                unbound: false,
+            }.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ActionBindings {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de> {
+        Ok(match Option::<ActionBindingsObject>::deserialize(deserializer)? {
+            Some(object) if object.unbound => ActionBindings::unbound(),
+            Some(object) => ActionBindings {
+                bindings: object.bindings,
+                unbound: false,
+            },
+            None => ActionBindings::unbound(),
+        })
+    }
+}
+
+///A single source bound to an action
+///
+///Bindings without any transforms or note are serialized as a plain path string so default_bindings.json stays readable
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Binding {
     pub path: String,
-    pub analog_threshold: Option<AnalogThreshold>,
+    ///A second scalar source, when set `path` drives x and this drives y of a vector action
+    pub y: Option<String>,
+    pub transform: Transform,
+    ///Read instead of `path` while the controller in use on the binding's hand doesn't have `path` (e.g. a thumbstick standing in for a trackpad),
+    ///looked up on that controller. Without one the binding leaves the action inactive while such a controller is in use
+    ///
+    ///Only for single sources, not composed vectors
+    pub fallback: Option<String>,
+    ///Another source on the same interaction profile that gates this binding, e.g. a grip held as a modifier, see [SourceCondition]
+    ///
+    ///While it isn't met the binding leaves its action to its other bindings, or inactive
+    pub when: Option<SourceCondition>,
+    ///Free text for the user, the layer never reads it but keeps it whenever it rewrites the binding
+    pub note: Option<String>,
+}
+
+///A source a binding only works alongside, checked on every sync
+///
+///Buttons count as held while pressed, analog sources from the default [AnalogThreshold]'s `on_threshold`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct SourceCondition {
+    pub path: String,
+    ///Flips the condition so the binding only works while the source isn't held, for the unmodified half of a modifier pair
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub released: bool,
+}
+
+///Per-binding modifications applied to the source state before it reaches the application action
+///
+///Transforms are applied in the order the fields are declared, after the global [analog_scale](crate::config::Settings::analog_scale) has scaled the source
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Transform {
+    ///Flips a boolean source (e.g. a normally-closed switch) or negates an analog one before any other transform sees it
+    #[serde(skip_serializing_if = "is_false")]
+    pub invert: bool,
+    ///Analog input closer to rest than this reads as zero, the remaining travel is rescaled so output still starts at zero
+    ///
+    ///Vector sources use a radial dead zone
+    #[serde(skip_serializing_if = "is_zero")]
+    pub dead_zone: f32,
+    ///Raises the magnitude of an analog value to this power, above 1 gives finer control near rest (e.g. a flight stick's twist), below 1 makes it more eager
+    ///
+    ///Vector sources are curved along their length
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub curve: Option<f32>,
+    ///Eases an analog value towards the source with this time constant in milliseconds, measured between predicted display times, to
+    ///steady a jittery sensor
+    ///
+    ///After one time constant a step in the source is about two thirds of the way through, the smoothing starts over whenever the source is inactive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smoothing_ms: Option<u32>,
+    ///Turns a vector source into a boolean pressed while it points into one slice of a radial menu, see [Sector]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sector: Option<Sector>,
+    ///Takes a single axis of a vector source so it can drive a float action
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub axis: Option<Axis>,
+    ///The range a float result is mapped into
+    #[serde(skip_serializing_if = "is_default")]
+    pub range: AxisRange,
+    ///Turns an analog source into a boolean, see [AnalogThreshold]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<AnalogThreshold>,
+    ///Only lets a particular timing of presses through, so one button can drive several boolean actions, see [Gesture]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gesture: Option<Gesture>,
+    ///Haptic outputs only, scales the amplitude of the vibrations sent to this output so e.g. trigger rumble can be softer than the main motors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amplitude: Option<f32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Axis {
+    X,
+    Y,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AxisRange {
+    ///Leaves the value in -1..1
+    #[default]
+    Signed,
+    ///Maps -1..1 onto 0..1, for applications expecting a trigger-like value
+    Unsigned,
+}
+
+impl Transform {
+    pub fn is_identity(&self) -> bool {
+        *self == Transform::default()
+    }
+}
+
+pub(crate) fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+fn is_zero(value: &f32) -> bool {
+    *value == 0f32
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+impl Binding {
+    pub fn new(path: String) -> Binding {
+        Binding {
+            path,
+            y: None,
+            transform: Transform::default(),
+            fallback: None,
+            when: None,
+            note: None,
+        }
+    }
 }
 
+#[derive(Deserialize, Serialize)]
+struct BindingObject {
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    y: Option<String>,
+    #[serde(flatten)]
+    transform: Transform,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fallback: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    when: Option<SourceCondition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+}
+
+impl Serialize for Binding {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer {
+        if self.y.is_none() && self.transform.is_identity() && self.fallback.is_none() && self.when.is_none() && self.note.is_none() {
+            serializer.serialize_str(&self.path)
+        } else {
+            BindingObject {
+                path: self.path.clone(),
+                y: self.y.clone(),
+                transform: self.transform.clone(),
+                fallback: self.fallback.clone(),
+                when: self.when.clone(),
+                note: self.note.clone(),
+            }.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Path(String),
+            Object(BindingObject),
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Path(path) => Binding::new(path),
+            Repr::Object(object) => Binding {
+                path: object.path,
+                y: object.y,
+                transform: object.transform,
+                fallback: object.fallback,
+                when: object.when,
+                note: object.note,
+            },
+        })
+    }
+}
+
+///A source reads as pressed once it reaches `on_threshold` and as released once it falls back to `off_threshold`, like XR_VALVE_analog_threshold
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
 pub struct AnalogThreshold {
     pub on_threshold: f32,
     pub off_threshold: f32,
 }
 
+impl Default for AnalogThreshold {
+    ///Used when neither the binding nor the interaction profile specify a threshold
+    fn default() -> Self {
+        AnalogThreshold {
+            on_threshold: 0.5,
+            off_threshold: 0.4,
+        }
+    }
+}
+
+///One of `count` equal slices around a trackpad or thumbstick, so binding the same source to several boolean actions with different sectors makes a radial menu
+///
+///Sector 0 is centered straight up and the rest follow clockwise. Moving the source back to its center (inside the binding's dead zone) doesn't release the
+///sector, the one chosen last stays pressed until the source points into another sector or goes inactive
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct Sector {
+    pub count: u32,
+    pub index: u32,
+}
+
+///Picks out one way of pressing a button, binding the same source to several actions with different gestures makes them mutually exclusive
+///
+///A press released before `hold_ms` is a tap candidate. If the button is pressed again within `double_tap_ms` of the release it is a double tap, otherwise once that window passes it is a tap.
+///A press held for `hold_ms` is a long press, no matter when it is released, and the second press of a double tap never counts as anything else
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct Gesture {
+    pub kind: GestureKind,
+    #[serde(default = "Gesture::default_hold_ms")]
+    pub hold_ms: u32,
+    #[serde(default = "Gesture::default_double_tap_ms")]
+    pub double_tap_ms: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GestureKind {
+    ///Pressed for a single sync once a tap is confirmed
+    Tap,
+    ///Pressed from the moment the hold time is reached until the button is released
+    LongPress,
+    ///Pressed for a single sync on the second press
+    DoubleTap,
+}
+
+impl Gesture {
+    pub fn new(kind: GestureKind) -> Gesture {
+        Gesture {
+            kind,
+            hold_ms: Gesture::default_hold_ms(),
+            double_tap_ms: Gesture::default_double_tap_ms(),
+        }
+    }
+
+    fn default_hold_ms() -> u32 {
+        500
+    }
+
+    fn default_double_tap_ms() -> u32 {
+        300
+    }
+}
+
 #[test]
 fn test_json(){
     let mut profiles = ApplicationBindings {
-        profiles: HashMap::new(),
+        profiles: BTreeMap::new(),
     };
 
     profiles.profiles.insert( "/interaction_profiles/oculus/touch_controller".to_owned(),
     {
         let mut profile = InteractionProfileBindings {
-            action_sets: HashMap::new()
+            action_sets: BTreeMap::new()
         };
         profile.action_sets.insert("hands".to_owned(), {
             let mut set = ActionSetBindings {
-                actions: HashMap::new(),
+                actions: BTreeMap::new(),
             };
-            set.actions.insert("pose_grip".to_owned(), ActionBindings{bindings: vec!["/user/hand/left/input/grip/pose".to_owned(), "/user/hand/right/input/grip/pose".to_owned()]});
+            set.actions.insert("pose_grip".to_owned(), ActionBindings{unbound: false, bindings: vec![Binding::new("/user/hand/left/input/grip/pose".to_owned()), Binding::new("/user/hand/right/input/grip/pose".to_owned())]});
             set
         });
         profile.action_sets.insert("gameplay".to_owned(), {
             let mut set = ActionSetBindings {
-                actions: HashMap::new(),
+                actions: BTreeMap::new(),
             };
-            set.actions.insert("use".to_owned(), ActionBindings{bindings: vec!["/user/hand/left/input/trigger/value".to_owned()]});
-            set.actions.insert("attack".to_owned(), ActionBindings{bindings: vec!["/user/hand/right/input/trigger/value".to_owned()]});
+            set.actions.insert("use".to_owned(), ActionBindings{unbound: false, bindings: vec![Binding::new("/user/hand/left/input/trigger/value".to_owned())]});
+            set.actions.insert("attack".to_owned(), ActionBindings{unbound: false, bindings: vec![Binding::new("/user/hand/right/input/trigger/value".to_owned())]});
             set
         });
         profile
     });
 
     println!("{}", serde_json::to_string_pretty(&profiles).unwrap());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_binding_forms() {
+    let bindings: Vec<Binding> = serde_json::from_str(r#"[
+        "/user/hand/left/input/a/click",
+        { "path": "/user/hand/left/input/b/click", "invert": true },
+        { "path": "/user/hand/left/input/thumbstick", "dead_zone": 0.1, "axis": "y", "range": "unsigned" },
+        { "path": "/user/hand/left/input/trigger/value", "y": "/user/hand/right/input/trigger/value" }
+    ]"#).unwrap();
+
+    assert_eq!(bindings[0], Binding::new("/user/hand/left/input/a/click".to_owned()));
+    assert!(bindings[1].transform.invert);
+    assert_eq!(bindings[2].transform.axis, Some(Axis::Y));
+    assert_eq!(bindings[2].transform.range, AxisRange::Unsigned);
+    assert_eq!(bindings[3].y.as_deref(), Some("/user/hand/right/input/trigger/value"));
+    assert!(bindings[3].transform.is_identity());
+
+    assert_eq!(
+        serde_json::to_string(&bindings[..2]).unwrap(),
+        r#"["/user/hand/left/input/a/click",{"path":"/user/hand/left/input/b/click","invert":true}]"#
+    );
+}
+
+#[test]
+fn test_unbound_action() {
+    let set: ActionSetBindings = serde_json::from_str(r#"{
+        "jump": null,
+        "crouch": { "bindings": ["/user/hand/left/input/a/click"] }
+    }"#).unwrap();
+
+    assert!(set.actions["jump"].unbound);
+    assert!(!set.actions["crouch"].unbound);
+    assert_eq!(serde_json::to_string(&set.actions["jump"]).unwrap(), "null");
+}
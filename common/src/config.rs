@@ -0,0 +1,1298 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::application_bindings::{is_false, ActionBindings, AnalogThreshold, ApplicationBindings, Binding, Sector, SourceCondition, Transform};
+use crate::interaction_profiles::{self, Root};
+use crate::serial::write_json_atomic;
+use crate::xrapplication_info::{ActionType, XrApplicationInfo};
+
+///A user's binding config, what custom_bindings.json holds
+pub type BindingConfig = ApplicationBindings;
+
+///Why a binding config couldn't be used
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    ///The error says which line and column the TOML parser stopped at
+    ParseToml(toml::de::Error),
+    ///The file parsed but describes something the layer can't apply
+    Invalid(String),
+    ///Like [ConfigError::Invalid], for a problem [diagnose] could place in the file
+    InvalidAt { line: usize, why: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(why) => write!(f, "couldn't read bindings: {}", why),
+            ConfigError::Parse(why) => write!(f, "couldn't parse bindings: {}", why),
+            ConfigError::ParseToml(why) => write!(f, "couldn't parse bindings: {}", why),
+            ConfigError::Invalid(why) => write!(f, "invalid bindings: {}", why),
+            ConfigError::InvalidAt { line, why } => write!(f, "invalid bindings at line {}: {}", line, why),
+        }
+    }
+}
+
+///Parses and validates a user's binding config
+///
+///Users edit these files by hand so this must never panic, whatever the input
+pub fn load(bytes: &[u8]) -> Result<ApplicationBindings, ConfigError> {
+    let bindings = serde_json::from_slice::<ApplicationBindings>(bytes).map_err(ConfigError::Parse)?;
+    validate(&bindings, &interaction_profiles::catalog())?;
+    Ok(bindings)
+}
+
+///Like [load] but for TOML, which has the same structure
+///
+///TOML has no null so an unbound action is written `action = { unbound = true }`
+pub fn load_toml(bytes: &[u8]) -> Result<ApplicationBindings, ConfigError> {
+    let bindings = toml::from_slice::<ApplicationBindings>(bytes).map_err(ConfigError::ParseToml)?;
+    validate(&bindings, &interaction_profiles::catalog())?;
+    Ok(bindings)
+}
+
+///Like [load] but reads a file first, a missing file means no custom bindings
+///
+///Files ending in .toml are read with [load_toml], anything else is JSON
+pub fn load_file(path: &Path) -> Result<ApplicationBindings, ConfigError> {
+    if !path.exists() {
+        return Ok(ApplicationBindings::default());
+    }
+    let bytes = fs::read(path).map_err(ConfigError::Io)?;
+    match path.extension() {
+        Some(extension) if extension == "toml" => load_toml(&bytes),
+        _ => load(&bytes),
+    }
+}
+
+///Loads each file in turn and [merges](ApplicationBindings::merge) it over the ones before, so a shared base can be overridden per machine
+///
+///Later files replace the bindings of the actions they mention, actions they leave out keep the earlier bindings. Missing files are skipped
+pub fn load_layered(paths: &[PathBuf]) -> Result<ApplicationBindings, ConfigError> {
+    let mut bindings = ApplicationBindings::default();
+    for path in paths {
+        bindings.merge(load_file(path)?);
+    }
+    Ok(bindings)
+}
+
+///Checks `bindings` and writes them to `path` in one step, readers never see a half written file
+pub fn save(bindings: &ApplicationBindings, path: &Path) -> Result<(), ConfigError> {
+    validate(bindings, &interaction_profiles::catalog())?;
+    write_json_atomic(bindings, path).map_err(ConfigError::Io)
+}
+
+///Moves the binding of `action` (named `action_set/action`) from source `from` to `to`, keeping its transform and note
+///
+///This is what a live rebind edits before saving, so nothing the user wrote by hand is lost
+pub fn rebind(bindings: &mut ApplicationBindings, profile: &str, action: &str, from: &str, to: &str) -> Result<(), ConfigError> {
+    let binding = action
+        .split_once('/')
+        .and_then(|(set_name, action_name)| {
+            bindings.profiles.get_mut(profile)?.action_sets.get_mut(set_name)?.actions.get_mut(action_name)
+        })
+        .and_then(|action_bindings| action_bindings.bindings.iter_mut().find(|binding| binding.path == from))
+        .ok_or_else(|| ConfigError::Invalid(format!("{} has no binding to {} under {}", action, from, profile)))?;
+    validate_path(to).map_err(ConfigError::Invalid)?;
+    binding.path = to.to_owned();
+    Ok(())
+}
+
+///Checks that every binding is well formed and, for sources `catalog` knows, that its transform fits the source
+pub fn validate(bindings: &ApplicationBindings, catalog: &Root) -> Result<(), ConfigError> {
+    first_error(problems(bindings, catalog, None))
+}
+
+///Like [validate] but also checks every binding can drive the type of the application action it is for
+///
+///For tools that have the application's actions.json, actions it doesn't list are only checked by [validate]
+pub fn validate_for(bindings: &ApplicationBindings, info: &XrApplicationInfo, catalog: &Root) -> Result<(), ConfigError> {
+    first_error(problems(bindings, catalog, Some(info)))
+}
+
+///Checks everything [validate_for] does, and that every profile is in `catalog`, collecting every problem instead of stopping at the first
+///
+///Without the application's actions.json bindings are only checked against their sources. `text` is what the config was parsed from,
+///when given each problem says which line it is on
+pub fn diagnose(bindings: &ApplicationBindings, info: Option<&XrApplicationInfo>, text: Option<&str>, catalog: &Root) -> Vec<ConfigError> {
+    problems(bindings, catalog, info)
+        .into_iter()
+        .map(|problem| match text.and_then(|text| problem.line_in(text)) {
+            Some(line) => ConfigError::InvalidAt {
+                line,
+                why: problem.to_string(),
+            },
+            None => ConfigError::Invalid(problem.to_string()),
+        })
+        .collect()
+}
+
+///Like [load_file] but reports every problem with the file at once, see [diagnose]
+pub fn load_file_diagnosed(path: &Path, info: Option<&XrApplicationInfo>, catalog: &Root) -> Result<ApplicationBindings, Vec<ConfigError>> {
+    if !path.exists() {
+        return Ok(ApplicationBindings::default());
+    }
+    let text = fs::read_to_string(path).map_err(|why| vec![ConfigError::Io(why)])?;
+    let bindings = match path.extension() {
+        Some(extension) if extension == "toml" => toml::from_str(&text).map_err(ConfigError::ParseToml),
+        _ => serde_json::from_str(&text).map_err(ConfigError::Parse),
+    }
+    .map_err(|why| vec![why])?;
+    let errors = diagnose(&bindings, info, Some(&text), catalog);
+    if errors.is_empty() {
+        Ok(bindings)
+    } else {
+        Err(errors)
+    }
+}
+
+///Something wrong with a config, see [problems]
+struct Problem {
+    profile: String,
+    ///The action set, action and binding path, `None` for problems with the profile itself
+    binding: Option<(String, String, String)>,
+    why: String,
+    ///Profiles missing from the catalog are skipped by the layer, only [diagnose] treats them as a problem
+    unknown_profile: bool,
+}
+
+impl Problem {
+    ///The line the problem is on, found by looking for the profile, then the action set, action and path after it
+    fn line_in(&self, text: &str) -> Option<usize> {
+        let mut offset = text.find(self.profile.as_str())?;
+        if let Some((set_name, action_name, path)) = &self.binding {
+            for needle in [set_name, action_name, path] {
+                offset += text[offset..].find(needle.as_str())?;
+            }
+        }
+        Some(text[..offset].matches('\n').count() + 1)
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.binding {
+            Some((set_name, action_name, _)) => write!(f, "{}/{} under {}: {}", set_name, action_name, self.profile, self.why),
+            None => write!(f, "{}", self.why),
+        }
+    }
+}
+
+fn first_error(problems: Vec<Problem>) -> Result<(), ConfigError> {
+    match problems.into_iter().find(|problem| !problem.unknown_profile) {
+        Some(problem) => Err(ConfigError::Invalid(problem.to_string())),
+        None => Ok(()),
+    }
+}
+
+///Every problem with `bindings`, those with the bindings themselves first and then, given the application's actions, bindings that can't drive their action
+fn problems(bindings: &ApplicationBindings, catalog: &Root, info: Option<&XrApplicationInfo>) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut coercion_problems = Vec::new();
+    for (profile_name, profile_bindings) in &bindings.profiles {
+        let profile_problem = |why: String, unknown_profile: bool| Problem {
+            profile: profile_name.clone(),
+            binding: None,
+            why,
+            unknown_profile,
+        };
+        if !profile_name.starts_with("/interaction_profiles/") {
+            problems.push(profile_problem(format!("{} is not an interaction profile", profile_name), false));
+            continue;
+        }
+        if !catalog.profiles.contains_key(profile_name) {
+            problems.push(profile_problem(format!("{} is not in the interaction profile catalog", profile_name), true));
+        }
+        for (set_name, set_bindings) in &profile_bindings.action_sets {
+            for (action_name, action_bindings) in &set_bindings.actions {
+                let action_type = info
+                    .and_then(|info| info.action_sets.get(set_name))
+                    .and_then(|set| set.actions.get(action_name))
+                    .map(|action_info| action_info.action_type);
+                for binding in &action_bindings.bindings {
+                    let binding_problem = |why: String| Problem {
+                        profile: profile_name.clone(),
+                        binding: Some((set_name.clone(), action_name.clone(), binding.path.clone())),
+                        why,
+                        unknown_profile: false,
+                    };
+                    let output_type = match validate_binding(binding).and_then(|_| catalog_output_type(catalog, profile_name, binding)) {
+                        Ok(output_type) => output_type,
+                        Err(why) => {
+                            problems.push(binding_problem(why));
+                            continue;
+                        }
+                    };
+                    if let (Some(output_type), Some(action_type)) = (output_type, action_type) {
+                        if !matches!(coercion(output_type, action_type), Some(coercion) if coercion.is_implicit()) {
+                            coercion_problems.push(binding_problem(format!(
+                                "{} gives {:?} which can't drive a {:?} action",
+                                binding.path, output_type, action_type
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    problems.extend(coercion_problems);
+    problems
+}
+
+fn validate_binding(binding: &Binding) -> Result<(), String> {
+    validate_path(&binding.path)?;
+    if let Some(y) = &binding.y {
+        validate_path(y)?;
+    }
+    if let Some(fallback) = &binding.fallback {
+        validate_path(fallback)?;
+        if binding.y.is_some() {
+            return Err(format!("fallback {} can't stand in for a composed vector", fallback));
+        }
+        if user_path_of(fallback) != user_path_of(&binding.path) {
+            return Err(format!("fallback {} must be on the same user path as {}", fallback, binding.path));
+        }
+    }
+    if let Some(when) = &binding.when {
+        validate_path(&when.path)?;
+        if when.path == binding.path || binding.y.as_ref() == Some(&when.path) {
+            return Err(format!("when {} can't gate the source it is read from", when.path));
+        }
+    }
+    validate_transform(&binding.transform)
+}
+
+///The top level user path a binding path is under, e.g. /user/hand/left for /user/hand/left/input/trigger/value
+fn user_path_of(path: &str) -> Option<&'static str> {
+    interaction_profiles::TOP_LEVEL_USER_PATHS
+        .iter()
+        .copied()
+        .find(|user_path| path.starts_with(user_path) && path[user_path.len()..].starts_with('/'))
+}
+
+fn validate_path(path: &str) -> Result<(), String> {
+    if !path.starts_with("/user/") || path.ends_with('/') || path.contains("//") {
+        return Err(format!("{} is not a binding path", path));
+    }
+    Ok(())
+}
+
+fn validate_transform(transform: &Transform) -> Result<(), String> {
+    //A dead zone of 1 or more would leave nothing to rescale the remaining travel into
+    if !(0f32..1f32).contains(&transform.dead_zone) {
+        return Err(format!("dead_zone {} must be at least 0 and below 1", transform.dead_zone));
+    }
+    if let Some(curve) = transform.curve {
+        if !(curve.is_finite() && curve > 0f32) {
+            return Err(format!("curve {} must be above 0", curve));
+        }
+    }
+    if transform.smoothing_ms == Some(0) {
+        return Err("smoothing_ms must be above 0".to_owned());
+    }
+    if let Some(amplitude) = transform.amplitude {
+        if !(0f32..=1f32).contains(&amplitude) {
+            return Err(format!("amplitude {} must be between 0 and 1", amplitude));
+        }
+    }
+    if let Some(sector) = transform.sector {
+        if sector.count < 2 || sector.index >= sector.count {
+            return Err(format!(
+                "sector {} of {} must be one of at least 2 sectors, counting from 0",
+                sector.index, sector.count
+            ));
+        }
+    }
+    if let Some(threshold) = &transform.threshold {
+        let in_range = |value: f32| (0f32..=1f32).contains(&value);
+        if !in_range(threshold.on_threshold)
+            || !in_range(threshold.off_threshold)
+            || threshold.off_threshold > threshold.on_threshold
+        {
+            return Err(format!(
+                "threshold {}/{} must be between 0 and 1 with off_threshold at most on_threshold",
+                threshold.on_threshold, threshold.off_threshold
+            ));
+        }
+    }
+    Ok(())
+}
+
+///How a binding turns a source of one type into another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coercion {
+    ///The source already has the type
+    Direct,
+    ///A boolean reads as 0 or 1, as the spec has runtimes do for float actions
+    BoolToFloat,
+    ///A float is pressed once it passes the binding's threshold, or the profile's default one when the binding has none
+    Threshold,
+    ///One axis of a vector, only through a binding's `axis`
+    Axis,
+    ///Whether a vector points into one slice of a radial menu, only through a binding's `sector`
+    Sector,
+    ///Two scalars drive x and y of a vector, only through a binding's `y`
+    Compose,
+}
+
+impl Coercion {
+    ///Whether the layer applies this coercion without the binding asking for it
+    pub fn is_implicit(self) -> bool {
+        !matches!(self, Coercion::Axis | Coercion::Sector | Coercion::Compose)
+    }
+}
+
+///Every supported source type to action type conversion, anything missing (e.g. a click driving a pose) is rejected
+pub const COERCIONS: &[(ActionType, ActionType, Coercion)] = &[
+    (ActionType::BooleanInput, ActionType::BooleanInput, Coercion::Direct),
+    (ActionType::FloatInput, ActionType::FloatInput, Coercion::Direct),
+    (ActionType::Vector2fInput, ActionType::Vector2fInput, Coercion::Direct),
+    (ActionType::PoseInput, ActionType::PoseInput, Coercion::Direct),
+    (ActionType::VibrationOutput, ActionType::VibrationOutput, Coercion::Direct),
+    (ActionType::BooleanInput, ActionType::FloatInput, Coercion::BoolToFloat),
+    (ActionType::FloatInput, ActionType::BooleanInput, Coercion::Threshold),
+    (ActionType::Vector2fInput, ActionType::FloatInput, Coercion::Axis),
+    (ActionType::Vector2fInput, ActionType::BooleanInput, Coercion::Sector),
+    (ActionType::BooleanInput, ActionType::Vector2fInput, Coercion::Compose),
+    (ActionType::FloatInput, ActionType::Vector2fInput, Coercion::Compose),
+];
+
+pub fn coercion(source: ActionType, target: ActionType) -> Option<Coercion> {
+    COERCIONS
+        .iter()
+        .find(|(from, to, _)| *from == source && *to == target)
+        .map(|(_, _, coercion)| *coercion)
+}
+
+///The type a binding hands to its action, given the types of its sources
+///
+///Each step of the transform that changes the type (`y`, then `sector`, then `axis`, then `threshold`) must be a coercion in [COERCIONS]
+pub fn binding_output_type(source: ActionType, y: Option<ActionType>, transform: &Transform) -> Result<ActionType, String> {
+    let step = |from: ActionType, to: ActionType, expected: Coercion, what: &str| {
+        if coercion(from, to) == Some(expected) {
+            Ok(to)
+        } else {
+            Err(format!("{} can't be applied to a {:?} source", what, from))
+        }
+    };
+
+    let mut output = source;
+    if let Some(y) = y {
+        step(source, ActionType::Vector2fInput, Coercion::Compose, "y")?;
+        output = step(y, ActionType::Vector2fInput, Coercion::Compose, "y")?;
+    }
+    if transform.amplitude.is_some() && source != ActionType::VibrationOutput {
+        return Err(format!("amplitude can't be applied to a {:?} source", source));
+    }
+    if transform.curve.is_some() && !matches!(output, ActionType::FloatInput | ActionType::Vector2fInput) {
+        return Err(format!("curve can't be applied to a {:?} source", output));
+    }
+    if transform.smoothing_ms.is_some() && !matches!(output, ActionType::FloatInput | ActionType::Vector2fInput) {
+        return Err(format!("smoothing_ms can't be applied to a {:?} source", output));
+    }
+    if transform.sector.is_some() {
+        output = step(output, ActionType::BooleanInput, Coercion::Sector, "sector")?;
+    }
+    if transform.axis.is_some() {
+        output = step(output, ActionType::FloatInput, Coercion::Axis, "axis")?;
+    }
+    if transform.threshold.is_some() {
+        output = step(output, ActionType::BooleanInput, Coercion::Threshold, "threshold")?;
+    }
+    if transform.gesture.is_some() && output != ActionType::BooleanInput {
+        return Err(format!("gesture can't be applied to a {:?} source", output));
+    }
+    Ok(output)
+}
+
+///[binding_output_type] with the source types from the catalog, `None` when the catalog doesn't know the profile
+///
+///Sources the profile doesn't have are an error, the layer would drop the binding
+fn catalog_output_type(catalog: &Root, profile_name: &str, binding: &Binding) -> Result<Option<ActionType>, String> {
+    let profile = match catalog.profiles.get(profile_name) {
+        Some(profile) => profile,
+        None => return Ok(None),
+    };
+    let source_type = |path: &str| {
+        profile
+            .action_type_for_binding(path)
+            .ok_or_else(|| format!("{} is not a source of {}", path, profile_name))
+    };
+    if let Some(when) = &binding.when {
+        match source_type(&when.path)? {
+            ActionType::BooleanInput | ActionType::FloatInput => {}
+            condition => {
+                return Err(format!("when {} must be a button or an analog trigger, not a {:?} source", when.path, condition))
+            }
+        }
+    }
+    let source = source_type(&binding.path)?;
+    let y = match &binding.y {
+        Some(y) => Some(source_type(y)?),
+        None => None,
+    };
+    binding_output_type(source, y, &binding.transform)
+        .map(Some)
+        .map_err(|why| format!("{}: {}", binding.path, why))
+}
+
+///Where [Activation] is read from, a missing file leaves the layer active for every application
+pub const ACTIVATION_FILE: &str = concat!(crate::config_dir!(), "activation.json");
+
+///Which applications the layer remaps, for installs that put the layer in front of every application
+///
+///Entries are matched exactly against both the application name and the engine name
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Activation {
+    ///When not empty only these applications are remapped
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    ///Never remapped, even when allowed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+}
+
+impl Activation {
+    pub fn is_active(&self, application_name: &str, engine_name: &str) -> bool {
+        let listed = |list: &Vec<String>| list.iter().any(|name| name == application_name || name == engine_name);
+        (self.allow.is_empty() || listed(&self.allow)) && !listed(&self.deny)
+    }
+}
+
+pub fn load_activation(path: &Path) -> Result<Activation, ConfigError> {
+    if !path.exists() {
+        return Ok(Activation::default());
+    }
+    serde_json::from_slice(&fs::read(path).map_err(ConfigError::Io)?).map_err(ConfigError::Parse)
+}
+
+///Where [Settings] are read from, a missing file means the defaults
+pub const SETTINGS_FILE: &str = concat!(crate::config_dir!(), "settings.json");
+
+///Knobs that apply to every binding of every application
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    ///Multiplied into every float and vector source before any of a binding's transforms (so ahead of the dead zone), the result is clamped back into -1..1
+    ///
+    ///Vectors keep their direction, only their length is clamped
+    pub analog_scale: f32,
+    ///When set, every xrAttachSessionActionSets writes the application, its extensions, the resolved catalog and the effective bindings here
+    ///
+    ///`{application}` is replaced with the application's config id, so one path can serve every application
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_path: Option<String>,
+    ///The priority of the layer's god action sets
+    ///
+    ///The layer syncs the god sets on their own, the application's sets never reach the runtime's xrSyncActions, so this only orders the
+    ///god sets against each other and whatever other layers sync in the same call. The highest priority is the default so nothing can suppress them
+    pub god_set_priority: u32,
+    ///Whether the application's own suggested bindings are also handed to the runtime
+    ///
+    ///Only the god actions need runtime bindings, so by default the application's suggestions stay in the layer whenever it remaps.
+    ///Some setups (e.g. a runtime binding UI listing the application's actions) need them forwarded anyway
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forward_suggestions: Option<bool>,
+    ///Creates the god action sets at the application's first xrAttachSessionActionSets instead of with the instance
+    ///
+    ///Only the profiles the application suggested bindings for (or that have custom bindings) then get a god set, rather than every profile in the catalog
+    #[serde(skip_serializing_if = "is_false")]
+    pub defer_god_sets: bool,
+    ///Warns whenever the controller in use on a hand changes to one the application may not expect, a diagnostic for setups where custom bindings drive a controller the application never suggested bindings for
+    #[serde(skip_serializing_if = "is_false")]
+    pub warn_profile_mismatch: bool,
+    ///Caps every vibration sent to a controller, to spare cheap actuators or for comfort
+    #[serde(skip_serializing_if = "HapticLimits::is_unlimited")]
+    pub haptic_limits: HapticLimits,
+    ///Caps for the controllers of one interaction profile, each limit set here replaces the one in `haptic_limits`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile_haptic_limits: BTreeMap<String, HapticLimits>,
+    ///The top level user paths the layer remaps, e.g. only the hands so an external gamepad stays the runtime's. Empty for all of them
+    ///
+    ///Suggested bindings on the other paths go straight to the runtime, and actions bound only there are read from the runtime as the
+    ///application left them. An action also bound on a managed path is remapped and only reads its managed sources
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub managed_user_paths: Vec<String>,
+    ///A source (e.g. /user/hand/left/input/menu/click) whose press returns every latched transform to rest, for when a toggle or threshold gets stuck.
+    ///It is read on every profile that has it, bindings to it still reach the application
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset_source: Option<String>,
+}
+
+///The strongest and longest vibrations forwarded to the runtime, anything past them is clamped
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct HapticLimits {
+    ///From 0 to 1, applied after a binding's own amplitude scale
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_amplitude: Option<f32>,
+    ///In nanoseconds. XR_MIN_HAPTIC_DURATION asks for the shortest pulse the device can do and is left alone
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_duration: Option<i64>,
+}
+
+impl HapticLimits {
+    pub fn is_unlimited(&self) -> bool {
+        *self == HapticLimits::default()
+    }
+
+    ///These limits with any that `overrides` sets replaced
+    pub fn with(&self, overrides: &HapticLimits) -> HapticLimits {
+        HapticLimits {
+            max_amplitude: overrides.max_amplitude.or(self.max_amplitude),
+            max_duration: overrides.max_duration.or(self.max_duration),
+        }
+    }
+
+    pub fn clamp_amplitude(&self, amplitude: f32) -> f32 {
+        match self.max_amplitude {
+            Some(max_amplitude) => amplitude.min(max_amplitude),
+            None => amplitude,
+        }
+    }
+
+    ///Durations only shrink, so XR_MIN_HAPTIC_DURATION (-1) passes through and XR_INFINITE_DURATION becomes the cap
+    pub fn clamp_duration(&self, duration: i64) -> i64 {
+        match self.max_duration {
+            Some(max_duration) => duration.min(max_duration),
+            None => duration,
+        }
+    }
+
+    fn validate(&self, name: &str) -> Result<(), ConfigError> {
+        if let Some(max_amplitude) = self.max_amplitude {
+            if !(0f32..=1f32).contains(&max_amplitude) {
+                return Err(ConfigError::Invalid(format!("{}: max_amplitude {} must be between 0 and 1", name, max_amplitude)));
+            }
+        }
+        if let Some(max_duration) = self.max_duration {
+            if max_duration <= 0 {
+                return Err(ConfigError::Invalid(format!("{}: max_duration {} must be positive", name, max_duration)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            analog_scale: 1f32,
+            snapshot_path: None,
+            god_set_priority: u32::MAX,
+            forward_suggestions: None,
+            defer_god_sets: false,
+            warn_profile_mismatch: false,
+            haptic_limits: Default::default(),
+            profile_haptic_limits: Default::default(),
+            managed_user_paths: Vec::new(),
+            reset_source: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !self.analog_scale.is_finite() || self.analog_scale < 0f32 {
+            return Err(ConfigError::Invalid(format!("analog_scale {} must be a number of at least 0", self.analog_scale)));
+        }
+        self.haptic_limits.validate("haptic_limits")?;
+        for (profile_name, limits) in &self.profile_haptic_limits {
+            limits.validate(profile_name)?;
+        }
+        for user_path in &self.managed_user_paths {
+            if !interaction_profiles::TOP_LEVEL_USER_PATHS.contains(&user_path.as_str()) {
+                return Err(ConfigError::Invalid(format!("managed_user_paths: {} is not a top level user path", user_path)));
+            }
+        }
+        if let Some(reset_source) = &self.reset_source {
+            let under_user_path = interaction_profiles::TOP_LEVEL_USER_PATHS
+                .iter()
+                .any(|user_path| reset_source.starts_with(user_path) && reset_source[user_path.len()..].starts_with("/input/"));
+            if !under_user_path {
+                return Err(ConfigError::Invalid(format!("reset_source: {} is not an input of a top level user path", reset_source)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn manages_every_user_path(&self) -> bool {
+        self.managed_user_paths.is_empty()
+    }
+
+    ///Whether `path`, a top level user path or a path under one, is remapped by the layer, see [Settings::managed_user_paths]
+    pub fn manages(&self, path: &str) -> bool {
+        self.manages_every_user_path()
+            || self
+                .managed_user_paths
+                .iter()
+                .any(|user_path| path == user_path || (path.starts_with(user_path.as_str()) && path[user_path.len()..].starts_with('/')))
+    }
+
+    ///The haptic limits for the controllers of `profile_name`
+    pub fn haptic_limits_for(&self, profile_name: &str) -> HapticLimits {
+        match self.profile_haptic_limits.get(profile_name) {
+            Some(overrides) => self.haptic_limits.with(overrides),
+            None => self.haptic_limits,
+        }
+    }
+}
+
+pub fn load_settings(path: &Path) -> Result<Settings, ConfigError> {
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+    let settings = serde_json::from_slice::<Settings>(&fs::read(path).map_err(ConfigError::Io)?).map_err(ConfigError::Parse)?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+///Builds a [BindingConfig] in code for embedders that don't want to go through a file
+///
+///Actions are named `action_set/action`. The `with_` methods adjust the transform of the binding added last
+#[derive(Debug, Default)]
+pub struct BindingConfigBuilder {
+    config: BindingConfig,
+    last: Option<(String, String, String)>,
+    error: Option<String>,
+}
+
+impl BindingConfigBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn bind(self, profile: &str, action: &str, source: &str) -> Self {
+        self.push(profile, action, Binding::new(source.to_owned()))
+    }
+
+    ///Composes two scalar sources into a vector action, `x_source` drives x and `y_source` drives y
+    pub fn bind_vector(self, profile: &str, action: &str, x_source: &str, y_source: &str) -> Self {
+        self.push(
+            profile,
+            action,
+            Binding {
+                y: Some(y_source.to_owned()),
+                ..Binding::new(x_source.to_owned())
+            },
+        )
+    }
+
+    ///Maps an action to null so it never fires
+    pub fn unbind(mut self, profile: &str, action: &str) -> Self {
+        if let Some(action_bindings) = self.action_bindings_mut(profile, action) {
+            *action_bindings = ActionBindings::unbound();
+        }
+        self.last = None;
+        self
+    }
+
+    pub fn with_threshold(self, on_threshold: f32, off_threshold: f32) -> Self {
+        self.with_transform("with_threshold", |transform| {
+            transform.threshold = Some(AnalogThreshold {
+                on_threshold,
+                off_threshold,
+            })
+        })
+    }
+
+    pub fn with_dead_zone(self, dead_zone: f32) -> Self {
+        self.with_transform("with_dead_zone", |transform| transform.dead_zone = dead_zone)
+    }
+
+    pub fn with_invert(self) -> Self {
+        self.with_transform("with_invert", |transform| transform.invert = true)
+    }
+
+    pub fn with_amplitude(self, amplitude: f32) -> Self {
+        self.with_transform("with_amplitude", |transform| transform.amplitude = Some(amplitude))
+    }
+
+    pub fn with_curve(self, curve: f32) -> Self {
+        self.with_transform("with_curve", |transform| transform.curve = Some(curve))
+    }
+
+    pub fn with_smoothing(self, smoothing_ms: u32) -> Self {
+        self.with_transform("with_smoothing", |transform| transform.smoothing_ms = Some(smoothing_ms))
+    }
+
+    pub fn with_sector(self, count: u32, index: u32) -> Self {
+        self.with_transform("with_sector", |transform| transform.sector = Some(Sector { count, index }))
+    }
+
+    ///Reads `source` instead while the controller in use doesn't have the bound one, see [Binding::fallback]
+    pub fn with_fallback(self, source: &str) -> Self {
+        self.with_binding("with_fallback", |binding| binding.fallback = Some(source.to_owned()))
+    }
+
+    ///Only lets the binding through while `source` is held, or while it isn't when `released`, see [Binding::when]
+    pub fn with_condition(self, source: &str, released: bool) -> Self {
+        self.with_binding("with_condition", |binding| {
+            binding.when = Some(SourceCondition {
+                path: source.to_owned(),
+                released,
+            })
+        })
+    }
+
+    pub fn with_note(self, note: &str) -> Self {
+        self.with_binding("with_note", |binding| binding.note = Some(note.to_owned()))
+    }
+
+    ///The config, checked the same way a file is
+    pub fn build(self) -> Result<BindingConfig, ConfigError> {
+        if let Some(why) = self.error {
+            return Err(ConfigError::Invalid(why));
+        }
+        validate(&self.config, &interaction_profiles::catalog())?;
+        Ok(self.config)
+    }
+
+    fn push(mut self, profile: &str, action: &str, binding: Binding) -> Self {
+        if let Some(action_bindings) = self.action_bindings_mut(profile, action) {
+            action_bindings.unbound = false;
+            action_bindings.bindings.push(binding);
+            let (set_name, action_name) = action.split_once('/').unwrap();
+            self.last = Some((profile.to_owned(), set_name.to_owned(), action_name.to_owned()));
+        }
+        self
+    }
+
+    fn with_transform<F: FnOnce(&mut Transform)>(self, method: &str, modify: F) -> Self {
+        self.with_binding(method, |binding| modify(&mut binding.transform))
+    }
+
+    fn with_binding<F: FnOnce(&mut Binding)>(mut self, method: &str, modify: F) -> Self {
+        let config = &mut self.config;
+        let binding = self.last.as_ref().and_then(|(profile, set_name, action_name)| {
+            config.profiles.get_mut(profile)?.action_sets.get_mut(set_name)?.actions.get_mut(action_name)?.bindings.last_mut()
+        });
+        match binding {
+            Some(binding) => modify(binding),
+            None => self.fail(format!("{} has no binding to apply to", method)),
+        }
+        self
+    }
+
+    ///Only the first problem is kept, later ones are usually caused by it
+    fn fail(&mut self, why: String) {
+        self.error.get_or_insert(why);
+    }
+
+    fn action_bindings_mut(&mut self, profile: &str, action: &str) -> Option<&mut ActionBindings> {
+        let (set_name, action_name) = match action.split_once('/') {
+            Some(names) => names,
+            None => {
+                self.fail(format!("{} is not named action_set/action", action));
+                return None;
+            }
+        };
+        Some(
+            self.config
+                .profiles
+                .entry(profile.to_owned())
+                .or_default()
+                .action_sets
+                .entry(set_name.to_owned())
+                .or_default()
+                .actions
+                .entry(action_name.to_owned())
+                .or_default(),
+        )
+    }
+}
+
+#[cfg(test)]
+const SAMPLE: &str = r#"{
+    "/interaction_profiles/valve/index_controller": {
+        "gameplay": {
+            "jump": { "bindings": ["/user/hand/left/input/a/click"] },
+            "fire": { "bindings": [{ "path": "/user/hand/right/input/trigger/value", "threshold": { "on_threshold": 0.8, "off_threshold": 0.7 } }] },
+            "move": { "bindings": [{ "path": "/user/hand/left/input/thumbstick", "dead_zone": 0.1 }] },
+            "crouch": null
+        }
+    }
+}"#;
+
+#[test]
+fn test_load() {
+    let bindings = load(SAMPLE.as_bytes()).unwrap();
+    let gameplay = &bindings.profiles["/interaction_profiles/valve/index_controller"].action_sets["gameplay"];
+    assert_eq!(gameplay.actions["move"].bindings[0].transform.dead_zone, 0.1);
+    assert!(gameplay.actions["crouch"].unbound);
+
+    assert!(matches!(load(b""), Err(ConfigError::Parse(_))));
+    assert!(matches!(
+        load(br#"{ "/interaction_profiles/valve/index_controller": { "gameplay": { "jump": { "bindings": ["a/click"] } } } }"#),
+        Err(ConfigError::Invalid(_))
+    ));
+}
+
+#[test]
+fn test_load_regressions() {
+    //Out of range f32s deserialize to infinity, which used to reach the dead zone maths
+    assert!(matches!(
+        load(br#"{ "/interaction_profiles/valve/index_controller": { "gameplay": { "move": { "bindings": [{ "path": "/user/hand/left/input/thumbstick", "dead_zone": 1e40 }] } } } }"#),
+        Err(ConfigError::Invalid(_))
+    ));
+    //A dead zone of exactly 1 divides by zero
+    assert!(matches!(
+        load(br#"{ "/interaction_profiles/valve/index_controller": { "gameplay": { "move": { "bindings": [{ "path": "/user/hand/left/input/thumbstick", "dead_zone": 1 }] } } } }"#),
+        Err(ConfigError::Invalid(_))
+    ));
+    //Truncated files used to panic in serial::read_json
+    assert!(matches!(load(&SAMPLE.as_bytes()[..SAMPLE.len() / 2]), Err(ConfigError::Parse(_))));
+}
+
+#[test]
+fn test_load_fuzz() {
+    //xorshift so failures can be reproduced from the seed
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+
+    //Arbitrary bytes
+    for _ in 0..2000 {
+        let bytes = (0..next() % 64).map(|_| next() as u8).collect::<Vec<_>>();
+        let _ = load(&bytes);
+    }
+
+    //Mutations of a valid file, which get much further into the deserializers
+    let interesting = b"{}[]\",:0.-e1n/ ";
+    for _ in 0..5000 {
+        let mut bytes = SAMPLE.as_bytes().to_vec();
+        for _ in 0..1 + next() % 4 {
+            let idx = (next() % bytes.len() as u64) as usize;
+            let byte = interesting[(next() % interesting.len() as u64) as usize];
+            match next() % 3 {
+                0 => bytes[idx] = byte,
+                1 => bytes.insert(idx, byte),
+                _ => {
+                    bytes.remove(idx);
+                }
+            }
+        }
+        if let Ok(bindings) = load(&bytes) {
+            assert!(validate(&bindings, &interaction_profiles::generate()).is_ok());
+        }
+    }
+}
+
+#[test]
+fn test_builder() {
+    let config = BindingConfigBuilder::new()
+        .bind("/interaction_profiles/valve/index_controller", "gameplay/fire", "/user/hand/right/input/trigger/value")
+        .with_threshold(0.8, 0.7)
+        .unbind("/interaction_profiles/valve/index_controller", "gameplay/crouch")
+        .build()
+        .unwrap();
+    let gameplay = &config.profiles["/interaction_profiles/valve/index_controller"].action_sets["gameplay"];
+    assert_eq!(gameplay.actions["fire"].bindings[0].transform.threshold.unwrap().on_threshold, 0.8);
+    assert!(gameplay.actions["crouch"].unbound);
+
+    assert!(matches!(
+        BindingConfigBuilder::new().with_dead_zone(0.1).build(),
+        Err(ConfigError::Invalid(_))
+    ));
+    assert!(matches!(
+        BindingConfigBuilder::new().bind("/interaction_profiles/valve/index_controller", "fire", "/user/hand/right/input/trigger/value").build(),
+        Err(ConfigError::Invalid(_))
+    ));
+    assert!(matches!(
+        BindingConfigBuilder::new()
+            .bind("/interaction_profiles/valve/index_controller", "gameplay/fire", "/user/hand/right/input/trigger/value")
+            .with_threshold(0.7, 0.8)
+            .build(),
+        Err(ConfigError::Invalid(_))
+    ));
+    assert!(matches!(
+        BindingConfigBuilder::new()
+            .bind("/interaction_profiles/valve/index_controller", "gameplay/weapon_1", "/user/hand/right/input/trackpad")
+            .with_sector(4, 4)
+            .build(),
+        Err(ConfigError::Invalid(_))
+    ));
+}
+
+#[test]
+fn test_activation() {
+    let everything = Activation::default();
+    assert!(everything.is_active("hello_xr", "none"));
+
+    let activation = serde_json::from_str::<Activation>(r#"{ "allow": ["hello_xr", "Unity"], "deny": ["Launcher"] }"#).unwrap();
+    assert!(activation.is_active("hello_xr", ""));
+    assert!(activation.is_active("Some Game", "Unity"));
+    assert!(!activation.is_active("Other Game", "Unreal Engine"));
+    assert!(!activation.is_active("Launcher", "Unity"));
+
+    let deny_only = serde_json::from_str::<Activation>(r#"{ "deny": ["Launcher"] }"#).unwrap();
+    assert!(deny_only.is_active("Other Game", "Unreal Engine"));
+    assert!(!deny_only.is_active("Launcher", ""));
+}
+
+#[test]
+fn test_settings() {
+    assert_eq!(serde_json::from_str::<Settings>("{}").unwrap(), Settings::default());
+    let settings = serde_json::from_str::<Settings>(r#"{ "analog_scale": 0.5 }"#).unwrap();
+    assert_eq!(settings.analog_scale, 0.5);
+    assert!(settings.validate().is_ok());
+    let invalid = |analog_scale| Settings { analog_scale, ..Default::default() }.validate();
+    assert!(matches!(invalid(-1f32), Err(ConfigError::Invalid(_))));
+    assert!(matches!(invalid(f32::NAN), Err(ConfigError::Invalid(_))));
+    //Zero mutes every analog source, which is allowed
+    assert!(Settings { analog_scale: 0f32, ..Default::default() }.validate().is_ok());
+
+    let settings = serde_json::from_str::<Settings>(
+        r#"{ "haptic_limits": { "max_amplitude": 0.5, "max_duration": 100000000 }, "profile_haptic_limits": { "/interaction_profiles/microsoft/xbox_controller": { "max_amplitude": 0.7 } } }"#,
+    )
+    .unwrap();
+    assert!(settings.validate().is_ok());
+    let xbox = settings.haptic_limits_for("/interaction_profiles/microsoft/xbox_controller");
+    assert_eq!(xbox.clamp_amplitude(1f32), 0.7);
+    assert_eq!(xbox.clamp_duration(i64::MAX), 100_000_000);
+    assert_eq!(xbox.clamp_duration(-1), -1);
+    assert_eq!(settings.haptic_limits_for("/interaction_profiles/valve/index_controller").clamp_amplitude(1f32), 0.5);
+    let invalid = |max_amplitude| Settings { haptic_limits: HapticLimits { max_amplitude: Some(max_amplitude), max_duration: None }, ..Default::default() }.validate();
+    assert!(matches!(invalid(1.5f32), Err(ConfigError::Invalid(_))));
+    assert!(matches!(invalid(f32::NAN), Err(ConfigError::Invalid(_))));
+
+    assert!(Settings::default().manages("/user/gamepad/input/a/click"));
+    let hands = serde_json::from_str::<Settings>(r#"{ "managed_user_paths": ["/user/hand/left", "/user/hand/right"] }"#).unwrap();
+    assert!(hands.validate().is_ok());
+    assert!(hands.manages("/user/hand/left"));
+    assert!(hands.manages("/user/hand/right/input/trigger/value"));
+    assert!(!hands.manages("/user/gamepad/input/a/click"));
+    assert!(!hands.manages("/user/hand/leftover"));
+    let invalid = Settings { managed_user_paths: vec!["/user/hand".to_owned()], ..Default::default() }.validate();
+    assert!(matches!(invalid, Err(ConfigError::Invalid(why)) if why.contains("/user/hand")));
+
+    let reset = |reset_source: &str| Settings { reset_source: Some(reset_source.to_owned()), ..Default::default() }.validate();
+    assert!(reset("/user/hand/left/input/menu/click").is_ok());
+    assert!(matches!(reset("/user/hand/left"), Err(ConfigError::Invalid(_))));
+    assert!(matches!(reset("/user/hand/leftover/input/menu/click"), Err(ConfigError::Invalid(_))));
+}
+
+#[test]
+fn test_note_round_trip() {
+    let profile = "/interaction_profiles/valve/index_controller";
+    let mut config = BindingConfigBuilder::new()
+        .bind(profile, "gameplay/fire", "/user/hand/right/input/trigger/value")
+        .with_threshold(0.8, 0.7)
+        .with_note("lighter trigger for the bow")
+        .bind(profile, "gameplay/jump", "/user/hand/left/input/a/click")
+        .build()
+        .unwrap();
+
+    rebind(&mut config, profile, "gameplay/fire", "/user/hand/right/input/trigger/value", "/user/hand/left/input/trigger/value").unwrap();
+    assert!(matches!(
+        rebind(&mut config, profile, "gameplay/fire", "/user/hand/right/input/trigger/value", "/user/hand/left/input/trigger/value"),
+        Err(ConfigError::Invalid(_))
+    ));
+
+    let path = std::env::temp_dir().join(format!("oxidexr_note_{}.json", std::process::id()));
+    save(&config, &path).unwrap();
+    let saved = load_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let gameplay = &saved.profiles[profile].action_sets["gameplay"];
+    let fire = &gameplay.actions["fire"].bindings[0];
+    assert_eq!(fire.path, "/user/hand/left/input/trigger/value");
+    assert_eq!(fire.note.as_deref(), Some("lighter trigger for the bow"));
+    assert_eq!(fire.transform.threshold.unwrap().on_threshold, 0.8);
+    //A binding without a note still saves as a plain path
+    assert_eq!(gameplay.actions["jump"].bindings[0], Binding::new("/user/hand/left/input/a/click".to_owned()));
+}
+
+#[cfg(test)]
+const SAMPLE_TOML: &str = r#"
+# The same bindings as SAMPLE
+["/interaction_profiles/valve/index_controller".gameplay]
+jump = { bindings = ["/user/hand/left/input/a/click"] }
+fire = { bindings = [{ path = "/user/hand/right/input/trigger/value", threshold = { on_threshold = 0.8, off_threshold = 0.7 } }] }
+move = { bindings = [{ path = "/user/hand/left/input/thumbstick", dead_zone = 0.1 }] }
+crouch = { unbound = true }
+"#;
+
+#[test]
+fn test_load_toml() {
+    let from_json = load(SAMPLE.as_bytes()).unwrap();
+    let from_toml = load_toml(SAMPLE_TOML.as_bytes()).unwrap();
+    assert_eq!(serde_json::to_value(&from_toml).unwrap(), serde_json::to_value(&from_json).unwrap());
+
+    //Both formats are picked by extension and survive a trip through a file
+    let dir = std::env::temp_dir().join(format!("oxidexr_toml_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("custom_bindings.toml"), SAMPLE_TOML).unwrap();
+    save(&from_json, &dir.join("custom_bindings.json")).unwrap();
+    let toml_file = load_file(&dir.join("custom_bindings.toml")).unwrap();
+    let json_file = load_file(&dir.join("custom_bindings.json")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(serde_json::to_value(&toml_file).unwrap(), serde_json::to_value(&from_json).unwrap());
+    assert_eq!(serde_json::to_value(&json_file).unwrap(), serde_json::to_value(&from_json).unwrap());
+
+    let error = load_toml(b"[\"/interaction_profiles/valve/index_controller\".gameplay]\njump = { bindings = [\"/user/hand/left/input/a/click\" }\n").unwrap_err();
+    assert!(matches!(error, ConfigError::ParseToml(_)));
+    assert!(error.to_string().contains("line 2 column"), "{}", error);
+    assert!(matches!(
+        load_toml(b"[\"/interaction_profiles/valve/index_controller\".gameplay]\njump = { bindings = [\"a/click\"] }\n"),
+        Err(ConfigError::Invalid(_))
+    ));
+}
+
+#[test]
+fn test_load_layered() {
+    let profile = "/interaction_profiles/valve/index_controller";
+    let dir = std::env::temp_dir().join(format!("oxidexr_layered_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let base = BindingConfigBuilder::new()
+        .bind(profile, "gameplay/jump", "/user/hand/right/input/a/click")
+        .bind(profile, "gameplay/crouch", "/user/hand/right/input/b/click")
+        .build()
+        .unwrap();
+    save(&base, &dir.join("base.json")).unwrap();
+    fs::write(
+        dir.join("machine.toml"),
+        "[\"/interaction_profiles/valve/index_controller\".gameplay]\njump = { bindings = [\"/user/hand/left/input/a/click\"] }\n",
+    )
+    .unwrap();
+
+    let layered = load_layered(&[dir.join("base.json"), dir.join("machine.toml"), dir.join("missing.json")]).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    let gameplay = &layered.profiles[profile].action_sets["gameplay"];
+    assert_eq!(gameplay.actions["jump"].bindings, vec![Binding::new("/user/hand/left/input/a/click".to_owned())]);
+    assert_eq!(gameplay.actions["crouch"].bindings, vec![Binding::new("/user/hand/right/input/b/click".to_owned())]);
+}
+
+#[test]
+fn test_coercions() {
+    use crate::application_bindings::Axis;
+    use crate::xrapplication_info::{ActionInfo, ActionSetInfo};
+
+    let with = |edit: fn(&mut Transform)| {
+        let mut transform = Transform::default();
+        edit(&mut transform);
+        transform
+    };
+    let output = |source, y, transform: &Transform| binding_output_type(source, y, transform);
+
+    //Implicit coercions
+    for (source, target) in [
+        (ActionType::BooleanInput, ActionType::BooleanInput),
+        (ActionType::FloatInput, ActionType::FloatInput),
+        (ActionType::Vector2fInput, ActionType::Vector2fInput),
+        (ActionType::PoseInput, ActionType::PoseInput),
+        (ActionType::VibrationOutput, ActionType::VibrationOutput),
+        (ActionType::BooleanInput, ActionType::FloatInput),
+        (ActionType::FloatInput, ActionType::BooleanInput),
+    ] {
+        assert!(coercion(source, target).unwrap().is_implicit(), "{:?} -> {:?}", source, target);
+    }
+    //Coercions the transform has to ask for
+    assert_eq!(coercion(ActionType::Vector2fInput, ActionType::FloatInput), Some(Coercion::Axis));
+    assert_eq!(coercion(ActionType::FloatInput, ActionType::Vector2fInput), Some(Coercion::Compose));
+    assert_eq!(coercion(ActionType::Vector2fInput, ActionType::BooleanInput), Some(Coercion::Sector));
+    assert_eq!(
+        output(ActionType::Vector2fInput, None, &with(|t| t.sector = Some(Sector { count: 4, index: 1 }))),
+        Ok(ActionType::BooleanInput)
+    );
+    assert_eq!(output(ActionType::Vector2fInput, None, &with(|t| t.axis = Some(Axis::Y))), Ok(ActionType::FloatInput));
+    assert_eq!(output(ActionType::FloatInput, Some(ActionType::FloatInput), &Transform::default()), Ok(ActionType::Vector2fInput));
+    assert_eq!(output(ActionType::BooleanInput, Some(ActionType::BooleanInput), &Transform::default()), Ok(ActionType::Vector2fInput));
+    assert_eq!(
+        output(ActionType::Vector2fInput, None, &with(|t| {
+            t.axis = Some(Axis::X);
+            t.threshold = Some(AnalogThreshold::default());
+        })),
+        Ok(ActionType::BooleanInput)
+    );
+
+    //Rejected
+    assert_eq!(coercion(ActionType::BooleanInput, ActionType::PoseInput), None);
+    assert_eq!(coercion(ActionType::PoseInput, ActionType::BooleanInput), None);
+    assert!(output(ActionType::FloatInput, None, &with(|t| t.sector = Some(Sector { count: 4, index: 1 }))).is_err());
+    assert!(output(ActionType::BooleanInput, None, &with(|t| t.threshold = Some(AnalogThreshold::default()))).is_err());
+    assert!(output(ActionType::FloatInput, None, &with(|t| t.axis = Some(Axis::X))).is_err());
+    assert!(output(ActionType::PoseInput, Some(ActionType::FloatInput), &Transform::default()).is_err());
+    assert!(output(ActionType::BooleanInput, None, &with(|t| t.curve = Some(2f32))).is_err());
+    assert!(output(ActionType::BooleanInput, None, &with(|t| t.smoothing_ms = Some(50))).is_err());
+    assert!(output(ActionType::FloatInput, None, &with(|t| t.amplitude = Some(0.5))).is_err());
+    assert_eq!(output(ActionType::VibrationOutput, None, &with(|t| t.amplitude = Some(0.5))), Ok(ActionType::VibrationOutput));
+
+    //At load time against the catalog
+    let catalog = interaction_profiles::generate();
+    let profile = "/interaction_profiles/valve/index_controller";
+    let config = BindingConfigBuilder::new()
+        .bind(profile, "gameplay/aim", "/user/hand/right/input/a/click")
+        .bind(profile, "gameplay/fire", "/user/hand/right/input/trigger/value")
+        .with_threshold(0.8, 0.7)
+        .build()
+        .unwrap();
+    assert!(validate(&config, &catalog).is_ok());
+    let mut bad_threshold = config.clone();
+    bad_threshold.profiles.get_mut(profile).unwrap().action_sets.get_mut("gameplay").unwrap().actions.get_mut("aim").unwrap().bindings[0]
+        .transform
+        .threshold = Some(AnalogThreshold::default());
+    assert!(matches!(validate(&bad_threshold, &catalog), Err(ConfigError::Invalid(_))));
+    //A source the profile doesn't have is reported rather than left for the layer to drop
+    let twist = |profile: &str, path: &str| {
+        let mut config = BindingConfig::default();
+        config.profiles.entry(profile.to_owned()).or_default().action_sets.entry("flight".to_owned()).or_default().actions.entry("yaw".to_owned()).or_default().bindings =
+            vec![Binding {
+                transform: with(|t| t.curve = Some(2f32)),
+                ..Binding::new(path.to_owned())
+            }];
+        validate(&config, &catalog)
+    };
+    assert!(twist(interaction_profiles::FLIGHT_STICK_PROFILE, "/user/gamepad/input/joystick/twist").is_ok());
+    assert!(matches!(twist(profile, "/user/hand/right/input/thumbstick/twist"), Err(ConfigError::Invalid(_))));
+    let unknown = BindingConfigBuilder::new().bind(profile, "gameplay/fire", "/user/hand/right/input/trackpad/click").build();
+    assert!(matches!(unknown, Err(ConfigError::Invalid(why)) if why.contains("/user/hand/right/input/trackpad/click")));
+    let unknown_y = BindingConfigBuilder::new()
+        .bind_vector(profile, "gameplay/move", "/user/hand/right/input/trigger/value", "/user/hand/right/input/pedal/value")
+        .build();
+    assert!(matches!(unknown_y, Err(ConfigError::Invalid(_))));
+    let unknown_condition = BindingConfigBuilder::new()
+        .bind(profile, "gameplay/fire", "/user/hand/right/input/a/click")
+        .with_condition("/user/hand/right/input/menu/click", false)
+        .build();
+    assert!(matches!(unknown_condition, Err(ConfigError::Invalid(_))));
+
+    //And against the application's action types, a pose action can't be driven by a click
+    let action = |action_type| ActionInfo {
+        localized_name: String::new(),
+        action_type,
+        subaction_paths: Vec::new(),
+        suggested_bindings: Default::default(),
+    };
+    let mut info = XrApplicationInfo::from_name("app");
+    info.action_sets.insert(
+        "gameplay".to_owned(),
+        ActionSetInfo {
+            localized_name: "Gameplay".to_owned(),
+            actions: vec![("aim".to_owned(), action(ActionType::PoseInput)), ("fire".to_owned(), action(ActionType::BooleanInput))]
+                .into_iter()
+                .collect(),
+        },
+    );
+    let error = validate_for(&config, &info, &catalog).unwrap_err();
+    assert!(error.to_string().contains("gameplay/aim"), "{}", error);
+    info.action_sets.get_mut("gameplay").unwrap().actions.insert("aim".to_owned(), action(ActionType::FloatInput));
+    assert!(validate_for(&config, &info, &catalog).is_ok());
+}
+
+#[test]
+fn test_diagnose() {
+    let text = r#"{
+    "/interaction_profiles/acme/wand": {
+        "gameplay": { "fire": { "bindings": [{ "path": "/user/hand/right/input/trigger" }] } }
+    },
+    "/interaction_profiles/valve/index_controller": {
+        "gameplay": {
+            "fire": { "bindings": [{ "path": "/user/hand//right/input/trigger" }] },
+            "move": { "bindings": [{ "path": "/user/hand/left/input/thumbstick", "dead_zone": 1.5 }] }
+        }
+    }
+}"#;
+    let dir = std::env::temp_dir().join(format!("oxidexr_diagnose_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("bindings.json");
+    fs::write(&path, text).unwrap();
+    let errors = load_file_diagnosed(&path, None, &interaction_profiles::generate()).unwrap_err();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let mut reported = errors
+        .iter()
+        .map(|error| match error {
+            ConfigError::InvalidAt { line, why } => (*line, why.as_str()),
+            other => panic!("{} has no line", other),
+        })
+        .collect::<Vec<_>>();
+    reported.sort_by_key(|(line, _)| *line);
+    assert_eq!(reported.len(), 3, "{:?}", reported);
+    assert_eq!(reported[0].0, 2);
+    assert!(reported[0].1.contains("not in the interaction profile catalog"), "{}", reported[0].1);
+    assert_eq!(reported[1].0, 7);
+    assert!(reported[1].1.contains("is not a binding path"), "{}", reported[1].1);
+    assert_eq!(reported[2].0, 8);
+    assert!(reported[2].1.contains("dead_zone 1.5"), "{}", reported[2].1);
+
+    //The layer skips profiles it doesn't know rather than rejecting the file, so validate still stops at the first real problem
+    let bindings = serde_json::from_str::<ApplicationBindings>(text).unwrap();
+    let catalog = interaction_profiles::generate();
+    assert!(matches!(validate(&bindings, &catalog), Err(ConfigError::Invalid(_))));
+    assert!(diagnose(&bindings, None, None, &catalog).iter().all(|error| matches!(error, ConfigError::Invalid(_))));
+}
+
+#[test]
+fn test_fallback() {
+    let bindings = load(
+        br#"{ "/interaction_profiles/valve/index_controller": { "gameplay": { "scroll": { "bindings": [
+            { "path": "/user/hand/right/input/trackpad", "fallback": "/user/hand/right/input/thumbstick" }
+        ] } } } }"#,
+    )
+    .unwrap();
+    let binding = &bindings.profiles["/interaction_profiles/valve/index_controller"].action_sets["gameplay"].actions["scroll"].bindings[0];
+    assert_eq!(binding.fallback.as_deref(), Some("/user/hand/right/input/thumbstick"));
+    assert_eq!(serde_json::to_value(binding).unwrap()["fallback"], "/user/hand/right/input/thumbstick");
+
+    let other_hand = BindingConfigBuilder::new()
+        .bind("/interaction_profiles/valve/index_controller", "gameplay/scroll", "/user/hand/right/input/trackpad")
+        .with_fallback("/user/hand/left/input/thumbstick")
+        .build();
+    assert!(matches!(other_hand, Err(ConfigError::Invalid(why)) if why.contains("same user path")));
+
+    let composed = BindingConfigBuilder::new()
+        .bind_vector(
+            "/interaction_profiles/valve/index_controller",
+            "gameplay/steer",
+            "/user/hand/right/input/trigger/value",
+            "/user/hand/right/input/squeeze/value",
+        )
+        .with_fallback("/user/hand/right/input/thumbstick")
+        .build();
+    assert!(matches!(composed, Err(ConfigError::Invalid(why)) if why.contains("composed vector")));
+}
+
+#[test]
+fn test_condition() {
+    let index = "/interaction_profiles/valve/index_controller";
+    let bindings = load(
+        br#"{ "/interaction_profiles/valve/index_controller": { "gameplay": { "jump": { "bindings": [
+            { "path": "/user/hand/right/input/trigger/click", "when": { "path": "/user/hand/left/input/squeeze/value" } }
+        ] } } } }"#,
+    )
+    .unwrap();
+    let binding = &bindings.profiles[index].action_sets["gameplay"].actions["jump"].bindings[0];
+    assert_eq!(
+        binding.when,
+        Some(SourceCondition {
+            path: "/user/hand/left/input/squeeze/value".to_owned(),
+            released: false,
+        })
+    );
+    //released is left out unless it is set
+    assert_eq!(serde_json::to_value(binding).unwrap()["when"], serde_json::json!({ "path": "/user/hand/left/input/squeeze/value" }));
+
+    let gates_itself = BindingConfigBuilder::new()
+        .bind(index, "gameplay/jump", "/user/hand/right/input/trigger/click")
+        .with_condition("/user/hand/right/input/trigger/click", false)
+        .build();
+    assert!(matches!(gates_itself, Err(ConfigError::Invalid(why)) if why.contains("can't gate")));
+
+    let stick = BindingConfigBuilder::new()
+        .bind(index, "gameplay/jump", "/user/hand/right/input/trigger/click")
+        .with_condition("/user/hand/left/input/thumbstick", true)
+        .build();
+    assert!(matches!(stick, Err(ConfigError::Invalid(why)) if why.contains("must be a button")));
+}
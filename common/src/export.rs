@@ -0,0 +1,324 @@
+//! Converts what the layer captured about an application into other input systems' formats
+//!
+//! SteamVR takes an action manifest naming every action plus one binding file per controller type. Only suggestions for profiles
+//! with a SteamVR controller type are exported, sources SteamVR has no equivalent for (e.g. a lone thumbstick axis) are skipped and reported
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::interaction_profiles::Root;
+use crate::xrapplication_info::{ActionType, XrApplicationInfo};
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ActionManifest {
+    pub actions: Vec<ManifestAction>,
+    pub action_sets: Vec<ManifestActionSet>,
+    pub default_bindings: Vec<DefaultBinding>,
+    pub localization: Vec<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ManifestAction {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ManifestActionSet {
+    pub name: String,
+    pub usage: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct DefaultBinding {
+    pub controller_type: String,
+    pub binding_url: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct BindingFile {
+    pub controller_type: String,
+    ///Keyed by action set name, e.g. /actions/gameplay
+    pub bindings: BTreeMap<String, SetBindings>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct SetBindings {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<Source>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub poses: Vec<PathOutput>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub haptics: Vec<PathOutput>,
+}
+
+///A physical input read in one mode, each of its inputs (click, touch, pull, position...) drives an action
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Source {
+    pub path: String,
+    pub mode: String,
+    pub inputs: BTreeMap<String, Output>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Output {
+    pub output: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PathOutput {
+    pub output: String,
+    pub path: String,
+}
+
+pub struct SteamVrExport {
+    pub manifest: ActionManifest,
+    ///Keyed by the file name the manifest's `binding_url` points at
+    pub binding_files: BTreeMap<String, BindingFile>,
+    ///Suggested bindings that have no SteamVR equivalent, as `profile: binding`
+    pub skipped: Vec<String>,
+}
+
+///The SteamVR controller type for an interaction profile
+pub fn steamvr_controller_type(profile: &str) -> Option<&'static str> {
+    Some(match profile {
+        "/interaction_profiles/valve/index_controller" => "knuckles",
+        "/interaction_profiles/htc/vive_controller" => "vive_controller",
+        "/interaction_profiles/oculus/touch_controller" => "oculus_touch",
+        "/interaction_profiles/microsoft/motion_controller" => "holographic_controller",
+        "/interaction_profiles/microsoft/xbox_controller" => "gamepad",
+        _ => return None,
+    })
+}
+
+fn steamvr_action_type(action_type: ActionType) -> Option<&'static str> {
+    Some(match action_type {
+        ActionType::BooleanInput => "boolean",
+        ActionType::FloatInput => "vector1",
+        ActionType::Vector2fInput => "vector2",
+        ActionType::PoseInput => "pose",
+        ActionType::VibrationOutput => "vibration",
+        ActionType::Unknown => return None,
+    })
+}
+
+fn action_name(set_name: &str, action_name: &str, action_type: ActionType) -> String {
+    let direction = if action_type.is_input() { "in" } else { "out" };
+    format!("/actions/{}/{}/{}", set_name, direction, action_name)
+}
+
+///Where a suggested binding ends up in a SteamVR binding file
+enum Target {
+    Source { path: String, mode: String, input: String },
+    Pose(String),
+    Haptic(String),
+}
+
+fn target(catalog: &Root, profile: &str, binding: &str, action_type: ActionType) -> Option<Target> {
+    let (rest, subpath, subpath_info) = catalog.profiles.get(profile)?.find_subpath(binding)?;
+    let user_path = &binding[..binding.len() - rest.len()];
+    let component = rest.strip_prefix(subpath.as_str())?.strip_prefix('/');
+
+    match subpath_info.r#type.as_str() {
+        "pose" => match subpath.as_str() {
+            "/input/grip" => Some(Target::Pose(format!("{}/pose/raw", user_path))),
+            "/input/aim" => Some(Target::Pose(format!("{}/pose/tip", user_path))),
+            _ => None,
+        },
+        "vibration" => Some(Target::Haptic(format!("{}/output/haptic", user_path))),
+        mode @ ("button" | "trigger" | "joystick" | "trackpad") => {
+            let input = match (component, action_type) {
+                (None, ActionType::Vector2fInput) => "position",
+                (Some("click"), _) | (Some("value"), ActionType::BooleanInput) | (None, ActionType::BooleanInput) => "click",
+                (Some("touch"), _) => "touch",
+                (Some("value"), ActionType::FloatInput) | (None, ActionType::FloatInput) => "pull",
+                _ => return None,
+            };
+            Some(Target::Source {
+                path: format!("{}{}", user_path, subpath),
+                mode: mode.to_owned(),
+                input: input.to_owned(),
+            })
+        }
+        _ => None,
+    }
+}
+
+///Builds a SteamVR action manifest and binding files from an application's actions and suggested bindings
+pub fn steamvr(info: &XrApplicationInfo, catalog: &Root) -> SteamVrExport {
+    let mut manifest = ActionManifest {
+        actions: Vec::new(),
+        action_sets: Vec::new(),
+        default_bindings: Vec::new(),
+        localization: Vec::new(),
+    };
+    let mut localization = BTreeMap::new();
+    localization.insert(String::from("language_tag"), String::from("en_US"));
+    let mut binding_files = BTreeMap::<String, BindingFile>::new();
+    let mut skipped = Vec::new();
+
+    for (set_name, set_info) in &info.action_sets {
+        let set_path = format!("/actions/{}", set_name);
+        manifest.action_sets.push(ManifestActionSet {
+            name: set_path.clone(),
+            usage: String::from("leftright"),
+        });
+        localization.insert(set_path.clone(), set_info.localized_name.clone());
+
+        for (name, action_info) in &set_info.actions {
+            let ty = match steamvr_action_type(action_info.action_type) {
+                Some(ty) => ty,
+                None => continue,
+            };
+            let output = action_name(set_name, name, action_info.action_type);
+            manifest.actions.push(ManifestAction {
+                name: output.clone(),
+                ty: ty.to_owned(),
+            });
+            localization.insert(output.clone(), action_info.localized_name.clone());
+
+            for (profile, bindings) in &action_info.suggested_bindings {
+                let controller_type = match steamvr_controller_type(profile) {
+                    Some(controller_type) => controller_type,
+                    None => continue,
+                };
+                let set_bindings = binding_files
+                    .entry(format!("bindings_{}.json", controller_type))
+                    .or_insert_with(|| BindingFile {
+                        controller_type: controller_type.to_owned(),
+                        bindings: BTreeMap::new(),
+                    })
+                    .bindings
+                    .entry(set_path.clone())
+                    .or_default();
+
+                for binding in bindings {
+                    match target(catalog, profile, &binding.path, action_info.action_type) {
+                        Some(Target::Source { path, mode, input }) => {
+                            let output = Output { output: output.clone() };
+                            match set_bindings.sources.iter_mut().find(|source| source.path == path && source.mode == mode) {
+                                Some(source) => {
+                                    source.inputs.insert(input, output);
+                                }
+                                None => set_bindings.sources.push(Source {
+                                    path,
+                                    mode,
+                                    inputs: vec![(input, output)].into_iter().collect(),
+                                }),
+                            }
+                        }
+                        Some(Target::Pose(path)) => set_bindings.poses.push(PathOutput { output: output.clone(), path }),
+                        Some(Target::Haptic(path)) => set_bindings.haptics.push(PathOutput { output: output.clone(), path }),
+                        None => skipped.push(format!("{}: {}", profile, binding.path)),
+                    }
+                }
+            }
+        }
+    }
+
+    manifest.localization.push(localization);
+    for (binding_url, binding_file) in &binding_files {
+        manifest.default_bindings.push(DefaultBinding {
+            controller_type: binding_file.controller_type.clone(),
+            binding_url: binding_url.clone(),
+        });
+    }
+
+    SteamVrExport {
+        manifest,
+        binding_files,
+        skipped,
+    }
+}
+
+#[test]
+fn test_steamvr_index() {
+    use serde_json::json;
+
+    use crate::interaction_profiles::generate;
+    use crate::xrapplication_info::{ActionInfo, ActionSetInfo, BindingInfo};
+
+    let index = "/interaction_profiles/valve/index_controller";
+    let action = |localized_name: &str, action_type, bindings: &[&str]| ActionInfo {
+        localized_name: localized_name.to_owned(),
+        action_type,
+        subaction_paths: Vec::new(),
+        suggested_bindings: vec![(
+            index.to_owned(),
+            bindings
+                .iter()
+                .map(|path| BindingInfo {
+                    path: path.to_string(),
+                    localized_name: None,
+                })
+                .collect(),
+        )]
+        .into_iter()
+        .collect(),
+    };
+    let mut info = XrApplicationInfo::from_name("app");
+    info.action_sets.insert(
+        String::from("gameplay"),
+        ActionSetInfo {
+            localized_name: String::from("Gameplay"),
+            actions: vec![
+                (String::from("fire"), action("Fire", ActionType::BooleanInput, &["/user/hand/right/input/trigger/value"])),
+                (String::from("squeeze"), action("Squeeze", ActionType::FloatInput, &["/user/hand/right/input/trigger/value"])),
+                (String::from("move"), action("Move", ActionType::Vector2fInput, &["/user/hand/left/input/thumbstick"])),
+                (String::from("strafe"), action("Strafe", ActionType::FloatInput, &["/user/hand/left/input/thumbstick/x"])),
+                (String::from("hand"), action("Hand", ActionType::PoseInput, &["/user/hand/left/input/grip/pose"])),
+                (String::from("buzz"), action("Buzz", ActionType::VibrationOutput, &["/user/hand/left/output/haptic"])),
+            ]
+            .into_iter()
+            .collect(),
+        },
+    );
+
+    let export = steamvr(&info, &generate());
+
+    assert_eq!(
+        serde_json::to_value(&export.manifest.default_bindings).unwrap(),
+        json!([{ "controller_type": "knuckles", "binding_url": "bindings_knuckles.json" }])
+    );
+    assert!(export.manifest.actions.contains(&ManifestAction {
+        name: String::from("/actions/gameplay/in/move"),
+        ty: String::from("vector2"),
+    }));
+    assert!(export.manifest.actions.contains(&ManifestAction {
+        name: String::from("/actions/gameplay/out/buzz"),
+        ty: String::from("vibration"),
+    }));
+    assert_eq!(export.manifest.localization[0]["/actions/gameplay/in/fire"], "Fire");
+
+    assert_eq!(
+        serde_json::to_value(&export.binding_files["bindings_knuckles.json"]).unwrap(),
+        json!({
+            "controller_type": "knuckles",
+            "bindings": {
+                "/actions/gameplay": {
+                    "sources": [
+                        {
+                            "path": "/user/hand/right/input/trigger",
+                            "mode": "trigger",
+                            "inputs": {
+                                "click": { "output": "/actions/gameplay/in/fire" },
+                                "pull": { "output": "/actions/gameplay/in/squeeze" }
+                            }
+                        },
+                        {
+                            "path": "/user/hand/left/input/thumbstick",
+                            "mode": "joystick",
+                            "inputs": { "position": { "output": "/actions/gameplay/in/move" } }
+                        }
+                    ],
+                    "poses": [{ "output": "/actions/gameplay/in/hand", "path": "/user/hand/left/pose/raw" }],
+                    "haptics": [{ "output": "/actions/gameplay/out/buzz", "path": "/user/hand/left/output/haptic" }]
+                }
+            }
+        })
+    );
+    //SteamVR can't bind a single thumbstick axis
+    assert_eq!(export.skipped, vec![format!("{}: /user/hand/left/input/thumbstick/x", index)]);
+}
@@ -1,19 +1,67 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
 
 use serde::{Deserialize, Serialize};
 
+use crate::application_bindings::Transform;
 use crate::xrapplication_info::ActionType;
 
+///A catalog of interaction profiles
+///
+///Profiles are behind [Arc]s so a catalog, or any one of its profiles, can be handed out without copying it
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Root {
-    pub profiles: HashMap<String, InteractionProfile>,
+    pub profiles: BTreeMap<String, Arc<InteractionProfile>>,
+}
+
+///The top level user paths the OpenXR spec defines, the only ones a profile's subaction paths can be
+pub const TOP_LEVEL_USER_PATHS: &[&str] = &["/user/hand/left", "/user/hand/right", "/user/head", "/user/gamepad", "/user/treadmill"];
+
+///How a top level user path is shown to users, e.g. "Left Hand" for /user/hand/left
+pub fn localized_user_path(user_path: &str) -> Option<&'static str> {
+    match user_path {
+        "/user/hand/left" => Some("Left Hand"),
+        "/user/hand/right" => Some("Right Hand"),
+        "/user/head" => Some("Head"),
+        "/user/gamepad" => Some("Gamepad"),
+        "/user/treadmill" => Some("Treadmill"),
+        _ => None,
+    }
+}
+
+impl Root {
+    ///Checks every profile only uses top level user paths the runtime knows, anything else fails xrStringToPath or xrCreateAction
+    pub fn validate(&self) -> Result<(), CatalogError> {
+        for (profile_name, profile) in &self.profiles {
+            if let Some(user_path) = profile
+                .subaction_paths
+                .iter()
+                .find(|user_path| !TOP_LEVEL_USER_PATHS.contains(&user_path.as_str()))
+            {
+                return Err(CatalogError::Invalid(format!(
+                    "{} has subaction path {} which isn't a top level user path",
+                    profile_name, user_path
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct InteractionProfile {
     pub title: String,
     pub subaction_paths: Vec<String>,
-    pub subpaths: HashMap<String, Subpath>,
+    pub subpaths: BTreeMap<String, Subpath>,
+    ///Transform parameters used for a component (e.g. /input/trigger/value) when a binding doesn't specify them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub defaults: BTreeMap<String, Transform>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -22,8 +70,48 @@ pub struct Subpath {
     pub localized_name: String,
     pub side: Option<String>,
     pub features: Vec<Feature>, 
+    ///The instance extension the runtime only exposes this subpath with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension: Option<String>,
+    ///Extensions individual features (keyed like `features`, e.g. "proximity_fb") need on top of `extension`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub feature_extensions: BTreeMap<String, String>,
 }
 
+impl Subpath {
+    ///Whether this subpath exists under a top level user path, subpaths with a side (e.g. the X button) only exist on that hand
+    pub fn exists_for(&self, user_path: &str) -> bool {
+        match &self.side {
+            Some(side) => user_path.ends_with(side.as_str()),
+            None => true,
+        }
+    }
+
+    ///Whether the runtime exposes this subpath given the instance's enabled extensions
+    pub fn is_enabled(&self, enabled_extensions: &[String]) -> bool {
+        match &self.extension {
+            Some(extension) => enabled_extensions.contains(extension),
+            None => true,
+        }
+    }
+
+    ///The extension a component (e.g. "proximity_fb") needs when the application didn't enable it
+    pub fn disabled_feature_extension(&self, component: &str, enabled_extensions: &[String]) -> Option<&String> {
+        self.feature_extensions
+            .get(component)
+            .filter(|extension| !enabled_extensions.contains(extension))
+    }
+}
+
+///Extension providing the /input/grip_surface pose
+pub const PALM_POSE_EXTENSION: &str = "XR_EXT_palm_pose";
+
+///Extension providing the proximity sensors of Touch controllers
+pub const TOUCH_PROXIMITY_EXTENSION: &str = "XR_FB_touch_controller_proximity";
+
+///A flight stick with a twist sensor, for runtimes and drivers that expose HOTAS sticks as their own profile
+pub const FLIGHT_STICK_PROFILE: &str = "/interaction_profiles/oxidexr/flight_stick";
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Feature {
     ///click - A physical switch has been pressed by the user. This is valid for all buttons, and is common for trackpads, thumbsticks, triggers, and dpads. "click" components are always boolean.
@@ -42,6 +130,8 @@ pub enum Feature {
     Pose,
     ///haptic - A haptic element like an LRA (Linear Resonant Actuator) or vibration motor
     Haptic,
+    ///proximity_fb - How close a finger is to the input, from XR_FB_touch_controller_proximity. Read as a 1D scalar from 0 (far) to 1 (touching) so it can be thresholded like a trigger
+    Proximity,
     ///TODO https://www.khronos.org/registry/OpenXR/specs/1.0/html/xrspec.html#_adding_input_sources_via_extensions
     Unknown(String),
 }
@@ -77,6 +167,7 @@ impl<'de> Deserialize<'de> for Feature {
 }
 
 impl Feature {
+    #[allow(clippy::should_implement_trait)] //Every string is a feature, unknown ones included, so it can't fail the way FromStr can
     pub fn from_str(string: &str) -> Feature {
         match string {
             "click" => Feature::Click,
@@ -87,11 +178,12 @@ impl Feature {
             "twist" => Feature::Twist,
             "pose" => Feature::Pose,
             "haptic" => Feature::Haptic,
+            "proximity_fb" => Feature::Proximity,
             _ => Feature::Unknown(String::from(string))
         }
     }
 
-    pub fn to_str<'a>(&'a self) -> &'a str {
+    pub fn to_str(&self) -> &str {
         match self {
             Feature::Click => "click",
             Feature::Touch => "touch",
@@ -101,6 +193,7 @@ impl Feature {
             Feature::Twist => "twist",
             Feature::Pose => "pose",
             Feature::Haptic => "haptic",
+            Feature::Proximity => "proximity_fb",
             Feature::Unknown(str) => str,
         }
     }
@@ -108,7 +201,7 @@ impl Feature {
     pub fn get_type(&self) -> ActionType {
         match self {
             Feature::Click | Feature::Touch => ActionType::BooleanInput,
-            Feature::Force | Feature::Value | Feature::Twist => ActionType::FloatInput,
+            Feature::Force | Feature::Value | Feature::Twist | Feature::Proximity => ActionType::FloatInput,
             Feature::Position => ActionType::Vector2fInput,
             Feature::Pose => ActionType::PoseInput,
             Feature::Haptic => ActionType::VibrationOutput,
@@ -117,6 +210,191 @@ impl Feature {
     }
 }
 
+impl InteractionProfile {
+    ///This profile without the subpaths and features of extensions that aren't enabled
+    pub fn with_extensions(&self, enabled_extensions: &[String]) -> InteractionProfile {
+        let mut profile = self.clone();
+        profile.subpaths.retain(|_, subpath_info| subpath_info.is_enabled(enabled_extensions));
+        for subpath_info in profile.subpaths.values_mut() {
+            let feature_extensions = &subpath_info.feature_extensions;
+            subpath_info.features.retain(|feature| match feature_extensions.get(feature.to_str()) {
+                Some(extension) => enabled_extensions.contains(extension),
+                None => true,
+            });
+        }
+        profile
+    }
+
+    ///Whether an action of `action_type` may be suggested for `binding`, a full path including the top level user path
+    ///
+    ///Parent paths (e.g. /input/trigger) are accepted as the spec lets the runtime pick a component for them
+    pub fn accepts_binding(&self, binding: &str, action_type: ActionType) -> bool {
+        self.subaction_paths
+            .iter()
+            .filter(|user_path| binding.starts_with(user_path.as_str()))
+            .any(|user_path| {
+                let rest = &binding[user_path.len()..];
+                self.subpaths
+                    .iter()
+                    .filter(|(_, subpath_info)| subpath_info.exists_for(user_path))
+                    .any(|(subpath, subpath_info)| {
+                        rest == subpath
+                            || subpath_info.features.iter().any(|feature| {
+                                binding_path(subpath, feature, action_type)
+                                    .iter()
+                                    .any(|path| path == rest)
+                            })
+                    })
+            })
+    }
+
+    ///The action type that naturally fits `binding`, a full path including the top level user path
+    ///
+    ///Parent paths get the type of the component a runtime would pick for them: the 2D position, then value, then click
+    pub fn action_type_for_binding(&self, binding: &str) -> Option<ActionType> {
+        let (rest, subpath, subpath_info) = self.find_subpath(binding)?;
+
+        if rest == subpath {
+            return [Feature::Position, Feature::Value, Feature::Click, Feature::Pose, Feature::Haptic]
+                .iter()
+                .chain(subpath_info.features.iter())
+                .find(|feature| subpath_info.features.contains(feature))
+                .map(Feature::get_type);
+        }
+
+        let component = &rest[subpath.len() + 1..];
+        subpath_info.features.iter().find_map(|feature| match (feature, component) {
+            (Feature::Position, "x") | (Feature::Position, "y") => Some(ActionType::FloatInput),
+            (Feature::Position, _) => None,
+            (feature, component) if feature.to_str() == component => Some(feature.get_type()),
+            _ => None,
+        })
+    }
+
+    ///The catalog's name for the subpath `binding` belongs to, e.g. "Trigger" for /user/hand/left/input/trigger/value
+    pub fn localized_name_for_binding(&self, binding: &str) -> Option<&str> {
+        self.find_subpath(binding)
+            .map(|(_, _, subpath_info)| subpath_info.localized_name.as_str())
+    }
+
+    ///Splits a full binding path into what follows the top level user path and the subpath that covers it
+    pub(crate) fn find_subpath<'a, 'b>(&'a self, binding: &'b str) -> Option<(&'b str, &'a String, &'a Subpath)> {
+        let user_path = self
+            .subaction_paths
+            .iter()
+            .find(|user_path| binding.starts_with(&format!("{}/", user_path)))?;
+        let rest = &binding[user_path.len()..];
+
+        let (subpath, subpath_info) = self
+            .subpaths
+            .iter()
+            .filter(|(_, subpath_info)| subpath_info.exists_for(user_path))
+            .find(|(subpath, _)| rest == subpath.as_str() || rest.starts_with(&format!("{}/", subpath)))?;
+        Some((rest, subpath, subpath_info))
+    }
+}
+
+///The action type expected for a full binding path (e.g. /user/hand/left/input/trigger/click) under an interaction profile of the current catalog
+pub fn action_type_for_binding(profile: &str, binding: &str) -> Option<ActionType> {
+    catalog().profiles.get(profile)?.action_type_for_binding(binding)
+}
+
+#[test]
+fn test_action_type_for_binding() {
+    //The catalog itself is swapped out by test_reload_catalog so the built in profile is checked directly
+    let index = &generate().profiles["/interaction_profiles/valve/index_controller"];
+    assert_eq!(index.action_type_for_binding("/user/hand/left/input/trigger/click"), Some(ActionType::BooleanInput));
+    assert_eq!(index.action_type_for_binding("/user/hand/left/input/trigger/value"), Some(ActionType::FloatInput));
+    assert_eq!(index.action_type_for_binding("/user/hand/left/input/trigger"), Some(ActionType::FloatInput));
+    assert_eq!(index.action_type_for_binding("/user/hand/left/input/thumbstick"), Some(ActionType::Vector2fInput));
+    assert_eq!(index.action_type_for_binding("/user/hand/left/input/thumbstick/y"), Some(ActionType::FloatInput));
+    assert_eq!(index.action_type_for_binding("/user/hand/left/input/grip/pose"), Some(ActionType::PoseInput));
+    assert_eq!(index.action_type_for_binding("/user/hand/left/input/trigger/pose"), None);
+    assert_eq!(index.action_type_for_binding("/user/head/input/trigger/value"), None);
+    assert_eq!(action_type_for_binding("/interaction_profiles/unknown", "/user/hand/left/input/trigger/value"), None);
+}
+
+///The paths relative to the top level user path an action of `action_type` can bind to for a feature of a subpath
+///
+///A position binds vector actions to the bare subpath (/input/thumbstick) and scalar actions to its x and y components (/input/thumbstick/x)
+pub fn binding_path(subpath: &str, feature: &Feature, action_type: ActionType) -> Vec<String> {
+    match feature {
+        Feature::Position => match action_type {
+            ActionType::Vector2fInput => vec![subpath.to_owned()],
+            _ if action_type.is_primitive() => vec![format!("{}/x", subpath), format!("{}/y", subpath)],
+            _ => Vec::new(),
+        },
+        Feature::Haptic if action_type == ActionType::VibrationOutput => vec![subpath.to_owned()],
+        Feature::Pose if action_type == ActionType::PoseInput => vec![format!("{}/pose", subpath)],
+        Feature::Haptic | Feature::Pose => Vec::new(),
+        _ if action_type.is_primitive() => vec![format!("{}/{}", subpath, feature.to_str())],
+        _ => Vec::new(),
+    }
+}
+
+#[test]
+fn test_binding_path() {
+    assert_eq!(binding_path("/input/thumbstick", &Feature::Position, ActionType::Vector2fInput), vec!["/input/thumbstick"]);
+    assert_eq!(binding_path("/input/thumbstick", &Feature::Position, ActionType::FloatInput), vec!["/input/thumbstick/x", "/input/thumbstick/y"]);
+
+    let index = &generate().profiles["/interaction_profiles/valve/index_controller"];
+    assert!(index.accepts_binding("/user/hand/left/input/thumbstick", ActionType::Vector2fInput));
+    assert!(index.accepts_binding("/user/hand/left/input/thumbstick/x", ActionType::FloatInput));
+    assert!(!index.accepts_binding("/user/hand/left/input/thumbstick/x", ActionType::Vector2fInput));
+    assert!(!index.accepts_binding("/user/hand/left/input/thumbstick/z", ActionType::FloatInput));
+    assert!(!index.accepts_binding("/user/head/input/thumbstick", ActionType::Vector2fInput));
+
+    //A firm squeeze can be bound on its own, apart from how far the squeeze travels
+    assert_eq!(binding_path("/input/squeeze", &Feature::Force, ActionType::BooleanInput), vec!["/input/squeeze/force"]);
+    assert!(index.accepts_binding("/user/hand/right/input/squeeze/force", ActionType::BooleanInput));
+    assert_eq!(index.action_type_for_binding("/user/hand/right/input/squeeze/force"), Some(ActionType::FloatInput));
+
+    //A finger resting on the trigger is its own boolean, apart from pulling it
+    assert_eq!(binding_path("/input/trigger", &Feature::Touch, ActionType::BooleanInput), vec!["/input/trigger/touch"]);
+    assert!(index.accepts_binding("/user/hand/left/input/trigger/touch", ActionType::BooleanInput));
+    assert_eq!(index.action_type_for_binding("/user/hand/left/input/trigger/touch"), Some(ActionType::BooleanInput));
+    assert_eq!(index.action_type_for_binding("/user/hand/left/input/trigger/click"), Some(ActionType::BooleanInput));
+    let vive = &generate().profiles["/interaction_profiles/htc/vive_controller"];
+    assert!(!vive.accepts_binding("/user/hand/left/input/trigger/touch", ActionType::BooleanInput));
+}
+
+#[test]
+fn test_palm_pose() {
+    let subpath = serde_json::from_str::<Subpath>(
+        r#"{"type": "pose", "localized_name": "Grip Surface", "side": null, "features": ["pose"], "extension": "XR_EXT_palm_pose"}"#,
+    )
+    .unwrap();
+    assert_eq!(subpath.extension.as_deref(), Some(PALM_POSE_EXTENSION));
+    assert!(!subpath.is_enabled(&[]));
+    assert!(subpath.is_enabled(&[PALM_POSE_EXTENSION.to_owned()]));
+
+    let index = &generate().profiles["/interaction_profiles/valve/index_controller"];
+    assert!(index.accepts_binding("/user/hand/left/input/grip_surface/pose", ActionType::PoseInput));
+    assert!(!index.with_extensions(&[]).accepts_binding("/user/hand/left/input/grip_surface/pose", ActionType::PoseInput));
+    assert!(!generate().profiles["/interaction_profiles/htc/vive_pro"].subpaths.contains_key("/input/grip_surface"));
+}
+
+#[test]
+fn test_touch_proximity() {
+    let touch = &generate().profiles["/interaction_profiles/oculus/touch_controller"];
+    let proximity = [TOUCH_PROXIMITY_EXTENSION.to_owned()];
+    assert_eq!(touch.action_type_for_binding("/user/hand/right/input/trigger/proximity_fb"), Some(ActionType::FloatInput));
+    assert_eq!(touch.action_type_for_binding("/user/hand/right/input/thumb_fb"), Some(ActionType::FloatInput));
+    //The trigger itself still reads its value
+    assert_eq!(touch.action_type_for_binding("/user/hand/right/input/trigger"), Some(ActionType::FloatInput));
+    assert!(touch.with_extensions(&proximity).accepts_binding("/user/hand/right/input/trigger/proximity_fb", ActionType::BooleanInput));
+    assert!(touch.with_extensions(&proximity).accepts_binding("/user/hand/left/input/thumb_fb/proximity_fb", ActionType::FloatInput));
+
+    let without = touch.with_extensions(&[]);
+    assert!(!without.accepts_binding("/user/hand/right/input/trigger/proximity_fb", ActionType::FloatInput));
+    assert!(!without.accepts_binding("/user/hand/left/input/thumb_fb/proximity_fb", ActionType::FloatInput));
+    assert!(without.accepts_binding("/user/hand/right/input/trigger/value", ActionType::FloatInput));
+    assert_eq!(
+        touch.subpaths["/input/trigger"].disabled_feature_extension("proximity_fb", &[]),
+        Some(&TOUCH_PROXIMITY_EXTENSION.to_owned())
+    );
+}
+
 #[test]
 fn test() {
     let root = generate();
@@ -124,16 +402,139 @@ fn test() {
     println!("{}", Feature::Click == Feature::Click);
 }
 
+///Profile authors can place a catalog here to use instead of the built in one, see [reload_catalog]
+pub const CATALOG_FILE: &str = concat!(crate::config_dir!(), "interaction_profiles.json");
+
+static BUILTIN: Lazy<Arc<Root>> = Lazy::new(|| Arc::new(generate()));
+
+static CATALOG: Lazy<RwLock<Arc<Root>>> = Lazy::new(|| {
+    RwLock::new(match load_catalog(Path::new(CATALOG_FILE)) {
+        Ok(root) => root,
+        Err(why) => {
+            println!("couldn't load {}: {}, using the built in catalog", CATALOG_FILE, why);
+            builtin()
+        }
+    })
+});
+
+#[derive(Debug)]
+pub enum CatalogError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    ///Parsed, but would fail once the layer hands it to the runtime
+    Invalid(String),
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::Io(why) => write!(f, "couldn't read catalog: {}", why),
+            CatalogError::Parse(why) => write!(f, "couldn't parse catalog: {}", why),
+            CatalogError::Invalid(why) => write!(f, "invalid catalog: {}", why),
+        }
+    }
+}
+
+///Reads a catalog file, a missing file means the built in catalog
+fn load_catalog(path: &Path) -> Result<Arc<Root>, CatalogError> {
+    if !path.exists() {
+        return Ok(builtin());
+    }
+    let json = fs::read_to_string(path).map_err(CatalogError::Io)?;
+    let root: Root = serde_json::from_str(&json).map_err(CatalogError::Parse)?;
+    root.validate()?;
+    Ok(Arc::new(root))
+}
+
+///The built in catalog, parsed once and shared. [generate] builds a fresh copy
+pub fn builtin() -> Arc<Root> {
+    BUILTIN.clone()
+}
+
+///The interaction profile catalog currently in use
+///
+///Every caller shares one allocation until the catalog is reloaded, so this is cheap to call repeatedly
+pub fn catalog() -> Arc<Root> {
+    CATALOG.read().unwrap().clone()
+}
+
+///A profile of the current catalog
+pub fn profile(profile_name: &str) -> Option<Arc<InteractionProfile>> {
+    catalog().profiles.get(profile_name).cloned()
+}
+
+///Re-reads [CATALOG_FILE] so profile changes are picked up without a restart
+///
+///A broken file leaves the previous catalog in place. Anything built from an earlier catalog (e.g. god action sets) keeps using it
+pub fn reload_catalog() -> Result<(), CatalogError> {
+    reload_catalog_from(Path::new(CATALOG_FILE))
+}
+
+///Readers holding the previous catalog keep it, the new one is swapped in whole
+pub fn reload_catalog_from(path: &Path) -> Result<(), CatalogError> {
+    let root = load_catalog(path)?;
+    *CATALOG.write().unwrap() = root;
+    Ok(())
+}
+
+///The titles of every profile in the current catalog, sorted
+pub fn profile_titles() -> Vec<String> {
+    let mut titles = catalog()
+        .profiles
+        .values()
+        .map(|profile| profile.title.clone())
+        .collect::<Vec<_>>();
+    titles.sort();
+    titles
+}
+
+#[test]
+fn test_validate_catalog() {
+    assert!(generate().validate().is_ok());
+
+    let root: Root = serde_json::from_str(
+        r#"{"profiles": {"/interaction_profiles/test/controller": {"title": "Test Controller", "subaction_paths": ["/user/hand/left", "/user/hand/middle"], "subpaths": {}}}}"#,
+    )
+    .unwrap();
+    let error = root.validate().unwrap_err();
+    assert!(matches!(error, CatalogError::Invalid(_)));
+    assert_eq!(
+        error.to_string(),
+        "invalid catalog: /interaction_profiles/test/controller has subaction path /user/hand/middle which isn't a top level user path"
+    );
+}
+
+#[test]
+fn test_catalog_shared() {
+    assert!(Arc::ptr_eq(&builtin(), &builtin()));
+    //Going back to the built in catalog doesn't parse it again
+    let missing = std::env::temp_dir().join(format!("oxidexr_no_catalog_{}.json", std::process::id()));
+    assert!(Arc::ptr_eq(&load_catalog(&missing).unwrap(), &builtin()));
+    let index = "/interaction_profiles/valve/index_controller";
+    assert!(Arc::ptr_eq(&builtin().profiles[index], &builtin().profiles[index]));
+    //Cloning a catalog shares its profiles rather than copying them
+    let copy = (*builtin()).clone();
+    assert!(Arc::ptr_eq(&copy.profiles[index], &builtin().profiles[index]));
+}
+
 pub fn generate() -> Root {
     //TODO replace with better approach
     //TODO deal with system components sometimes not existing
+    let mut root: Root = serde_json::from_str(BUILTIN_CATALOG).unwrap();
+
+    add_palm_pose(&mut root);
+    add_touch_proximity(&mut root);
+    add_flight_stick(&mut root);
+    root
+}
 
-    //JSON license:
-    //Copyright 2020-2021, Collabora, Ltd.
-    //
-    //SPDX-License-Identifier: BSL-1.0
+//JSON license:
+//Copyright 2020-2021, Collabora, Ltd.
+//
+//SPDX-License-Identifier: BSL-1.0
 
-    return serde_json::from_str(r#"{
+///The built in catalog before extension subpaths are added, `monado_bindings` are kept for reference and ignored when parsed
+const BUILTIN_CATALOG: &str = r#"{
         "profiles": {
             "/interaction_profiles/khr/simple_controller": {
                 "title": "Khronos Simple Controller",
@@ -585,7 +986,7 @@ pub fn generate() -> Root {
                         "localized_name": "Right Haptic",
                         "features": ["haptic"],
                         "monado_bindings": {
-                            "haptic": "XRT_OUTPUT_NAME_XBOX_HAPTIC_RIGHTT"
+                            "haptic": "XRT_OUTPUT_NAME_XBOX_HAPTIC_RIGHT"
                         }
                     },
                     "/output/haptic_left_trigger": {
@@ -806,6 +1207,11 @@ pub fn generate() -> Root {
                     "/user/hand/left",
                     "/user/hand/right"
                 ],
+                "defaults": {
+                    "/input/trigger/value": {
+                        "threshold": { "on_threshold": 0.7, "off_threshold": 0.6 }
+                    }
+                },
                 "subpaths": {
                     "/input/system": {
                         "type": "button",
@@ -1149,5 +1555,129 @@ pub fn generate() -> Root {
             }
         }
     }
-    "#).unwrap();
-}
\ No newline at end of file
+    "#;
+
+///The palm pose of XR_EXT_palm_pose, available on every controller with a grip pose
+fn add_palm_pose(root: &mut Root) {
+    for profile in root.profiles.values_mut().map(Arc::make_mut) {
+        if profile.subpaths.contains_key("/input/grip") {
+            profile.subpaths.insert(
+                "/input/grip_surface".to_owned(),
+                Subpath {
+                    r#type: "pose".to_owned(),
+                    localized_name: "Grip Surface".to_owned(),
+                    side: None,
+                    features: vec![Feature::Pose],
+                    extension: Some(PALM_POSE_EXTENSION.to_owned()),
+                    feature_extensions: BTreeMap::new(),
+                },
+            );
+        }
+    }
+}
+
+///The trigger and thumb proximity sensors of XR_FB_touch_controller_proximity
+fn add_touch_proximity(root: &mut Root) {
+    let profile = match root.profiles.get_mut("/interaction_profiles/oculus/touch_controller") {
+        Some(profile) => Arc::make_mut(profile),
+        None => return,
+    };
+    if let Some(trigger) = profile.subpaths.get_mut("/input/trigger") {
+        trigger.features.push(Feature::Proximity);
+        trigger
+            .feature_extensions
+            .insert(Feature::Proximity.to_str().to_owned(), TOUCH_PROXIMITY_EXTENSION.to_owned());
+    }
+    profile.subpaths.insert(
+        "/input/thumb_fb".to_owned(),
+        Subpath {
+            r#type: "proximity".to_owned(),
+            localized_name: "Thumb".to_owned(),
+            side: None,
+            features: vec![Feature::Proximity],
+            extension: Some(TOUCH_PROXIMITY_EXTENSION.to_owned()),
+            feature_extensions: BTreeMap::new(),
+        },
+    );
+}
+
+///A stick that twists about its own axis, along with the throttle and buttons HOTAS sets usually have
+fn add_flight_stick(root: &mut Root) {
+    let subpath = |r#type: &str, localized_name: &str, features: Vec<Feature>| Subpath {
+        r#type: r#type.to_owned(),
+        localized_name: localized_name.to_owned(),
+        side: None,
+        features,
+        extension: None,
+        feature_extensions: BTreeMap::new(),
+    };
+    let subpaths = vec![
+        ("/input/joystick", subpath("joystick", "Joystick", vec![Feature::Position, Feature::Twist])),
+        ("/input/throttle", subpath("trigger", "Throttle", vec![Feature::Value])),
+        ("/input/trigger", subpath("button", "Trigger", vec![Feature::Click])),
+        ("/input/a", subpath("button", "A", vec![Feature::Click])),
+        ("/input/b", subpath("button", "B", vec![Feature::Click])),
+    ];
+    root.profiles.insert(
+        FLIGHT_STICK_PROFILE.to_owned(),
+        Arc::new(InteractionProfile {
+            title: "Flight Stick".to_owned(),
+            subaction_paths: vec!["/user/gamepad".to_owned()],
+            subpaths: subpaths.into_iter().map(|(name, subpath)| (name.to_owned(), subpath)).collect(),
+            defaults: BTreeMap::new(),
+        }),
+    );
+}
+
+#[test]
+fn test_twist() {
+    let subpath: Subpath = serde_json::from_str(
+        r#"{"type": "joystick", "localized_name": "Joystick", "side": null, "features": ["position", "twist"]}"#,
+    )
+    .unwrap();
+    assert_eq!(subpath.features, vec![Feature::Position, Feature::Twist]);
+    assert_eq!(Feature::Twist.get_type(), ActionType::FloatInput);
+
+    let stick = &generate().profiles[FLIGHT_STICK_PROFILE];
+    assert_eq!(stick.subpaths["/input/joystick"].features, subpath.features);
+    assert_eq!(stick.action_type_for_binding("/user/gamepad/input/joystick/twist"), Some(ActionType::FloatInput));
+    assert!(stick.accepts_binding("/user/gamepad/input/joystick/twist", ActionType::FloatInput));
+    assert!(!stick.accepts_binding("/user/gamepad/input/throttle/twist", ActionType::FloatInput));
+}
+
+#[test]
+fn test_monado_bindings() {
+    //One tracked pose serves both grip and aim on these
+    const SHARED: &[(&str, &str)] = &[("/interaction_profiles/google/daydream_controller", "XRT_INPUT_DAYDREAM_POSE")];
+
+    let root = serde_json::from_str::<serde_json::Value>(BUILTIN_CATALOG).unwrap();
+    let mut offenders = Vec::new();
+    for (profile_name, profile) in root["profiles"].as_object().unwrap() {
+        let mut seen = BTreeMap::<&str, Vec<&str>>::new();
+        for (subpath, subpath_info) in profile["subpaths"].as_object().unwrap() {
+            for constant in subpath_info["monado_bindings"].as_object().into_iter().flat_map(|bindings| bindings.values()) {
+                seen.entry(constant.as_str().unwrap()).or_default().push(subpath);
+            }
+        }
+        for (constant, subpaths) in &seen {
+            let named_well = ["XRT_INPUT_", "XRT_OUTPUT_NAME_"].iter().any(|prefix| {
+                constant.strip_prefix(prefix).is_some_and(|rest| {
+                    !rest.is_empty() && rest.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+                })
+            });
+            if !named_well {
+                offenders.push(format!("{} {}: {} isn't XRT_INPUT_* or XRT_OUTPUT_NAME_*", profile_name, subpaths[0], constant));
+            }
+            if subpaths.len() > 1 && !SHARED.contains(&(profile_name.as_str(), *constant)) {
+                offenders.push(format!("{}: {} is used by {}", profile_name, constant, subpaths.join(", ")));
+            }
+            //Typos like XBOX_HAPTIC_RIGHTT show up as a side without its mirror
+            for (side, mirror) in [("_LEFT", "_RIGHT"), ("_RIGHT", "_LEFT")] {
+                if constant.contains(side) && !seen.contains_key(constant.replace(side, mirror).as_str()) {
+                    offenders.push(format!("{} {}: {} has no {} counterpart", profile_name, subpaths[0], constant, mirror));
+                }
+            }
+        }
+    }
+    assert!(offenders.is_empty(), "bad monado_bindings:\n{}", offenders.join("\n"));
+}
@@ -1,4 +1,6 @@
 pub mod serial;
 pub mod xrapplication_info;
 pub mod application_bindings;
-pub mod interaction_profiles;
\ No newline at end of file
+pub mod interaction_profiles;
+pub mod config;
+pub mod export;
@@ -1,21 +1,26 @@
-use std::{collections::HashMap, fs, path::Path};
+use std::{collections::BTreeMap, fs, io, path::Path};
 
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
-pub const CONFIG_DIR: &'static str = "xrconfig/";
-pub const APPLICATIONS: &'static str = "xrconfig/applications.json";
+///[CONFIG_DIR] as a literal, so the paths of the files in it can be built with `concat!`
+#[macro_export]
+macro_rules! config_dir {
+    () => {
+        "xrconfig/"
+    };
+}
+
+pub const CONFIG_DIR: &str = config_dir!();
+pub const APPLICATIONS: &str = "xrconfig/applications.json";
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Applications {
     #[serde(flatten)]
-    pub map: HashMap<String, String>
+    pub map: BTreeMap<String, String>
 }
 
 pub fn get_uuid(application_name: &str) -> String {
-    let mut applications = match read_json::<Applications>(APPLICATIONS) {
-        Some(applications) => applications,
-        None => Applications::default(),
-    };
+    let mut applications = read_json::<Applications>(APPLICATIONS).unwrap_or_default();
 
     match applications.map.get(application_name) {
         Some(id) => id.clone(),
@@ -38,7 +43,7 @@ pub fn read_json<T>(path_str: &str) -> Option<T> where T: DeserializeOwned {
     let display = path.display();
 
     if path.exists() {
-        let file = match fs::read_to_string(&path) {
+        let file = match fs::read_to_string(path) {
             Err(why) => panic!("couldn't read {}: {}", display, why),
             Ok(file) => file,
         };
@@ -52,6 +57,7 @@ pub fn read_json<T>(path_str: &str) -> Option<T> where T: DeserializeOwned {
     }
 }
 
+#[allow(unused_variables)] //Writing is disabled for now
 pub fn write_json<T>(value: &T, path: &Path) where T: Serialize {
     // let display = path.display();
 
@@ -67,4 +73,27 @@ pub fn write_json<T>(value: &T, path: &Path) where T: Serialize {
     //     },
     //     Err(why) => panic!("couldn't write to {}: {}", display, why),
     // }
-}
\ No newline at end of file
+}
+
+///Writes `value` next to `path` first and renames it over `path`, so readers only ever see a complete file
+pub fn write_json_atomic<T>(value: &T, path: &Path) -> io::Result<()> where T: Serialize {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(value).map_err(|why| io::Error::new(io::ErrorKind::InvalidData, why))?;
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    fs::write(&temp_path, json)?;
+    fs::rename(&temp_path, path)
+}
+
+#[test]
+fn test_write_json_atomic() {
+    let dir = std::env::temp_dir().join(format!("oxidexr_atomic_{}", std::process::id()));
+    let path = dir.join("nested/value.json");
+    write_json_atomic(&vec![1, 2, 3], &path).unwrap();
+    write_json_atomic(&vec![4], &path).unwrap();
+    assert_eq!(serde_json::from_str::<Vec<i32>>(&fs::read_to_string(&path).unwrap()).unwrap(), vec![4]);
+    assert!(!dir.join("nested/value.json.tmp").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -1,18 +1,18 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct XrApplicationInfo {
     pub application_name: String,
-    pub action_sets: HashMap<String, ActionSetInfo>,
+    pub action_sets: BTreeMap<String, ActionSetInfo>,
 }
 
 impl XrApplicationInfo {
-    pub fn from_name(name: &String) -> XrApplicationInfo {
+    pub fn from_name(name: &str) -> XrApplicationInfo {
         XrApplicationInfo {
-            application_name: name.clone(),
-            action_sets: HashMap::new(),
+            application_name: name.to_owned(),
+            action_sets: BTreeMap::new(),
         }
     }
 }
@@ -20,7 +20,7 @@ impl XrApplicationInfo {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ActionSetInfo {
     pub localized_name: String,
-    pub actions: HashMap<String, ActionInfo>,
+    pub actions: BTreeMap<String, ActionInfo>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -29,6 +29,17 @@ pub struct ActionInfo {
     pub action_type: ActionType,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub subaction_paths: Vec<String>,
+    ///The application's suggested bindings for each interaction profile
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub suggested_bindings: BTreeMap<String, Vec<BindingInfo>>,
+}
+
+///A suggested binding along with what the interaction profile catalog calls its source, so tools can show "Trigger" next to the application's own names
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct BindingInfo {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub localized_name: Option<String>,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Deserialize, Serialize, Hash)]
@@ -69,17 +80,11 @@ impl ActionType {
     }
 
     pub fn is_primitive(&self) -> bool {
-        match self {
-            ActionType::BooleanInput | ActionType::FloatInput => true,
-            _ => false,
-        }
+        matches!(self, ActionType::BooleanInput | ActionType::FloatInput)
     }
 
     pub fn is_input(&self) -> bool {
-        match self {
-            ActionType::VibrationOutput | ActionType::Unknown => false,
-            _ => true,
-        }
+        !matches!(self, ActionType::VibrationOutput | ActionType::Unknown)
     }
 
     pub const fn all() -> [ActionType; 6] {
@@ -91,4 +96,31 @@ impl From<openxr::sys::ActionType> for ActionType {
     fn from(action_type: openxr::sys::ActionType) -> Self {
         Self::from_raw(action_type)
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_deterministic_json() {
+    let build = |names: &[&str]| {
+        let mut info = XrApplicationInfo::from_name("app");
+        for name in names {
+            let mut set = ActionSetInfo {
+                localized_name: name.to_string(),
+                actions: BTreeMap::new(),
+            };
+            for action in names {
+                set.actions.insert(action.to_string(), ActionInfo {
+                    localized_name: action.to_string(),
+                    action_type: ActionType::BooleanInput,
+                    subaction_paths: Vec::new(),
+                    suggested_bindings: BTreeMap::new(),
+                });
+            }
+            info.action_sets.insert(name.to_string(), set);
+        }
+        serde_json::to_string_pretty(&info).unwrap()
+    };
+
+    let json = build(&["gameplay", "menu", "driving"]);
+    assert_eq!(json, build(&["gameplay", "menu", "driving"]));
+    assert_eq!(json, build(&["driving", "gameplay", "menu"]));
+}
@@ -0,0 +1,33 @@
+//Swaps the process wide catalog, so it gets a test binary of its own rather than racing the unit tests that read it
+
+use std::fs;
+
+use common::interaction_profiles::{generate, profile_titles, reload_catalog_from};
+
+#[test]
+fn test_reload_catalog() {
+    let path = std::env::temp_dir().join(format!("oxidexr_catalog_{}.json", std::process::id()));
+    let write_catalog = |title: &str| {
+        let json = format!(
+            r#"{{"profiles": {{"/interaction_profiles/test/controller": {{"title": "{}", "subaction_paths": [], "subpaths": {{}}}}}}}}"#,
+            title
+        );
+        fs::write(&path, json).unwrap();
+    };
+
+    write_catalog("Test Controller");
+    reload_catalog_from(&path).unwrap();
+    assert_eq!(profile_titles(), vec!["Test Controller"]);
+
+    write_catalog("Renamed Controller");
+    reload_catalog_from(&path).unwrap();
+    assert_eq!(profile_titles(), vec!["Renamed Controller"]);
+
+    fs::write(&path, "{ broken").unwrap();
+    assert!(reload_catalog_from(&path).is_err());
+    assert_eq!(profile_titles(), vec!["Renamed Controller"]);
+
+    fs::remove_file(&path).unwrap();
+    reload_catalog_from(&path).unwrap();
+    assert_eq!(profile_titles().len(), generate().profiles.len());
+}
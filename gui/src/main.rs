@@ -112,7 +112,10 @@ impl Application for BindingsGUI {
                 let file_path = format!("{}{}/default_bindings.json", CONFIG_DIR, uuid);
                 let default_bindings = serial::read_json::<ApplicationBindings>(&file_path).unwrap();
 
-                let root = common::interaction_profiles::generate();
+                if let Err(why) = common::interaction_profiles::reload_catalog() {
+                    println!("{}, keeping the previous interaction profiles", why);
+                }
+                let root = common::interaction_profiles::catalog();
 
                 self.action_sets.clear();
                 for (set_name, set_info) in application_info.action_sets.iter() {
@@ -137,9 +140,9 @@ impl Application for BindingsGUI {
                     });
                 }
                 
-                self.interaction_profiles = root.profiles.into_iter()
+                self.interaction_profiles = root.profiles.iter()
                 .map(|(profile_name, profile_info)| {
-                    let profile_bindings = default_bindings.profiles.get(&profile_name);
+                    let profile_bindings = default_bindings.profiles.get(profile_name);
 
                     let child_action_sets = application_info.action_sets.iter()
                     .map(|(set_name, set_info)| {
@@ -157,8 +160,8 @@ impl Application for BindingsGUI {
                         (set_info.localized_name.clone(), ActionSetWidget(subaction_widgets, set_info.localized_name.clone()))
                     }).collect();
 
-                    (profile_info.title, InteractionProfileGUI {
-                        delocalized_name: profile_name,
+                    (profile_info.title.clone(), InteractionProfileGUI {
+                        delocalized_name: profile_name.clone(),
                         action_sets: child_action_sets
                     })    
                 }).collect();
@@ -0,0 +1,325 @@
+//! Host keyboard/gamepad input presented as an extra interaction profile so applications can be driven at a desk without controllers
+//!
+//! The emulated profile has the Xbox controller's layout and is bound through custom bindings like any other profile.
+//! Its god states are written from a [HostInput] on every xrSyncActions instead of being read from the runtime, on Linux the layer
+//! reads a gamepad through the joystick interface itself
+
+use std::collections::HashMap;
+use std::env;
+#[cfg(target_os = "linux")]
+use std::fs::File;
+#[cfg(target_os = "linux")]
+use std::io::{self, Read};
+#[cfg(target_os = "linux")]
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use common::interaction_profiles::{self, InteractionProfile};
+use once_cell::sync::Lazy;
+use openxr::sys as xr;
+
+use crate::god_actions::{GodActionStateEnum, InputBinding};
+
+///Set to anything to add the emulated profile to every activated application, a /dev path picks the joystick device read on Linux
+pub const EMULATE_VAR: &str = "OXIDEXR_EMULATE_INPUT";
+
+///The joystick device read when [EMULATE_VAR] doesn't name one
+#[cfg(target_os = "linux")]
+const DEFAULT_JOYSTICK: &str = "/dev/input/js0";
+
+///The profile custom bindings use to bind host input, e.g. /user/gamepad/input/a/click
+pub const EMULATED_PROFILE: &str = "/interaction_profiles/oxidexr/emulated_input";
+
+///The catalog profile whose subpaths the emulated profile copies
+pub const LAYOUT_PROFILE: &str = "/interaction_profiles/microsoft/xbox_controller";
+
+///Keyboard and gamepad input read from the host
+///
+///Keyboards map keys onto the gamepad's sources, the layer only ever sees the emulated profile
+pub trait HostInput: Send {
+    ///The current value of every source the host has input for, keyed by source path (e.g. /user/gamepad/input/a/click)
+    ///
+    ///Buttons count as pressed from 0.5, vectors are read from their /x and /y components. Missing sources are inactive
+    fn poll(&mut self) -> HashMap<String, f32>;
+}
+
+static HOST_INPUT: Lazy<Mutex<Option<Box<dyn HostInput>>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn enabled() -> bool {
+    env::var_os(EMULATE_VAR).is_some()
+}
+
+///Installs the source emulated states are read from in every instance, ahead of the joystick an instance opens itself
+///
+///Without either every emulated source is inactive
+pub fn set_host_input(host_input: Option<Box<dyn HostInput>>) {
+    *HOST_INPUT.lock().unwrap() = host_input;
+}
+
+///The emulated profile, `None` when the catalog has no [LAYOUT_PROFILE] to copy
+pub fn profile() -> Option<InteractionProfile> {
+    let mut profile = InteractionProfile::clone(interaction_profiles::catalog().profiles.get(LAYOUT_PROFILE)?);
+    profile.title = String::from("OxideXR Emulated Input");
+    Some(profile)
+}
+
+///Writes the latest host input into a session's emulated god states, `own_input` is read when no process wide one is installed
+pub fn sync(states: &HashMap<xr::Path, Arc<InputBinding>>, own_input: &Mutex<Option<Box<dyn HostInput>>>, now: xr::Time) {
+    let snapshot = match (HOST_INPUT.lock().unwrap().as_mut(), own_input.lock().unwrap().as_mut()) {
+        (Some(host_input), _) | (None, Some(host_input)) => host_input.poll(),
+        (None, None) => HashMap::new(),
+    };
+    for binding in states.values() {
+        update(binding, &snapshot, now);
+    }
+}
+
+fn update(binding: &InputBinding, snapshot: &HashMap<String, f32>, now: xr::Time) {
+    let read = |path: &str| snapshot.get(path).copied();
+    match &mut *binding.action_state.write().unwrap() {
+        GodActionStateEnum::Boolean(state) => set_state(state, read(&binding.binding_str).map(|value| value >= 0.5), now),
+        GodActionStateEnum::Float(state) => set_state(state, read(&binding.binding_str), now),
+        GodActionStateEnum::Vector2f(state) => {
+            let x = read(&format!("{}/x", binding.binding_str));
+            let y = read(&format!("{}/y", binding.binding_str));
+            let value = if x.is_none() && y.is_none() {
+                None
+            } else {
+                Some(openxr::Vector2f {
+                    x: x.unwrap_or(0f32),
+                    y: y.unwrap_or(0f32),
+                })
+            };
+            set_state(state, value, now)
+        }
+        GodActionStateEnum::Pose(_) => {}
+    }
+}
+
+fn set_state<T: Copy + PartialEq>(state: &mut openxr::ActionState<T>, value: Option<T>, now: xr::Time) {
+    match value {
+        Some(value) => {
+            state.changed_since_last_sync = state.is_active && state.current_state != value;
+            if state.changed_since_last_sync || !state.is_active {
+                state.last_change_time = now;
+            }
+            state.current_state = value;
+            state.is_active = true;
+        }
+        None => {
+            state.changed_since_last_sync = false;
+            state.is_active = false;
+        }
+    }
+}
+
+///A gamepad read through the Linux joystick interface, its buttons and axes mapped onto the Xbox layout as the xpad driver numbers them
+///
+///A thread blocks on the device and keeps the latest values, it stops at the first event after the reader is dropped
+#[cfg(target_os = "linux")]
+pub struct Joystick {
+    values: Arc<Mutex<HashMap<String, f32>>>,
+}
+
+#[cfg(target_os = "linux")]
+impl Joystick {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut device = File::open(path)?;
+        let values = Arc::new(Mutex::new(HashMap::new()));
+        let thread_values = Arc::downgrade(&values);
+        std::thread::Builder::new().name(String::from("oxidexr joystick")).spawn(move || {
+            let mut event = [0u8; 8];
+            while device.read_exact(&mut event).is_ok() {
+                match thread_values.upgrade() {
+                    Some(values) => apply_joystick_event(&mut values.lock().unwrap(), event),
+                    None => break,
+                }
+            }
+        })?;
+        Ok(Joystick { values })
+    }
+
+    ///Opens the device [EMULATE_VAR] names, or [DEFAULT_JOYSTICK]
+    pub fn open_configured() -> io::Result<Self> {
+        match env::var(EMULATE_VAR) {
+            Ok(path) if path.starts_with("/dev/") => Self::open(Path::new(&path)),
+            _ => Self::open(Path::new(DEFAULT_JOYSTICK)),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl HostInput for Joystick {
+    fn poll(&mut self) -> HashMap<String, f32> {
+        self.values.lock().unwrap().clone()
+    }
+}
+
+#[cfg(target_os = "linux")]
+const JOYSTICK_BUTTONS: &[Option<&str>] = &[
+    Some("/user/gamepad/input/a/click"),
+    Some("/user/gamepad/input/b/click"),
+    Some("/user/gamepad/input/x/click"),
+    Some("/user/gamepad/input/y/click"),
+    Some("/user/gamepad/input/shoulder_left/click"),
+    Some("/user/gamepad/input/shoulder_right/click"),
+    Some("/user/gamepad/input/view/click"),
+    Some("/user/gamepad/input/menu/click"),
+    //The guide button isn't part of the profile
+    None,
+    Some("/user/gamepad/input/thumbstick_left/click"),
+    Some("/user/gamepad/input/thumbstick_right/click"),
+];
+
+///Applies one `struct js_event` (time, value, type, number) to the latest values
+///
+///Sticks are flipped so up is positive as OpenXR has it, triggers go from 0 to 1 and the dpad axes become its four buttons
+#[cfg(target_os = "linux")]
+fn apply_joystick_event(values: &mut HashMap<String, f32>, event: [u8; 8]) {
+    const BUTTON: u8 = 0x01;
+    const AXIS: u8 = 0x02;
+    //Set on the events describing the state the device was opened in
+    const INIT: u8 = 0x80;
+
+    let raw = i16::from_le_bytes([event[4], event[5]]);
+    let value = (raw as f32 / i16::MAX as f32).max(-1f32);
+    let number = event[7] as usize;
+    let mut set = |path: &str, value: f32| {
+        values.insert(path.to_owned(), value);
+    };
+    match event[6] & !INIT {
+        BUTTON => {
+            if let Some(Some(path)) = JOYSTICK_BUTTONS.get(number) {
+                set(path, (raw != 0) as u8 as f32);
+            }
+        }
+        AXIS => match number {
+            0 => set("/user/gamepad/input/thumbstick_left/x", value),
+            1 => set("/user/gamepad/input/thumbstick_left/y", -value),
+            2 => set("/user/gamepad/input/trigger_left/value", (value + 1f32) / 2f32),
+            3 => set("/user/gamepad/input/thumbstick_right/x", value),
+            4 => set("/user/gamepad/input/thumbstick_right/y", -value),
+            5 => set("/user/gamepad/input/trigger_right/value", (value + 1f32) / 2f32),
+            6 => {
+                set("/user/gamepad/input/dpad_left/click", (value < 0f32) as u8 as f32);
+                set("/user/gamepad/input/dpad_right/click", (value > 0f32) as u8 as f32);
+            }
+            7 => {
+                set("/user/gamepad/input/dpad_up/click", (value < 0f32) as u8 as f32);
+                set("/user/gamepad/input/dpad_down/click", (value > 0f32) as u8 as f32);
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+#[test]
+fn test_emulated_input() {
+    use std::ptr;
+
+    use common::config::BindingConfigBuilder;
+
+    use crate::fake_runtime;
+    use crate::injections::session::apply_haptic_feedback;
+    use crate::path::{InteractionProfilePath, TopLevelUserPath};
+    use crate::wrappers::WrappedHandle;
+
+    struct FakeHost(Arc<Mutex<HashMap<String, f32>>>);
+
+    impl HostInput for FakeHost {
+        fn poll(&mut self) -> HashMap<String, f32> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    let config = BindingConfigBuilder::new()
+        .bind(EMULATED_PROFILE, "gameplay/jump", "/user/gamepad/input/a/click")
+        .bind(EMULATED_PROFILE, "gameplay/rumble", "/user/gamepad/output/haptic_left")
+        .build()
+        .unwrap();
+    let instance = fake_runtime::create_emulated_instance(config);
+    //Installed on this instance alone, the process wide host input other tests would see stays empty
+    let host = Arc::new(Mutex::new(HashMap::new()));
+    *instance.host_input.lock().unwrap() = Some(Box::new(FakeHost(host.clone())));
+
+    let session = fake_runtime::new_session(&instance);
+    let (gameplay, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[("jump", xr::ActionType::BOOLEAN_INPUT), ("rumble", xr::ActionType::VIBRATION_OUTPUT)],
+    );
+    let (jump, rumble) = (actions[0], actions[1]);
+
+    //The application suggests nothing, the custom bindings alone put jump on the emulated A button
+    assert_eq!(fake_runtime::attach(session, &[gameplay]), xr::Result::SUCCESS);
+    let jump = || {
+        assert_eq!(fake_runtime::sync(session, &[gameplay]), xr::Result::SUCCESS);
+        fake_runtime::boolean_state(session, jump, xr::Path::NULL)
+    };
+
+    assert!(!jump().is_active);
+
+    host.lock().unwrap().insert(String::from("/user/gamepad/input/a/click"), 1f32);
+    let pressed = jump();
+    assert!(pressed.is_active);
+    assert!(pressed.current_state);
+
+    host.lock().unwrap().insert(String::from("/user/gamepad/input/a/click"), 0f32);
+    let released = jump();
+    assert!(!released.current_state);
+    assert!(released.changed_since_last_sync);
+
+    //The emulated haptics have no runtime action, nothing is passed on even while the profile is in use
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let gamepad = TopLevelUserPath(instance.string_to_path(openxr::USER_GAMEPAD).unwrap());
+    *wrapper.active_profiles[&gamepad].write().unwrap() = InteractionProfilePath(instance.string_to_path(EMULATED_PROFILE).unwrap());
+    let haptic_action_info = xr::HapticActionInfo {
+        ty: xr::HapticActionInfo::TYPE,
+        next: ptr::null(),
+        action: rumble,
+        subaction_path: xr::Path::NULL,
+    };
+    let vibration = xr::HapticVibration {
+        ty: xr::HapticVibration::TYPE,
+        next: ptr::null(),
+        duration: xr::Duration::from_nanos(1_000_000),
+        frequency: xr::FREQUENCY_UNSPECIFIED,
+        amplitude: 1f32,
+    };
+    let result = unsafe { apply_haptic_feedback(session, &haptic_action_info, &vibration as *const _ as *const xr::HapticBaseHeader) };
+    assert_eq!(result, xr::Result::SUCCESS);
+    assert!(fake_runtime::haptics(session).is_empty());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_joystick_events() {
+    let event = |value: i16, ty: u8, number: u8| {
+        let value = value.to_le_bytes();
+        [0, 0, 0, 0, value[0], value[1], ty, number]
+    };
+    let mut values = HashMap::new();
+
+    //The state the device was opened in comes flagged as init events
+    apply_joystick_event(&mut values, event(1, 0x81, 0));
+    apply_joystick_event(&mut values, event(i16::MIN, 0x82, 1));
+    apply_joystick_event(&mut values, event(i16::MIN, 0x82, 5));
+    assert_eq!(values["/user/gamepad/input/a/click"], 1f32);
+    assert_eq!(values["/user/gamepad/input/thumbstick_left/y"], 1f32);
+    assert_eq!(values["/user/gamepad/input/trigger_right/value"], 0f32);
+
+    apply_joystick_event(&mut values, event(0, 0x01, 0));
+    apply_joystick_event(&mut values, event(i16::MAX, 0x02, 5));
+    apply_joystick_event(&mut values, event(-i16::MAX, 0x02, 7));
+    assert_eq!(values["/user/gamepad/input/a/click"], 0f32);
+    assert_eq!(values["/user/gamepad/input/trigger_right/value"], 1f32);
+    assert_eq!(values["/user/gamepad/input/dpad_up/click"], 1f32);
+    assert_eq!(values["/user/gamepad/input/dpad_down/click"], 0f32);
+
+    //The guide button and axes past the dpad aren't part of the profile
+    let known = values.len();
+    apply_joystick_event(&mut values, event(1, 0x01, 8));
+    apply_joystick_event(&mut values, event(1, 0x02, 9));
+    assert_eq!(values.len(), known);
+}
@@ -0,0 +1,830 @@
+//! A stand-in for the runtime below the layer, implementing just enough of OpenXR to drive the injections from tests
+
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::{Lazy, OnceCell};
+use openxr::builder as xr_builder;
+use openxr::sys as xr;
+use openxr::sys::pfn;
+
+use common::application_bindings::ApplicationBindings;
+use common::config::Settings;
+use common::xrapplication_info::ActionType;
+
+use crate::emulated;
+use crate::god_actions::{self, GodAction, GodActionStateEnum, InputBinding};
+use crate::injections;
+use crate::wrappers::*;
+
+//Kept well away from the small handle values tests construct by hand
+static NEXT_HANDLE: AtomicU64 = AtomicU64::new(1 << 32);
+static ALLOCATOR: Lazy<Mutex<Allocator>> = Lazy::new(Default::default);
+static FRAME_TIME: AtomicI64 = AtomicI64::new(0);
+static PATHS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static FAILING_ATTACH: Lazy<Mutex<HashSet<xr::Instance>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+static FAILING_SUGGESTIONS: Lazy<Mutex<HashSet<xr::Instance>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+static SESSIONS: Lazy<Mutex<Vec<(xr::Instance, xr::Session)>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static ACTION_SET_PRIORITIES: Lazy<Mutex<HashMap<xr::ActionSet, u32>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+type SuggestedActions = HashMap<(xr::Instance, xr::Path), Vec<(xr::Action, xr::Path)>>;
+static SUGGESTED_ACTIONS: Lazy<Mutex<SuggestedActions>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static SUGGESTION_ORDER: Lazy<Mutex<HashMap<xr::Instance, Vec<xr::Path>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static CURRENT_PROFILES: Lazy<Mutex<HashMap<(xr::Session, xr::Path), xr::Path>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static EVENTS: Lazy<Mutex<HashMap<xr::Instance, VecDeque<xr::Session>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static ACTION_LIMITS: Lazy<Mutex<HashMap<xr::Instance, usize>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static ATTACHED_SETS: Lazy<Mutex<HashMap<xr::Session, Vec<xr::ActionSet>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+static INPUT_STATES: Lazy<Mutex<HashMap<(xr::Session, xr::Action), GodActionStateEnum>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+type Haptics = Vec<(xr::Session, xr::Action, f32, i64)>;
+static HAPTICS: Lazy<Mutex<Haptics>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+///Where a handle the fake runtime handed out came from, see [allocation]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Allocation {
+    pub object_type: xr::ObjectType,
+    ///The raw handle of the instance or action set it was created from, 0 for instances
+    pub parent: u64,
+    ///Its place among the handles of its type created from the same parent, counting from 1
+    pub ordinal: u64,
+}
+
+#[derive(Default)]
+struct Allocator {
+    allocations: HashMap<u64, Allocation>,
+    counts: HashMap<(u64, i32), u64>,
+}
+
+///Hands out the next handle, recording where it came from
+///
+///Raw values only ever increase, and as tests run in parallel each parent numbers its own children so they come out the same on every run
+fn allocate(object_type: xr::ObjectType, parent: u64) -> u64 {
+    let mut allocator = ALLOCATOR.lock().unwrap();
+    let count = allocator.counts.entry((parent, object_type.into_raw())).or_default();
+    *count += 1;
+    let allocation = Allocation {
+        object_type,
+        parent,
+        ordinal: *count,
+    };
+    let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+    allocator.allocations.insert(handle, allocation);
+    handle
+}
+
+///Where the fake runtime allocated a handle from, `None` for handles it never handed out
+pub fn allocation(handle: u64) -> Option<Allocation> {
+    ALLOCATOR.lock().unwrap().allocations.get(&handle).copied()
+}
+
+///Every handle of `object_type` the fake runtime created from `parent`, in the order they were handed out
+pub fn allocated(parent: u64, object_type: xr::ObjectType) -> Vec<u64> {
+    let allocator = ALLOCATOR.lock().unwrap();
+    let mut handles = allocator
+        .allocations
+        .iter()
+        .filter(|(_, allocation)| allocation.parent == parent && allocation.object_type == object_type)
+        .map(|(handle, _)| *handle)
+        .collect::<Vec<_>>();
+    handles.sort_unstable();
+    handles
+}
+
+static CLOCK_BASE: Lazy<Instant> = Lazy::new(Instant::now);
+static CLOCK_ELAPSED: AtomicU64 = AtomicU64::new(0);
+thread_local! {
+    static CLOCK_STEP: Cell<u64> = const { Cell::new(0) };
+}
+
+///A clock standing in for [Instant::now], it only moves by the step set with [set_clock_step] each time it is read
+pub fn now() -> Instant {
+    *CLOCK_BASE + Duration::from_nanos(CLOCK_ELAPSED.fetch_add(CLOCK_STEP.with(Cell::get), Ordering::Relaxed))
+}
+
+///Sets the step for reads on this thread only, other tests reading the clock at the same time don't move it
+pub fn set_clock_step(step: Duration) {
+    CLOCK_STEP.with(|clock_step| clock_step.set(step.as_nanos() as u64));
+}
+
+///Creates an instance on the fake runtime, god action sets included, and registers it with the layer as xrCreateApiLayerInstance would
+pub fn create_instance() -> Arc<InstanceWrapper> {
+    create_instance_with_bindings(Default::default())
+}
+
+pub fn create_instance_with_bindings(custom_bindings: ApplicationBindings) -> Arc<InstanceWrapper> {
+    create_instance_with_extensions(custom_bindings, Vec::new())
+}
+
+pub fn create_instance_with_extensions(custom_bindings: ApplicationBindings, enabled_extensions: Vec<String>) -> Arc<InstanceWrapper> {
+    create(custom_bindings, enabled_extensions, get_instance_proc_addr, false, false, Default::default())
+}
+
+///An instance for an application the layer isn't activated for
+pub fn create_passthrough_instance() -> Arc<InstanceWrapper> {
+    create(Default::default(), Vec::new(), get_instance_proc_addr, true, false, Default::default())
+}
+
+pub fn create_instance_with_settings(settings: Settings) -> Arc<InstanceWrapper> {
+    create_configured_instance(Default::default(), settings)
+}
+
+pub fn create_configured_instance(custom_bindings: ApplicationBindings, settings: Settings) -> Arc<InstanceWrapper> {
+    create(custom_bindings, Vec::new(), get_instance_proc_addr, false, false, settings)
+}
+
+///An instance with the emulated input profile, as if OXIDEXR_EMULATE_INPUT were set
+pub fn create_emulated_instance(custom_bindings: ApplicationBindings) -> Arc<InstanceWrapper> {
+    create(custom_bindings, Vec::new(), get_instance_proc_addr, false, true, Default::default())
+}
+
+///An instance whose xrGetInstanceProcAddr calls go to `get_instance_proc_addr_next` rather than the fake runtime, everything else still uses the fake runtime
+pub fn create_instance_with_proc_addr(get_instance_proc_addr_next: pfn::GetInstanceProcAddr) -> Arc<InstanceWrapper> {
+    create(Default::default(), Vec::new(), get_instance_proc_addr_next, false, false, Default::default())
+}
+
+fn create(
+    custom_bindings: ApplicationBindings,
+    enabled_extensions: Vec<String>,
+    get_instance_proc_addr_next: pfn::GetInstanceProcAddr,
+    passthrough: bool,
+    emulate_input: bool,
+    settings: Settings,
+) -> Arc<InstanceWrapper> {
+    unsafe {
+        static_init();
+
+        let handle = xr::Instance::from_raw(allocate(xr::ObjectType::INSTANCE, 0));
+        let entry = openxr::Entry::from_proc_addr(get_instance_proc_addr).unwrap();
+
+        let mut wrapper = InstanceWrapper {
+            handle,
+            sessions: RwLock::new(Vec::new()),
+            action_sets: RwLock::new(Vec::new()),
+            god_action_sets: Default::default(),
+            emulated_set: None,
+            host_input: Default::default(),
+            custom_bindings,
+            unknown_profiles: Default::default(),
+            forwarded_suggestions: Default::default(),
+            settings,
+            enabled_extensions,
+            application_name: String::from("fake_application"),
+            application_version: 0,
+            engine_name: String::from("fake_engine"),
+            engine_version: 0,
+            core: openxr::raw::Instance::load(&entry, handle).unwrap(),
+            exts: openxr::InstanceExtensions::load(&entry, handle, &Default::default()).unwrap(),
+            passthrough,
+            get_instance_proc_addr_next,
+        };
+        if !passthrough && !wrapper.settings.defer_god_sets {
+            wrapper.god_action_sets = OnceCell::from(god_actions::create_god_action_sets(&wrapper).unwrap());
+        }
+        if emulate_input {
+            let profile_info = emulated::profile().unwrap();
+            wrapper.emulated_set = Some(god_actions::GodActionSet::create_emulated_set(&wrapper, profile_info).unwrap());
+        }
+
+        let wrapper = Arc::new(wrapper);
+        instances().insert(handle, wrapper.clone());
+        wrapper
+    }
+}
+
+///Creates a session through the layer, as the application's xrCreateSession would
+pub fn new_session(instance: &InstanceWrapper) -> xr::Session {
+    let create_info = xr::SessionCreateInfo {
+        ty: xr::SessionCreateInfo::TYPE,
+        next: ptr::null(),
+        create_flags: xr::SessionCreateFlags::EMPTY,
+        system_id: xr::SystemId::from_raw(1),
+    };
+    let mut session = xr::Session::NULL;
+    assert_eq!(unsafe { injections::create_session(instance.handle, &create_info, &mut session) }, xr::Result::SUCCESS);
+    session
+}
+
+///Creates an action set through the layer, localized with its own name
+pub fn new_action_set(instance: &InstanceWrapper, name: &str) -> xr::ActionSet {
+    let create_info = xr_builder::ActionSetCreateInfo::new().action_set_name(name).localized_action_set_name(name);
+    let mut action_set = xr::ActionSet::NULL;
+    assert_eq!(unsafe { injections::create_action_set(instance.handle, create_info.as_raw(), &mut action_set) }, xr::Result::SUCCESS);
+    action_set
+}
+
+///Creates an action through the layer, localized with its own name
+pub fn new_action(action_set: xr::ActionSet, name: &str, action_type: xr::ActionType, subaction_paths: &[xr::Path]) -> xr::Action {
+    let create_info = xr_builder::ActionCreateInfo::new()
+        .action_name(name)
+        .localized_action_name(name)
+        .action_type(action_type)
+        .subaction_paths(subaction_paths);
+    let mut action = xr::Action::NULL;
+    assert_eq!(unsafe { injections::create_action(action_set, create_info.as_raw(), &mut action) }, xr::Result::SUCCESS);
+    action
+}
+
+///Creates an action set holding an action for each name and type, without subaction paths, see [new_action]
+pub fn new_actions(instance: &InstanceWrapper, set_name: &str, actions: &[(&str, xr::ActionType)]) -> (xr::ActionSet, Vec<xr::Action>) {
+    let action_set = new_action_set(instance, set_name);
+    let actions = actions
+        .iter()
+        .map(|(name, action_type)| new_action(action_set, name, *action_type, &[]))
+        .collect();
+    (action_set, actions)
+}
+
+///Makes one xrSuggestInteractionProfileBindings call through the layer
+pub fn suggest(instance: &InstanceWrapper, profile_name: &str, bindings: &[(xr::Action, &str)]) -> xr::Result {
+    let bindings = bindings
+        .iter()
+        .map(|(action, binding)| xr::ActionSuggestedBinding {
+            action: *action,
+            binding: instance.string_to_path(binding).unwrap(),
+        })
+        .collect::<Vec<_>>();
+    let suggested_bindings = xr::InteractionProfileSuggestedBinding {
+        ty: xr::InteractionProfileSuggestedBinding::TYPE,
+        next: ptr::null(),
+        interaction_profile: instance.string_to_path(profile_name).unwrap(),
+        count_suggested_bindings: bindings.len() as u32,
+        suggested_bindings: bindings.as_ptr(),
+    };
+    unsafe { injections::instance::suggest_interaction_profile_bindings(instance.handle, &suggested_bindings) }
+}
+
+///Attaches the action sets through the layer's xrAttachSessionActionSets
+pub fn attach(session: xr::Session, action_sets: &[xr::ActionSet]) -> xr::Result {
+    let attach_info = xr::SessionActionSetsAttachInfo {
+        ty: xr::SessionActionSetsAttachInfo::TYPE,
+        next: ptr::null(),
+        count_action_sets: action_sets.len() as u32,
+        action_sets: action_sets.as_ptr(),
+    };
+    unsafe { injections::session::attach_session_action_sets(session, &attach_info) }
+}
+
+///Syncs the action sets for every subaction path through the layer's xrSyncActions
+pub fn sync(session: xr::Session, action_sets: &[xr::ActionSet]) -> xr::Result {
+    let active_action_sets = action_sets
+        .iter()
+        .map(|action_set| xr::ActiveActionSet {
+            action_set: *action_set,
+            subaction_path: xr::Path::NULL,
+        })
+        .collect::<Vec<_>>();
+    let sync_info = xr::ActionsSyncInfo {
+        ty: xr::ActionsSyncInfo::TYPE,
+        next: ptr::null(),
+        count_active_action_sets: active_action_sets.len() as u32,
+        active_action_sets: active_action_sets.as_ptr(),
+    };
+    unsafe { injections::session::sync_actions(session, &sync_info) }
+}
+
+fn state_get_info(action: xr::Action, subaction_path: xr::Path) -> xr::ActionStateGetInfo {
+    xr::ActionStateGetInfo {
+        ty: xr::ActionStateGetInfo::TYPE,
+        next: ptr::null(),
+        action,
+        subaction_path,
+    }
+}
+
+///The state the layer's xrGetActionStateBoolean gives the application
+pub fn boolean_state(session: xr::Session, action: xr::Action, subaction_path: xr::Path) -> openxr::ActionState<bool> {
+    let mut state = xr::ActionStateBoolean {
+        ty: xr::ActionStateBoolean::TYPE,
+        next: ptr::null_mut(),
+        current_state: false.into(),
+        changed_since_last_sync: false.into(),
+        last_change_time: xr::Time::from_nanos(0),
+        is_active: false.into(),
+    };
+    let result = unsafe { injections::session::get_action_state_boolean(session, &state_get_info(action, subaction_path), &mut state) };
+    assert_eq!(result, xr::Result::SUCCESS);
+    openxr::ActionState {
+        current_state: state.current_state.into(),
+        changed_since_last_sync: state.changed_since_last_sync.into(),
+        last_change_time: state.last_change_time,
+        is_active: state.is_active.into(),
+    }
+}
+
+///The state the layer's xrGetActionStateFloat gives the application
+pub fn float_state(session: xr::Session, action: xr::Action, subaction_path: xr::Path) -> openxr::ActionState<f32> {
+    let mut state = xr::ActionStateFloat {
+        ty: xr::ActionStateFloat::TYPE,
+        next: ptr::null_mut(),
+        current_state: 0f32,
+        changed_since_last_sync: false.into(),
+        last_change_time: xr::Time::from_nanos(0),
+        is_active: false.into(),
+    };
+    let result = unsafe { injections::session::get_action_state_float(session, &state_get_info(action, subaction_path), &mut state) };
+    assert_eq!(result, xr::Result::SUCCESS);
+    openxr::ActionState {
+        current_state: state.current_state,
+        changed_since_last_sync: state.changed_since_last_sync.into(),
+        last_change_time: state.last_change_time,
+        is_active: state.is_active.into(),
+    }
+}
+
+///The state the layer's xrGetActionStateVector2f gives the application
+pub fn vector2f_state(session: xr::Session, action: xr::Action, subaction_path: xr::Path) -> openxr::ActionState<openxr::Vector2f> {
+    let mut state = xr::ActionStateVector2f {
+        ty: xr::ActionStateVector2f::TYPE,
+        next: ptr::null_mut(),
+        current_state: xr::Vector2f { x: 0f32, y: 0f32 },
+        changed_since_last_sync: false.into(),
+        last_change_time: xr::Time::from_nanos(0),
+        is_active: false.into(),
+    };
+    let result = unsafe { injections::session::get_action_state_vector2f(session, &state_get_info(action, subaction_path), &mut state) };
+    assert_eq!(result, xr::Result::SUCCESS);
+    openxr::ActionState {
+        current_state: state.current_state,
+        changed_since_last_sync: state.changed_since_last_sync.into(),
+        last_change_time: state.last_change_time,
+        is_active: state.is_active.into(),
+    }
+}
+
+///A god input as the session would create it, starting out inactive
+pub fn god_input(binding_str: &str, action_type: ActionType) -> Arc<InputBinding> {
+    Arc::new(InputBinding {
+        action: Arc::new(GodAction {
+            handle: xr::Action::from_raw(allocate(xr::ObjectType::ACTION, 0)),
+            profile_name_str: String::from("/interaction_profiles/khr/simple_controller"),
+            profile_name: xr::Path::NULL,
+            name: String::from(binding_str),
+            subaction_paths: Vec::new(),
+            action_type,
+        }),
+        binding_str: String::from(binding_str),
+        subaction_path: xr::Path::NULL,
+        action_state: RwLock::new(GodActionStateEnum::new(action_type).unwrap()),
+    })
+}
+
+///The priority an action set was created with
+pub fn action_set_priority(action_set: xr::ActionSet) -> Option<u32> {
+    ACTION_SET_PRIORITIES.lock().unwrap().get(&action_set).copied()
+}
+
+///Every action in the last suggestion the runtime was given for `profile`
+pub fn suggested_actions(instance: xr::Instance, profile: xr::Path) -> Vec<xr::Action> {
+    SUGGESTED_ACTIONS
+        .lock()
+        .unwrap()
+        .get(&(instance, profile))
+        .into_iter()
+        .flatten()
+        .map(|(action, _)| *action)
+        .collect()
+}
+
+///The profiles suggested on this instance, in the order the suggestions were made
+pub fn suggestion_order(instance: xr::Instance) -> Vec<xr::Path> {
+    SUGGESTION_ORDER.lock().unwrap().get(&instance).cloned().unwrap_or_default()
+}
+
+///The action sets of every successful xrAttachSessionActionSets call on `session`, in the order they were passed
+pub fn attached_sets(session: xr::Session) -> Vec<xr::ActionSet> {
+    ATTACHED_SETS.lock().unwrap().get(&session).cloned().unwrap_or_default()
+}
+
+///Makes every xrAttachSessionActionSets call on sessions of this instance fail
+pub fn fail_attach(instance: xr::Instance) {
+    FAILING_ATTACH.lock().unwrap().insert(instance);
+}
+
+///Makes every xrSuggestInteractionProfileBindings call on this instance fail
+pub fn fail_suggestions(instance: xr::Instance) {
+    FAILING_SUGGESTIONS.lock().unwrap().insert(instance);
+}
+
+///Lets only `count` more xrCreateAction calls on this instance succeed, later ones fail with ERROR_LIMIT_REACHED
+pub fn limit_actions(instance: xr::Instance, count: usize) {
+    ACTION_LIMITS.lock().unwrap().insert(instance, count);
+}
+
+///The sessions of this instance the runtime still considers alive
+pub fn live_sessions(instance: xr::Instance) -> Vec<xr::Session> {
+    SESSIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(owner, _)| *owner == instance)
+        .map(|(_, session)| *session)
+        .collect()
+}
+
+pub unsafe extern "system" fn get_instance_proc_addr(
+    _instance: xr::Instance,
+    name: *const c_char,
+    function: *mut Option<pfn::VoidFunction>,
+) -> xr::Result {
+    *function = Some(match CStr::from_ptr(name).to_str().unwrap() {
+        "xrGetInstanceProperties" => {
+            std::mem::transmute::<pfn::GetInstanceProperties, pfn::VoidFunction>(get_instance_properties)
+        }
+        "xrStringToPath" => std::mem::transmute::<pfn::StringToPath, pfn::VoidFunction>(string_to_path),
+        "xrPathToString" => std::mem::transmute::<pfn::PathToString, pfn::VoidFunction>(path_to_string),
+        "xrCreateSession" => std::mem::transmute::<pfn::CreateSession, pfn::VoidFunction>(create_session),
+        "xrDestroySession" => std::mem::transmute::<pfn::DestroySession, pfn::VoidFunction>(destroy_session),
+        "xrCreateActionSet" => std::mem::transmute::<pfn::CreateActionSet, pfn::VoidFunction>(create_action_set),
+        "xrCreateAction" => std::mem::transmute::<pfn::CreateAction, pfn::VoidFunction>(create_action),
+        "xrSuggestInteractionProfileBindings" => std::mem::transmute::<
+            pfn::SuggestInteractionProfileBindings,
+            pfn::VoidFunction,
+        >(suggest_interaction_profile_bindings),
+        "xrSyncActions" => std::mem::transmute::<pfn::SyncActions, pfn::VoidFunction>(sync_actions),
+        "xrGetActionStateBoolean" => {
+            std::mem::transmute::<pfn::GetActionStateBoolean, pfn::VoidFunction>(get_action_state_boolean)
+        }
+        "xrGetActionStateFloat" => std::mem::transmute::<pfn::GetActionStateFloat, pfn::VoidFunction>(get_action_state_float),
+        "xrGetActionStateVector2f" => {
+            std::mem::transmute::<pfn::GetActionStateVector2f, pfn::VoidFunction>(get_action_state_vector2f)
+        }
+        "xrGetActionStatePose" => std::mem::transmute::<pfn::GetActionStatePose, pfn::VoidFunction>(get_action_state_pose),
+        "xrWaitFrame" => std::mem::transmute::<pfn::WaitFrame, pfn::VoidFunction>(wait_frame),
+        "xrApplyHapticFeedback" => std::mem::transmute::<pfn::ApplyHapticFeedback, pfn::VoidFunction>(apply_haptic_feedback),
+        "xrGetCurrentInteractionProfile" => {
+            std::mem::transmute::<pfn::GetCurrentInteractionProfile, pfn::VoidFunction>(get_current_interaction_profile)
+        }
+        "xrPollEvent" => std::mem::transmute::<pfn::PollEvent, pfn::VoidFunction>(poll_event),
+        "xrAttachSessionActionSets" => {
+            std::mem::transmute::<pfn::AttachSessionActionSets, pfn::VoidFunction>(attach_session_action_sets)
+        }
+        "xrEnumerateBoundSourcesForAction" => std::mem::transmute::<
+            pfn::EnumerateBoundSourcesForAction,
+            pfn::VoidFunction,
+        >(enumerate_bound_sources_for_action),
+        "xrGetInputSourceLocalizedName" => std::mem::transmute::<
+            pfn::GetInputSourceLocalizedName,
+            pfn::VoidFunction,
+        >(get_input_source_localized_name),
+        _ => std::mem::transmute::<unsafe extern "system" fn() -> xr::Result, pfn::VoidFunction>(unsupported),
+    });
+    xr::Result::SUCCESS
+}
+
+unsafe extern "system" fn unsupported() -> xr::Result {
+    xr::Result::ERROR_FUNCTION_UNSUPPORTED
+}
+
+pub const RUNTIME_NAME: &str = "Fake Runtime";
+pub const RUNTIME_VERSION: xr::Version = xr::Version::new(1, 2, 3);
+
+unsafe extern "system" fn get_instance_properties(
+    _instance: xr::Instance,
+    instance_properties: *mut xr::InstanceProperties,
+) -> xr::Result {
+    (*instance_properties).runtime_version = RUNTIME_VERSION;
+    crate::util::place_cstr(&mut (*instance_properties).runtime_name, RUNTIME_NAME);
+    xr::Result::SUCCESS
+}
+
+unsafe extern "system" fn string_to_path(
+    _instance: xr::Instance,
+    path_string: *const c_char,
+    path: *mut xr::Path,
+) -> xr::Result {
+    let path_string = CStr::from_ptr(path_string).to_str().unwrap();
+    let mut paths = PATHS.lock().unwrap();
+    let idx = match paths.iter().position(|existing| existing == path_string) {
+        Some(idx) => idx,
+        None => {
+            paths.push(String::from(path_string));
+            paths.len() - 1
+        }
+    };
+    *path = xr::Path::from_raw(idx as u64 + 1);
+    xr::Result::SUCCESS
+}
+
+//Far past any path string_to_path hands out
+const ZERO_LENGTH_PATH: u64 = u64::MAX - 1;
+
+///A path the runtime claims exists but reports a length of zero for, terminator included
+pub fn zero_length_path() -> xr::Path {
+    xr::Path::from_raw(ZERO_LENGTH_PATH)
+}
+
+unsafe extern "system" fn path_to_string(
+    _instance: xr::Instance,
+    path: xr::Path,
+    buffer_capacity_input: u32,
+    buffer_count_output: *mut u32,
+    buffer: *mut c_char,
+) -> xr::Result {
+    if path.into_raw() == ZERO_LENGTH_PATH {
+        *buffer_count_output = 0;
+        return xr::Result::SUCCESS;
+    }
+    let paths = PATHS.lock().unwrap();
+    let path_string = match paths.get((path.into_raw() as usize).wrapping_sub(1)) {
+        Some(path_string) => path_string,
+        None => return xr::Result::ERROR_PATH_INVALID,
+    };
+    crate::util::fill_output_string(buffer, buffer_capacity_input, buffer_count_output, path_string)
+}
+
+unsafe extern "system" fn create_action_set(
+    instance: xr::Instance,
+    create_info: *const xr::ActionSetCreateInfo,
+    action_set: *mut xr::ActionSet,
+) -> xr::Result {
+    *action_set = xr::ActionSet::from_raw(allocate(xr::ObjectType::ACTION_SET, instance.into_raw()));
+    ACTION_SET_PRIORITIES.lock().unwrap().insert(*action_set, (*create_info).priority);
+    xr::Result::SUCCESS
+}
+
+unsafe extern "system" fn create_action(
+    action_set: xr::ActionSet,
+    _create_info: *const xr::ActionCreateInfo,
+    action: *mut xr::Action,
+) -> xr::Result {
+    if let Some(allocation) = allocation(action_set.into_raw()) {
+        if let Some(remaining) = ACTION_LIMITS.lock().unwrap().get_mut(&xr::Instance::from_raw(allocation.parent)) {
+            if *remaining == 0 {
+                return xr::Result::ERROR_LIMIT_REACHED;
+            }
+            *remaining -= 1;
+        }
+    }
+    *action = xr::Action::from_raw(allocate(xr::ObjectType::ACTION, action_set.into_raw()));
+    xr::Result::SUCCESS
+}
+
+///Accepts anything, the layer is the one being tested for what it lets through
+unsafe extern "system" fn suggest_interaction_profile_bindings(
+    instance: xr::Instance,
+    suggested_bindings: *const xr::InteractionProfileSuggestedBinding,
+) -> xr::Result {
+    if FAILING_SUGGESTIONS.lock().unwrap().contains(&instance) {
+        return xr::Result::ERROR_PATH_UNSUPPORTED;
+    }
+    let suggested_bindings = &*suggested_bindings;
+    let actions = if suggested_bindings.count_suggested_bindings == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(suggested_bindings.suggested_bindings, suggested_bindings.count_suggested_bindings as usize)
+    };
+    //Like a real runtime, only the last suggestion for a profile counts
+    SUGGESTED_ACTIONS
+        .lock()
+        .unwrap()
+        .insert(
+            (instance, suggested_bindings.interaction_profile),
+            actions.iter().map(|binding| (binding.action, binding.binding)).collect(),
+        );
+    SUGGESTION_ORDER.lock().unwrap().entry(instance).or_default().push(suggested_bindings.interaction_profile);
+    xr::Result::SUCCESS
+}
+
+///Every binding suggested for the action on any profile, as though each of them were current
+unsafe extern "system" fn enumerate_bound_sources_for_action(
+    session: xr::Session,
+    enumerate_info: *const xr::BoundSourcesForActionEnumerateInfo,
+    source_capacity_input: u32,
+    source_count_output: *mut u32,
+    sources: *mut xr::Path,
+) -> xr::Result {
+    let instance = match SESSIONS.lock().unwrap().iter().find(|(_, existing)| *existing == session) {
+        Some((instance, _)) => *instance,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+    let bound = SUGGESTED_ACTIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|((owner, _), _)| *owner == instance)
+        .flat_map(|(_, bindings)| bindings.iter())
+        .filter(|(action, _)| *action == (*enumerate_info).action)
+        .map(|(_, binding)| *binding)
+        .collect::<Vec<_>>();
+    crate::util::fill_output_array(sources, source_capacity_input, source_count_output, &bound)
+}
+
+///Names every source after its path, prefixed so tests can tell the runtime's names from the layer's
+unsafe extern "system" fn get_input_source_localized_name(
+    _session: xr::Session,
+    get_info: *const xr::InputSourceLocalizedNameGetInfo,
+    buffer_capacity_input: u32,
+    buffer_count_output: *mut u32,
+    buffer: *mut c_char,
+) -> xr::Result {
+    let paths = PATHS.lock().unwrap();
+    let path_string = match paths.get(((*get_info).source_path.into_raw() as usize).wrapping_sub(1)) {
+        Some(path_string) => path_string,
+        None => return xr::Result::ERROR_PATH_INVALID,
+    };
+    let name = format!("{} {}", RUNTIME_NAME, path_string);
+    crate::util::fill_output_string(buffer, buffer_capacity_input, buffer_count_output, &name)
+}
+
+unsafe extern "system" fn create_session(
+    instance: xr::Instance,
+    _create_info: *const xr::SessionCreateInfo,
+    session: *mut xr::Session,
+) -> xr::Result {
+    *session = xr::Session::from_raw(allocate(xr::ObjectType::SESSION, instance.into_raw()));
+    SESSIONS.lock().unwrap().push((instance, *session));
+    xr::Result::SUCCESS
+}
+
+unsafe extern "system" fn destroy_session(session: xr::Session) -> xr::Result {
+    let mut sessions = SESSIONS.lock().unwrap();
+    match sessions.iter().position(|(_, existing)| *existing == session) {
+        Some(idx) => {
+            sessions.remove(idx);
+            xr::Result::SUCCESS
+        }
+        None => xr::Result::ERROR_HANDLE_INVALID,
+    }
+}
+
+unsafe extern "system" fn attach_session_action_sets(
+    session: xr::Session,
+    attach_info: *const xr::SessionActionSetsAttachInfo,
+) -> xr::Result {
+    let sessions = SESSIONS.lock().unwrap();
+    let instance = match sessions.iter().find(|(_, existing)| *existing == session) {
+        Some((instance, _)) => *instance,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+
+    if FAILING_ATTACH.lock().unwrap().contains(&instance) {
+        return xr::Result::ERROR_VALIDATION_FAILURE;
+    }
+    let attach_info = &*attach_info;
+    if attach_info.count_action_sets > 0 {
+        let action_sets = std::slice::from_raw_parts(attach_info.action_sets, attach_info.count_action_sets as usize);
+        ATTACHED_SETS.lock().unwrap().entry(session).or_default().extend_from_slice(action_sets);
+    }
+    xr::Result::SUCCESS
+}
+
+unsafe extern "system" fn sync_actions(
+    _session: xr::Session,
+    _sync_info: *const xr::ActionsSyncInfo,
+) -> xr::Result {
+    xr::Result::SUCCESS
+}
+
+///Switches the controller the runtime reports on `user_path` to `profile` (NULL for none) and queues an XrEventDataInteractionProfileChanged for the session
+pub fn change_interaction_profile(session: xr::Session, user_path: xr::Path, profile: xr::Path) {
+    CURRENT_PROFILES.lock().unwrap().insert((session, user_path), profile);
+    let instance = SESSIONS.lock().unwrap().iter().find(|(_, existing)| *existing == session).unwrap().0;
+    EVENTS.lock().unwrap().entry(instance).or_default().push_back(session);
+}
+
+unsafe extern "system" fn get_current_interaction_profile(
+    session: xr::Session,
+    top_level_user_path: xr::Path,
+    interaction_profile: *mut xr::InteractionProfileState,
+) -> xr::Result {
+    (*interaction_profile).interaction_profile =
+        CURRENT_PROFILES.lock().unwrap().get(&(session, top_level_user_path)).copied().unwrap_or(xr::Path::NULL);
+    xr::Result::SUCCESS
+}
+
+unsafe extern "system" fn poll_event(instance: xr::Instance, event_data: *mut xr::EventDataBuffer) -> xr::Result {
+    let session = match EVENTS.lock().unwrap().get_mut(&instance).and_then(VecDeque::pop_front) {
+        Some(session) => session,
+        None => return xr::Result::EVENT_UNAVAILABLE,
+    };
+    let event = event_data as *mut xr::EventDataInteractionProfileChanged;
+    (*event).ty = xr::EventDataInteractionProfileChanged::TYPE;
+    (*event).next = ptr::null();
+    (*event).session = session;
+    xr::Result::SUCCESS
+}
+
+///Sets what the runtime reports for a god action from the next xrSyncActions on, every other action reads as inactive
+pub fn set_input_state(session: xr::Session, action: xr::Action, state: GodActionStateEnum) {
+    INPUT_STATES.lock().unwrap().insert((session, action), state);
+}
+
+unsafe fn input_state(session: xr::Session, get_info: *const xr::ActionStateGetInfo, action_type: ActionType) -> GodActionStateEnum {
+    let state = INPUT_STATES.lock().unwrap().get(&(session, (*get_info).action)).copied();
+    state.unwrap_or_else(|| GodActionStateEnum::new(action_type).unwrap())
+}
+
+unsafe extern "system" fn get_action_state_boolean(
+    session: xr::Session,
+    get_info: *const xr::ActionStateGetInfo,
+    state: *mut xr::ActionStateBoolean,
+) -> xr::Result {
+    match input_state(session, get_info, ActionType::BooleanInput) {
+        GodActionStateEnum::Boolean(value) => {
+            (*state).current_state = value.current_state.into();
+            (*state).changed_since_last_sync = value.changed_since_last_sync.into();
+            (*state).last_change_time = value.last_change_time;
+            (*state).is_active = value.is_active.into();
+            xr::Result::SUCCESS
+        }
+        _ => xr::Result::ERROR_ACTION_TYPE_MISMATCH,
+    }
+}
+
+unsafe extern "system" fn get_action_state_float(
+    session: xr::Session,
+    get_info: *const xr::ActionStateGetInfo,
+    state: *mut xr::ActionStateFloat,
+) -> xr::Result {
+    match input_state(session, get_info, ActionType::FloatInput) {
+        GodActionStateEnum::Float(value) => {
+            (*state).current_state = value.current_state;
+            (*state).changed_since_last_sync = value.changed_since_last_sync.into();
+            (*state).last_change_time = value.last_change_time;
+            (*state).is_active = value.is_active.into();
+            xr::Result::SUCCESS
+        }
+        _ => xr::Result::ERROR_ACTION_TYPE_MISMATCH,
+    }
+}
+
+unsafe extern "system" fn get_action_state_vector2f(
+    session: xr::Session,
+    get_info: *const xr::ActionStateGetInfo,
+    state: *mut xr::ActionStateVector2f,
+) -> xr::Result {
+    match input_state(session, get_info, ActionType::Vector2fInput) {
+        GodActionStateEnum::Vector2f(value) => {
+            (*state).current_state = xr::Vector2f {
+                x: value.current_state.x,
+                y: value.current_state.y,
+            };
+            (*state).changed_since_last_sync = value.changed_since_last_sync.into();
+            (*state).last_change_time = value.last_change_time;
+            (*state).is_active = value.is_active.into();
+            xr::Result::SUCCESS
+        }
+        _ => xr::Result::ERROR_ACTION_TYPE_MISMATCH,
+    }
+}
+
+unsafe extern "system" fn get_action_state_pose(
+    session: xr::Session,
+    get_info: *const xr::ActionStateGetInfo,
+    state: *mut xr::ActionStatePose,
+) -> xr::Result {
+    match input_state(session, get_info, ActionType::PoseInput) {
+        GodActionStateEnum::Pose(value) => {
+            (*state).is_active = value.is_active.into();
+            xr::Result::SUCCESS
+        }
+        _ => xr::Result::ERROR_ACTION_TYPE_MISMATCH,
+    }
+}
+
+///Records the amplitude of every vibration, see [haptics]
+unsafe extern "system" fn apply_haptic_feedback(
+    session: xr::Session,
+    haptic_action_info: *const xr::HapticActionInfo,
+    haptic_feedback: *const xr::HapticBaseHeader,
+) -> xr::Result {
+    if (*haptic_feedback).ty != xr::HapticVibration::TYPE {
+        return xr::Result::ERROR_VALIDATION_FAILURE;
+    }
+    let vibration = &*(haptic_feedback as *const xr::HapticVibration);
+    HAPTICS
+        .lock()
+        .unwrap()
+        .push((session, (*haptic_action_info).action, vibration.amplitude, vibration.duration.as_nanos()));
+    xr::Result::SUCCESS
+}
+
+///The god action, amplitude and duration in nanoseconds of every vibration the layer has applied on a session, oldest first
+pub fn haptics(session: xr::Session) -> Vec<(xr::Action, f32, i64)> {
+    HAPTICS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(existing, _, _, _)| *existing == session)
+        .map(|(_, action, amplitude, duration)| (*action, *amplitude, *duration))
+        .collect()
+}
+
+///Every frame is predicted to be displayed 10ms after the previous one
+unsafe extern "system" fn wait_frame(
+    _session: xr::Session,
+    _frame_wait_info: *const xr::FrameWaitInfo,
+    frame_state: *mut xr::FrameState,
+) -> xr::Result {
+    let predicted_display_time = FRAME_TIME.fetch_add(10_000_000, Ordering::Relaxed) + 10_000_000;
+    (*frame_state).predicted_display_time = xr::Time::from_nanos(predicted_display_time);
+    (*frame_state).predicted_display_period = xr::Duration::from_nanos(10_000_000);
+    (*frame_state).should_render = true.into();
+    xr::Result::SUCCESS
+}
@@ -0,0 +1,59 @@
+//! What the full layer and the [passthrough](crate::passthrough) build both need where the loader calls into us
+
+use std::ffi::CString;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use openxr::sys as xr;
+use openxr::sys::pfn;
+
+pub const LAYER_NAME: &str = "XR_APILAYER_BULLCH_oxidexr";
+pub const LAYER_VERSION: u32 = 1;
+
+///Looks a function up in the layer below us, None if it isn't there
+///
+///The name is copied into a null terminated string first, a name that contains a null can't be looked up and is reported missing
+pub unsafe fn get_func(get_instance_proc_addr: pfn::GetInstanceProcAddr, instance: xr::Instance, name: &str) -> Option<pfn::VoidFunction> {
+    let name = CString::new(name).ok()?;
+    let mut function = None;
+    if get_instance_proc_addr(instance, name.as_ptr(), &mut function).into_raw() < 0 {
+        return None;
+    }
+    function
+}
+
+///Adds logging through the layer's output to the panic hook, the hook the application had installed still runs after it
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            println!("{} panicked: {}", LAYER_NAME, info);
+            previous_hook(info);
+        }));
+    });
+}
+
+///Runs the body of an exported function, turning any panic into ERROR_RUNTIME_FAILURE so it never unwinds across FFI
+///
+///Also where calls are counted for the metrics trace, which the passthrough build leaves out
+pub fn catch_panic<F: FnOnce() -> xr::Result>(name: &str, body: F) -> xr::Result {
+    #[cfg(feature = "remap")]
+    crate::metrics::count(name);
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(result) => result,
+        Err(_) => {
+            println!("{} failed due to a panic, returning ERROR_RUNTIME_FAILURE", name);
+            xr::Result::ERROR_RUNTIME_FAILURE
+        }
+    }
+}
+
+#[test]
+fn test_catch_panic() {
+    assert_eq!(catch_panic("xrTest", || xr::Result::SUCCESS), xr::Result::SUCCESS);
+    assert_eq!(
+        catch_panic("xrTest", || panic!("deliberate panic")),
+        xr::Result::ERROR_RUNTIME_FAILURE
+    );
+}
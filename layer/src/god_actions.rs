@@ -5,58 +5,178 @@ use common::xrapplication_info::ActionType;
 use crate::path::*;
 
 use openxr::Result;
-use openxr::builder as xr_builder;
 use openxr::sys as xr;
 use openxr::Vector2f;
 
 use core::f32;
 use std::cmp;
 use std::collections::HashMap;
+use std::marker::PhantomData;
 use std::ops::Add;
-use std::ops::Deref;
+use std::os::raw::c_char;
 use std::ptr;
 use std::sync::Arc;
 use std::sync::RwLock;
 
+use crate::emulated;
+use crate::remap::RemappedBinding;
+use crate::util::try_place_cstr;
 use crate::wrappers::ActionWrapper;
 use crate::wrappers::InstanceWrapper;
 use crate::wrappers::SessionWrapper;
 
 pub fn create_god_action_sets(
     instance: &InstanceWrapper,
+) -> Result<HashMap<xr::Path, GodActionSet>> {
+    create_god_action_sets_for(instance, None)
+}
+
+///Creates god action sets for the catalog profiles in `profiles`, or for every catalog profile if None
+///
+///A runtime that runs out of room for action sets or actions (ERROR_LIMIT_REACHED) leaves the sets made so far, the rest of the catalog
+///then can't be remapped from
+pub fn create_god_action_sets_for(
+    instance: &InstanceWrapper,
+    profiles: Option<&[xr::Path]>,
 ) -> Result<HashMap<xr::Path, GodActionSet>> {
     let mut map = HashMap::new();
-    for (profile_name, profile_info) in interaction_profiles::generate().profiles {
-        map.insert(
-            instance.string_to_path(&profile_name)?,
-            GodActionSet::create_set(instance, &profile_name, &profile_info)?,
-        );
+    let mut limit_reached = false;
+    for (profile_name, profile_info) in &interaction_profiles::catalog().profiles {
+        let profile_path = instance.string_to_path(profile_name)?;
+        if matches!(profiles, Some(profiles) if !profiles.contains(&profile_path)) {
+            continue;
+        }
+        let mut profile_info = profile_info.with_extensions(&instance.enabled_extensions);
+        profile_info.subaction_paths.retain(|subaction_path| instance.settings.manages(subaction_path));
+        if profile_info.subaction_paths.is_empty() {
+            println!("{} is left to the runtime, none of its user paths are managed", profile_name);
+            continue;
+        }
+        if limit_reached {
+            println!("{} gets no god set as the runtime's action limit was reached, its bindings go to the runtime unremapped", profile_name);
+            continue;
+        }
+        match GodActionSet::create_set(instance, profile_name, Arc::new(profile_info), &mut limit_reached) {
+            Ok(god_set) => {
+                map.insert(profile_path, god_set);
+            }
+            Err(xr::Result::ERROR_LIMIT_REACHED) => limit_reached = true,
+            Err(result) => return Err(result),
+        }
+        if limit_reached {
+            println!(
+                "the runtime's action limit was reached after {} god actions in {} god action sets, continuing with those",
+                map.values().map(|god_set: &GodActionSet| god_set.god_actions().count()).sum::<usize>(),
+                map.len()
+            );
+        }
     }
     Ok(map)
 }
 
+///Turns a path into a valid action (set) name
+///
+///`_` only ever starts an escape so no two paths share a name, replacing `-` with `--` let `/-` and `-/` collide
 fn sanitize(name: &str) -> String {
-    name.replace("-", "--").replace("/", "-")
+    let mut sanitized = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '_' => sanitized.push_str("__"),
+            '-' => sanitized.push_str("_d"),
+            '/' => sanitized.push('-'),
+            c => sanitized.push(c),
+        }
+    }
+    sanitized
+}
+
+///Copies a generated god action (set) name into its create info, a name the runtime can't take is ERROR_NAME_INVALID rather than a panic
+fn place_name(out: &mut [c_char], name: &str) -> Result<()> {
+    try_place_cstr(out, name).map_err(|too_long| {
+        println!("god action name {} needs {} bytes but only {} fit", name, too_long.required, too_long.available);
+        xr::Result::ERROR_NAME_INVALID
+    })
+}
+
+///An XrActionSetCreateInfo for a god set, like [openxr::builder::ActionSetCreateInfo] but a name that doesn't fit is an error, see [place_name]
+struct GodActionSetCreateInfo {
+    inner: xr::ActionSetCreateInfo,
+}
+
+impl GodActionSetCreateInfo {
+    fn new(name: &str, localized_name: &str, priority: u32) -> Result<Self> {
+        let mut inner = xr::ActionSetCreateInfo {
+            ty: xr::ActionSetCreateInfo::TYPE,
+            next: ptr::null(),
+            action_set_name: [0; xr::MAX_ACTION_SET_NAME_SIZE],
+            localized_action_set_name: [0; xr::MAX_LOCALIZED_ACTION_SET_NAME_SIZE],
+            priority,
+        };
+        place_name(&mut inner.action_set_name, name)?;
+        place_name(&mut inner.localized_action_set_name, localized_name)?;
+        Ok(Self { inner })
+    }
+
+    fn as_raw(&self) -> &xr::ActionSetCreateInfo {
+        &self.inner
+    }
+}
+
+///An XrActionCreateInfo for a god action, like [openxr::builder::ActionCreateInfo] but a name that doesn't fit is an error, see [place_name]
+struct GodActionCreateInfo<'a> {
+    inner: xr::ActionCreateInfo,
+    _subaction_paths: PhantomData<&'a [xr::Path]>,
+}
+
+impl<'a> GodActionCreateInfo<'a> {
+    fn new(name: &str, localized_name: &str, action_type: ActionType, subaction_paths: &'a [xr::Path]) -> Result<Self> {
+        let mut inner = xr::ActionCreateInfo {
+            ty: xr::ActionCreateInfo::TYPE,
+            next: ptr::null(),
+            action_name: [0; xr::MAX_ACTION_NAME_SIZE],
+            action_type: action_type.as_raw(),
+            count_subaction_paths: subaction_paths.len() as u32,
+            subaction_paths: subaction_paths.as_ptr(),
+            localized_action_name: [0; xr::MAX_LOCALIZED_ACTION_NAME_SIZE],
+        };
+        place_name(&mut inner.action_name, name)?;
+        place_name(&mut inner.localized_action_name, localized_name)?;
+        Ok(Self {
+            inner,
+            _subaction_paths: PhantomData,
+        })
+    }
+
+    fn as_raw(&self) -> &xr::ActionCreateInfo {
+        &self.inner
+    }
 }
 
 pub struct GodActionSet {
     pub handle: xr::ActionSet,
     pub subaction_paths: Vec<String>,
-    pub god_actions: HashMap<xr::Path, Arc<GodAction>>,
+    ///The catalog entry this set was generated from
+    pub profile_info: Arc<InteractionProfile>,
+    ///Every input god action keyed by its path relative to the top level user path (e.g. /input/trigger/value)
+    pub input_actions: HashMap<xr::Path, Arc<GodAction>>,
+    ///Every output god action (haptics), kept apart so they are never synced as inputs
+    pub output_actions: HashMap<xr::Path, Arc<GodAction>>,
     pub name: String,
+    ///What the runtime made of the set's suggested bindings, the set is kept even when it rejected them
+    pub suggestion_result: xr::Result,
 }
 
 impl GodActionSet {
+    ///Creates the set and as many of its god actions as the runtime allows, `limit_reached` is set when it refused any for ERROR_LIMIT_REACHED
     fn create_set(
         instance: &InstanceWrapper,
         profile_name: &String,
-        profile_info: &InteractionProfile,
+        profile_info: Arc<InteractionProfile>,
+        limit_reached: &mut bool,
     ) -> Result<Self> {
         let mut handle = xr::ActionSet::NULL;
 
-        let create_info = xr_builder::ActionSetCreateInfo::new()
-            .action_set_name(&sanitize(profile_name))
-            .localized_action_set_name(profile_name);
+        let create_info = GodActionSetCreateInfo::new(&sanitize(profile_name), profile_name, instance.settings.god_set_priority)?;
 
         let result = instance.create_action_set(create_info.as_raw(), &mut handle);
 
@@ -67,8 +187,11 @@ impl GodActionSet {
         let mut god_set = GodActionSet {
             handle,
             subaction_paths: profile_info.subaction_paths.clone(),
-            god_actions: Default::default(),
+            profile_info: profile_info.clone(),
+            input_actions: Default::default(),
+            output_actions: Default::default(),
             name: profile_name.clone(),
+            suggestion_result: xr::Result::SUCCESS,
         };
 
         println!(
@@ -77,25 +200,27 @@ impl GodActionSet {
         );
 
         for (subpath, subpath_info) in &profile_info.subpaths {
-            god_set.create_actions_for_subpath(instance, &subpath, &subpath_info)?;
+            match god_set.create_actions_for_subpath(instance, subpath, subpath_info) {
+                Ok(()) => {}
+                Err(xr::Result::ERROR_LIMIT_REACHED) => {
+                    *limit_reached = true;
+                    break;
+                }
+                Err(result) => return Err(result),
+            }
         }
 
-        let mut bindings = Vec::new();
-
-        for god_action in god_set.god_actions.values() {
-            for subaction_path in &god_action.subaction_paths {
-                let name = instance.path_to_string(*subaction_path)?.add(&god_action.name);
-                bindings.push(xr::ActionSuggestedBinding {
-                    action: god_action.handle,
-                    binding: instance.string_to_path(&name)?,
-                })
-            }
+        let profile = instance.string_to_path(profile_name)?;
+        //The runtime only keeps the last suggestion for a profile, so whatever the application had forwarded goes along
+        let mut bindings = god_set.suggested_bindings(instance)?;
+        if let Some(forwarded) = instance.forwarded_suggestions.read().unwrap().get(&profile) {
+            bindings.extend_from_slice(forwarded);
         }
 
         let suggested_bindings = xr::InteractionProfileSuggestedBinding {
             ty: xr::InteractionProfileSuggestedBinding::TYPE,
             next: ptr::null(),
-            interaction_profile: instance.string_to_path(&profile_name)?,
+            interaction_profile: profile,
             count_suggested_bindings: bindings.len() as u32,
             suggested_bindings: bindings.as_ptr(),
         };
@@ -108,33 +233,99 @@ impl GodActionSet {
         } else {
             println!("loaded profile: {}", profile_name);
         }
+        god_set.suggestion_result = result;
 
         Ok(god_set)
     }
 
+    ///A set for [emulated](crate::emulated) input, it exists only in the layer so none of its handles reach the runtime
+    ///
+    ///`profile_info` is the [emulated profile](emulated::profile)
+    pub fn create_emulated_set(instance: &InstanceWrapper, profile_info: InteractionProfile) -> Result<Self> {
+        let profile_info = Arc::new(profile_info);
+        let mut god_set = GodActionSet {
+            handle: xr::ActionSet::NULL,
+            subaction_paths: profile_info.subaction_paths.clone(),
+            profile_info: profile_info.clone(),
+            input_actions: Default::default(),
+            output_actions: Default::default(),
+            name: String::from(emulated::EMULATED_PROFILE),
+            suggestion_result: xr::Result::SUCCESS,
+        };
+
+        for (subpath, subpath_info) in &profile_info.subpaths {
+            god_set.create_actions_for_subpath(instance, subpath, subpath_info)?;
+        }
+
+        Ok(god_set)
+    }
+
+    ///A binding for every god action on each of its subaction paths
+    pub fn suggested_bindings(&self, instance: &InstanceWrapper) -> Result<Vec<xr::ActionSuggestedBinding>> {
+        let mut bindings = Vec::new();
+        for god_action in self.god_actions() {
+            for subaction_path in &god_action.subaction_paths {
+                let name = instance.path_to_nonempty_string(*subaction_path)?.add(&god_action.name);
+                bindings.push(xr::ActionSuggestedBinding {
+                    action: god_action.handle,
+                    binding: instance.string_to_path(&name)?,
+                })
+            }
+        }
+        Ok(bindings)
+    }
+
+    pub fn god_actions(&self) -> impl Iterator<Item = &Arc<GodAction>> {
+        self.input_actions.values().chain(self.output_actions.values())
+    }
+
+    ///The name, type and subaction paths of every god action in this set, inputs then outputs, each sorted by name
+    ///
+    ///Subaction paths the runtime can't turn back into strings are left out
+    pub fn describe(&self, instance: &InstanceWrapper) -> Vec<(String, ActionType, Vec<String>)> {
+        let describe_all = |actions: &HashMap<xr::Path, Arc<GodAction>>| {
+            let mut described = actions
+                .values()
+                .map(|god_action| {
+                    let subaction_paths = god_action
+                        .subaction_paths
+                        .iter()
+                        .filter_map(|subaction_path| instance.path_to_nonempty_string(*subaction_path).ok())
+                        .collect();
+                    (god_action.name.clone(), god_action.action_type, subaction_paths)
+                })
+                .collect::<Vec<_>>();
+            described.sort_by(|a, b| a.0.cmp(&b.0));
+            described
+        };
+        let mut described = describe_all(&self.input_actions);
+        described.extend(describe_all(&self.output_actions));
+        described
+    }
+
     fn create_actions_for_subpath(
         &mut self,
         instance: &InstanceWrapper,
-        subpath: &String,
+        subpath: &str,
         subpath_info: &Subpath,
     ) -> Result<()> {
         let mut subaction_paths = Vec::new();
         for subaction_path in &self.subaction_paths {
-            if let Some(side) = &subpath_info.side {
-                if subaction_path.ends_with(side) {
-                    subaction_paths.push(instance.string_to_path(subaction_path)?)
-                }
-            } else {
+            if subpath_info.exists_for(subaction_path) {
                 subaction_paths.push(instance.string_to_path(subaction_path)?)
             }
         }
+        //Only on user paths the layer doesn't manage
+        if subaction_paths.is_empty() {
+            return Ok(());
+        }
 
         for feature in &subpath_info.features {
             match feature {
                 interaction_profiles::Feature::Position => {
                     self.create_action(
                         instance,
-                        subpath.clone(),
+                        subpath.to_owned(),
                         Some("x"),
                         subaction_paths.clone(),
                         ActionType::FloatInput,
@@ -142,7 +333,7 @@ impl GodActionSet {
 
                     self.create_action(
                         instance,
-                        subpath.clone(),
+                        subpath.to_owned(),
                         Some("y"),
                         subaction_paths.clone(),
                         ActionType::FloatInput,
@@ -150,7 +341,7 @@ impl GodActionSet {
 
                     self.create_action(
                         instance,
-                        subpath.clone(),
+                        subpath.to_owned(),
                         None,
                         subaction_paths.clone(),
                         ActionType::Vector2fInput,
@@ -159,7 +350,7 @@ impl GodActionSet {
                 interaction_profiles::Feature::Haptic => {
                     self.create_action(
                         instance,
-                        subpath.clone(),
+                        subpath.to_owned(),
                         None,
                         subaction_paths.clone(),
                         ActionType::VibrationOutput,
@@ -168,7 +359,7 @@ impl GodActionSet {
                 _ => {
                     self.create_action(
                         instance,
-                        subpath.clone(),
+                        subpath.to_owned(),
                         Some(feature.to_str()),
                         subaction_paths.clone(),
                         feature.get_type(),
@@ -194,23 +385,26 @@ impl GodActionSet {
             subpath
         };
 
-        let create_info = xr_builder::ActionCreateInfo::new()
-            .action_name(&sanitize(&name))
-            .action_type(action_type.as_raw())
-            .localized_action_name(&name)
-            .subaction_paths(&subaction_paths[..]);
+        let create_info = GodActionCreateInfo::new(&sanitize(&name), &name, action_type, &subaction_paths)?;
 
         println!("Created God Action: {}, {:?}", &name, action_type);
 
         let mut handle = xr::Action::NULL;
-        let result = unsafe {
-            (instance.core.create_action)(self.handle, create_info.as_raw(), &mut handle)
-        };
-        if result.into_raw() < 0 {
-            return Err(result);
+        if self.handle != xr::ActionSet::NULL {
+            let result = unsafe {
+                (instance.core.create_action)(self.handle, create_info.as_raw(), &mut handle)
+            };
+            if result.into_raw() < 0 {
+                return Err(result);
+            }
         }
 
-        self.god_actions.insert(
+        let actions = if action_type.is_input() {
+            &mut self.input_actions
+        } else {
+            &mut self.output_actions
+        };
+        actions.insert(
             instance.string_to_path(&name)?,
             Arc::new(GodAction {
                 handle,
@@ -250,12 +444,17 @@ pub struct OutputBinding {
 
 pub trait Binding {
     fn is_active(&self, session: &SessionWrapper) -> bool;
+    fn subaction_path(&self) -> xr::Path;
 }
 
 impl Binding for InputBinding {
     fn is_active(&self, _: &SessionWrapper) -> bool {
         self.action_state.read().unwrap().get_inner().is_active()
     }
+
+    fn subaction_path(&self) -> xr::Path {
+        self.subaction_path
+    }
 }
 
 impl Binding for OutputBinding {
@@ -265,6 +464,49 @@ impl Binding for OutputBinding {
             TopLevelUserPath(self.subaction_path) as SubactionPath,
         )
     }
+
+    fn subaction_path(&self) -> xr::Path {
+        self.subaction_path
+    }
+}
+
+///What each binding of an action read from its god state in one sync, laid out like the [SubactionBindings] they came from
+pub enum Samples {
+    Singleton(Vec<GodActionStateEnum>),
+    Subactions(HashMap<xr::Path, Vec<GodActionStateEnum>>),
+}
+
+impl Samples {
+    ///Samples every binding exactly once so the subaction states and the main state see the same values
+    ///
+    ///`synced` limits this to the subaction paths an active action set was given, None samples them all.
+    ///An action without subaction paths has nothing to sample on a limited sync. `active_profiles` is passed on to [RemappedBinding::sample]
+    ///
+    ///Each god state is only locked while it is read, none are held once this returns
+    pub fn take(
+        subaction_bindings: &SubactionBindings<RemappedBinding>,
+        synced: Option<&[xr::Path]>,
+        active_profiles: &HashMap<xr::Path, xr::Path>,
+        predicted_display_time: xr::Time,
+    ) -> Option<Self> {
+        let sample_all = |bindings: &[Arc<RemappedBinding>]| {
+            bindings.iter().map(|binding| binding.sample(active_profiles, predicted_display_time)).collect::<Vec<_>>()
+        };
+        match subaction_bindings {
+            SubactionBindings::Singleton(_) if synced.is_some() => None,
+            SubactionBindings::Singleton(bindings) => Some(Samples::Singleton(sample_all(bindings))),
+            SubactionBindings::Subactions(bindings_map) => Some(Samples::Subactions(
+                bindings_map
+                    .iter()
+                    .filter(|(subaction_path, _)| match synced {
+                        Some(synced) => synced.contains(subaction_path),
+                        None => true,
+                    })
+                    .map(|(subaction_path, bindings)| (*subaction_path, sample_all(bindings)))
+                    .collect(),
+            )),
+        }
+    }
 }
 
 pub enum CachedActionStatesEnum {
@@ -277,6 +519,10 @@ pub enum CachedActionStatesEnum {
 pub struct CachedActionStates<T: OxideActionState> {
     pub main_state: T,
     pub subaction_states: Option<HashMap<xr::Path, T>>,
+    ///The last active state of anything currently inactive, what changedSinceLastSync is measured against once it is active again
+    last_active: HashMap<xr::Path, T>,
+    ///What each subaction path last sampled, the main state combines them so a sync limited to one subaction path keeps the others' part
+    subaction_samples: HashMap<xr::Path, Vec<GodActionStateEnum>>,
 }
 pub enum SubactionBindings<T>
 where
@@ -295,44 +541,23 @@ pub enum GodActionStateEnum {
 }
 
 impl<T: Binding> SubactionBindings<T> {
-    pub fn new(
-        instance: &InstanceWrapper,
-        action: &ActionWrapper,
-        profile_map: &HashMap<xr::Path, HashMap<xr::Path, Arc<T>>>,
-    ) -> Self {
+    pub fn new(action: &ActionWrapper, bindings: Vec<Arc<T>>) -> Self {
         let subaction_paths = &action.subaction_paths;
         if subaction_paths.is_empty() {
-            let mut vec = Vec::new();
-
-            for (profile, bindings) in action.bindings.read().unwrap().iter() {
-                let bindings_map = profile_map.get(profile).unwrap();
-                for binding in bindings {
-                    vec.push(bindings_map.get(binding).unwrap().clone());
-                }
-            }
-
-            SubactionBindings::Singleton(vec)
+            SubactionBindings::Singleton(bindings)
         } else {
             let mut map = subaction_paths
                 .iter()
                 .map(|subaction_path| (*subaction_path, Vec::new()))
                 .collect::<HashMap<_, _>>();
 
-            for (profile, bindings) in action.bindings.read().unwrap().iter() {
-                let bindings_map = profile_map.get(profile).unwrap();
-                for binding in bindings {
-                    let binding_str = instance.path_to_string(*binding).unwrap();
-                    let subaction_path = subaction_paths
-                        .iter()
-                        .filter(|subaction_path| {
-                            binding_str
-                                .starts_with(&instance.path_to_string(**subaction_path).unwrap())
-                        })
-                        .next()
-                        .unwrap();
-                    let vec = map.get_mut(subaction_path).unwrap();
-                    println!("{}", binding_str);
-                    vec.push(bindings_map.get(binding).unwrap().clone());
+            for binding in bindings {
+                match map.get_mut(&binding.subaction_path()) {
+                    Some(vec) => vec.push(binding),
+                    None => println!(
+                        "{} has a binding outside of its subaction paths, ignoring it",
+                        action.name
+                    ),
                 }
             }
 
@@ -340,7 +565,21 @@ impl<T: Binding> SubactionBindings<T> {
         }
     }
 
-    pub fn get_matching<'a>(&'a self, subaction_path: xr::Path) -> Result<Vec<&'a Arc<T>>> {
+    ///A copy holding only the bindings `keep` returns true for
+    pub fn filtered<F: Fn(&Arc<T>) -> bool>(&self, keep: F) -> Self {
+        let filter = |bindings: &Vec<Arc<T>>| bindings.iter().filter(|binding| keep(binding)).cloned().collect();
+        match self {
+            SubactionBindings::Singleton(bindings) => SubactionBindings::Singleton(filter(bindings)),
+            SubactionBindings::Subactions(bindings_map) => SubactionBindings::Subactions(
+                bindings_map
+                    .iter()
+                    .map(|(subaction_path, bindings)| (*subaction_path, filter(bindings)))
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn get_matching(&self, subaction_path: xr::Path) -> Result<Vec<&Arc<T>>> {
         if subaction_path == xr::Path::NULL {
             Ok(match self {
                 SubactionBindings::Singleton(state) => state.iter().collect(),
@@ -361,7 +600,7 @@ impl<T: Binding> SubactionBindings<T> {
 }
 
 impl CachedActionStatesEnum {
-    pub fn new(action_type: ActionType, subaction_paths: &Vec<xr::Path>) -> Self {
+    pub fn new(action_type: ActionType, subaction_paths: &[xr::Path]) -> Self {
         match action_type {
             ActionType::BooleanInput => CachedActionStatesEnum::Boolean(CachedActionStates::new(
                 openxr::ActionState::<bool> {
@@ -398,30 +637,79 @@ impl CachedActionStatesEnum {
         }
     }
 
-    pub fn sync(&mut self, subaction_bindings: &SubactionBindings<InputBinding>) -> Result<()> {
-        match self as &mut CachedActionStatesEnum {
-            CachedActionStatesEnum::Boolean(states) => {
-                states.update_from_bindings(subaction_bindings);
-            }
-            CachedActionStatesEnum::Float(states) => {
-                states.update_from_bindings(subaction_bindings);
-            }
-            CachedActionStatesEnum::Vector2f(states) => {
-                states.update_from_bindings(subaction_bindings);
-            }
-            CachedActionStatesEnum::Pose(states) => {
-                states.update_from_bindings(subaction_bindings);
-            }
+    ///Samples the bindings and applies them in one go, xrSyncActions keeps the two apart to follow the lock order on [SessionWrapper]
+    ///
+    ///`predicted_display_time` is the time of the frame the application is working on, see [change_time]
+    #[cfg(test)]
+    pub fn sync(
+        &mut self,
+        subaction_bindings: &SubactionBindings<RemappedBinding>,
+        predicted_display_time: xr::Time,
+    ) -> Result<()> {
+        if let Some(samples) = Samples::take(subaction_bindings, None, &HashMap::new(), predicted_display_time) {
+            self.apply(&samples, predicted_display_time);
         }
         Ok(())
     }
+
+    ///Syncs every state from samples already taken, so the god states don't have to be locked alongside this
+    pub fn apply(&mut self, samples: &Samples, predicted_display_time: xr::Time) {
+        match self as &mut CachedActionStatesEnum {
+            CachedActionStatesEnum::Boolean(states) => states.update_from_samples(samples, predicted_display_time),
+            CachedActionStatesEnum::Float(states) => states.update_from_samples(samples, predicted_display_time),
+            CachedActionStatesEnum::Vector2f(states) => states.update_from_samples(samples, predicted_display_time),
+            CachedActionStatesEnum::Pose(states) => states.update_from_samples(samples, predicted_display_time),
+        }
+    }
+
+    ///Marks every state inactive, for actions whose action set was left out of an xrSyncActions
+    pub fn deactivate(&mut self) {
+        match self {
+            CachedActionStatesEnum::Boolean(states) => states.deactivate(),
+            CachedActionStatesEnum::Float(states) => states.deactivate(),
+            CachedActionStatesEnum::Vector2f(states) => states.deactivate(),
+            CachedActionStatesEnum::Pose(states) => states.deactivate(),
+        }
+    }
+
+    ///The state the application sees when it doesn't filter by subaction path
+    pub fn main_state(&self) -> GodActionStateEnum {
+        match self {
+            CachedActionStatesEnum::Boolean(states) => GodActionStateEnum::Boolean(states.main_state),
+            CachedActionStatesEnum::Float(states) => GodActionStateEnum::Float(states.main_state),
+            CachedActionStatesEnum::Vector2f(states) => GodActionStateEnum::Vector2f(states.main_state),
+            CachedActionStatesEnum::Pose(states) => GodActionStateEnum::Pose(states.main_state),
+        }
+    }
 }
 
-impl<T: OxideActionState> CachedActionStates<T> {
-    pub fn new(default_state: T, subaction_paths: &Vec<xr::Path>) -> Self
-    where
-        T: Clone,
-    {
+///Syncs `state` as [OxideActionState::sync_from_god_states] does, but measures an inactive state coming back against its last active value
+///
+///An inactive state reads as the type's default, so without this a button held across an inactive period would report a fresh press and one released during it no release
+fn sync_tracked<T, I>(
+    state: &mut T,
+    last_active: Option<T>,
+    god_states: I,
+    predicted_display_time: xr::Time,
+) -> Option<T>
+where
+    T: OxideActionState + Clone,
+    I: Iterator<Item = GodActionStateEnum>,
+{
+    let baseline = if state.is_active() { Some(state.clone()) } else { last_active };
+    if let Some(baseline) = &baseline {
+        *state = baseline.clone();
+    }
+    state.sync_from_god_states(god_states, predicted_display_time).unwrap();
+    if state.is_active() {
+        None
+    } else {
+        baseline
+    }
+}
+
+impl<T: OxideActionState + Clone> CachedActionStates<T> {
+    pub fn new(default_state: T, subaction_paths: &[xr::Path]) -> Self {
         let subaction_states = if subaction_paths.is_empty() {
             None
         } else {
@@ -436,10 +724,12 @@ impl<T: OxideActionState> CachedActionStates<T> {
         Self {
             main_state: default_state,
             subaction_states,
+            last_active: HashMap::new(),
+            subaction_samples: HashMap::new(),
         }
     }
 
-    pub fn get_state<'a>(&'a self, subaction_path: xr::Path) -> Result<&'a T> {
+    pub fn get_state(&self, subaction_path: xr::Path) -> Result<&T> {
         if subaction_path == xr::Path::NULL {
             Ok(&self.main_state)
         } else {
@@ -453,39 +743,65 @@ impl<T: OxideActionState> CachedActionStates<T> {
         }
     }
 
-    pub fn update_from_bindings(&mut self, subaction_bindings: &SubactionBindings<InputBinding>) {
-        match subaction_bindings {
-            SubactionBindings::Singleton(bindings) => {
+    pub fn update_from_samples(&mut self, samples: &Samples, predicted_display_time: xr::Time) {
+        match samples {
+            Samples::Singleton(samples) => {
                 debug_assert!(self.subaction_states.is_none());
 
-                self.main_state
-                    .sync_from_god_states(bindings.iter().map(|a| &a.action_state))
-                    .unwrap();
+                self.sync_state(xr::Path::NULL, samples.iter().copied(), predicted_display_time);
             }
-            SubactionBindings::Subactions(bindings_map) => {
-                let subaction_states = self.subaction_states.as_mut().unwrap();
-                debug_assert!(bindings_map.len() <= subaction_states.len());
-
-                for (states, bindings) in
-                    subaction_states
-                        .iter_mut()
-                        .filter_map(|(subaction_path, states)| {
-                            bindings_map
-                                .get(subaction_path)
-                                .map(|bindings| (states, bindings))
-                        })
-                {
-                    states
-                        .sync_from_god_states(bindings.iter().map(|a| &a.action_state))
-                        .unwrap();
+            Samples::Subactions(samples) => {
+                debug_assert!(samples.len() <= self.subaction_states.as_ref().unwrap().len());
+
+                for (subaction_path, samples) in samples {
+                    self.sync_state(*subaction_path, samples.iter().copied(), predicted_display_time);
+                    self.subaction_samples.insert(*subaction_path, samples.clone());
                 }
 
-                self.main_state
-                    .sync_from_god_states(bindings_map.values().flatten().map(|a| &a.action_state))
-                    .unwrap();
+                //Subaction paths left out of a limited sync keep their state, and their part of the main state
+                let main_samples = self.subaction_samples.values().flatten().copied().collect::<Vec<_>>();
+                self.sync_state(xr::Path::NULL, main_samples.into_iter(), predicted_display_time);
             }
         }
     }
+
+    ///Makes the main state and every subaction state inactive, remembering their values for when they are synced again
+    pub fn deactivate(&mut self) {
+        self.subaction_samples.clear();
+        let subaction_paths = self
+            .subaction_states
+            .iter()
+            .flat_map(|subaction_states| subaction_states.keys().copied())
+            .collect::<Vec<_>>();
+        for subaction_path in subaction_paths.into_iter().chain(std::iter::once(xr::Path::NULL)) {
+            self.sync_state(subaction_path, std::iter::empty(), xr::Time::from_nanos(0));
+        }
+    }
+
+    ///Syncs the state of a subaction path, or the main state for [xr::Path::NULL], through [sync_tracked]
+    fn sync_state<I: Iterator<Item = GodActionStateEnum>>(
+        &mut self,
+        subaction_path: xr::Path,
+        god_states: I,
+        predicted_display_time: xr::Time,
+    ) {
+        let state = if subaction_path == xr::Path::NULL {
+            &mut self.main_state
+        } else {
+            match self
+                .subaction_states
+                .as_mut()
+                .and_then(|subaction_states| subaction_states.get_mut(&subaction_path))
+            {
+                Some(state) => state,
+                None => return,
+            }
+        };
+        let last_active = self.last_active.remove(&subaction_path);
+        if let Some(last_active) = sync_tracked(state, last_active, god_states, predicted_display_time) {
+            self.last_active.insert(subaction_path, last_active);
+        }
+    }
 }
 
 impl GodActionStateEnum {
@@ -520,7 +836,7 @@ impl GodActionStateEnum {
         }
     }
 
-    pub fn get_inner<'a>(&'a self) -> &'a dyn OxideActionState {
+    pub fn get_inner(&self) -> &dyn OxideActionState {
         match self {
             GodActionStateEnum::Boolean(inner) => inner,
             GodActionStateEnum::Float(inner) => inner,
@@ -546,7 +862,7 @@ impl InputBinding {
                         state.current_state = state_xr.current_state.into();
                         // println!("{}, {}", state.current_state, state.is_active);
                         state.is_active = state_xr.is_active.into();
-                        state.last_change_time = state_xr.last_change_time.into();
+                        state.last_change_time = state_xr.last_change_time;
                         state.changed_since_last_sync = state_xr.changed_since_last_sync.into();
                     }
                     result
@@ -560,9 +876,9 @@ impl InputBinding {
                 } else {
                     unsafe {
                         let state_xr = state_xr.assume_init();
-                        state.current_state = state_xr.current_state.into();
+                        state.current_state = state_xr.current_state;
                         state.is_active = state_xr.is_active.into();
-                        state.last_change_time = state_xr.last_change_time.into();
+                        state.last_change_time = state_xr.last_change_time;
                         state.changed_since_last_sync = state_xr.changed_since_last_sync.into();
                     }
                     result
@@ -576,9 +892,9 @@ impl InputBinding {
                 } else {
                     unsafe {
                         let state_xr = state_xr.assume_init();
-                        state.current_state = state_xr.current_state.into();
+                        state.current_state = state_xr.current_state;
                         state.is_active = state_xr.is_active.into();
-                        state.last_change_time = state_xr.last_change_time.into();
+                        state.last_change_time = state_xr.last_change_time;
                         state.changed_since_last_sync = state_xr.changed_since_last_sync.into();
                     }
                     result
@@ -632,9 +948,10 @@ pub trait OxideActionState {
     /// Float actions - The current state must be the state of the input with the largest absolute value
     ///
     /// Vector2 actions - The current state must be the state of the input with the longest length
-    fn sync_from_god_states<'a, I: Iterator<Item = &'a RwLock<GodActionStateEnum>>>(
+    fn sync_from_god_states<I: Iterator<Item = GodActionStateEnum>>(
         &mut self,
         god_states: I,
+        predicted_display_time: xr::Time,
     ) -> Result<()>
     where
         Self: Sized;
@@ -645,10 +962,25 @@ pub trait OxideActionState {
     fn is_active(&self) -> bool;
 }
 
+///The time an application state changed at
+///
+///The source's own time is used when it moves forward, but a different binding taking over (or a runtime that doesn't stamp its states) can report a time at or before the last change.
+///That is replaced with the predicted display time of the latest xrWaitFrame, or if the application hasn't called it yet, a nanosecond after the last change so time never goes backwards
+pub fn change_time(candidate: xr::Time, previous: xr::Time, predicted_display_time: xr::Time) -> xr::Time {
+    if candidate.as_nanos() > previous.as_nanos() {
+        candidate
+    } else if predicted_display_time.as_nanos() > previous.as_nanos() {
+        predicted_display_time
+    } else {
+        xr::Time::from_nanos(previous.as_nanos() + 1)
+    }
+}
+
 impl OxideActionState for openxr::ActionState<bool> {
-    fn sync_from_god_states<'a, I: Iterator<Item = &'a RwLock<GodActionStateEnum>>>(
+    fn sync_from_god_states<I: Iterator<Item = GodActionStateEnum>>(
         &mut self,
         god_states: I,
+        predicted_display_time: xr::Time,
     ) -> Result<()>
     where
         Self: Sized,
@@ -660,16 +992,14 @@ impl OxideActionState for openxr::ActionState<bool> {
         let mut new_last_change_time = 0;
 
         //The current state must be the result of a boolean OR of all bound inputs
-        for god_state in god_states
-            .map(|e| e.read().unwrap())
-            .filter(|e| e.get_inner().is_active())
+        for god_state in god_states.filter(|e| e.get_inner().is_active())
         {
             let god_state = god_state.get_inner();
             self.is_active = true;
             if new_last_change_time == 0 {
                 new_last_change_time = god_state.last_change_time()?.as_nanos();
             }
-            if god_state.get_bool()? == true {
+            if god_state.get_bool()? {
                 new_state = true;
                 //We want the time of the earliest change to true
                 new_last_change_time = cmp::min(
@@ -677,7 +1007,7 @@ impl OxideActionState for openxr::ActionState<bool> {
                     god_state.last_change_time()?.as_nanos(),
                 );
             } else {
-                if new_state == false {
+                if !new_state {
                     //We want the time of the latest change to false
                     new_last_change_time = cmp::max(
                         new_last_change_time,
@@ -692,9 +1022,12 @@ impl OxideActionState for openxr::ActionState<bool> {
             self.last_change_time = xr::Time::from_nanos(0);
         } else {
             if self.current_state != new_state {
-                debug_assert!(new_last_change_time > self.last_change_time.as_nanos()); //No time travel please, this crashes for some reason
                 self.current_state = new_state;
-                self.last_change_time = xr::Time::from_nanos(new_last_change_time);
+                self.last_change_time = change_time(
+                    xr::Time::from_nanos(new_last_change_time),
+                    self.last_change_time,
+                    predicted_display_time,
+                );
                 self.changed_since_last_sync = true;
             }
         }
@@ -720,9 +1053,10 @@ impl OxideActionState for openxr::ActionState<bool> {
 }
 
 impl OxideActionState for openxr::ActionState<f32> {
-    fn sync_from_god_states<'a, I: Iterator<Item = &'a RwLock<GodActionStateEnum>>>(
+    fn sync_from_god_states<I: Iterator<Item = GodActionStateEnum>>(
         &mut self,
         states: I,
+        predicted_display_time: xr::Time,
     ) -> Result<()>
     where
         Self: Sized,
@@ -734,10 +1068,7 @@ impl OxideActionState for openxr::ActionState<f32> {
         let mut new_last_change_time = xr::Time::from_nanos(0);
 
         //The current state must be the state of the input with the largest absolute value
-        for iter_state in states
-            .map(|e| e.read().unwrap())
-            .filter(|e| e.get_inner().is_active())
-        {
+        for iter_state in states.filter(|e| e.get_inner().is_active()) {
             let iter_state = iter_state.get_inner();
             self.is_active = true;
             if iter_state.get_scalar()?.abs() >= new_state.abs() {
@@ -751,15 +1082,9 @@ impl OxideActionState for openxr::ActionState<f32> {
             self.last_change_time = xr::Time::from_nanos(0);
         } else {
             if self.current_state != new_state {
-                //This can crash TODO estimate last_change_time when time travel occurs
-                debug_assert!(
-                    new_last_change_time.as_nanos() > self.last_change_time.as_nanos(),
-                    "{} < {}",
-                    new_last_change_time.as_nanos(),
-                    self.last_change_time.as_nanos()
-                ); //No time travel please
                 self.current_state = new_state;
-                self.last_change_time = new_last_change_time;
+                self.last_change_time =
+                    change_time(new_last_change_time, self.last_change_time, predicted_display_time);
                 self.changed_since_last_sync = true;
             }
         }
@@ -786,9 +1111,10 @@ impl OxideActionState for openxr::ActionState<f32> {
 }
 
 impl OxideActionState for openxr::ActionState<Vector2f> {
-    fn sync_from_god_states<'a, I: Iterator<Item = &'a RwLock<GodActionStateEnum>>>(
+    fn sync_from_god_states<I: Iterator<Item = GodActionStateEnum>>(
         &mut self,
         states: I,
+        predicted_display_time: xr::Time,
     ) -> Result<()>
     where
         Self: Sized,
@@ -800,15 +1126,12 @@ impl OxideActionState for openxr::ActionState<Vector2f> {
         let mut new_last_change_time = xr::Time::from_nanos(0);
 
         fn len2(vec: openxr::Vector2f) -> f32 {
-            return vec.x * vec.x + vec.y * vec.y;
+            vec.x * vec.x + vec.y * vec.y
         }
 
         //The current state must be the state of the input with the longest length
-        for iter_state in states
-            .map(|e| e.read().unwrap())
-            .filter(|e| e.get_inner().is_active())
-        {
-            if let GodActionStateEnum::Vector2f(iter_state) = iter_state.deref() {
+        for iter_state in states.filter(|e| e.get_inner().is_active()) {
+            if let GodActionStateEnum::Vector2f(iter_state) = iter_state {
                 self.is_active = true;
                 if len2(iter_state.current_state) >= len2(new_state) {
                     new_state = iter_state.current_state;
@@ -824,9 +1147,9 @@ impl OxideActionState for openxr::ActionState<Vector2f> {
             self.last_change_time = xr::Time::from_nanos(0);
         } else {
             if self.current_state != new_state {
-                debug_assert!(new_last_change_time.as_nanos() > self.last_change_time.as_nanos()); //No time travel please
                 self.current_state = new_state;
-                self.last_change_time = new_last_change_time;
+                self.last_change_time =
+                    change_time(new_last_change_time, self.last_change_time, predicted_display_time);
                 self.changed_since_last_sync = true;
             }
         }
@@ -852,18 +1175,15 @@ impl OxideActionState for openxr::ActionState<Vector2f> {
 }
 
 impl OxideActionState for ActionStatePose {
-    fn sync_from_god_states<'a, I: Iterator<Item = &'a RwLock<GodActionStateEnum>>>(
+    fn sync_from_god_states<I: Iterator<Item = GodActionStateEnum>>(
         &mut self,
-        states: I,
+        mut states: I,
+        _predicted_display_time: xr::Time,
     ) -> Result<()>
     where
         Self: Sized,
     {
-        self.is_active = states
-            .map(|e| e.read().unwrap())
-            .filter(|e| e.get_inner().is_active())
-            .next()
-            .is_some();
+        self.is_active = states.any(|e| e.get_inner().is_active());
         Ok(())
     }
 
@@ -883,3 +1203,151 @@ impl OxideActionState for ActionStatePose {
         Err(xr::Result::ERROR_ACTION_TYPE_MISMATCH)
     }
 }
+
+#[test]
+fn test_sanitize() {
+    assert_eq!(sanitize("/input/grip_surface/pose"), "-input-grip__surface-pose");
+    assert_ne!(sanitize("/a/-b"), sanitize("/a-/b"));
+    assert_ne!(sanitize("/a_d"), sanitize("/a-"));
+
+    //Names of the built in catalog fit, even with escapes
+    for (profile_name, profile_info) in &interaction_profiles::generate().profiles {
+        assert!(sanitize(profile_name).len() < xr::MAX_ACTION_SET_NAME_SIZE, "{} is too long", profile_name);
+        for (subpath, subpath_info) in &profile_info.subpaths {
+            for feature in &subpath_info.features {
+                let name = format!("{}/{}", subpath, feature.to_str());
+                assert!(sanitize(&name).len() < xr::MAX_ACTION_NAME_SIZE, "{} is too long", name);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_overlong_god_action_name() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let profile_name = String::from("/interaction_profiles/valve/index_controller");
+    let mut profile_info = (*interaction_profiles::profile(&profile_name).unwrap()).clone();
+    let trigger = profile_info.subpaths["/input/trigger"].clone();
+    let subpath = format!("/input/{}", "long".repeat(xr::MAX_ACTION_NAME_SIZE / 4));
+    profile_info.subpaths.insert(subpath, trigger);
+
+    //The runtime never sees the name, the set fails to build instead
+    assert_eq!(
+        GodActionSet::create_set(&instance, &profile_name, Arc::new(profile_info), &mut false).err(),
+        Some(xr::Result::ERROR_NAME_INVALID)
+    );
+
+    let mut out = [0; 4];
+    assert_eq!(place_name(&mut out, "abcd"), Err(xr::Result::ERROR_NAME_INVALID));
+    assert_eq!(place_name(&mut out, "abc"), Ok(()));
+}
+
+#[test]
+fn test_input_output_actions() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let index = &instance.god_action_sets()[&instance.string_to_path("/interaction_profiles/valve/index_controller").unwrap()];
+    let haptic = instance.string_to_path("/output/haptic").unwrap();
+    let trigger = instance.string_to_path("/input/trigger/value").unwrap();
+
+    assert!(index.output_actions.contains_key(&haptic));
+    assert!(!index.input_actions.contains_key(&haptic));
+    assert!(index.input_actions.contains_key(&trigger));
+    assert!(index.output_actions.values().all(|action| !action.action_type.is_input()));
+    assert!(index.input_actions.values().all(|action| action.action_type.is_input()));
+}
+
+#[test]
+fn test_describe() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let described = instance.describe_god_actions();
+    let index = &described["/interaction_profiles/valve/index_controller"];
+    let hands = vec![String::from("/user/hand/left"), String::from("/user/hand/right")];
+    let find = |name: &str| index.iter().find(|(action_name, _, _)| action_name == name).cloned();
+
+    assert_eq!(find("/input/trigger/value"), Some((String::from("/input/trigger/value"), ActionType::FloatInput, hands.clone())));
+    assert_eq!(find("/input/trigger/click"), Some((String::from("/input/trigger/click"), ActionType::BooleanInput, hands.clone())));
+    assert_eq!(find("/input/squeeze/value"), Some((String::from("/input/squeeze/value"), ActionType::FloatInput, hands.clone())));
+    assert_eq!(find("/input/squeeze/force"), Some((String::from("/input/squeeze/force"), ActionType::FloatInput, hands.clone())));
+    assert_eq!(find("/input/thumbstick"), Some((String::from("/input/thumbstick"), ActionType::Vector2fInput, hands.clone())));
+    assert_eq!(find("/input/thumbstick/x"), Some((String::from("/input/thumbstick/x"), ActionType::FloatInput, hands.clone())));
+    assert_eq!(find("/output/haptic"), Some((String::from("/output/haptic"), ActionType::VibrationOutput, hands)));
+
+    //Outputs come after every input
+    assert_eq!(index.last().unwrap().1, ActionType::VibrationOutput);
+    let god_set = &instance.god_action_sets()[&instance.string_to_path("/interaction_profiles/valve/index_controller").unwrap()];
+    assert_eq!(index.len(), god_set.input_actions.len() + god_set.output_actions.len());
+}
+
+#[test]
+fn test_god_set_priority() {
+    use common::config::Settings;
+
+    use crate::fake_runtime;
+
+    let default = fake_runtime::create_instance();
+    for god_set in default.god_action_sets().values() {
+        assert_eq!(fake_runtime::action_set_priority(god_set.handle), Some(u32::MAX));
+    }
+
+    let configured = fake_runtime::create_instance_with_settings(Settings {
+        god_set_priority: 7,
+        ..Default::default()
+    });
+    assert!(!configured.god_action_sets().is_empty());
+    for god_set in configured.god_action_sets().values() {
+        assert_eq!(fake_runtime::action_set_priority(god_set.handle), Some(7));
+    }
+}
+
+#[test]
+fn test_reactivation_change_flags() {
+    use crate::fake_runtime;
+
+    let source = fake_runtime::god_input("/user/hand/left/input/select/click", ActionType::BooleanInput);
+    let press = |pressed: bool| {
+        *source.action_state.write().unwrap() = GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+            current_state: pressed,
+            changed_since_last_sync: false,
+            last_change_time: xr::Time::from_nanos(0),
+            is_active: true,
+        })
+    };
+    let bindings = SubactionBindings::Singleton(vec![Arc::new(RemappedBinding::new(source.clone(), None, Default::default()))]);
+    let mut states = CachedActionStatesEnum::new(ActionType::BooleanInput, &Vec::new());
+    let mut sync = |active: bool| {
+        if active {
+            states.sync(&bindings, xr::Time::from_nanos(10)).unwrap();
+        } else {
+            states.deactivate();
+        }
+        match states.main_state() {
+            GodActionStateEnum::Boolean(state) => (state.is_active, state.current_state, state.changed_since_last_sync),
+            _ => panic!("state has the wrong type"),
+        }
+    };
+
+    press(true);
+    assert_eq!(sync(true), (true, true, true));
+    //Inactive states read as false without a change
+    assert_eq!(sync(false), (false, false, false));
+    //Held the whole time, so coming back is not a new press
+    assert_eq!(sync(true), (true, true, false));
+
+    assert_eq!(sync(false), (false, false, false));
+    press(false);
+    //Released while inactive, reported against the last active value
+    assert_eq!(sync(true), (true, false, true));
+    assert_eq!(sync(true), (true, false, false));
+
+    //Several inactive syncs in a row keep the same baseline
+    assert_eq!(sync(false), (false, false, false));
+    press(true);
+    assert_eq!(sync(false), (false, false, false));
+    assert_eq!(sync(true), (true, true, true));
+}
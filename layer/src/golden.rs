@@ -0,0 +1,112 @@
+//! Golden tests locking down how suggested bindings are interpreted
+//!
+//! A capture is the actions.json the layer writes for every application it attaches: its action sets, actions and the bindings it suggested for each profile.
+//! Replaying one through the injections and comparing the effective bindings against the stored remap.json catches refactors that change what a suggestion remaps to
+//!
+//! Set OXIDEXR_UPDATE_GOLDEN to rewrite remap.json from the current behavior instead of comparing against it
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use common::application_bindings::ApplicationBindings;
+use common::xrapplication_info::XrApplicationInfo;
+use openxr::builder as xr_builder;
+use openxr::sys as xr;
+
+use crate::fake_runtime;
+use crate::injections::{create_action, create_action_set};
+use crate::wrappers::*;
+
+const UPDATE_VAR: &str = "OXIDEXR_UPDATE_GOLDEN";
+
+fn golden_dir(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "golden", name].iter().collect()
+}
+
+///Creates the captured action sets and actions on a fresh instance, makes one xrSuggestInteractionProfileBindings call per profile and attaches everything
+pub fn replay(application: &XrApplicationInfo, custom_bindings: ApplicationBindings) -> Arc<SessionWrapper> {
+    let instance = fake_runtime::create_instance_with_bindings(custom_bindings);
+    let session = fake_runtime::new_session(&instance);
+
+    let mut action_sets = Vec::new();
+    let mut suggestions = BTreeMap::<&str, Vec<(xr::Action, &str)>>::new();
+    for (set_name, set_info) in &application.action_sets {
+        let set_create_info = xr_builder::ActionSetCreateInfo::new()
+            .action_set_name(set_name)
+            .localized_action_set_name(&set_info.localized_name);
+        let mut action_set = xr::ActionSet::NULL;
+        assert_eq!(
+            unsafe { create_action_set(instance.handle, set_create_info.as_raw(), &mut action_set) },
+            xr::Result::SUCCESS
+        );
+        action_sets.push(action_set);
+
+        for (action_name, action_info) in &set_info.actions {
+            let subaction_paths = action_info
+                .subaction_paths
+                .iter()
+                .map(|path| instance.string_to_path(path).unwrap())
+                .collect::<Vec<_>>();
+            let action_create_info = xr_builder::ActionCreateInfo::new()
+                .action_name(action_name)
+                .localized_action_name(&action_info.localized_name)
+                .action_type(action_info.action_type.as_raw())
+                .subaction_paths(&subaction_paths);
+            let mut action = xr::Action::NULL;
+            assert_eq!(
+                unsafe { create_action(action_set, action_create_info.as_raw(), &mut action) },
+                xr::Result::SUCCESS
+            );
+
+            for (profile_name, bindings) in &action_info.suggested_bindings {
+                suggestions
+                    .entry(profile_name.as_str())
+                    .or_default()
+                    .extend(bindings.iter().map(|binding| (action, binding.path.as_str())));
+            }
+        }
+    }
+
+    for (profile_name, bindings) in &suggestions {
+        assert_eq!(
+            fake_runtime::suggest(&instance, profile_name, bindings),
+            xr::Result::SUCCESS,
+            "replaying {}",
+            profile_name
+        );
+    }
+
+    assert_eq!(fake_runtime::attach(session, &action_sets), xr::Result::SUCCESS);
+
+    session.get_wrapper().unwrap().clone()
+}
+
+///Replays golden/{name}/actions.json and compares the effective bindings with golden/{name}/remap.json
+fn check_golden(name: &str) {
+    let dir = golden_dir(name);
+    let capture = serde_json::from_str::<XrApplicationInfo>(&fs::read_to_string(dir.join("actions.json")).unwrap()).unwrap();
+    let remap = replay(&capture, Default::default()).effective_config_json();
+
+    let remap_path = dir.join("remap.json");
+    if env::var_os(UPDATE_VAR).is_some() {
+        fs::write(&remap_path, serde_json::to_string_pretty(&remap).unwrap() + "\n").unwrap();
+        return;
+    }
+    let golden = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(&remap_path).unwrap()).unwrap();
+    assert!(
+        remap == golden,
+        "{} no longer remaps like {}, rerun with {} set if the change is intended\n{}",
+        name,
+        remap_path.display(),
+        UPDATE_VAR,
+        serde_json::to_string_pretty(&remap).unwrap()
+    );
+}
+
+#[test]
+fn test_index_application() {
+    check_golden("index_application");
+}
@@ -0,0 +1,233 @@
+//! The bindings a session resolved drawn as a Graphviz DOT graph, for configs with enough composed or transformed bindings that the
+//! effective_bindings.json listing gets hard to follow
+//!
+//! Inputs flow from the physical source through the god action reading it and the binding's transform into the application action,
+//! haptic outputs flow the other way
+
+use std::collections::{HashMap, HashSet};
+
+use common::application_bindings::Transform;
+use common::interaction_profiles;
+
+use crate::wrappers::*;
+
+///Renders [SessionWrapper::effective_bindings] as a DOT digraph, nodes are labeled with what the catalog and the application call them
+pub fn binding_graph_dot(session: &SessionWrapper) -> String {
+    let instance = session.instance();
+
+    //The effective bindings only keep the names of the application actions, not what the application called them
+    let mut localized_names = HashMap::new();
+    let attached_actions = session
+        .input_bindings
+        .get()
+        .into_iter()
+        .flat_map(|input_bindings| input_bindings.values().flat_map(|actions| actions.keys()))
+        .chain(session.output_bindings.get().into_iter().flat_map(|output_bindings| output_bindings.keys()));
+    for action_handle in attached_actions {
+        if let Some(action) = action_handle.get_wrapper() {
+            localized_names.insert(format!("{}/{}", action.action_set().name, action.name), action.localized_name.clone());
+        }
+    }
+
+    let mut graph = Graph::default();
+    for (profile_name, profile_bindings) in &session.effective_bindings().profiles {
+        let profile = match instance.string_to_path(profile_name) {
+            Ok(profile) => profile,
+            Err(_) => continue,
+        };
+        for (action_set_name, action_set_bindings) in &profile_bindings.action_sets {
+            for (action_name, action_bindings) in &action_set_bindings.actions {
+                let full_name = format!("{}/{}", action_set_name, action_name);
+                let app_node = format!("app:{}", full_name);
+                let app_label = match localized_names.get(&full_name) {
+                    Some(localized_name) => format!("{}\n{}", localized_name, full_name),
+                    None => full_name.clone(),
+                };
+                graph.node(&app_node, &app_label, "box, style=bold");
+
+                for binding in &action_bindings.bindings {
+                    let god_action_for = |path: &str| {
+                        let source = instance.string_to_path(path).ok()?;
+                        match session.god_states().get(&profile).and_then(|states| states.get(&source)) {
+                            Some(state) => Some((state.action.name.clone(), true)),
+                            None => session
+                                .god_outputs()
+                                .get(&profile)
+                                .and_then(|outputs| outputs.get(&source))
+                                .map(|output| (output.action.name.clone(), false)),
+                        }
+                    };
+                    let (god_action, is_input) = match god_action_for(&binding.path) {
+                        Some(god_action) => god_action,
+                        None => continue,
+                    };
+
+                    let source_node = graph.source(profile_name, &binding.path);
+                    let god_node = graph.god_action(profile_name, &god_action);
+                    let target = graph.transform(&binding.transform).unwrap_or_else(|| app_node.clone());
+                    if is_input {
+                        graph.edge(&source_node, &god_node, None);
+                        graph.edge(&god_node, &target, binding.y.as_ref().map(|_| "x"));
+                        if target != app_node {
+                            graph.edge(&target, &app_node, None);
+                        }
+                    } else {
+                        if target != app_node {
+                            graph.edge(&app_node, &target, None);
+                        }
+                        graph.edge(&target, &god_node, None);
+                        graph.edge(&god_node, &source_node, None);
+                    }
+
+                    if let Some(y_path) = &binding.y {
+                        if let Some((y_god_action, _)) = god_action_for(y_path) {
+                            let y_source_node = graph.source(profile_name, y_path);
+                            let y_god_node = graph.god_action(profile_name, &y_god_action);
+                            graph.edge(&y_source_node, &y_god_node, None);
+                            graph.edge(&y_god_node, &target, Some("y"));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    graph.finish()
+}
+
+#[derive(Default)]
+struct Graph {
+    lines: Vec<String>,
+    written: HashSet<String>,
+    transforms: usize,
+}
+
+impl Graph {
+    fn node(&mut self, id: &str, label: &str, shape: &str) {
+        if self.written.insert(id.to_owned()) {
+            self.lines.push(format!("    {} [label={}, shape={}];", quote(id), quote(label), shape));
+        }
+    }
+
+    fn edge(&mut self, from: &str, to: &str, label: Option<&str>) {
+        let edge = match label {
+            Some(label) => format!("    {} -> {} [label={}];", quote(from), quote(to), quote(label)),
+            None => format!("    {} -> {};", quote(from), quote(to)),
+        };
+        if self.written.insert(edge.clone()) {
+            self.lines.push(edge);
+        }
+    }
+
+    ///A physical source, labeled the way xrGetInputSourceLocalizedName names it
+    fn source(&mut self, profile_name: &str, path: &str) -> String {
+        let id = format!("source:{}{}", profile_name, path);
+        let label = match source_localized_name(profile_name, path) {
+            Some(localized_name) => format!("{}\n{}", localized_name, path),
+            None => path.to_owned(),
+        };
+        self.node(&id, &label, "box");
+        id
+    }
+
+    fn god_action(&mut self, profile_name: &str, name: &str) -> String {
+        let id = format!("god:{} {}", profile_name, name);
+        self.node(&id, name, "ellipse");
+        id
+    }
+
+    ///Every transform gets a node of its own as even identical ones keep separate state, None for bindings without one
+    fn transform(&mut self, transform: &Transform) -> Option<String> {
+        if transform.is_identity() {
+            return None;
+        }
+        self.transforms += 1;
+        let id = format!("transform:{}", self.transforms);
+        self.node(&id, &describe_transform(transform), "diamond");
+        Some(id)
+    }
+
+    fn finish(self) -> String {
+        let mut dot = String::from("digraph bindings {\n    rankdir=LR;\n");
+        for line in self.lines {
+            dot += &line;
+            dot += "\n";
+        }
+        dot += "}\n";
+        dot
+    }
+}
+
+fn source_localized_name(profile_name: &str, path: &str) -> Option<String> {
+    let profile_info = interaction_profiles::profile(profile_name)?;
+    let component = profile_info.localized_name_for_binding(path)?;
+    let user_path = interaction_profiles::TOP_LEVEL_USER_PATHS
+        .iter()
+        .find(|user_path| path.starts_with(&format!("{}/", user_path)))
+        .and_then(|user_path| interaction_profiles::localized_user_path(user_path));
+    Some(user_path.into_iter().chain([profile_info.title.as_str(), component]).collect::<Vec<_>>().join(" "))
+}
+
+///One `field: value` line for each field the transform sets, as custom_bindings.json would spell it
+fn describe_transform(transform: &Transform) -> String {
+    match serde_json::to_value(transform) {
+        Ok(serde_json::Value::Object(fields)) => fields
+            .iter()
+            .map(|(field, value)| format!("{}: {}", field, value))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => format!("{:?}", transform),
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+#[test]
+fn test_chord_graph() {
+    use common::config::BindingConfigBuilder;
+    use openxr::sys as xr;
+
+    use crate::fake_runtime;
+
+    let profile = "/interaction_profiles/valve/index_controller";
+    let config = BindingConfigBuilder::new()
+        .bind_vector(profile, "gameplay/steer", "/user/hand/right/input/trigger/value", "/user/hand/right/input/squeeze/value")
+        .with_dead_zone(0.25)
+        .build()
+        .unwrap();
+    let instance = fake_runtime::create_instance_with_bindings(config);
+
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, _) = fake_runtime::new_actions(&instance, "gameplay", &[("steer", xr::ActionType::VECTOR2F_INPUT)]);
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let session = session.get_wrapper().unwrap().clone();
+    let god_action = |source: &str| {
+        let states = &session.god_states()[&instance.string_to_path(profile).unwrap()];
+        states[&instance.string_to_path(source).unwrap()].action.name.clone()
+    };
+    let trigger = format!("god:{} {}", profile, god_action("/user/hand/right/input/trigger/value"));
+    let squeeze = format!("god:{} {}", profile, god_action("/user/hand/right/input/squeeze/value"));
+
+    let dot = binding_graph_dot(&session);
+    assert!(dot.starts_with("digraph bindings {"), "{}", dot);
+    for line in &[
+        "\"app:gameplay/steer\" [label=\"steer\\ngameplay/steer\", shape=box, style=bold];".to_owned(),
+        format!(
+            "\"source:{}/user/hand/right/input/trigger/value\" [label=\"Right Hand Valve Index Controller Trigger\\n/user/hand/right/input/trigger/value\", shape=box];",
+            profile
+        ),
+        "\"transform:1\" [label=\"dead_zone: 0.25\", shape=diamond];".to_owned(),
+        format!("\"source:{}/user/hand/right/input/trigger/value\" -> \"{}\";", profile, trigger),
+        format!("\"source:{}/user/hand/right/input/squeeze/value\" -> \"{}\";", profile, squeeze),
+        format!("\"{}\" -> \"transform:1\" [label=\"x\"];", trigger),
+        format!("\"{}\" -> \"transform:1\" [label=\"y\"];", squeeze),
+        "\"transform:1\" -> \"app:gameplay/steer\";".to_owned(),
+    ] {
+        assert!(dot.contains(line.as_str()), "{} is missing from\n{}", line, dot);
+    }
+    //Both halves of the chord feed the one transform, nothing skips it
+    assert!(!dot.contains(&format!("\"{}\" -> \"app:gameplay/steer\"", trigger)));
+}
@@ -2,9 +2,11 @@ use std::path::Path;
 
 use common::serial::CONFIG_DIR;
 use common::application_bindings::*;
+use common::interaction_profiles::InteractionProfile;
 use common::serial::read_json;
 use common::serial::get_uuid;
 use common::serial::write_json;
+use crate::ffi::catch_panic;
 use crate::wrappers::*;
 
 use openxr::sys as xr;
@@ -13,32 +15,202 @@ pub unsafe extern "system" fn suggest_interaction_profile_bindings(
     instance: xr::Instance, 
     suggested_bindings: *const xr::InteractionProfileSuggestedBinding
 ) -> xr::Result {
-    let instance = InstanceWrapper::from_handle_panic(instance);
+    catch_panic("xrSuggestInteractionProfileBindings", || {
+        let instance = InstanceWrapper::from_handle_panic(instance);
 
-    let action_suggested_bindings = std::slice::from_raw_parts((*suggested_bindings).suggested_bindings, (*suggested_bindings).count_suggested_bindings as usize);
+        let action_suggested_bindings = std::slice::from_raw_parts((*suggested_bindings).suggested_bindings, (*suggested_bindings).count_suggested_bindings as usize);
 
-    let profile_path = &(*suggested_bindings).interaction_profile;
+        let profile_path = &(*suggested_bindings).interaction_profile;
 
-    let god_set = instance.god_action_sets.get(&(*suggested_bindings).interaction_profile).unwrap();
-    println!("Bindings: {}", god_set.name);
-    for action_suggested_binding in action_suggested_bindings {
-        let action = ActionWrapper::from_handle_panic(action_suggested_binding.action);
-        let mut action_bindings = action.bindings.write().unwrap();
+        let profile_name = match instance.path_to_nonempty_string(*profile_path) {
+            Ok(profile_name) => profile_name,
+            Err(_) => return xr::Result::ERROR_PATH_INVALID,
+        };
+
+        let (managed_bindings, unmanaged_bindings) = match split_managed(&instance, action_suggested_bindings) {
+            Ok(split) => split,
+            Err(result) => return result,
+        };
+        //A profile bound only on unmanaged paths (e.g. a gamepad while only the hands are managed) is the runtime's alone
+        if managed_bindings.is_empty() && !unmanaged_bindings.is_empty() {
+            return forward_unmanaged(&instance, *profile_path, &unmanaged_bindings, &[]);
+        }
+
+        let profile_info = match instance.profile_info(*profile_path) {
+            Some(profile_info) => profile_info,
+            None => {
+                //Profiles missing from the catalog have no god set to remap through, the runtime gets to decide what to make of them
+                let result = instance.suggest_interaction_profile_bindings(suggested_bindings);
+                if result.into_raw() < 0 {
+                    return result;
+                }
+                //A catalog profile only goes without a god set when the runtime's action limit was reached before it was made
+                if common::interaction_profiles::profile(&profile_name).is_some() {
+                    println!(
+                        "warning: {} has no god set as the runtime's action limit was reached, passing its {} suggested bindings to the runtime without remapping",
+                        profile_name,
+                        action_suggested_bindings.len()
+                    );
+                } else {
+                    println!("{}", unknown_profile_warning(&profile_name, action_suggested_bindings.len()));
+                    instance.unknown_profiles.write().unwrap().insert(profile_name, action_suggested_bindings.len());
+                }
+                return result;
+            }
+        };
+        println!("Bindings: {}", profile_name);
+
+        //Validate everything up front so a rejected call leaves no bindings behind
+        for action_suggested_binding in &managed_bindings {
+            let action = match action_suggested_binding.action.get_wrapper() {
+                Some(action) => action,
+                None => return xr::Result::ERROR_HANDLE_INVALID,
+            };
+            let binding_string = match instance.path_to_nonempty_string(action_suggested_binding.binding) {
+                Ok(binding_string) => binding_string,
+                Err(_) => return xr::Result::ERROR_PATH_INVALID,
+            };
+            if let Err(result) = check_catalog(&profile_info, &action, &binding_string) {
+                return result;
+            }
+        }
+
+        //Otherwise the runtime only ever sees the god bindings, the application's would compete with them
+        let forwarded_managed = if instance.forwards_suggestions() { &managed_bindings[..] } else { &[] };
+        let result = forward_unmanaged(&instance, *profile_path, &unmanaged_bindings, forwarded_managed);
+        if result.into_raw() < 0 {
+            return result;
+        }
+
+        //A repeated profile replaces every earlier suggestion for it, including those for actions this call leaves out
+        let earlier_actions = instance
+            .action_sets
+            .read()
+            .unwrap()
+            .iter()
+            .flat_map(|action_set| action_set.actions.read().unwrap().clone())
+            .collect::<Vec<_>>();
+        for action in earlier_actions {
+            action.bindings.write().unwrap().remove(profile_path);
+        }
 
-        if let Some(bindings) = action_bindings.get_mut(profile_path) {
-            bindings.push(action_suggested_binding.binding);
+        for action_suggested_binding in &managed_bindings {
+            let action = ActionWrapper::from_handle_panic(action_suggested_binding.action);
+            let mut action_bindings = action.bindings.write().unwrap();
+
+            if let Some(bindings) = action_bindings.get_mut(profile_path) {
+                bindings.push(action_suggested_binding.binding);
+            } else {
+                action_bindings.insert(*profile_path, vec![action_suggested_binding.binding]);
+            }
+        }
+
+        update_default_bindings_file(
+            &instance, 
+            action_suggested_bindings,
+            &profile_name
+        );
+
+        xr::Result::SUCCESS
+    })
+}
+
+///Refuses a suggested binding the catalog says can't drive the action
+///
+///Only done with the strict feature, otherwise bad paths are left for the god actions to ignore
+#[cfg(any(test, feature = "strict"))]
+fn check_catalog(profile_info: &InteractionProfile, action: &ActionWrapper, binding_string: &str) -> Result<(), xr::Result> {
+    if !profile_info.accepts_binding(binding_string, action.action_type) {
+        println!("{} cannot be bound to {:?} action {}", binding_string, action.action_type, action.name);
+        return Err(xr::Result::ERROR_PATH_UNSUPPORTED);
+    }
+    Ok(())
+}
+
+#[cfg(not(any(test, feature = "strict")))]
+fn check_catalog(_: &InteractionProfile, _: &ActionWrapper, _: &str) -> Result<(), xr::Result> {
+    Ok(())
+}
+
+///Splits suggested bindings into those on [managed](common::config::Settings::managed_user_paths) user paths and the rest
+fn split_managed(
+    instance: &InstanceWrapper,
+    suggested_bindings: &[xr::ActionSuggestedBinding],
+) -> Result<(Vec<xr::ActionSuggestedBinding>, Vec<xr::ActionSuggestedBinding>), xr::Result> {
+    if instance.settings.manages_every_user_path() {
+        return Ok((suggested_bindings.to_vec(), Vec::new()));
+    }
+    let mut managed = Vec::new();
+    let mut unmanaged = Vec::new();
+    for suggested_binding in suggested_bindings {
+        if suggested_binding.action.get_wrapper().is_none() {
+            return Err(xr::Result::ERROR_HANDLE_INVALID);
+        }
+        let binding_string = instance.path_to_nonempty_string(suggested_binding.binding).map_err(|_| xr::Result::ERROR_PATH_INVALID)?;
+        if instance.settings.manages(&binding_string) {
+            managed.push(*suggested_binding);
         } else {
-            action_bindings.insert(*profile_path, vec![action_suggested_binding.binding]);
+            unmanaged.push(*suggested_binding);
         }
     }
+    Ok((managed, unmanaged))
+}
 
-    update_default_bindings_file(
-        &instance, 
-        action_suggested_bindings,
-        &god_set.name
-    );
+///Suggests the unmanaged bindings (and `managed` ones when those are forwarded too) to the runtime as they are and records them for
+///the profile
+///
+///The runtime only keeps the last suggestion for a profile, so the profile's god bindings go in the same call and a god set made later
+///passes the recorded ones along
+fn forward_unmanaged(
+    instance: &InstanceWrapper,
+    profile: xr::Path,
+    unmanaged: &[xr::ActionSuggestedBinding],
+    managed: &[xr::ActionSuggestedBinding],
+) -> xr::Result {
+    let had_forwarded = instance.forwarded_suggestions.read().unwrap().contains_key(&profile);
+    if unmanaged.is_empty() && managed.is_empty() && !had_forwarded {
+        return xr::Result::SUCCESS;
+    }
+
+    let mut bindings = unmanaged.to_vec();
+    bindings.extend_from_slice(managed);
+    if let Some(god_set) = instance.god_action_sets().get(&profile) {
+        match god_set.suggested_bindings(instance) {
+            Ok(god_bindings) => bindings.extend(god_bindings),
+            Err(result) => return result,
+        }
+    }
+    let suggested_bindings = xr::InteractionProfileSuggestedBinding {
+        ty: xr::InteractionProfileSuggestedBinding::TYPE,
+        next: std::ptr::null(),
+        interaction_profile: profile,
+        count_suggested_bindings: bindings.len() as u32,
+        suggested_bindings: bindings.as_ptr(),
+    };
+    let result = instance.suggest_interaction_profile_bindings(&suggested_bindings);
+    if result.into_raw() < 0 {
+        return result;
+    }
+
+    if !unmanaged.is_empty() {
+        println!("passing {} suggested bindings on unmanaged user paths to the runtime", unmanaged.len());
+    }
+    let mut forwarded_suggestions = instance.forwarded_suggestions.write().unwrap();
+    if unmanaged.is_empty() && managed.is_empty() {
+        forwarded_suggestions.remove(&profile);
+    } else {
+        let mut forwarded = unmanaged.to_vec();
+        forwarded.extend_from_slice(managed);
+        forwarded_suggestions.insert(profile, forwarded);
+    }
+    result
+}
 
-    xr::Result::SUCCESS
+fn unknown_profile_warning(profile_name: &str, binding_count: usize) -> String {
+    format!(
+        "warning: {} is not in the interaction profile catalog, passing its {} suggested bindings to the runtime without remapping",
+        profile_name, binding_count
+    )
 }
 
 fn update_default_bindings_file(instance: &InstanceWrapper, suggested_bindings: &[xr::ActionSuggestedBinding], interaction_profile: &str) {
@@ -46,10 +218,7 @@ fn update_default_bindings_file(instance: &InstanceWrapper, suggested_bindings:
 
     println!("{}", file_path);
 
-    let mut default_bindings = match read_json(&file_path) {
-        Some(default_bindings) => default_bindings,
-        None => ApplicationBindings::default(),
-    };
+    let mut default_bindings = read_json::<ApplicationBindings>(&file_path).unwrap_or_default();
 
     let mut profile = InteractionProfileBindings::default();
 
@@ -69,10 +238,11 @@ fn update_default_bindings_file(instance: &InstanceWrapper, suggested_bindings:
         };
 
         match action_set.actions.get_mut(&action.name) {
-            Some(action) => action.bindings.push(binding_string),
+            Some(action) => action.bindings.push(Binding::new(binding_string)),
             None => {
                 action_set.actions.insert(action.name.clone(), ActionBindings {
-                    bindings: vec![binding_string],
+                    bindings: vec![Binding::new(binding_string)],
+                    unbound: false,
                 });
             },
         }        
@@ -80,5 +250,226 @@ fn update_default_bindings_file(instance: &InstanceWrapper, suggested_bindings:
 
     default_bindings.profiles.insert(interaction_profile.to_owned(), profile);
 
-    write_json(&default_bindings, &Path::new(&file_path));
-}
\ No newline at end of file
+    write_json(&default_bindings, Path::new(&file_path));
+}
+
+pub unsafe extern "system" fn poll_event(
+    instance: xr::Instance,
+    event_data: *mut xr::EventDataBuffer
+) -> xr::Result {
+    catch_panic("xrPollEvent", || {
+        let instance = InstanceWrapper::from_handle_panic(instance);
+
+        let result = (instance.core.poll_event)(instance.handle, event_data);
+        if result != xr::Result::SUCCESS {
+            return result;
+        }
+
+        //Controllers being swapped are seen by the layer before the application, so its next calls already use the new profile
+        if (*event_data).ty == xr::StructureType::EVENT_DATA_INTERACTION_PROFILE_CHANGED {
+            let event = &*(event_data as *const xr::EventDataInteractionProfileChanged);
+            if let Some(session) = event.session.get_wrapper() {
+                session.interaction_profile_changed(&instance);
+            }
+        }
+
+        result
+    })
+}
+
+#[test]
+fn test_suggest_unsupported_binding() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let (_, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+    let action = actions[0].get_wrapper().unwrap().clone();
+
+    let profile_name = "/interaction_profiles/khr/simple_controller";
+    let profile = instance.string_to_path(profile_name).unwrap();
+    let suggest = |binding: &str| fake_runtime::suggest(&instance, profile_name, &[(action.handle, binding)]);
+
+    //The simple controller has no thumbstick
+    assert_eq!(suggest("/user/hand/left/input/thumbstick/click"), xr::Result::ERROR_PATH_UNSUPPORTED);
+    assert!(action.bindings.read().unwrap().is_empty());
+
+    assert_eq!(suggest("/user/hand/left/input/select/click"), xr::Result::SUCCESS);
+    assert_eq!(action.bindings.read().unwrap()[&profile].len(), 1);
+}
+
+#[test]
+fn test_suggest_unknown_profile() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let (_, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+    let action = actions[0].get_wrapper().unwrap().clone();
+
+    let profile_name = "/interaction_profiles/bogus/made_up_controller";
+    assert_eq!(
+        fake_runtime::suggest(&instance, profile_name, &[(action.handle, "/user/hand/left/input/a/click")]),
+        xr::Result::SUCCESS
+    );
+    //Nothing is remapped, the runtime was handed the suggestion as is
+    assert!(action.bindings.read().unwrap().is_empty());
+    assert_eq!(instance.unknown_profiles.read().unwrap().get(profile_name), Some(&1));
+
+    let session = fake_runtime::new_session(&instance);
+    assert_eq!(
+        session.get_wrapper().unwrap().effective_config_json()[profile_name],
+        serde_json::Value::from(UNKNOWN_PROFILE_NOTE)
+    );
+
+    let warning = unknown_profile_warning(profile_name, 1);
+    assert!(warning.contains(profile_name));
+    assert!(warning.contains("1 suggested bindings"));
+}
+
+#[test]
+fn test_suggestions_not_forwarded() {
+    use common::config::Settings;
+
+    use crate::fake_runtime;
+
+    let profile_name = "/interaction_profiles/khr/simple_controller";
+    let forwarded = |settings: Settings| {
+        let instance = fake_runtime::create_instance_with_settings(settings);
+        let (_, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+        assert_eq!(
+            fake_runtime::suggest(&instance, profile_name, &[(actions[0], "/user/hand/left/input/select/click")]),
+            xr::Result::SUCCESS
+        );
+        //Recorded for remapping either way
+        let profile = instance.string_to_path(profile_name).unwrap();
+        assert_eq!(actions[0].get_wrapper().unwrap().bindings.read().unwrap()[&profile].len(), 1);
+
+        let runtime_actions = fake_runtime::suggested_actions(instance.handle, profile);
+        let god_set = &instance.god_action_sets()[&profile];
+        assert!(god_set.god_actions().all(|god_action| runtime_actions.contains(&god_action.handle)));
+        runtime_actions.contains(&actions[0])
+    };
+
+    assert!(!forwarded(Settings::default()));
+    assert!(forwarded(Settings { forward_suggestions: Some(true), ..Default::default() }));
+}
+
+#[test]
+fn test_forwarded_suggestions_remapped() {
+    use common::config::Settings;
+
+    use crate::fake_runtime;
+    use crate::god_actions::GodActionStateEnum;
+
+    let instance = fake_runtime::create_instance_with_settings(Settings { forward_suggestions: Some(true), ..Default::default() });
+    let session = fake_runtime::new_session(&instance);
+    let (gameplay, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+    let jump = actions[0];
+    let profile_name = "/interaction_profiles/khr/simple_controller";
+    let select = "/user/hand/left/input/select/click";
+    assert_eq!(fake_runtime::suggest(&instance, profile_name, &[(jump, select)]), xr::Result::SUCCESS);
+    assert_eq!(fake_runtime::attach(session, &[gameplay]), xr::Result::SUCCESS);
+
+    //The runtime is told about the application's action without losing the god actions it reads from
+    let profile = instance.string_to_path(profile_name).unwrap();
+    let runtime_actions = fake_runtime::suggested_actions(instance.handle, profile);
+    assert!(runtime_actions.contains(&jump));
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let god_action = wrapper.god_states()[&profile][&instance.string_to_path(select).unwrap()].action.handle;
+    assert!(runtime_actions.contains(&god_action));
+
+    fake_runtime::set_input_state(
+        session,
+        god_action,
+        GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+            current_state: true,
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(1),
+            is_active: true,
+        }),
+    );
+    assert_eq!(fake_runtime::sync(session, &[gameplay]), xr::Result::SUCCESS);
+    let state = fake_runtime::boolean_state(session, jump, xr::Path::NULL);
+    assert!(state.is_active && state.current_state);
+}
+
+#[test]
+fn test_repeated_suggestions() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[("fire", xr::ActionType::BOOLEAN_INPUT), ("jump", xr::ActionType::BOOLEAN_INPUT)],
+    );
+    let (fire, jump) = (actions[0], actions[1]);
+
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let profile = instance.string_to_path(profile_name).unwrap();
+    let suggested = [(fire, "/user/hand/right/input/trigger/click"), (jump, "/user/hand/right/input/a/click")];
+    assert_eq!(fake_runtime::suggest(&instance, profile_name, &suggested), xr::Result::SUCCESS);
+    //The second call wins, jump is left out of it so it loses its suggestion too
+    assert_eq!(
+        fake_runtime::suggest(&instance, profile_name, &[(fire, "/user/hand/left/input/trigger/click")]),
+        xr::Result::SUCCESS
+    );
+
+    let left_trigger = instance.string_to_path("/user/hand/left/input/trigger/click").unwrap();
+    assert_eq!(fire.get_wrapper().unwrap().bindings.read().unwrap()[&profile], vec![left_trigger]);
+    assert!(!jump.get_wrapper().unwrap().bindings.read().unwrap().contains_key(&profile));
+
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+    let effective = session.get_wrapper().unwrap().effective_config_json();
+    assert_eq!(
+        effective[profile_name]["gameplay"]["fire"]["bindings"],
+        serde_json::json!(["/user/hand/left/input/trigger/click"])
+    );
+    assert!(effective[profile_name]["gameplay"].get("jump").is_none());
+}
+
+#[test]
+fn test_interaction_profile_changed() {
+    use std::ptr;
+
+    use crate::fake_runtime;
+    use crate::god_actions::GodActionStateEnum;
+    use crate::path::{InteractionProfilePath, TopLevelUserPath};
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+    let wrapper = session.get_wrapper().unwrap().clone();
+
+    let left = TopLevelUserPath(instance.string_to_path(openxr::USER_HAND_LEFT).unwrap());
+    let index = instance.string_to_path("/interaction_profiles/valve/index_controller").unwrap();
+    let trigger = &wrapper.god_states()[&index][&instance.string_to_path("/user/hand/left/input/trigger/click").unwrap()];
+    *trigger.action_state.write().unwrap() = GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+        current_state: true,
+        changed_since_last_sync: true,
+        last_change_time: xr::Time::from_nanos(1),
+        is_active: true,
+    });
+
+    let poll = || {
+        let mut event = xr::EventDataBuffer {
+            ty: xr::EventDataBuffer::TYPE,
+            next: ptr::null(),
+            varying: [0; 4000],
+        };
+        let result = unsafe { poll_event(instance.handle, &mut event) };
+        (result, event.ty)
+    };
+    assert_eq!(poll().0, xr::Result::EVENT_UNAVAILABLE);
+    assert_eq!(wrapper.active_profile(left), InteractionProfilePath(xr::Path::NULL));
+
+    //The Index controller is picked up as soon as the application polls the event, not at its next xrSyncActions
+    fake_runtime::change_interaction_profile(session, left.0, index);
+    assert_eq!(poll(), (xr::Result::SUCCESS, xr::StructureType::EVENT_DATA_INTERACTION_PROFILE_CHANGED));
+    assert_eq!(wrapper.active_profile(left), InteractionProfilePath(index));
+
+    //Turning it off leaves nothing active on that hand
+    fake_runtime::change_interaction_profile(session, left.0, xr::Path::NULL);
+    assert_eq!(poll().0, xr::Result::SUCCESS);
+    assert_eq!(wrapper.active_profile(left), InteractionProfilePath(xr::Path::NULL));
+    assert!(!trigger.action_state.read().unwrap().get_inner().is_active());
+}
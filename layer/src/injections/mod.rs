@@ -7,7 +7,10 @@ use std::ptr;
 use std::sync::Arc;
 use std::sync::RwLock;
 
-use crate::i8_arr_to_owned;
+use crate::metrics;
+use crate::trace;
+use crate::ffi::catch_panic;
+use crate::util::char_arr_to_string_lossy;
 use crate::wrappers::*;
 
 use common::xrapplication_info::ActionType;
@@ -18,29 +21,36 @@ pub unsafe extern "system" fn create_session(
     create_info: *const xr::SessionCreateInfo,
     session: *mut xr::Session,
 ) -> xr::Result {
-    let instance = InstanceWrapper::from_handle_panic(instance);
+    catch_panic("xrCreateSession", || {
+        let instance = InstanceWrapper::from_handle_panic(instance);
 
-    let result = instance.create_session(create_info, session);
+        let result = instance.create_session(create_info, session);
 
-    if result.into_raw() < 0 {
-        return result;
-    }
-
-    let wrapper = match SessionWrapper::new(*session, &instance) {
-        Ok(wrapper) => Arc::new(wrapper),
-        Err(result) => {
-            instance.destroy_session(*session);
+        if result.into_raw() < 0 {
             return result;
         }
-    };
 
-    //Add this session to the wrapper tree
-    instance.sessions.write().unwrap().push(wrapper.clone());
+        //The wrapper is only registered once it is fully built, if building it fails the runtime session
+        //is destroyed (which also detaches the god action sets) so the app never sees a half made session
+        let wrapper = match SessionWrapper::new(*session, &instance) {
+            Ok(wrapper) => Arc::new(wrapper),
+            Err(result) => {
+                instance.destroy_session(*session);
+                *session = xr::Session::NULL;
+                return result;
+            }
+        };
+
+        //Add this session to the wrapper tree
+        instance.sessions.write().unwrap().push(wrapper.clone());
 
-    //Add this session to the wrapper map
-    sessions().insert(*session, wrapper);
+        //Add this session to the wrapper map
+        if let Some(warning) = register_handle(sessions(), *session, wrapper) {
+            println!("{}", warning);
+        }
 
-    result
+        result
+    })
 }
 
 pub unsafe extern "system" fn create_action_set(
@@ -48,32 +58,37 @@ pub unsafe extern "system" fn create_action_set(
     create_info: *const xr::ActionSetCreateInfo,
     action_set: *mut xr::ActionSet,
 ) -> xr::Result {
-    let instance = InstanceWrapper::from_handle_panic(instance);
+    catch_panic("xrCreateActionSet", || {
+        let instance = InstanceWrapper::from_handle_panic(instance);
 
-    let result = instance.create_action_set(create_info, action_set);
+        let result = instance.create_action_set(create_info, action_set);
 
-    if result.into_raw() < 0 {
-        return result;
-    }
+        if result.into_raw() < 0 {
+            return result;
+        }
 
-    let create_info = *create_info;
+        //The runtime has already judged the names, a malformed one is only stored lossily so it can't panic here
+        let create_info = *create_info;
 
-    let wrapper = Arc::new(ActionSetWrapper {
-        handle: *action_set,
-        instance: Arc::downgrade(&instance),
-        actions: RwLock::new(Vec::new()),
-        name: i8_arr_to_owned(&create_info.action_set_name),
-        localized_name: i8_arr_to_owned(&create_info.localized_action_set_name),
-        priority: create_info.priority,
-    });
+        let wrapper = Arc::new(ActionSetWrapper {
+            handle: *action_set,
+            instance: Arc::downgrade(&instance),
+            actions: RwLock::new(Vec::new()),
+            name: char_arr_to_string_lossy(&create_info.action_set_name),
+            localized_name: char_arr_to_string_lossy(&create_info.localized_action_set_name),
+            priority: create_info.priority,
+        });
 
-    //Add this action_set to the wrapper tree
-    instance.action_sets.write().unwrap().push(wrapper.clone());
+        //Add this action_set to the wrapper tree
+        instance.action_sets.write().unwrap().push(wrapper.clone());
 
-    //Add this action_set to the wrapper map
-    action_sets().insert(*action_set, wrapper);
+        //Add this action_set to the wrapper map
+        if let Some(warning) = register_handle(action_sets(), *action_set, wrapper) {
+            println!("{}", warning);
+        }
 
-    result
+        result
+    })
 }
 
 pub unsafe extern "system" fn create_action(
@@ -81,37 +96,68 @@ pub unsafe extern "system" fn create_action(
     create_info: *const xr::ActionCreateInfo,
     action: *mut xr::Action,
 ) -> xr::Result {
-    let action_set = ActionSetWrapper::from_handle_panic(action_set);
+    catch_panic("xrCreateAction", || {
+        let action_set = ActionSetWrapper::from_handle_panic(action_set);
 
-    let result = action_set.create_action(create_info, action);
+        if action_set.is_attached() {
+            return xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED;
+        }
 
-    if result.into_raw() < 0 {
-        return result;
-    }
+        let result = action_set.create_action(create_info, action);
+
+        //Only register the action once the runtime has accepted it
+        if result.into_raw() < 0 {
+            return result;
+        }
+
+        //The runtime has already judged the names, a malformed one is only stored lossily so it can't panic here
+        let create_info = *create_info;
+
+        let wrapper = Arc::new(ActionWrapper {
+            handle: *action,
+            action_set: Arc::downgrade(&action_set),
+            name: char_arr_to_string_lossy(&create_info.action_name),
+            action_type: ActionType::from_raw(create_info.action_type),
+            //subactionPaths may be null when the count is zero
+            subaction_paths: if create_info.count_subaction_paths == 0 {
+                Vec::new()
+            } else {
+                std::slice::from_raw_parts(
+                    create_info.subaction_paths,
+                    create_info.count_subaction_paths as usize,
+                )
+                .to_owned()
+            },
+            localized_name: char_arr_to_string_lossy(&create_info.localized_action_name),
+            bindings: Default::default(),
+        });
+
+        //A conformant runtime rejects this, but if one lets it through the action is still tracked by handle
+        if let Some(warning) = duplicate_name_warning(&action_set, &wrapper.name) {
+            println!("{}", warning);
+        }
+
+        //Add this action to the wrapper tree
+        action_set.actions.write().unwrap().push(wrapper.clone());
+
+        //Add this action to the wrapper map
+        if let Some(warning) = register_handle(actions(), *action, wrapper) {
+            println!("{}", warning);
+        }
+
+        result
+    })
+}
 
-    let create_info = *create_info;
-
-    let wrapper = Arc::new(ActionWrapper {
-        handle: *action,
-        action_set: Arc::downgrade(&action_set),
-        name: i8_arr_to_owned(&create_info.action_name),
-        action_type: ActionType::from_raw(create_info.action_type),
-        subaction_paths: std::slice::from_raw_parts(
-            create_info.subaction_paths,
-            create_info.count_subaction_paths as usize,
-        )
-        .to_owned(),
-        localized_name: i8_arr_to_owned(&create_info.localized_action_name),
-        bindings: Default::default(),
-    });
-
-    //Add this action to the wrapper tree
-    action_set.actions.write().unwrap().push(wrapper.clone());
-
-    //Add this action to the wrapper map
-    actions().insert(*action, wrapper);
-
-    result
+///Action names must be unique within their set, anything looking actions up by name can't tell duplicates apart
+fn duplicate_name_warning(action_set: &ActionSetWrapper, name: &str) -> Option<String> {
+    action_set
+        .actions
+        .read()
+        .unwrap()
+        .iter()
+        .find(|action| action.name == name)
+        .map(|_| format!("{} already has an action named {}, lookups by name will be ambiguous", action_set.name, name))
 }
 
 pub unsafe extern "system" fn create_action_space(
@@ -119,67 +165,69 @@ pub unsafe extern "system" fn create_action_space(
     create_info: *const xr::ActionSpaceCreateInfo,
     handle: *mut xr::Space,
 ) -> xr::Result {
-    let create_info = *create_info;
-    let session = match session.get_wrapper() {
-        Some(session) => session,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    };
-    let action = match create_info.action.get_wrapper() {
-        Some(action) => action,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    };
+    catch_panic("xrCreateActionSpace", || {
+        let create_info = *create_info;
+        let session = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
+        let action = match create_info.action.get_wrapper() {
+            Some(action) => action,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
 
-    if create_info.subaction_path != xr::Path::NULL {
-        if !action.subaction_paths.contains(&create_info.subaction_path) {
+        if create_info.subaction_path != xr::Path::NULL && !action.subaction_paths.contains(&create_info.subaction_path) {
             return xr::Result::ERROR_PATH_UNSUPPORTED;
         }
-    }
 
-    let result = {
-        let create_info = xr::ReferenceSpaceCreateInfo {
-            ty: xr::ReferenceSpaceCreateInfo::TYPE,
-            next: ptr::null(),
-            reference_space_type: xr::ReferenceSpaceType::LOCAL,
-            pose_in_reference_space: create_info.pose_in_action_space,
+        let result = {
+            let create_info = xr::ReferenceSpaceCreateInfo {
+                ty: xr::ReferenceSpaceCreateInfo::TYPE,
+                next: ptr::null(),
+                reference_space_type: xr::ReferenceSpaceType::LOCAL,
+                pose_in_reference_space: create_info.pose_in_action_space,
+            };
+            (session.instance().core.create_reference_space)(session.handle, &create_info, handle)
         };
-        (session.instance().core.create_reference_space)(session.handle, &create_info, handle)
-    };
-    if result.into_raw() < 0 {
-        return result;
-    }
+        if result.into_raw() < 0 {
+            return result;
+        }
 
-    let action_space = Arc::new(ActionSpace {
-        action: action.clone(),
-        subaction_path: create_info.subaction_path,
-        pose_in_action_space: create_info.pose_in_action_space,
+        let action_space = Arc::new(ActionSpace {
+            action: action.clone(),
+            subaction_path: create_info.subaction_path,
+            pose_in_action_space: create_info.pose_in_action_space,
 
-        sync_idx: RwLock::new(0),
+            sync_idx: RwLock::new(0),
 
-        cur_binding: RwLock::new(None),
-    });
+            cur_binding: RwLock::new(None),
+        });
 
-    let wrapper = Arc::new(SpaceWrapper {
-        unchecked_handle: *handle,
-        session: Arc::downgrade(&session),
-        ty: SpaceType::ACTION(action_space.clone()),
-    });
+        let wrapper = Arc::new(SpaceWrapper {
+            unchecked_handle: *handle,
+            session: Arc::downgrade(&session),
+            ty: SpaceType::ACTION(action_space.clone()),
+        });
 
-    match session.action_spaces.get_mut(&action.handle) {
-        Some(mut action_spaces) => action_spaces.push(action_space),
-        None => {
-            session
-                .action_spaces
-                .insert(action.handle, vec![action_space]);
+        match session.action_spaces.get_mut(&action.handle) {
+            Some(mut action_spaces) => action_spaces.push(action_space),
+            None => {
+                session
+                    .action_spaces
+                    .insert(action.handle, vec![action_space]);
+            }
         }
-    }
 
-    //Add this space to the wrapper tree
-    session.spaces.write().unwrap().push(wrapper.clone());
+        //Add this space to the wrapper tree
+        session.spaces.write().unwrap().push(wrapper.clone());
 
-    //Add this space to the wrapper map
-    spaces().insert(*handle, wrapper);
+        //Add this space to the wrapper map
+        if let Some(warning) = register_handle(spaces(), *handle, wrapper) {
+            println!("{}", warning);
+        }
 
-    xr::Result::SUCCESS
+        xr::Result::SUCCESS
+    })
 }
 
 pub unsafe extern "system" fn create_reference_space(
@@ -187,30 +235,34 @@ pub unsafe extern "system" fn create_reference_space(
     create_info: *const xr::ReferenceSpaceCreateInfo,
     handle: *mut xr::Space,
 ) -> xr::Result {
-    let session = match session.get_wrapper() {
-        Some(session) => session,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    };
+    catch_panic("xrCreateReferenceSpace", || {
+        let session = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
 
-    let result =
-        (session.instance().core.create_reference_space)(session.handle, create_info, handle);
-    if result.into_raw() < 0 {
-        return result;
-    }
+        let result =
+            (session.instance().core.create_reference_space)(session.handle, create_info, handle);
+        if result.into_raw() < 0 {
+            return result;
+        }
 
-    let wrapper = Arc::new(SpaceWrapper {
-        unchecked_handle: *handle,
-        session: Arc::downgrade(&session),
-        ty: SpaceType::REFERENCE,
-    });
+        let wrapper = Arc::new(SpaceWrapper {
+            unchecked_handle: *handle,
+            session: Arc::downgrade(&session),
+            ty: SpaceType::REFERENCE,
+        });
 
-    //Add this space to the wrapper tree
-    session.spaces.write().unwrap().push(wrapper.clone());
+        //Add this space to the wrapper tree
+        session.spaces.write().unwrap().push(wrapper.clone());
 
-    //Add this space to the wrapper map
-    spaces().insert(*handle, wrapper);
+        //Add this space to the wrapper map
+        if let Some(warning) = register_handle(spaces(), *handle, wrapper) {
+            println!("{}", warning);
+        }
 
-    result
+        result
+    })
 }
 
 /*
@@ -220,107 +272,121 @@ START DESTRUCTORS
 //TODO clean up this mess using the Drop trait
 
 pub unsafe extern "system" fn destroy_instance(instance: xr::Instance) -> xr::Result {
-    let result = InstanceWrapper::from_handle_panic(instance).destroy_instance();
+    catch_panic("xrDestroyInstance", || {
+        let result = InstanceWrapper::from_handle_panic(instance).destroy_instance();
 
-    if result.into_raw() < 0 {
-        return result;
-    }
+        if result.into_raw() < 0 {
+            return result;
+        }
 
-    destroy_instance_internal(instance);
+        destroy_instance_internal(instance);
 
-    result
+        if trace::enabled(trace::METRICS) {
+            metrics::log();
+        }
+
+        result
+    })
 }
 
 pub unsafe extern "system" fn destroy_session(session: xr::Session) -> xr::Result {
-    let instance = match session.get_wrapper() {
-        Some(session) => session,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    }
-    .instance();
+    catch_panic("xrDestroySession", || {
+        let instance = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        }
+        .instance();
 
-    let result = instance.destroy_session(session);
+        let result = instance.destroy_session(session);
 
-    if result.into_raw() < 0 {
-        return result;
-    }
+        if result.into_raw() < 0 {
+            return result;
+        }
 
-    let session = destroy_session_internal(session);
+        let session = destroy_session_internal(session);
 
-    let mut vec = instance.sessions.write().unwrap();
-    let index = vec.iter().position(|s| Arc::ptr_eq(s, &session)).unwrap();
-    vec.swap_remove(index);
+        let mut vec = instance.sessions.write().unwrap();
+        let index = vec.iter().position(|s| Arc::ptr_eq(s, &session)).unwrap();
+        vec.swap_remove(index);
 
-    result
+        result
+    })
 }
 
 pub unsafe extern "system" fn destroy_action_set(action_set: xr::ActionSet) -> xr::Result {
-    let instance = ActionSetWrapper::from_handle_panic(action_set).instance();
+    catch_panic("xrDestroyActionSet", || {
+        let instance = ActionSetWrapper::from_handle_panic(action_set).instance();
 
-    let result = instance.destroy_action_set(action_set);
+        let result = instance.destroy_action_set(action_set);
 
-    if result.into_raw() < 0 {
-        return result;
-    }
+        if result.into_raw() < 0 {
+            return result;
+        }
 
-    let action_set = destroy_action_set_internal(action_set);
+        let action_set = destroy_action_set_internal(action_set);
 
-    let mut vec = instance.action_sets.write().unwrap();
-    let index = vec
-        .iter()
-        .position(|s| Arc::ptr_eq(s, &action_set))
-        .unwrap();
-    vec.swap_remove(index);
+        let mut vec = instance.action_sets.write().unwrap();
+        let index = vec
+            .iter()
+            .position(|s| Arc::ptr_eq(s, &action_set))
+            .unwrap();
+        vec.swap_remove(index);
 
-    result
+        result
+    })
 }
 
 pub unsafe extern "system" fn destroy_action(action: xr::Action) -> xr::Result {
-    let action_set = ActionWrapper::from_handle_panic(action).action_set();
+    catch_panic("xrDestroyAction", || {
+        let action_set = ActionWrapper::from_handle_panic(action).action_set();
 
-    let result = action_set.instance().destroy_action(action);
+        let result = action_set.instance().destroy_action(action);
 
-    if result.into_raw() < 0 {
-        return result;
-    }
+        if result.into_raw() < 0 {
+            return result;
+        }
 
-    let action = destroy_action_internal(action);
+        let action = destroy_action_internal(action);
 
-    let mut vec = action_set.actions.write().unwrap();
-    let index = vec.iter().position(|s| Arc::ptr_eq(s, &action)).unwrap();
-    vec.swap_remove(index);
+        let mut vec = action_set.actions.write().unwrap();
+        let index = vec.iter().position(|s| Arc::ptr_eq(s, &action)).unwrap();
+        vec.swap_remove(index);
 
-    result
+        result
+    })
 }
 
 pub unsafe extern "system" fn destroy_space(handle: xr::Space) -> xr::Result {
-    let space = match handle.get_wrapper() {
-        Some(space) => space,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    };
-    let session = space.session();
-    let instance = session.instance();
-
-    if let SpaceType::ACTION(action_space) = &space.ty {
-        let mut cur_binding = action_space.cur_binding.write().unwrap();
-        if let Some(cur_binding) = cur_binding.deref() {
-            if let Err(result) = instance.destroy_space(cur_binding.space_handle) {
-                return result;
+    catch_panic("xrDestroySpace", || {
+        let space = match handle.get_wrapper() {
+            Some(space) => space,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
+        let session = space.session();
+        let instance = session.instance();
+
+        if let SpaceType::ACTION(action_space) = &space.ty {
+            let mut cur_binding = action_space.cur_binding.write().unwrap();
+            if let Some(cur_binding) = cur_binding.deref() {
+                if let Err(result) = instance.destroy_space(cur_binding.space_handle) {
+                    return result;
+                }
             }
-        }
-        *cur_binding = None;
-    };
+            *cur_binding = None;
+        };
 
-    if let Err(result) = instance.destroy_space(handle) {
-        return result;
-    }
+        if let Err(result) = instance.destroy_space(handle) {
+            return result;
+        }
 
-    drop(space);
+        drop(space);
 
-    destroy_space_internal(handle);
+        destroy_space_internal(handle);
 
-    println!("Destroyed {:?}", handle);
+        println!("Destroyed {:?}", handle);
 
-    xr::Result::SUCCESS
+        xr::Result::SUCCESS
+    })
 }
 
 fn destroy_instance_internal(handle: xr::Instance) {
@@ -334,13 +400,13 @@ fn destroy_instance_internal(handle: xr::Instance) {
         destroy_action_set_internal(action_set.handle);
     }
 
-    println!("Destroyed {:?}", handle);
+    println!("Destroyed {}", instance.1);
 }
 
 fn destroy_session_internal(handle: xr::Session) -> Arc<SessionWrapper> {
     let session = sessions().remove(&handle).unwrap().1;
 
-    println!("Destroyed {:?}", handle);
+    println!("Destroyed {}", session);
 
     session
 }
@@ -352,7 +418,7 @@ fn destroy_action_set_internal(handle: xr::ActionSet) -> Arc<ActionSetWrapper> {
         destroy_action_internal(action.handle);
     }
 
-    println!("Destroyed {:?}", handle);
+    println!("Destroyed {}", action_set);
 
     action_set
 }
@@ -369,7 +435,7 @@ fn destroy_action_internal(handle: xr::Action) -> Arc<ActionWrapper> {
     //     }
     // }
 
-    println!("Destroyed {:?}", handle);
+    println!("Destroyed {}", action);
 
     action
 }
@@ -391,7 +457,7 @@ fn destroy_space_internal(handle: xr::Space) -> Arc<SpaceWrapper> {
         );
     }
 
-    println!("Destroyed {:?}", handle);
+    println!("Destroyed {}", space);
 
     space
 }
@@ -399,7 +465,146 @@ fn destroy_space_internal(handle: xr::Space) -> Arc<SpaceWrapper> {
 fn remove_matching<T>(vec: &mut Vec<Arc<T>>, to_remove: &Arc<T>) {
     let index = vec
         .iter()
-        .position(|arc| Arc::ptr_eq(arc, &to_remove))
+        .position(|arc| Arc::ptr_eq(arc, to_remove))
         .unwrap();
     vec.swap_remove(index);
 }
+
+#[test]
+fn test_create_session_failure() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    fake_runtime::fail_attach(instance.handle);
+
+    let create_info = xr::SessionCreateInfo {
+        ty: xr::SessionCreateInfo::TYPE,
+        next: ptr::null(),
+        create_flags: xr::SessionCreateFlags::EMPTY,
+        system_id: xr::SystemId::from_raw(1),
+    };
+    let mut session = xr::Session::NULL;
+    let result = unsafe { create_session(instance.handle, &create_info, &mut session) };
+
+    assert_eq!(result, xr::Result::ERROR_VALIDATION_FAILURE);
+    assert_eq!(session, xr::Session::NULL);
+    assert!(instance.sessions.read().unwrap().is_empty());
+    //The runtime did create the session, the layer destroyed it again when attaching the god action sets failed
+    assert_eq!(fake_runtime::allocated(instance.handle.into_raw(), xr::ObjectType::SESSION).len(), 1);
+    assert!(fake_runtime::live_sessions(instance.handle).is_empty());
+    assert!(sessions().iter().all(|session| session.instance().handle != instance.handle));
+}
+
+#[test]
+fn test_create_action_after_attach() {
+    use openxr::builder as xr_builder;
+
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+    let jump = actions[0];
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let action_create_info = xr_builder::ActionCreateInfo::new()
+        .action_name("crouch")
+        .localized_action_name("crouch")
+        .action_type(xr::ActionType::BOOLEAN_INPUT);
+    let mut crouch = xr::Action::NULL;
+    assert_eq!(
+        unsafe { create_action(action_set, action_create_info.as_raw(), &mut crouch) },
+        xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED
+    );
+    assert_eq!(crouch, xr::Action::NULL);
+    assert_eq!(fake_runtime::allocated(action_set.into_raw(), xr::ObjectType::ACTION), vec![jump.into_raw()]);
+    assert_eq!(action_set.get_wrapper().unwrap().actions.read().unwrap().len(), 1);
+}
+
+#[test]
+fn test_create_action_malformed_name() {
+    use openxr::builder as xr_builder;
+
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+
+    let mut set_create_info = *xr_builder::ActionSetCreateInfo::new()
+        .localized_action_set_name("Gameplay")
+        .as_raw();
+    //No terminator anywhere in the array
+    set_create_info.action_set_name = [b'a' as _; xr::MAX_ACTION_SET_NAME_SIZE];
+    let mut action_set = xr::ActionSet::NULL;
+    assert_eq!(
+        unsafe { create_action_set(instance.handle, &set_create_info, &mut action_set) },
+        xr::Result::SUCCESS
+    );
+    assert_eq!(action_set.get_wrapper().unwrap().name, "a".repeat(xr::MAX_ACTION_SET_NAME_SIZE));
+
+    let mut action_create_info = *xr_builder::ActionCreateInfo::new()
+        .localized_action_name("Jump")
+        .action_type(xr::ActionType::BOOLEAN_INPUT)
+        .as_raw();
+    action_create_info.action_name = [0xff_u8 as _; xr::MAX_ACTION_NAME_SIZE];
+    let mut action = xr::Action::NULL;
+    assert_eq!(
+        unsafe { create_action(action_set, &action_create_info, &mut action) },
+        xr::Result::SUCCESS
+    );
+    let wrapper = action.get_wrapper().unwrap();
+    assert_eq!(wrapper.name, "\u{fffd}".repeat(xr::MAX_ACTION_NAME_SIZE));
+    assert_eq!(wrapper.localized_name, "Jump");
+}
+
+#[test]
+fn test_create_action_duplicate_name() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let action_set = fake_runtime::new_action_set(&instance, "gameplay");
+
+    let first = fake_runtime::new_action(action_set, "jump", xr::ActionType::BOOLEAN_INPUT, &[]);
+    let set_wrapper = action_set.get_wrapper().unwrap().clone();
+    assert!(duplicate_name_warning(&set_wrapper, "jump").unwrap().contains("ambiguous"));
+    assert!(duplicate_name_warning(&set_wrapper, "crouch").is_none());
+    let second = fake_runtime::new_action(action_set, "jump", xr::ActionType::BOOLEAN_INPUT, &[]);
+
+    assert_ne!(first, second);
+    assert_eq!(first.get_wrapper().unwrap().name, "jump");
+    assert_eq!(second.get_wrapper().unwrap().name, "jump");
+    assert_eq!(set_wrapper.actions.read().unwrap().len(), 2);
+}
+
+#[test]
+fn test_action_handles() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let (action_set, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[
+            ("jump", xr::ActionType::BOOLEAN_INPUT),
+            ("crouch", xr::ActionType::BOOLEAN_INPUT),
+            ("fire", xr::ActionType::BOOLEAN_INPUT),
+        ],
+    );
+
+    let ordinals = actions
+        .iter()
+        .map(|action| fake_runtime::allocation(action.into_raw()).unwrap())
+        .inspect(|allocation| {
+            assert_eq!(allocation.object_type, xr::ObjectType::ACTION);
+            assert_eq!(allocation.parent, action_set.into_raw());
+        })
+        .map(|allocation| allocation.ordinal)
+        .collect::<Vec<_>>();
+    assert_eq!(ordinals, vec![1, 2, 3]);
+    assert!(actions.windows(2).all(|pair| pair[0].into_raw() < pair[1].into_raw()));
+    assert_eq!(fake_runtime::allocated(action_set.into_raw(), xr::ObjectType::ACTION), actions.iter().map(|action| action.into_raw()).collect::<Vec<_>>());
+
+    let wrappers = actions.iter().map(|action| action.get_wrapper().unwrap().clone()).collect::<Vec<_>>();
+    assert_eq!(wrappers.iter().map(|wrapper| wrapper.name.as_str()).collect::<Vec<_>>(), vec!["jump", "crouch", "fire"]);
+    assert!(!Arc::ptr_eq(&wrappers[0], &wrappers[1]) && !Arc::ptr_eq(&wrappers[1], &wrappers[2]) && !Arc::ptr_eq(&wrappers[0], &wrappers[2]));
+    assert_eq!(action_set.get_wrapper().unwrap().actions.read().unwrap().len(), 3);
+}
@@ -1,17 +1,24 @@
 use core::slice;
-use std::collections::HashMap;
-use std::ops::Deref;
+use std::cmp;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::os::raw::c_char;
 use std::path::Path;
 use std::ptr;
 use std::sync::{Arc, RwLock, Weak};
 
+use crate::emulated;
 use crate::god_actions::{self, Binding, CachedActionStatesEnum, SubactionBindings};
-use crate::path::*;
+use crate::graph;
+use crate::remap;
+use crate::trace;
+use crate::ffi::catch_panic;
+use crate::util::{fill_output_array, fill_output_string};
 use crate::validation::Validate;
 use crate::wrappers::*;
 use common::serial::get_uuid;
 use common::serial::read_json;
 use common::serial::write_json;
+use common::serial::write_json_atomic;
 use common::serial::CONFIG_DIR;
 use common::xrapplication_info::*;
 
@@ -21,221 +28,518 @@ pub unsafe extern "system" fn attach_session_action_sets(
     session: xr::Session,
     attach_info: *const xr::SessionActionSetsAttachInfo,
 ) -> xr::Result {
-    let session = match session.get_wrapper() {
-        Some(session) => session,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    };
-
-    let instance = session.instance();
-
-    let action_sets = std::slice::from_raw_parts(
-        (*attach_info).action_sets,
-        (*attach_info).count_action_sets as usize,
-    );
+    catch_panic("xrAttachSessionActionSets", || {
+        let session = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
 
-    let mut input_bindings_sets = HashMap::new();
-    let mut cached_action_states = HashMap::new();
-    let mut output_bindings = HashMap::new();
+        let instance = session.instance();
 
-    for action_set in action_sets {
-        let action_set = match action_set.get_wrapper() {
-            Some(action_set) => action_set,
-            None => return xr::Result::ERROR_HANDLE_INVALID,
+        //actionSets may be null when the count is zero, the session still ends up attached with nothing to sync
+        let action_sets = if (*attach_info).count_action_sets == 0 {
+            &[]
+        } else {
+            std::slice::from_raw_parts(
+                (*attach_info).action_sets,
+                (*attach_info).count_action_sets as usize,
+            )
         };
 
-        let mut input_bindings = HashMap::new();
+        //The runtime never sees the application's action sets attached so its default bindings can't apply, identity bindings stand in for them
+        if !action_sets.is_empty() && remap::suggested_nothing(&instance) {
+            println!("{} suggested no bindings, binding its actions to the sources they are named after", instance.application_name);
+            remap::derive_identity_bindings(&instance);
+        }
 
-        for action in action_set.actions.read().unwrap().iter() {
-            let bindings = action
-                .bindings
+        //Actions bound only on unmanaged user paths are read by the runtime, which needs their sets attached alongside the god sets
+        let mut runtime_actions = HashMap::new();
+        for action_set in action_sets {
+            let action_set = match action_set.get_wrapper() {
+                Some(action_set) => action_set,
+                None => return xr::Result::ERROR_HANDLE_INVALID,
+            };
+            let actions = action_set
+                .actions
                 .read()
                 .unwrap()
                 .iter()
-                .map(|(p, v)| (p.to_owned(), v.to_owned()))
-                .collect::<Vec<_>>();
+                .filter(|action| remap::is_left_to_runtime(&instance, action))
+                .map(|action| action.handle)
+                .collect::<HashSet<_>>();
+            if !actions.is_empty() {
+                runtime_actions.insert(action_set.handle, actions);
+            }
+        }
+        let runtime_sets = action_sets.iter().filter(|action_set| runtime_actions.contains_key(action_set)).copied().collect::<Vec<_>>();
+
+        //Deferred god action sets only exist once the suggestions are in, so they are created here
+        if session.god_states.get().is_none() {
+            if instance.settings.defer_god_sets {
+                let god_action_sets = instance
+                    .god_action_sets
+                    .get_or_try_init(|| god_actions::create_god_action_sets_for(&instance, Some(&instance.used_profiles())));
+                if let Err(result) = god_action_sets {
+                    println!("failed to create god action sets");
+                    return result;
+                }
+            }
+            if let Err(result) = session.attach_god_action_sets(&instance, &runtime_sets) {
+                return result;
+            }
+        }
 
-            println!(
-                "Attaching: {} to session with {} bindings over {} profiles",
-                action.name,
-                bindings.iter().fold(0, |i, (_, vec)| i + vec.len()),
-                bindings.len()
-            );
+        let mut input_bindings_sets = HashMap::new();
+        let mut cached_action_states = HashMap::new();
+        let mut output_bindings = HashMap::new();
 
-            if action.action_type.is_input() {
-                input_bindings.insert(
-                    action.handle,
-                    RwLock::new(SubactionBindings::new(
-                        &instance,
-                        &action,
-                        &session.god_states,
-                    )),
-                );
-                cached_action_states.insert(
-                    action.handle,
-                    RwLock::new(CachedActionStatesEnum::new(
-                        action.action_type,
-                        &action.subaction_paths,
-                    )),
-                );
+        for action_set in action_sets {
+            let action_set = match action_set.get_wrapper() {
+                Some(action_set) => action_set,
+                None => return xr::Result::ERROR_HANDLE_INVALID,
+            };
 
-                for (profile_name, bindings) in action.bindings.read().unwrap().iter() {
-                    println!(" {}", instance.path_to_string(*profile_name).unwrap());
-                    let states = session.god_states.get(profile_name).unwrap();
-                    for binding in bindings {
-                        println!("  {}", &states.get(&binding).unwrap().binding_str);
-                    }
+            let mut input_bindings = HashMap::new();
+
+            for action in action_set.actions.read().unwrap().iter() {
+                if matches!(runtime_actions.get(&action_set.handle), Some(actions) if actions.contains(&action.handle)) {
+                    println!("Attaching: {} to {}, it is only bound on unmanaged user paths and left to the runtime", action, *session);
+                    continue;
                 }
-            } else {
-                output_bindings.insert(
-                    action.handle,
-                    RwLock::new(SubactionBindings::new(
-                        &instance,
-                        &action,
-                        &session.god_outputs,
-                    )),
+
+                let bindings = action
+                    .bindings
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .map(|(p, v)| (p.to_owned(), v.to_owned()))
+                    .collect::<Vec<_>>();
+
+                println!(
+                    "Attaching: {} to {} with {} bindings over {} profiles",
+                    action,
+                    *session,
+                    bindings.iter().fold(0, |i, (_, vec)| i + vec.len()),
+                    bindings.len()
                 );
 
-                for (profile_name, bindings) in action.bindings.read().unwrap().iter() {
-                    println!(" {}", instance.path_to_string(*profile_name).unwrap());
-                    let outputs = session.god_outputs.get(profile_name).unwrap();
-                    for binding in bindings {
-                        println!("  {}", &outputs.get(&binding).unwrap().binding_str);
+                if action.action_type.is_input() {
+                    input_bindings.insert(
+                        action.handle,
+                        RwLock::new(SubactionBindings::new(
+                            action,
+                            remap::resolve_input_bindings(&instance, action, session.god_states()),
+                        )),
+                    );
+                    cached_action_states.insert(
+                        action.handle,
+                        RwLock::new(CachedActionStatesEnum::new(
+                            action.action_type,
+                            &action.subaction_paths,
+                        )),
+                    );
+
+                    for (profile_name, bindings) in action.bindings.read().unwrap().iter() {
+                        println!(" {}", instance.path_to_string(*profile_name).unwrap());
+                        let states = session.god_states().get(profile_name);
+                        for binding in bindings {
+                            match states.and_then(|states| states.get(binding)) {
+                                Some(god) => println!("  {}", &god.binding_str),
+                                None => println!("  {} has no god action", instance.path_to_string(*binding).unwrap()),
+                            }
+                        }
+                    }
+                } else if action.is_passthrough() {
+                    println!(" {} is left to the runtime", action);
+                } else {
+                    let bindings = SubactionBindings::new(action, remap::resolve_output_bindings(&instance, action, session.god_outputs()));
+                    output_bindings.insert(action.handle, RwLock::new(bindings));
+
+                    for (profile_name, bindings) in action.bindings.read().unwrap().iter() {
+                        println!(" {}", instance.path_to_string(*profile_name).unwrap());
+                        let outputs = session.god_outputs().get(profile_name);
+                        for binding in bindings {
+                            match outputs.and_then(|outputs| outputs.get(binding)) {
+                                Some(god) => println!("  {}", &god.binding_str),
+                                None => println!("  {} has no god action", instance.path_to_string(*binding).unwrap()),
+                            }
+                        }
                     }
                 }
             }
+            input_bindings_sets.insert(action_set.handle, input_bindings);
         }
-        input_bindings_sets.insert(action_set.handle, input_bindings);
-    }
 
-    if let Err(_) = session.input_bindings.set(input_bindings_sets) {
-        return xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED;
-    }
-    if let Err(_) = session.cached_action_states.set(cached_action_states) {
-        return xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED;
-    }
-    if let Err(_) = session.output_bindings.set(output_bindings) {
-        return xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED;
-    }
+        if session.input_bindings.set(input_bindings_sets).is_err() {
+            return xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED;
+        }
+        if session.cached_action_states.set(cached_action_states).is_err() {
+            return xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED;
+        }
+        if session.output_bindings.set(output_bindings).is_err() {
+            return xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED;
+        }
+        if session.runtime_actions.set(runtime_actions).is_err() {
+            return xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED;
+        }
+
+        update_application_actions(&session.instance(), action_sets);
+
+        if trace::enabled(trace::EFFECTIVE_CONFIG) {
+            let path_str = format!(
+                "{}{}/effective_bindings.json",
+                CONFIG_DIR,
+                get_uuid(&instance.application_name)
+            );
+            write_json(&session.effective_config_json(), Path::new(&path_str));
+        }
 
-    update_application_actions(&session.instance(), &action_sets);
+        if trace::enabled(trace::BINDING_GRAPH) {
+            let path_str = format!(
+                "{}{}/binding_graph.dot",
+                CONFIG_DIR,
+                get_uuid(&instance.application_name)
+            );
+            if let Err(why) = std::fs::write(&path_str, graph::binding_graph_dot(&session)) {
+                println!("couldn't write {}: {}", path_str, why);
+            }
+        }
+
+        if let Some(snapshot_path) = &instance.settings.snapshot_path {
+            let snapshot_path = snapshot_path.replace("{application}", &get_uuid(&instance.application_name));
+            if let Err(why) = write_json_atomic(&session.snapshot_json(), Path::new(&snapshot_path)) {
+                println!("couldn't write snapshot {}: {}", snapshot_path, why);
+            }
+        }
 
-    xr::Result::SUCCESS
+        xr::Result::SUCCESS
+    })
 }
 
 pub unsafe extern "system" fn sync_actions(
     session: xr::Session,
     app_sync_info: *const xr::ActionsSyncInfo,
 ) -> xr::Result {
-    let session = match session.get_wrapper() {
-        Some(session) => session,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    };
-    let instance = session.instance();
-
-    let result = {
-        let god_sets = instance
-            .god_action_sets
-            .values()
-            .map(|god_set| xr::ActiveActionSet {
-                action_set: god_set.handle,
-                subaction_path: xr::Path::NULL,
-            })
-            .collect::<Vec<_>>();
+    catch_panic("xrSyncActions", || {
+        let session = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
+        let instance = session.instance();
 
-        session.sync_actions(&xr::ActionsSyncInfo {
-            ty: xr::ActionsSyncInfo::TYPE,
-            next: ptr::null(),
-            count_active_action_sets: god_sets.len() as u32,
-            active_action_sets: god_sets.as_ptr(),
-        })
-    };
-    if result.into_raw() < 0 {
-        return result;
-    }
+        //The god states are built and their sets attached in xrCreateSession (or xrAttachSessionActionSets when deferred), without them there is nothing to remap from
+        if session.god_states().is_empty() {
+            println!("xrSyncActions: {} has no god states, its god action sets were never attached", *session);
+            return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED;
+        }
 
-    //Update the active profile for each user path TODO: listen to XR_TYPE_EVENT_DATA_INTERACTION_PROFILE_CHANGED
-    for (user_path, active_profile) in &session.active_profiles {
-        let mut profile_state = xr::InteractionProfileState {
-            ty: xr::InteractionProfileState::TYPE,
-            next: ptr::null_mut(),
-            interaction_profile: xr::Path::NULL,
+        //activeActionSets may be null when the count is zero
+        let active_action_sets = if (*app_sync_info).count_active_action_sets == 0 {
+            &[]
+        } else {
+            std::slice::from_raw_parts(
+                (*app_sync_info).active_action_sets,
+                (*app_sync_info).count_active_action_sets as usize,
+            )
         };
 
-        let result = (instance.core.get_current_interaction_profile)(
-            session.handle,
-            user_path.0,
-            &mut profile_state,
-        );
+        let result = {
+            //Application sets with actions left to the runtime are synced there as the application asked
+            let runtime_sets = active_action_sets.iter().filter(|active_action_set| {
+                matches!(session.runtime_actions.get(), Some(runtime_actions) if runtime_actions.contains_key(&active_action_set.action_set))
+            });
+            let god_sets = instance
+                .god_action_sets_in_order()
+                .into_iter()
+                .map(|god_set| xr::ActiveActionSet {
+                    action_set: god_set.handle,
+                    subaction_path: xr::Path::NULL,
+                })
+                .chain(runtime_sets.copied())
+                .collect::<Vec<_>>();
 
+            session.sync_actions(&xr::ActionsSyncInfo {
+                ty: xr::ActionsSyncInfo::TYPE,
+                next: ptr::null(),
+                count_active_action_sets: god_sets.len() as u32,
+                active_action_sets: god_sets.as_ptr(),
+            })
+        };
         if result.into_raw() < 0 {
-            // panic!("user path does not exist: {}", instance.path_to_string(user_path.0).unwrap());
+            return result;
+        }
+        #[cfg(any(test, feature = "sync_timing"))]
+        let _sync_timer = crate::metrics::SyncTimer::start(&session.sync_timing);
+
+        let emulated_profile = instance
+            .emulated_set
+            .as_ref()
+            .and_then(|emulated_set| instance.string_to_path(&emulated_set.name).ok());
+        for (profile_name, god_states) in session.god_states() {
+            if Some(*profile_name) == emulated_profile {
+                emulated::sync(god_states, &instance.host_input, session.predicted_display_time());
+                continue;
+            }
+            for god_state in god_states.values() {
+                //Check if the state has more than one reference since states with only one reference are not being used
+                if Arc::strong_count(god_state) > 1 {
+                    god_state.sync(&session).unwrap();
+                }
+            }
         }
 
-        *active_profile.write().unwrap() =
-            InteractionProfilePath(profile_state.interaction_profile);
-    }
+        //Update the active profile for each user path now the god states are fresh
+        session.refresh_active_profiles(&instance);
+        let active_profiles = session.active_profile_snapshot();
+        session.poll_reset_sources();
 
-    for god_state in session
-        .god_states
-        .values()
-        .map(|map| map.values())
-        .flatten()
-    {
-        //Check if the state has more than one reference since states with only one reference are not being used
-        if Arc::strong_count(god_state) > 1 {
-            god_state.sync(&session).unwrap();
+        let sync_idx = {
+            let mut sync_idx = session.sync_idx.write().unwrap();
+            *sync_idx += 1;
+            *sync_idx
+        };
+
+        let (attached_actions, cached_action_states) =
+            match (session.input_bindings.get(), session.cached_action_states.get()) {
+                (Some(attached_actions), Some(cached_action_states)) => {
+                    (attached_actions, cached_action_states)
+                }
+                _ if active_action_sets.is_empty() => return result,
+                _ => return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED,
+            };
+        let priorities = match sync_priorities(&*app_sync_info, active_action_sets) {
+            Ok(priorities) => priorities,
+            Err(result) => return result,
+        };
+        let synced_sets = match synced_subaction_paths(active_action_sets) {
+            Ok(synced_sets) => synced_sets,
+            Err(result) => return result,
+        };
+        let suppressed_sources = suppressed_sources(attached_actions, &priorities);
+        let mut remap_trace = trace::RemapTrace::begin();
+        for (action_set, synced) in &synced_sets {
+            let actions = match attached_actions.get(action_set) {
+                Some(actions) => actions,
+                None => return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED,
+            };
+            let suppressed = suppressed_sources.get(action_set);
+            for (action_handle, subaction_bindings) in actions {
+                let subaction_bindings = subaction_bindings.read().unwrap();
+                //Sources a higher priority set binds act as if this set never bound them
+                let unsuppressed;
+                let subaction_bindings = match suppressed {
+                    Some(suppressed) if !suppressed.is_empty() => {
+                        unsuppressed = subaction_bindings.filtered(|binding| {
+                            !binding_sources(binding).any(|source| suppressed.contains(&Arc::as_ptr(source)))
+                        });
+                        &unsuppressed
+                    }
+                    _ => &*subaction_bindings,
+                };
+
+                //The god states are sampled and released before the cached state is locked, see the lock order on SessionWrapper
+                let samples = god_actions::Samples::take(
+                    subaction_bindings,
+                    synced.as_deref(),
+                    &active_profiles,
+                    session.predicted_display_time(),
+                );
+                let main_state = {
+                    let mut action_cache_states = cached_action_states.get(action_handle).unwrap().write().unwrap();
+                    if let Some(samples) = &samples {
+                        action_cache_states.apply(samples, session.predicted_display_time());
+                    }
+                    action_cache_states.main_state()
+                };
+
+                if let Some(remap_trace) = &mut remap_trace {
+                    let action = action_handle.get_wrapper().unwrap();
+                    remap_trace.record(
+                        &format!("{}/{}", action.action_set().name, action.name),
+                        subaction_bindings,
+                        &main_state,
+                    );
+                }
+
+                if let god_actions::GodActionStateEnum::Pose(_) = main_state {
+                    if let Some(action_spaces) = session.action_spaces.get_mut(action_handle) {
+                        for action_space in action_spaces.iter() {
+                            if let Err(result) =
+                                action_space.sync(&session, sync_idx, subaction_bindings)
+                            {
+                                return result;
+                            }
+                        }
+                    }
+                }
+            }
         }
-    }
 
-    let sync_idx = {
-        let mut sync_idx = session.sync_idx.write().unwrap();
-        *sync_idx += 1;
-        *sync_idx
-    };
+        //Actions of attached sets left out of this sync are inactive until a sync includes their set again
+        for (action_set, actions) in attached_actions {
+            if active_action_sets.iter().any(|active_action_set| active_action_set.action_set == *action_set) {
+                continue;
+            }
+            for action_handle in actions.keys() {
+                cached_action_states.get(action_handle).unwrap().write().unwrap().deactivate();
+            }
+        }
 
-    let active_action_sets = std::slice::from_raw_parts(
-        (*app_sync_info).active_action_sets,
-        (*app_sync_info).count_active_action_sets as usize,
-    );
-    let attached_actions = session.input_bindings.get().unwrap();
-    let cached_action_states = session.cached_action_states.get().unwrap();
-    for active_action_set in active_action_sets {
-        if active_action_set.action_set.get_wrapper().is_none() {
-            return xr::Result::ERROR_HANDLE_INVALID;
+        if let Some(remap_trace) = remap_trace {
+            remap_trace.finish(sync_idx);
         }
-        let actions = match attached_actions.get(&active_action_set.action_set) {
-            Some(actions) => actions,
-            None => return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED,
+
+        result
+    })
+}
+
+///Forwarded untouched, the layer only keeps the predicted display time to stamp remapped state changes with
+pub unsafe extern "system" fn wait_frame(
+    session: xr::Session,
+    frame_wait_info: *const xr::FrameWaitInfo,
+    frame_state: *mut xr::FrameState,
+) -> xr::Result {
+    catch_panic("xrWaitFrame", || {
+        let session = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
         };
-        assert_eq!(active_action_set.subaction_path, xr::Path::NULL); //TODO decipher how active_action_set.subaction_path is actually supposed to work
-        for (action_handle, subaction_bindings) in actions {
-            let mut action_cache_states = cached_action_states
-                .get(action_handle)
-                .unwrap()
-                .write()
-                .unwrap();
 
-            let subaction_bindings = subaction_bindings.read().unwrap();
+        let result = session.wait_frame(frame_wait_info, frame_state);
+        if result.into_raw() >= 0 {
+            session.predicted_display_time.store(
+                (*frame_state).predicted_display_time.as_nanos(),
+                std::sync::atomic::Ordering::Relaxed,
+            );
+        }
+        result
+    })
+}
 
-            if let Err(result) = action_cache_states.sync(&subaction_bindings) {
-                return result;
+///Each active action set once, with the subaction paths it syncs or None when it syncs all of them
+///
+///A set may be listed once per subaction path to sync several hands, listing it with XR_NULL_PATH as well syncs it whole.
+///The subaction path has to be one an action of the set was created with
+fn synced_subaction_paths(active_action_sets: &[xr::ActiveActionSet]) -> Result<Vec<(xr::ActionSet, Option<Vec<xr::Path>>)>> {
+    let mut synced_sets = Vec::<(xr::ActionSet, Option<Vec<xr::Path>>)>::new();
+    for active_action_set in active_action_sets {
+        let action_set = match active_action_set.action_set.get_wrapper() {
+            Some(action_set) => action_set,
+            None => return Err(xr::Result::ERROR_HANDLE_INVALID),
+        };
+        let subaction_path = active_action_set.subaction_path;
+        if subaction_path != xr::Path::NULL
+            && !action_set.actions.read().unwrap().iter().any(|action| action.subaction_paths.contains(&subaction_path))
+        {
+            return Err(xr::Result::ERROR_PATH_UNSUPPORTED);
+        }
+
+        let synced = match synced_sets.iter_mut().find(|(synced_set, _)| *synced_set == action_set.handle) {
+            Some((_, synced)) => synced,
+            None => {
+                synced_sets.push((action_set.handle, Some(Vec::new())));
+                &mut synced_sets.last_mut().unwrap().1
             }
+        };
+        if subaction_path == xr::Path::NULL {
+            *synced = None;
+        } else if let Some(synced) = synced {
+            synced.push(subaction_path);
+        }
+    }
+    Ok(synced_sets)
+}
 
-            if let god_actions::CachedActionStatesEnum::Pose(_) = action_cache_states.deref() {
-                if let Some(action_spaces) = session.action_spaces.get_mut(action_handle) {
-                    for action_space in action_spaces.iter() {
-                        if let Err(result) =
-                            action_space.sync(&session, sync_idx, &subaction_bindings)
-                        {
-                            return result;
-                        }
+///The priority of every active action set for this sync, an XrActiveActionSetPrioritiesEXT in the next chain overrides the priority a set was created with
+///
+///The god sets all share one priority so the overrides are never forwarded to the runtime, the app's sets are only ever synced by the layer
+unsafe fn sync_priorities(
+    sync_info: &xr::ActionsSyncInfo,
+    active_action_sets: &[xr::ActiveActionSet],
+) -> Result<HashMap<xr::ActionSet, u32>> {
+    let mut priorities = HashMap::new();
+    for active_action_set in active_action_sets {
+        match active_action_set.action_set.get_wrapper() {
+            Some(action_set) => priorities.insert(action_set.handle, action_set.priority),
+            None => return Err(xr::Result::ERROR_HANDLE_INVALID),
+        };
+    }
+
+    let mut next = sync_info.next as *const xr::BaseInStructure;
+    while !next.is_null() {
+        if (*next).ty == xr::ActiveActionSetPrioritiesEXT::TYPE {
+            let overrides = &*(next as *const xr::ActiveActionSetPrioritiesEXT);
+            if overrides.action_set_priority_count > 0 {
+                let overrides = slice::from_raw_parts(overrides.action_set_priorities, overrides.action_set_priority_count as usize);
+                for priority in overrides {
+                    //Overriding a set that isn't active this sync is invalid usage
+                    match priorities.get_mut(&priority.action_set) {
+                        Some(active_priority) => *active_priority = priority.priority_override,
+                        None => return Err(xr::Result::ERROR_VALIDATION_FAILURE),
                     }
                 }
             }
         }
+        next = (*next).next;
+    }
+
+    Ok(priorities)
+}
+
+fn binding_sources(binding: &remap::RemappedBinding) -> impl Iterator<Item = &Arc<god_actions::InputBinding>> {
+    std::iter::once(&binding.source).chain(binding.y_source.iter())
+}
+
+///For every active action set, the god sources it binds that an active set of higher priority binds as well
+///
+///Per the spec only the highest priority sets binding a source receive its input, the others behave as though they had no binding to it
+fn suppressed_sources(
+    attached_actions: &AttachedActions,
+    priorities: &HashMap<xr::ActionSet, u32>,
+) -> HashMap<xr::ActionSet, HashSet<*const god_actions::InputBinding>> {
+    let mut suppressed = HashMap::new();
+    if priorities.values().all(|priority| Some(priority) == priorities.values().next()) {
+        return suppressed;
     }
 
-    result
+    let sources_of = |action_set: &xr::ActionSet| {
+        attached_actions
+            .get(action_set)
+            .into_iter()
+            .flat_map(|actions| actions.values())
+            .flat_map(|subaction_bindings| {
+                let subaction_bindings = subaction_bindings.read().unwrap();
+                subaction_bindings
+                    .get_matching(xr::Path::NULL)
+                    .unwrap()
+                    .into_iter()
+                    .flat_map(|binding| binding_sources(binding).map(Arc::as_ptr).collect::<Vec<_>>())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<HashSet<_>>()
+    };
+    let sources = priorities
+        .keys()
+        .map(|action_set| (*action_set, sources_of(action_set)))
+        .collect::<HashMap<_, _>>();
+
+    let mut highest = HashMap::<*const god_actions::InputBinding, u32>::new();
+    for (action_set, set_sources) in &sources {
+        for source in set_sources {
+            let priority = highest.entry(*source).or_insert(priorities[action_set]);
+            *priority = cmp::max(*priority, priorities[action_set]);
+        }
+    }
+    for (action_set, set_sources) in sources {
+        suppressed.insert(
+            action_set,
+            set_sources
+                .into_iter()
+                .filter(|source| highest[source] > priorities[&action_set])
+                .collect(),
+        );
+    }
+    suppressed
 }
 
 pub unsafe extern "system" fn get_action_state_boolean(
@@ -243,48 +547,53 @@ pub unsafe extern "system" fn get_action_state_boolean(
     get_info: *const xr::ActionStateGetInfo,
     out_state: *mut xr::ActionStateBoolean,
 ) -> xr::Result {
-    let get_info = &*get_info;
-    let out_state = &mut *out_state;
+    catch_panic("xrGetActionStateBoolean", || {
+        let get_info = &*get_info;
+        let out_state = &mut *out_state;
 
-    if let Err(result) = get_info.validate() {
-        return result;
-    };
-    if let Err(result) = out_state.validate() {
-        return result;
-    };
+        if let Err(result) = get_info.validate() {
+            return result;
+        };
+        if let Err(result) = out_state.validate() {
+            return result;
+        };
 
-    let session = match session.get_wrapper() {
-        Some(session) => session,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    };
+        let session = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
 
-    let cas_enum = match session
-        .cached_action_states
-        .get()
-        .unwrap()
-        .get(&get_info.action)
-    {
-        Some(cas_enum) => cas_enum,
-        None => return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED,
-    }
-    .read()
-    .unwrap();
-
-    match &cas_enum as &god_actions::CachedActionStatesEnum {
-        god_actions::CachedActionStatesEnum::Boolean(cached_action_states) => {
-            match cached_action_states.get_state(get_info.subaction_path) {
-                Ok(cached_state) => {
-                    out_state.current_state = cached_state.current_state.into();
-                    out_state.last_change_time = cached_state.last_change_time.into();
-                    out_state.changed_since_last_sync = cached_state.changed_since_last_sync.into();
-                    out_state.is_active = cached_state.is_active.into();
-                    xr::Result::SUCCESS
+        match get_info.action.get_wrapper() {
+            Some(action) if action.is_passthrough() || session.leaves_to_runtime(action.handle) => {
+                return session.get_action_state_boolean(get_info, out_state);
+            }
+            Some(_) => {}
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        }
+
+        let cas_enum = match session.attached_action_state(get_info.action) {
+            Ok(cas_enum) => cas_enum,
+            Err(result) => return result,
+        }
+        .read()
+        .unwrap();
+
+        match &cas_enum as &god_actions::CachedActionStatesEnum {
+            god_actions::CachedActionStatesEnum::Boolean(cached_action_states) => {
+                match cached_action_states.get_state(get_info.subaction_path) {
+                    Ok(cached_state) => {
+                        out_state.current_state = cached_state.current_state.into();
+                        out_state.last_change_time = cached_state.last_change_time;
+                        out_state.changed_since_last_sync = cached_state.changed_since_last_sync.into();
+                        out_state.is_active = cached_state.is_active.into();
+                        xr::Result::SUCCESS
+                    }
+                    Err(result) => result,
                 }
-                Err(result) => return result,
             }
+            _ => xr::Result::ERROR_ACTION_TYPE_MISMATCH,
         }
-        _ => return xr::Result::ERROR_ACTION_TYPE_MISMATCH,
-    }
+    })
 }
 
 pub unsafe extern "system" fn get_action_state_float(
@@ -292,48 +601,53 @@ pub unsafe extern "system" fn get_action_state_float(
     get_info: *const xr::ActionStateGetInfo,
     out_state: *mut xr::ActionStateFloat,
 ) -> xr::Result {
-    let get_info = &*get_info;
-    let out_state = &mut *out_state;
+    catch_panic("xrGetActionStateFloat", || {
+        let get_info = &*get_info;
+        let out_state = &mut *out_state;
 
-    if let Err(result) = get_info.validate() {
-        return result;
-    };
-    if let Err(result) = out_state.validate() {
-        return result;
-    };
+        if let Err(result) = get_info.validate() {
+            return result;
+        };
+        if let Err(result) = out_state.validate() {
+            return result;
+        };
 
-    let session = match session.get_wrapper() {
-        Some(session) => session,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    };
+        let session = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
 
-    let cas_enum = match session
-        .cached_action_states
-        .get()
-        .unwrap()
-        .get(&get_info.action)
-    {
-        Some(cas_enum) => cas_enum,
-        None => return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED,
-    }
-    .read()
-    .unwrap();
-
-    match &cas_enum as &god_actions::CachedActionStatesEnum {
-        god_actions::CachedActionStatesEnum::Float(cached_action_states) => {
-            match cached_action_states.get_state(get_info.subaction_path) {
-                Ok(cached_state) => {
-                    out_state.current_state = cached_state.current_state;
-                    out_state.last_change_time = cached_state.last_change_time.into();
-                    out_state.changed_since_last_sync = cached_state.changed_since_last_sync.into();
-                    out_state.is_active = cached_state.is_active.into();
-                    xr::Result::SUCCESS
+        match get_info.action.get_wrapper() {
+            Some(action) if action.is_passthrough() || session.leaves_to_runtime(action.handle) => {
+                return session.get_action_state_float(get_info, out_state);
+            }
+            Some(_) => {}
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        }
+
+        let cas_enum = match session.attached_action_state(get_info.action) {
+            Ok(cas_enum) => cas_enum,
+            Err(result) => return result,
+        }
+        .read()
+        .unwrap();
+
+        match &cas_enum as &god_actions::CachedActionStatesEnum {
+            god_actions::CachedActionStatesEnum::Float(cached_action_states) => {
+                match cached_action_states.get_state(get_info.subaction_path) {
+                    Ok(cached_state) => {
+                        out_state.current_state = cached_state.current_state;
+                        out_state.last_change_time = cached_state.last_change_time;
+                        out_state.changed_since_last_sync = cached_state.changed_since_last_sync.into();
+                        out_state.is_active = cached_state.is_active.into();
+                        xr::Result::SUCCESS
+                    }
+                    Err(result) => result,
                 }
-                Err(result) => return result,
             }
+            _ => xr::Result::ERROR_ACTION_TYPE_MISMATCH,
         }
-        _ => return xr::Result::ERROR_ACTION_TYPE_MISMATCH,
-    }
+    })
 }
 
 pub unsafe extern "system" fn get_action_state_vector2f(
@@ -341,48 +655,53 @@ pub unsafe extern "system" fn get_action_state_vector2f(
     get_info: *const xr::ActionStateGetInfo,
     out_state: *mut xr::ActionStateVector2f,
 ) -> xr::Result {
-    let get_info = &*get_info;
-    let out_state = &mut *out_state;
+    catch_panic("xrGetActionStateVector2f", || {
+        let get_info = &*get_info;
+        let out_state = &mut *out_state;
 
-    if let Err(result) = get_info.validate() {
-        return result;
-    };
-    if let Err(result) = out_state.validate() {
-        return result;
-    };
+        if let Err(result) = get_info.validate() {
+            return result;
+        };
+        if let Err(result) = out_state.validate() {
+            return result;
+        };
 
-    let session = match session.get_wrapper() {
-        Some(session) => session,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    };
+        let session = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
 
-    let cas_enum = match session
-        .cached_action_states
-        .get()
-        .unwrap()
-        .get(&get_info.action)
-    {
-        Some(cas_enum) => cas_enum,
-        None => return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED,
-    }
-    .read()
-    .unwrap();
-
-    match &cas_enum as &god_actions::CachedActionStatesEnum {
-        god_actions::CachedActionStatesEnum::Vector2f(cached_action_states) => {
-            match cached_action_states.get_state(get_info.subaction_path) {
-                Ok(cached_state) => {
-                    out_state.current_state = cached_state.current_state;
-                    out_state.last_change_time = cached_state.last_change_time.into();
-                    out_state.changed_since_last_sync = cached_state.changed_since_last_sync.into();
-                    out_state.is_active = cached_state.is_active.into();
-                    xr::Result::SUCCESS
+        match get_info.action.get_wrapper() {
+            Some(action) if action.is_passthrough() || session.leaves_to_runtime(action.handle) => {
+                return session.get_action_state_vector2f(get_info, out_state);
+            }
+            Some(_) => {}
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        }
+
+        let cas_enum = match session.attached_action_state(get_info.action) {
+            Ok(cas_enum) => cas_enum,
+            Err(result) => return result,
+        }
+        .read()
+        .unwrap();
+
+        match &cas_enum as &god_actions::CachedActionStatesEnum {
+            god_actions::CachedActionStatesEnum::Vector2f(cached_action_states) => {
+                match cached_action_states.get_state(get_info.subaction_path) {
+                    Ok(cached_state) => {
+                        out_state.current_state = cached_state.current_state;
+                        out_state.last_change_time = cached_state.last_change_time;
+                        out_state.changed_since_last_sync = cached_state.changed_since_last_sync.into();
+                        out_state.is_active = cached_state.is_active.into();
+                        xr::Result::SUCCESS
+                    }
+                    Err(result) => result,
                 }
-                Err(result) => return result,
             }
+            _ => xr::Result::ERROR_ACTION_TYPE_MISMATCH,
         }
-        _ => return xr::Result::ERROR_ACTION_TYPE_MISMATCH,
-    }
+    })
 }
 
 pub unsafe extern "system" fn get_action_state_pose(
@@ -390,45 +709,50 @@ pub unsafe extern "system" fn get_action_state_pose(
     get_info: *const xr::ActionStateGetInfo,
     out_state: *mut xr::ActionStatePose,
 ) -> xr::Result {
-    let get_info = &*get_info;
-    let out_state = &mut *out_state;
+    catch_panic("xrGetActionStatePose", || {
+        let get_info = &*get_info;
+        let out_state = &mut *out_state;
 
-    if let Err(result) = get_info.validate() {
-        return result;
-    };
-    if let Err(result) = out_state.validate() {
-        return result;
-    };
+        if let Err(result) = get_info.validate() {
+            return result;
+        };
+        if let Err(result) = out_state.validate() {
+            return result;
+        };
 
-    let session = match session.get_wrapper() {
-        Some(session) => session,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    };
+        let session = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
 
-    let cas_enum = match session
-        .cached_action_states
-        .get()
-        .unwrap()
-        .get(&get_info.action)
-    {
-        Some(cas_enum) => cas_enum,
-        None => return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED,
-    }
-    .read()
-    .unwrap();
-
-    match &cas_enum as &god_actions::CachedActionStatesEnum {
-        god_actions::CachedActionStatesEnum::Pose(cached_action_states) => {
-            match cached_action_states.get_state(get_info.subaction_path) {
-                Ok(cached_state) => {
-                    out_state.is_active = cached_state.is_active.into();
-                    xr::Result::SUCCESS
+        match get_info.action.get_wrapper() {
+            Some(action) if action.is_passthrough() || session.leaves_to_runtime(action.handle) => {
+                return session.get_action_state_pose(get_info, out_state);
+            }
+            Some(_) => {}
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        }
+
+        let cas_enum = match session.attached_action_state(get_info.action) {
+            Ok(cas_enum) => cas_enum,
+            Err(result) => return result,
+        }
+        .read()
+        .unwrap();
+
+        match &cas_enum as &god_actions::CachedActionStatesEnum {
+            god_actions::CachedActionStatesEnum::Pose(cached_action_states) => {
+                match cached_action_states.get_state(get_info.subaction_path) {
+                    Ok(cached_state) => {
+                        out_state.is_active = cached_state.is_active.into();
+                        xr::Result::SUCCESS
+                    }
+                    Err(result) => result,
                 }
-                Err(result) => return result,
             }
+            _ => xr::Result::ERROR_ACTION_TYPE_MISMATCH,
         }
-        _ => return xr::Result::ERROR_ACTION_TYPE_MISMATCH,
-    }
+    })
 }
 
 pub unsafe extern "system" fn locate_views(
@@ -439,65 +763,67 @@ pub unsafe extern "system" fn locate_views(
     view_count_output: *mut u32,
     views: *mut xr::View,
 ) -> xr::Result {
-    let view_locate_info = &*view_locate_info;
+    catch_panic("xrLocateViews", || {
+        let view_locate_info = &*view_locate_info;
 
-    let session = match session.get_wrapper() {
-        Some(session) => session,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    };
-
-    let space = match view_locate_info.space.get_wrapper() {
-        Some(space) => space,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
-    };
+        let session = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
 
-    if !Arc::ptr_eq(&session, &Weak::upgrade(&space.session).unwrap()) {
-        return xr::Result::ERROR_VALIDATION_FAILURE;
-    }
+        let space = match view_locate_info.space.get_wrapper() {
+            Some(space) => space,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
 
-    let space_handle = match space.get_handle() {
-        Some(space_handle) => space_handle,
-        None => {
-            //space is an unbound action space
-            let mut my_view_locate_info = *view_locate_info;
-            my_view_locate_info.space = space.unchecked_handle;
+        if !Arc::ptr_eq(&session, &Weak::upgrade(&space.session).unwrap()) {
+            return xr::Result::ERROR_VALIDATION_FAILURE;
+        }
 
-            let result = (session.instance().core.locate_views)(
-                session.handle,
-                &my_view_locate_info,
-                view_state,
-                view_capacity_input,
-                view_count_output,
-                views,
-            );
+        let space_handle = match space.get_handle() {
+            Some(space_handle) => space_handle,
+            None => {
+                //space is an unbound action space
+                let mut my_view_locate_info = *view_locate_info;
+                my_view_locate_info.space = space.unchecked_handle;
+
+                let result = (session.instance().core.locate_views)(
+                    session.handle,
+                    &my_view_locate_info,
+                    view_state,
+                    view_capacity_input,
+                    view_count_output,
+                    views,
+                );
 
-            if result.into_raw() < 0 {
-                return result;
-            }
+                if result.into_raw() < 0 {
+                    return result;
+                }
 
-            (*view_state).view_state_flags = xr::ViewStateFlags::EMPTY;
-            if view_capacity_input != 0 {
-                for view in slice::from_raw_parts_mut(views, view_capacity_input as usize) {
-                    view.pose = Default::default();
-                    view.pose.orientation.w = 1.;
+                (*view_state).view_state_flags = xr::ViewStateFlags::EMPTY;
+                if view_capacity_input != 0 {
+                    for view in slice::from_raw_parts_mut(views, view_capacity_input as usize) {
+                        view.pose = Default::default();
+                        view.pose.orientation.w = 1.;
+                    }
                 }
-            }
 
-            return result;
-        }
-    };
+                return result;
+            }
+        };
 
-    let mut my_view_locate_info = *view_locate_info;
-    my_view_locate_info.space = space_handle;
+        let mut my_view_locate_info = *view_locate_info;
+        my_view_locate_info.space = space_handle;
 
-    (session.instance().core.locate_views)(
-        session.handle,
-        &my_view_locate_info,
-        view_state,
-        view_capacity_input,
-        view_count_output,
-        views,
-    )
+        (session.instance().core.locate_views)(
+            session.handle,
+            &my_view_locate_info,
+            view_state,
+            view_capacity_input,
+            view_count_output,
+            views,
+        )
+    })
 }
 
 pub unsafe extern "system" fn apply_haptic_feedback(
@@ -505,62 +831,83 @@ pub unsafe extern "system" fn apply_haptic_feedback(
     haptic_action_info: *const xr::HapticActionInfo,
     haptic_feedback: *const xr::HapticBaseHeader,
 ) -> xr::Result {
-    match for_each_output_binding(
-        session,
-        &*haptic_action_info,
-        |session, info| -> Result<xr::Result> {
-            session.apply_haptic_feedback(&info, haptic_feedback)
-        },
-    ) {
-        Ok(result) => result,
-        Err(result) => result,
-    }
+    catch_panic("xrApplyHapticFeedback", || {
+        //Actions left to the runtime have no god outputs in between
+        if let Some(session) = session.get_wrapper() {
+            if session.leaves_to_runtime((*haptic_action_info).action) {
+                return session.apply_haptic_feedback(haptic_action_info, haptic_feedback).unwrap_or_else(|result| result);
+            }
+        }
+        match for_each_output_binding(
+            session,
+            &*haptic_action_info,
+            |session, info, binding| -> Result<xr::Result> {
+                let limits = session.instance().settings.haptic_limits_for(&binding.action.profile_name_str);
+                //Only vibrations have an amplitude to scale, anything else an extension defines is passed on untouched
+                if (binding.amplitude != 1f32 || !limits.is_unlimited()) && (*haptic_feedback).ty == xr::HapticVibration::TYPE {
+                    let mut vibration = *(haptic_feedback as *const xr::HapticVibration);
+                    vibration.amplitude = limits.clamp_amplitude((vibration.amplitude * binding.amplitude).clamp(0f32, 1f32));
+                    vibration.duration = xr::Duration::from_nanos(limits.clamp_duration(vibration.duration.as_nanos()));
+                    session.apply_haptic_feedback(&info, &vibration as *const _ as *const xr::HapticBaseHeader)
+                } else {
+                    session.apply_haptic_feedback(&info, haptic_feedback)
+                }
+            },
+        ) {
+            Ok(result) => result,
+            Err(result) => result,
+        }
+    })
 }
 
 pub unsafe extern "system" fn stop_haptic_feedback(
     session: xr::Session,
     haptic_action_info: *const xr::HapticActionInfo,
 ) -> xr::Result {
-    match for_each_output_binding(
-        session,
-        &*haptic_action_info,
-        |session, info| -> Result<xr::Result> { session.stop_haptic_feedback(&info) },
-    ) {
-        Ok(result) => result,
-        Err(result) => result,
-    }
-}
-
+    catch_panic("xrStopHapticFeedback", || {
+        if let Some(session) = session.get_wrapper() {
+            if session.leaves_to_runtime((*haptic_action_info).action) {
+                return session.stop_haptic_feedback(haptic_action_info).unwrap_or_else(|result| result);
+            }
+        }
+        match for_each_output_binding(
+            session,
+            &*haptic_action_info,
+            |session, info, _| -> Result<xr::Result> { session.stop_haptic_feedback(&info) },
+        ) {
+            Ok(result) => result,
+            Err(result) => result,
+        }
+    })
+}
+
 fn for_each_output_binding<F>(
     session: xr::Session,
     haptic_action_info: &xr::HapticActionInfo,
     callback: F,
 ) -> Result<xr::Result>
 where
-    F: Fn(&SessionWrapper, xr::HapticActionInfo) -> Result<xr::Result>,
+    F: Fn(&SessionWrapper, xr::HapticActionInfo, &remap::RemappedOutput) -> Result<xr::Result>,
 {
     let session = session.try_get_wrapper()?;
     let action = haptic_action_info.action.try_get_wrapper()?;
 
-    let subaction_bindings = match session.output_bindings.get().unwrap().get(&action.handle) {
-        Some(subaction_bindings) => subaction_bindings,
-        None => return Err(xr::Result::ERROR_ACTIONSET_NOT_ATTACHED),
-    }
-    .read()
-    .unwrap();
+    let subaction_bindings = session.attached_output_bindings(action.handle)?.read().unwrap();
 
     for binding in subaction_bindings
         .get_matching(haptic_action_info.subaction_path)
         .unwrap()
         .iter()
         .filter(|output_binding| output_binding.is_active(&session))
+        //Emulated outputs have no runtime action behind them
+        .filter(|output_binding| output_binding.action.handle != xr::Action::NULL)
     {
         println!("{}", binding.action.profile_name_str);
 
         let mut my_haptic_action_info = *haptic_action_info;
         my_haptic_action_info.action = binding.action.handle;
 
-        callback(&session, my_haptic_action_info)?;
+        callback(&session, my_haptic_action_info, binding)?;
     }
 
     Ok(xr::Result::SUCCESS)
@@ -585,35 +932,59 @@ fn update_application_actions(instance: &InstanceWrapper, action_set_handles: &[
     };
 
     for action_set in action_set_handles {
-        let action_set_wrapper = ActionSetWrapper::from_handle_panic(action_set.clone());
+        let action_set_wrapper = ActionSetWrapper::from_handle_panic(*action_set);
         application_actions.action_sets.insert(
             action_set_wrapper.name.clone(),
             set_info_from_wrapper(&action_set_wrapper),
         );
     }
 
-    write_json(&application_actions, &Path::new(&path_str));
+    write_json(&application_actions, Path::new(&path_str));
 }
 
 fn set_info_from_wrapper(wrapper: &ActionSetWrapper) -> ActionSetInfo {
     let mut action_set_info = ActionSetInfo {
         localized_name: wrapper.localized_name.clone(),
-        actions: HashMap::new(),
+        actions: BTreeMap::new(),
     };
 
     let instance = wrapper.instance();
 
     for action_wrapper in wrapper.actions.read().unwrap().iter() {
+        let mut subaction_paths = action_wrapper
+            .subaction_paths
+            .iter()
+            .map(|path| -> String { instance.path_to_string(*path).unwrap() })
+            .collect::<Vec<_>>();
+        //Keep actions.json stable between runs no matter what order the application listed them in
+        subaction_paths.sort();
+
+        let mut suggested_bindings = BTreeMap::new();
+        for (profile, bindings) in action_wrapper.bindings.read().unwrap().iter() {
+            let profile_info = instance.god_action_sets().get(profile).map(|god_set| &god_set.profile_info);
+            let mut binding_infos = bindings
+                .iter()
+                .map(|binding| {
+                    let path = instance.path_to_string(*binding).unwrap();
+                    BindingInfo {
+                        localized_name: profile_info
+                            .and_then(|profile_info| profile_info.localized_name_for_binding(&path))
+                            .map(str::to_owned),
+                        path,
+                    }
+                })
+                .collect::<Vec<_>>();
+            binding_infos.sort_by(|a, b| a.path.cmp(&b.path));
+            suggested_bindings.insert(instance.path_to_string(*profile).unwrap(), binding_infos);
+        }
+
         action_set_info.actions.insert(
             action_wrapper.name.clone(),
             ActionInfo {
                 localized_name: action_wrapper.localized_name.clone(),
                 action_type: action_wrapper.action_type,
-                subaction_paths: action_wrapper
-                    .subaction_paths
-                    .iter()
-                    .map(|path| -> String { instance.path_to_string(path.clone()).unwrap() })
-                    .collect(),
+                subaction_paths,
+                suggested_bindings,
             },
         );
     }
@@ -628,72 +999,1380 @@ pub unsafe extern "system" fn enumerate_bound_sources_for_action(
     source_count_output: *mut u32,
     sources: *mut xr::Path,
 ) -> xr::Result {
-    let enumerate_info = &*enumerate_info;
+    catch_panic("xrEnumerateBoundSourcesForAction", || {
+        let enumerate_info = &*enumerate_info;
+
+        let session = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
+
+        let action = match enumerate_info.action.get_wrapper() {
+            Some(action) => action,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
+
+        if !Weak::ptr_eq(&session.instance, &action.action_set().instance) {
+            return xr::Result::ERROR_VALIDATION_FAILURE;
+        }
+
+        let instance = session.instance();
+        if action.is_passthrough() || session.leaves_to_runtime(action.handle) {
+            return (instance.core.enumerate_bound_sources_for_action)(
+                session.handle,
+                enumerate_info,
+                source_capacity_input,
+                source_count_output,
+                sources,
+            );
+        }
+
+        let mut acc = Vec::with_capacity(source_capacity_input as usize);
+
+        if action.action_type.is_input() {
+            let subaction_bindings = match session.attached_input_bindings(action.action_set().handle, action.handle) {
+                Ok(subaction_bindings) => subaction_bindings,
+                Err(result) => return result,
+            }
+            .read()
+            .unwrap();
+
+            let bindings = subaction_bindings.get_matching(xr::Path::NULL).unwrap();
+
+            for binding in bindings {
+                let state = binding.action_state.read().unwrap();
+                if state.get_inner().is_active() {
+                    acc.push(instance.string_to_path(&binding.binding_str).unwrap())
+                }
+            }
+        } else {
+            let subaction_bindings = match session.attached_output_bindings(action.handle) {
+                Ok(subaction_bindings) => subaction_bindings,
+                Err(result) => return result,
+            }
+            .read()
+            .unwrap();
+
+            let bindings = subaction_bindings.get_matching(xr::Path::NULL).unwrap();
+
+            for binding in bindings
+                .iter()
+                .filter(|output_binding| output_binding.is_active(&session))
+            {
+                acc.push(instance.string_to_path(&binding.binding_str).unwrap())
+            }
+        }
+
+        fill_output_array(sources, source_capacity_input, source_count_output, &acc)
+    })
+}
+
+pub unsafe extern "system" fn get_input_source_localized_name(
+    session: xr::Session,
+    get_info: *const xr::InputSourceLocalizedNameGetInfo,
+    buffer_capacity_input: u32,
+    buffer_count_output: *mut u32,
+    buffer: *mut c_char,
+) -> xr::Result {
+    catch_panic("xrGetInputSourceLocalizedName", || {
+        let session = match session.get_wrapper() {
+            Some(session) => session,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
+        let instance = session.instance();
+        let get_info = &*get_info;
+
+        if get_info.which_components == xr::InputSourceLocalizedNameFlags::EMPTY {
+            return xr::Result::ERROR_VALIDATION_FAILURE;
+        }
 
-    let session = match session.get_wrapper() {
-        Some(session) => session,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
+        //Sources the layer didn't hand out are still the runtime's to name
+        let name = match session.localized_source_name(&instance, get_info.source_path, get_info.which_components) {
+            Ok(Some(name)) => name,
+            Ok(None) => {
+                return (instance.core.get_input_source_localized_name)(
+                    session.handle,
+                    get_info,
+                    buffer_capacity_input,
+                    buffer_count_output,
+                    buffer,
+                )
+            }
+            Err(result) => return result,
+        };
+
+        fill_output_string(buffer, buffer_capacity_input, buffer_count_output, &name)
+    })
+}
+
+#[test]
+fn test_get_action_state_before_attach() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+    let (_, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+
+    let get_info = xr::ActionStateGetInfo {
+        ty: xr::ActionStateGetInfo::TYPE,
+        next: ptr::null(),
+        action: actions[0],
+        subaction_path: xr::Path::NULL,
+    };
+    let mut state = xr::ActionStateBoolean {
+        ty: xr::ActionStateBoolean::TYPE,
+        next: ptr::null_mut(),
+        current_state: false.into(),
+        changed_since_last_sync: false.into(),
+        last_change_time: xr::Time::from_nanos(0),
+        is_active: false.into(),
     };
+    let result = unsafe { get_action_state_boolean(session, &get_info, &mut state) };
+
+    assert_eq!(result, xr::Result::ERROR_ACTIONSET_NOT_ATTACHED);
+}
 
-    let action = match enumerate_info.action.get_wrapper() {
-        Some(action) => action,
-        None => return xr::Result::ERROR_HANDLE_INVALID,
+#[test]
+fn test_wait_frame_time_stamps_changes() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance).get_wrapper().unwrap().clone();
+    assert_eq!(session.predicted_display_time().as_nanos(), 0);
+
+    let frame_wait_info = xr::FrameWaitInfo {
+        ty: xr::FrameWaitInfo::TYPE,
+        next: ptr::null(),
     };
+    let mut frame_state = xr::FrameState {
+        ty: xr::FrameState::TYPE,
+        next: ptr::null_mut(),
+        predicted_display_time: xr::Time::from_nanos(0),
+        predicted_display_period: xr::Duration::from_nanos(0),
+        should_render: false.into(),
+    };
+    assert_eq!(
+        unsafe { wait_frame(session.handle, &frame_wait_info, &mut frame_state) },
+        xr::Result::SUCCESS
+    );
+    assert_ne!(frame_state.predicted_display_time.as_nanos(), 0);
+    assert_eq!(session.predicted_display_time(), frame_state.predicted_display_time);
+
+    //A source the runtime never stamps gets the time of the frame it changed on
+    let source = fake_runtime::god_input("/user/hand/left/input/select/click", ActionType::BooleanInput);
+    *source.action_state.write().unwrap() = god_actions::GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+        current_state: true,
+        changed_since_last_sync: true,
+        last_change_time: xr::Time::from_nanos(0),
+        is_active: true,
+    });
+    let bindings = SubactionBindings::Singleton(vec![Arc::new(remap::RemappedBinding::new(
+        source,
+        None,
+        Default::default(),
+    ))]);
+    let mut states = CachedActionStatesEnum::new(ActionType::BooleanInput, &Vec::new());
+    states.sync(&bindings, session.predicted_display_time()).unwrap();
+
+    match states.main_state() {
+        god_actions::GodActionStateEnum::Boolean(state) => {
+            assert!(state.current_state);
+            assert_eq!(state.last_change_time, frame_state.predicted_display_time);
+        }
+        _ => panic!("state has the wrong type"),
+    }
+}
+
+#[test]
+fn test_sync_actions_metrics() {
+    use crate::fake_runtime;
+    use crate::metrics;
 
-    if !Weak::ptr_eq(&session.instance, &action.action_set().instance) {
-        return xr::Result::ERROR_VALIDATION_FAILURE;
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+    let before = metrics::metrics()["xrSyncActions"];
+    for _ in 0..5 {
+        assert_eq!(fake_runtime::sync(session, &[]), xr::Result::SUCCESS);
     }
 
-    let mut acc = Vec::with_capacity(source_capacity_input as usize);
-    let instance = session.instance();
+    //The session's own count is exact, other tests syncing at the same time can only add to the process wide one
+    assert_eq!(*session.get_wrapper().unwrap().sync_idx.read().unwrap(), 5);
+    assert!(metrics::metrics()["xrSyncActions"] - before >= 5);
+}
 
-    if action.action_type.is_input() {
-        let subaction_bindings = match session.input_bindings.get() {
-            Some(s) => s,
-            None => return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED,
-        }
-        .get(&action.action_set().handle)
-        .unwrap()
-        .get(&action.handle)
-        .unwrap()
-        .read()
-        .unwrap();
+#[test]
+fn test_sync_timing() {
+    use std::time::Duration;
 
-        let bindings = subaction_bindings.get_matching(xr::Path::NULL).unwrap();
+    use crate::fake_runtime;
+    use crate::metrics;
 
-        for binding in bindings {
-            let state = binding.action_state.read().unwrap();
-            if state.get_inner().is_active() {
-                acc.push(instance.string_to_path(&binding.binding_str).unwrap())
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+    //The fake clock moves by this thread's step on every read, so each remap pass takes exactly one step
+    for step in &[1, 3, 2] {
+        fake_runtime::set_clock_step(Duration::from_millis(*step));
+        assert_eq!(fake_runtime::sync(session, &[]), xr::Result::SUCCESS);
+    }
+    fake_runtime::set_clock_step(Duration::default());
+
+    let timing = *session.get_wrapper().unwrap().sync_timing.lock().unwrap();
+    assert_eq!(timing.count, 3);
+    assert_eq!(timing.min, Duration::from_millis(1));
+    assert_eq!(timing.max, Duration::from_millis(3));
+    assert_eq!(timing.total, Duration::from_millis(6));
+    assert_eq!(timing.average(), Duration::from_millis(2));
+    //The process wide timings take in every session's
+    assert!(metrics::sync_timing().count >= 3);
+}
+
+#[test]
+fn test_effective_config() {
+    use common::config::BindingConfigBuilder;
+    use serde_json::json;
+
+    use crate::fake_runtime;
+
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let config = BindingConfigBuilder::new()
+        .bind(profile_name, "gameplay/jump", "/user/hand/left/input/b/click")
+        .build()
+        .unwrap();
+    let instance = fake_runtime::create_instance_with_bindings(config);
+
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[("fire", xr::ActionType::BOOLEAN_INPUT), ("jump", xr::ActionType::BOOLEAN_INPUT)],
+    );
+    assert_eq!(
+        fake_runtime::suggest(
+            &instance,
+            profile_name,
+            &[(actions[0], "/user/hand/right/input/trigger/value"), (actions[1], "/user/hand/left/input/a/click")],
+        ),
+        xr::Result::SUCCESS
+    );
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    //fire keeps its suggestion but picks up the Index trigger's threshold, jump is moved to b by the custom bindings
+    assert_eq!(
+        session.get_wrapper().unwrap().effective_config_json(),
+        json!({
+            profile_name: {
+                "gameplay": {
+                    "fire": {
+                        "bindings": [{
+                            "path": "/user/hand/right/input/trigger/value",
+                            "threshold": { "on_threshold": 0.7f32, "off_threshold": 0.6f32 }
+                        }]
+                    },
+                    "jump": {
+                        "bindings": ["/user/hand/left/input/b/click"]
+                    }
+                }
             }
+        })
+    );
+}
+
+#[test]
+fn test_set_info_localized_bindings() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let trigger = "/user/hand/right/input/trigger/value";
+
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("fire", xr::ActionType::BOOLEAN_INPUT)]);
+    assert_eq!(fake_runtime::suggest(&instance, profile_name, &[(actions[0], trigger)]), xr::Result::SUCCESS);
+
+    let set_info = set_info_from_wrapper(&action_set.get_wrapper().unwrap());
+    let fire = &set_info.actions["fire"];
+    assert_eq!(fire.localized_name, "fire");
+    assert_eq!(
+        fire.suggested_bindings[profile_name],
+        vec![BindingInfo {
+            path: trigger.to_owned(),
+            localized_name: Some(String::from("Trigger")),
+        }]
+    );
+
+    //Round trips through actions.json
+    let json = serde_json::to_string(&set_info).unwrap();
+    let read = serde_json::from_str::<ActionSetInfo>(&json).unwrap();
+    assert_eq!(read.actions["fire"].suggested_bindings, fire.suggested_bindings);
+}
+
+#[test]
+fn test_get_action_state_ownership() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+    let other_session = fake_runtime::new_session(&instance);
+    let (attached_set, attached_actions) = fake_runtime::new_actions(
+        &instance,
+        "attached",
+        &[("jump", xr::ActionType::BOOLEAN_INPUT), ("rumble", xr::ActionType::VIBRATION_OUTPUT)],
+    );
+    let (_, unattached_actions) = fake_runtime::new_actions(&instance, "unattached", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+    assert_eq!(fake_runtime::attach(session, &[attached_set]), xr::Result::SUCCESS);
+
+    let get_state = |session: xr::Session, action: xr::Action| {
+        let get_info = xr::ActionStateGetInfo {
+            ty: xr::ActionStateGetInfo::TYPE,
+            next: ptr::null(),
+            action,
+            subaction_path: xr::Path::NULL,
+        };
+        let mut state = xr::ActionStateBoolean {
+            ty: xr::ActionStateBoolean::TYPE,
+            next: ptr::null_mut(),
+            current_state: false.into(),
+            changed_since_last_sync: false.into(),
+            last_change_time: xr::Time::from_nanos(0),
+            is_active: false.into(),
+        };
+        unsafe { get_action_state_boolean(session, &get_info, &mut state) }
+    };
+
+    assert_eq!(get_state(session, attached_actions[0]), xr::Result::SUCCESS);
+    assert_eq!(get_state(session, unattached_actions[0]), xr::Result::ERROR_ACTIONSET_NOT_ATTACHED);
+    //Attached, but to a different session
+    assert_eq!(get_state(other_session, attached_actions[0]), xr::Result::ERROR_ACTIONSET_NOT_ATTACHED);
+    assert_eq!(get_state(session, xr::Action::from_raw(136)), xr::Result::ERROR_HANDLE_INVALID);
+
+    let enumerate = |session: xr::Session, action: xr::Action| {
+        let enumerate_info = xr::BoundSourcesForActionEnumerateInfo {
+            ty: xr::BoundSourcesForActionEnumerateInfo::TYPE,
+            next: ptr::null(),
+            action,
+        };
+        let mut count = 0;
+        unsafe { enumerate_bound_sources_for_action(session, &enumerate_info, 0, &mut count, ptr::null_mut()) }
+    };
+    assert_eq!(enumerate(session, attached_actions[0]), xr::Result::SUCCESS);
+    assert_eq!(enumerate(session, attached_actions[1]), xr::Result::SUCCESS);
+    assert_eq!(enumerate(other_session, attached_actions[1]), xr::Result::ERROR_ACTIONSET_NOT_ATTACHED);
+    //The other session having sets of its own doesn't make ours attached to it
+    let (other_set, _) = fake_runtime::new_actions(&instance, "other", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+    assert_eq!(fake_runtime::attach(other_session, &[other_set]), xr::Result::SUCCESS);
+    assert_eq!(enumerate(other_session, attached_actions[0]), xr::Result::ERROR_ACTIONSET_NOT_ATTACHED);
+    assert_eq!(enumerate(other_session, attached_actions[1]), xr::Result::ERROR_ACTIONSET_NOT_ATTACHED);
+}
+
+#[test]
+fn test_attach_no_action_sets() {
+    use serde_json::json;
+
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+    let (_, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+
+    assert_eq!(fake_runtime::attach(session, &[]), xr::Result::SUCCESS);
+    assert_eq!(session.get_wrapper().unwrap().effective_config_json(), json!({}));
+    //Attaching nothing still counts as attaching
+    assert_eq!(fake_runtime::attach(session, &[]), xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED);
+
+    let get_info = xr::ActionStateGetInfo {
+        ty: xr::ActionStateGetInfo::TYPE,
+        next: ptr::null(),
+        action: actions[0],
+        subaction_path: xr::Path::NULL,
+    };
+    let mut state = xr::ActionStateBoolean {
+        ty: xr::ActionStateBoolean::TYPE,
+        next: ptr::null_mut(),
+        current_state: false.into(),
+        changed_since_last_sync: false.into(),
+        last_change_time: xr::Time::from_nanos(0),
+        is_active: false.into(),
+    };
+    assert_eq!(
+        unsafe { get_action_state_boolean(session, &get_info, &mut state) },
+        xr::Result::ERROR_ACTIONSET_NOT_ATTACHED
+    );
+}
+
+#[test]
+fn test_sync_without_god_states() {
+    use common::config::Settings;
+
+    use crate::fake_runtime;
+
+    //God sets are deferred to attach, so until then the session has no god states to sync
+    let instance = fake_runtime::create_instance_with_settings(Settings {
+        defer_god_sets: true,
+        ..Default::default()
+    });
+    let session = fake_runtime::new_session(&instance);
+    assert!(session.get_wrapper().unwrap().god_states.get().is_none());
+
+    let sync_info = xr::ActionsSyncInfo {
+        ty: xr::ActionsSyncInfo::TYPE,
+        next: ptr::null(),
+        count_active_action_sets: 0,
+        active_action_sets: ptr::null(),
+    };
+    assert_eq!(unsafe { sync_actions(session, &sync_info) }, xr::Result::ERROR_ACTIONSET_NOT_ATTACHED);
+}
+
+#[test]
+fn test_deferred_god_sets() {
+    use common::config::Settings;
+
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance_with_settings(Settings {
+        defer_god_sets: true,
+        ..Default::default()
+    });
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+    //Nothing is created until the application has said which profiles it uses
+    assert!(instance.god_action_sets().is_empty());
+    assert!(session.get_wrapper().unwrap().god_states().is_empty());
+
+    let simple_name = "/interaction_profiles/khr/simple_controller";
+    assert_eq!(
+        fake_runtime::suggest(&instance, simple_name, &[(actions[0], "/user/hand/left/input/select/click")]),
+        xr::Result::SUCCESS
+    );
+
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+    let simple = instance.string_to_path(simple_name).unwrap();
+    assert_eq!(instance.god_action_sets().keys().copied().collect::<Vec<_>>(), vec![simple]);
+    assert_eq!(session.get_wrapper().unwrap().god_states().keys().copied().collect::<Vec<_>>(), vec![simple]);
+
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED);
+}
+
+#[test]
+fn test_haptic_amplitude() {
+    use common::config::BindingConfigBuilder;
+
+    use crate::fake_runtime;
+    use crate::path::{InteractionProfilePath, TopLevelUserPath};
+
+    let profile = "/interaction_profiles/microsoft/xbox_controller";
+    let main = "/user/gamepad/output/haptic_left";
+    let trigger = "/user/gamepad/output/haptic_left_trigger";
+    let config = BindingConfigBuilder::new()
+        .bind(profile, "gameplay/rumble", main)
+        .bind(profile, "gameplay/rumble", trigger)
+        .with_amplitude(0.5)
+        .build()
+        .unwrap();
+    let instance = fake_runtime::create_instance_with_bindings(config);
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("rumble", xr::ActionType::VIBRATION_OUTPUT)]);
+    let action = actions[0];
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    //Haptics only reach the controller in use
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let profile_path = instance.string_to_path(profile).unwrap();
+    let gamepad = TopLevelUserPath(instance.string_to_path(openxr::USER_GAMEPAD).unwrap());
+    *wrapper.active_profiles[&gamepad].write().unwrap() = InteractionProfilePath(profile_path);
+    let god_action = |output: &str| wrapper.god_outputs()[&profile_path][&instance.string_to_path(output).unwrap()].action.handle;
+
+    let haptic_action_info = xr::HapticActionInfo {
+        ty: xr::HapticActionInfo::TYPE,
+        next: ptr::null(),
+        action,
+        subaction_path: xr::Path::NULL,
+    };
+    let vibration = xr::HapticVibration {
+        ty: xr::HapticVibration::TYPE,
+        next: ptr::null(),
+        duration: xr::Duration::from_nanos(1_000_000),
+        frequency: xr::FREQUENCY_UNSPECIFIED,
+        amplitude: 1f32,
+    };
+    let result = unsafe {
+        apply_haptic_feedback(session, &haptic_action_info, &vibration as *const _ as *const xr::HapticBaseHeader)
+    };
+    assert_eq!(result, xr::Result::SUCCESS);
+
+    let mut haptics = fake_runtime::haptics(session);
+    haptics.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    assert_eq!(haptics, vec![(god_action(trigger), 0.5, 1_000_000), (god_action(main), 1f32, 1_000_000)]);
+
+    let effective = wrapper.effective_config_json();
+    assert_eq!(effective[profile]["gameplay"]["rumble"]["bindings"][1], serde_json::json!({ "path": trigger, "amplitude": 0.5 }));
+}
+
+#[test]
+fn test_haptic_limits() {
+    use common::config::{BindingConfigBuilder, HapticLimits, Settings};
+
+    use crate::fake_runtime;
+    use crate::path::{InteractionProfilePath, TopLevelUserPath};
+
+    let profile = "/interaction_profiles/microsoft/xbox_controller";
+    let main = "/user/gamepad/output/haptic_left";
+    let config = BindingConfigBuilder::new().bind(profile, "gameplay/rumble", main).build().unwrap();
+    let settings = Settings {
+        haptic_limits: HapticLimits {
+            max_amplitude: Some(0.5),
+            max_duration: Some(100_000_000),
+        },
+        profile_haptic_limits: vec![(
+            profile.to_owned(),
+            HapticLimits {
+                max_amplitude: Some(0.7),
+                max_duration: None,
+            },
+        )]
+        .into_iter()
+        .collect(),
+        ..Default::default()
+    };
+    let instance = fake_runtime::create_configured_instance(config, settings);
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("rumble", xr::ActionType::VIBRATION_OUTPUT)]);
+    let action = actions[0];
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let profile_path = instance.string_to_path(profile).unwrap();
+    let gamepad = TopLevelUserPath(instance.string_to_path(openxr::USER_GAMEPAD).unwrap());
+    *wrapper.active_profiles[&gamepad].write().unwrap() = InteractionProfilePath(profile_path);
+    let god_action = wrapper.god_outputs()[&profile_path][&instance.string_to_path(main).unwrap()].action.handle;
+
+    let haptic_action_info = xr::HapticActionInfo {
+        ty: xr::HapticActionInfo::TYPE,
+        next: ptr::null(),
+        action,
+        subaction_path: xr::Path::NULL,
+    };
+    let vibrate = |duration: i64| {
+        let vibration = xr::HapticVibration {
+            ty: xr::HapticVibration::TYPE,
+            next: ptr::null(),
+            duration: xr::Duration::from_nanos(duration),
+            frequency: xr::FREQUENCY_UNSPECIFIED,
+            amplitude: 1f32,
+        };
+        let result = unsafe {
+            apply_haptic_feedback(session, &haptic_action_info, &vibration as *const _ as *const xr::HapticBaseHeader)
+        };
+        assert_eq!(result, xr::Result::SUCCESS);
+    };
+    vibrate(1_000_000_000);
+    vibrate(xr::MIN_HAPTIC_DURATION);
+    vibrate(xr::INFINITE_DURATION);
+
+    //The profile's amplitude cap replaces the global one, the global duration cap still applies
+    assert_eq!(
+        fake_runtime::haptics(session),
+        vec![
+            (god_action, 0.7, 100_000_000),
+            (god_action, 0.7, xr::MIN_HAPTIC_DURATION),
+            (god_action, 0.7, 100_000_000),
+        ]
+    );
+}
+
+#[test]
+fn test_no_suggested_bindings() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[("trigger", xr::ActionType::FLOAT_INPUT), ("jump", xr::ActionType::BOOLEAN_INPUT)],
+    );
+    let (trigger, jump) = (actions[0], actions[1]);
+
+    //Nothing is suggested before attaching, the actions are bound to the sources they are named after
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let index = instance.string_to_path("/interaction_profiles/valve/index_controller").unwrap();
+    let right_trigger = instance.string_to_path("/user/hand/right/input/trigger/value").unwrap();
+    assert!(trigger.get_wrapper().unwrap().bindings.read().unwrap()[&index].contains(&right_trigger));
+    assert!(jump.get_wrapper().unwrap().bindings.read().unwrap().is_empty());
+
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let god_action = wrapper.god_states()[&index][&right_trigger].action.handle;
+    fake_runtime::set_input_state(
+        session,
+        god_action,
+        god_actions::GodActionStateEnum::Float(openxr::ActionState::<f32> {
+            current_state: 0.75,
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(1),
+            is_active: true,
+        }),
+    );
+    assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+
+    let trigger_state = fake_runtime::float_state(session, trigger, xr::Path::NULL);
+    assert!(trigger_state.is_active);
+    assert_eq!(trigger_state.current_state, 0.75);
+    assert!(!fake_runtime::boolean_state(session, jump, xr::Path::NULL).is_active);
+}
+
+#[test]
+fn test_per_hand_transforms() {
+    use common::config::BindingConfigBuilder;
+
+    use crate::fake_runtime;
+
+    //The left stick drifts so it gets a wider dead zone, the right one a curve for finer turning
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let config = BindingConfigBuilder::new()
+        .bind(profile_name, "gameplay/move", "/user/hand/left/input/thumbstick")
+        .with_dead_zone(0.4)
+        .bind(profile_name, "gameplay/move", "/user/hand/right/input/thumbstick")
+        .with_dead_zone(0.1)
+        .with_curve(2f32)
+        .build()
+        .unwrap();
+
+    let instance = fake_runtime::create_instance_with_bindings(config);
+    let session = fake_runtime::new_session(&instance);
+    let left = instance.string_to_path(openxr::USER_HAND_LEFT).unwrap();
+    let right = instance.string_to_path(openxr::USER_HAND_RIGHT).unwrap();
+    let action_set = fake_runtime::new_action_set(&instance, "gameplay");
+    let action = fake_runtime::new_action(action_set, "move", xr::ActionType::VECTOR2F_INPUT, &[left, right]);
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let index = instance.string_to_path(profile_name).unwrap();
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let set_sticks = |x: f32| {
+        for stick in &["/user/hand/left/input/thumbstick", "/user/hand/right/input/thumbstick"] {
+            let stick = instance.string_to_path(stick).unwrap();
+            fake_runtime::set_input_state(
+                session,
+                wrapper.god_states()[&index][&stick].action.handle,
+                god_actions::GodActionStateEnum::Vector2f(openxr::ActionState::<openxr::Vector2f> {
+                    current_state: openxr::Vector2f { x, y: 0f32 },
+                    changed_since_last_sync: true,
+                    last_change_time: xr::Time::from_nanos(1),
+                    is_active: true,
+                }),
+            );
         }
-    } else {
-        let subaction_bindings = match session.output_bindings.get().unwrap().get(&action.handle) {
-            Some(s) => s,
-            None => return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED,
-        }
-        .read()
+    };
+    let stick_x = |subaction_path| {
+        let state = fake_runtime::vector2f_state(session, action, subaction_path);
+        assert!(state.is_active);
+        state.current_state.x
+    };
+
+    //Inside the left dead zone but past the right one
+    set_sticks(0.3);
+    assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+    assert_eq!(stick_x(left), 0f32);
+    assert!((stick_x(right) - (0.2f32 / 0.9).powi(2)).abs() < 1e-5);
+
+    set_sticks(0.7);
+    assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+    assert!((stick_x(left) - 0.5).abs() < 1e-5);
+    assert!((stick_x(right) - (0.6f32 / 0.9).powi(2)).abs() < 1e-5);
+    //Without a subaction path the application gets whichever hand is pushed further after its own tuning
+    assert!((stick_x(xr::Path::NULL) - 0.5).abs() < 1e-5);
+}
+
+#[test]
+fn test_sync_priority_override() {
+    use openxr::builder as xr_builder;
+
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+
+    //The menu and gameplay both use the trigger, gameplay normally wins
+    let create_set_with_action = |name: &str, priority: u32, action_name: &str| {
+        let set_create_info = xr_builder::ActionSetCreateInfo::new()
+            .action_set_name(name)
+            .localized_action_set_name(name)
+            .priority(priority);
+        let mut action_set = xr::ActionSet::NULL;
+        assert_eq!(
+            unsafe { super::create_action_set(instance.handle, set_create_info.as_raw(), &mut action_set) },
+            xr::Result::SUCCESS
+        );
+        (action_set, fake_runtime::new_action(action_set, action_name, xr::ActionType::BOOLEAN_INPUT, &[]))
+    };
+    let (gameplay, fire) = create_set_with_action("gameplay", 1, "fire");
+    let (menu, select) = create_set_with_action("menu", 0, "select");
+
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let trigger_name = "/user/hand/right/input/trigger/click";
+    assert_eq!(
+        fake_runtime::suggest(&instance, profile_name, &[(fire, trigger_name), (select, trigger_name)]),
+        xr::Result::SUCCESS
+    );
+    let action_sets = [gameplay, menu];
+    assert_eq!(fake_runtime::attach(session, &action_sets), xr::Result::SUCCESS);
+
+    let profile = instance.string_to_path(profile_name).unwrap();
+    let trigger = instance.string_to_path(trigger_name).unwrap();
+    let wrapper = session.get_wrapper().unwrap().clone();
+    fake_runtime::set_input_state(
+        session,
+        wrapper.god_states()[&profile][&trigger].action.handle,
+        god_actions::GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+            current_state: true,
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(1),
+            is_active: true,
+        }),
+    );
+
+    let active_action_sets = action_sets
+        .iter()
+        .map(|action_set| xr::ActiveActionSet {
+            action_set: *action_set,
+            subaction_path: xr::Path::NULL,
+        })
+        .collect::<Vec<_>>();
+    let sync_with_priorities = |priorities: &xr::ActiveActionSetPrioritiesEXT| {
+        let sync_info = xr::ActionsSyncInfo {
+            ty: xr::ActionsSyncInfo::TYPE,
+            next: priorities as *const _ as *const std::ffi::c_void,
+            count_active_action_sets: active_action_sets.len() as u32,
+            active_action_sets: active_action_sets.as_ptr(),
+        };
+        assert_eq!(unsafe { sync_actions(session, &sync_info) }, xr::Result::SUCCESS);
+    };
+    let pressed = |action| {
+        let state = fake_runtime::boolean_state(session, action, xr::Path::NULL);
+        state.is_active && state.current_state
+    };
+
+    assert_eq!(fake_runtime::sync(session, &action_sets), xr::Result::SUCCESS);
+    assert!(pressed(fire));
+    assert!(!pressed(select));
+
+    //With the menu open the application raises its priority for the sync, now the menu gets the trigger
+    let priority_overrides = [xr::ActiveActionSetPriorityEXT {
+        action_set: menu,
+        priority_override: 2,
+    }];
+    sync_with_priorities(&xr::ActiveActionSetPrioritiesEXT {
+        ty: xr::ActiveActionSetPrioritiesEXT::TYPE,
+        next: ptr::null(),
+        action_set_priority_count: priority_overrides.len() as u32,
+        action_set_priorities: priority_overrides.as_ptr(),
+    });
+    assert!(!pressed(fire));
+    assert!(pressed(select));
+
+    //Overrides only last for the sync they were given to
+    assert_eq!(fake_runtime::sync(session, &action_sets), xr::Result::SUCCESS);
+    assert!(pressed(fire));
+    assert!(!pressed(select));
+}
+
+#[test]
+fn test_localized_source_name() {
+    use common::config::BindingConfigBuilder;
+
+    use crate::fake_runtime;
+
+    //The application grabs with the trigger, the user moved it to the grip
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let config = BindingConfigBuilder::new()
+        .bind(profile_name, "gameplay/grab", "/user/hand/right/input/squeeze/value")
+        .build()
         .unwrap();
 
-        let bindings = subaction_bindings.get_matching(xr::Path::NULL).unwrap();
+    let instance = fake_runtime::create_instance_with_bindings(config);
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("grab", xr::ActionType::FLOAT_INPUT)]);
+    let action = actions[0];
+    assert_eq!(
+        fake_runtime::suggest(&instance, profile_name, &[(action, "/user/hand/right/input/trigger/value")]),
+        xr::Result::SUCCESS
+    );
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
 
-        for binding in bindings
-            .iter()
-            .filter(|output_binding| output_binding.is_active(&session))
-        {
-            acc.push(instance.string_to_path(&binding.binding_str).unwrap())
+    let index = instance.string_to_path(profile_name).unwrap();
+    let squeeze = instance.string_to_path("/user/hand/right/input/squeeze/value").unwrap();
+    let wrapper = session.get_wrapper().unwrap().clone();
+    fake_runtime::set_input_state(
+        session,
+        wrapper.god_states()[&index][&squeeze].action.handle,
+        god_actions::GodActionStateEnum::Float(openxr::ActionState::<f32> {
+            current_state: 1f32,
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(1),
+            is_active: true,
+        }),
+    );
+    assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+
+    let enumerate_info = xr::BoundSourcesForActionEnumerateInfo {
+        ty: xr::BoundSourcesForActionEnumerateInfo::TYPE,
+        next: ptr::null(),
+        action,
+    };
+    let mut source = xr::Path::NULL;
+    let mut count = 0;
+    assert_eq!(
+        unsafe { enumerate_bound_sources_for_action(session, &enumerate_info, 1, &mut count, &mut source) },
+        xr::Result::SUCCESS
+    );
+    assert_eq!(source, squeeze);
+
+    let localized_name = |source_path, which_components| {
+        let get_info = xr::InputSourceLocalizedNameGetInfo {
+            ty: xr::InputSourceLocalizedNameGetInfo::TYPE,
+            next: ptr::null(),
+            source_path,
+            which_components,
+        };
+        let mut count = 0;
+        assert_eq!(unsafe { get_input_source_localized_name(session, &get_info, 0, &mut count, ptr::null_mut()) }, xr::Result::SUCCESS);
+        let mut buffer = vec![0 as c_char; count as usize];
+        assert_eq!(
+            unsafe { get_input_source_localized_name(session, &get_info, count, &mut count, buffer.as_mut_ptr()) },
+            xr::Result::SUCCESS
+        );
+        crate::util::char_arr_to_string_lossy(&buffer)
+    };
+    assert_eq!(localized_name(source, xr::InputSourceLocalizedNameFlags::COMPONENT), "Squeeze");
+    assert_eq!(
+        localized_name(
+            source,
+            xr::InputSourceLocalizedNameFlags::USER_PATH
+                | xr::InputSourceLocalizedNameFlags::INTERACTION_PROFILE
+                | xr::InputSourceLocalizedNameFlags::COMPONENT
+        ),
+        "Right Hand Valve Index Controller Squeeze"
+    );
+    //The diagnostics summary pairs the same source with its name
+    let summary = wrapper.attached_action_summary();
+    assert_eq!(
+        summary[0].sources,
+        vec![BindingInfo {
+            path: String::from("/user/hand/right/input/squeeze/value"),
+            localized_name: Some(String::from("Squeeze")),
+        }]
+    );
+
+    //Any source a god action reads is named from the catalog, poses included
+    let grip = instance.string_to_path("/user/hand/right/input/grip/pose").unwrap();
+    assert_eq!(localized_name(grip, xr::InputSourceLocalizedNameFlags::COMPONENT), "Grip");
+}
+
+#[test]
+fn test_concurrent_sync_and_get_state() {
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("fire", xr::ActionType::FLOAT_INPUT)]);
+    let action = actions[0];
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let trigger_path = "/user/hand/right/input/trigger/value";
+    assert_eq!(fake_runtime::suggest(&instance, profile_name, &[(action, trigger_path)]), xr::Result::SUCCESS);
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let profile = instance.string_to_path(profile_name).unwrap();
+    let trigger_action = wrapper.god_states()[&profile][&instance.string_to_path(trigger_path).unwrap()].action.handle;
+
+    //The application reads its actions on another thread while the main thread syncs, a deadlock leaves the reader unfinished
+    let (done_send, done_recv) = mpsc::channel();
+    let reader = thread::spawn(move || {
+        let session_wrapper = session.get_wrapper().unwrap().clone();
+        for _ in 0..500 {
+            fake_runtime::float_state(session, action, xr::Path::NULL);
+            assert_eq!(session_wrapper.attached_action_summary().len(), 1);
+            session_wrapper.reset_transform_states();
         }
+        done_send.send(()).unwrap();
+    });
+
+    for step in 0..500 {
+        fake_runtime::set_input_state(
+            session,
+            trigger_action,
+            god_actions::GodActionStateEnum::Float(openxr::ActionState::<f32> {
+                current_state: (step % 2) as f32,
+                changed_since_last_sync: true,
+                last_change_time: xr::Time::from_nanos(step),
+                is_active: true,
+            }),
+        );
+        assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
     }
 
-    if source_capacity_input == 0 {
-        *source_count_output = acc.len() as u32;
-    } else {
-        if source_capacity_input < acc.len() as u32 {
-            return xr::Result::ERROR_SIZE_INSUFFICIENT;
+    assert!(done_recv.recv_timeout(Duration::from_secs(30)).is_ok(), "the reader deadlocked against xrSyncActions");
+    reader.join().unwrap();
+}
+
+#[test]
+fn test_sync_one_hand() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+    let left = instance.string_to_path(openxr::USER_HAND_LEFT).unwrap();
+    let right = instance.string_to_path(openxr::USER_HAND_RIGHT).unwrap();
+    let action_set = fake_runtime::new_action_set(&instance, "gameplay");
+    let action = fake_runtime::new_action(action_set, "grab", xr::ActionType::BOOLEAN_INPUT, &[left, right]);
+
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let trigger_paths = ["/user/hand/left/input/trigger/click", "/user/hand/right/input/trigger/click"];
+    assert_eq!(
+        fake_runtime::suggest(&instance, profile_name, &[(action, trigger_paths[0]), (action, trigger_paths[1])]),
+        xr::Result::SUCCESS
+    );
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let profile = instance.string_to_path(profile_name).unwrap();
+    let set_triggers = |pressed: bool, time: i64| {
+        for trigger in &trigger_paths {
+            fake_runtime::set_input_state(
+                session,
+                wrapper.god_states()[&profile][&instance.string_to_path(trigger).unwrap()].action.handle,
+                god_actions::GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+                    current_state: pressed,
+                    changed_since_last_sync: true,
+                    last_change_time: xr::Time::from_nanos(time),
+                    is_active: true,
+                }),
+            );
         }
-        let paths = slice::from_raw_parts_mut(sources, acc.len());
-        paths.copy_from_slice(&acc);
-    }
+    };
+    let sync = |subaction_path: xr::Path| {
+        let active_action_set = xr::ActiveActionSet {
+            action_set,
+            subaction_path,
+        };
+        let sync_info = xr::ActionsSyncInfo {
+            ty: xr::ActionsSyncInfo::TYPE,
+            next: ptr::null(),
+            count_active_action_sets: 1,
+            active_action_sets: &active_action_set,
+        };
+        unsafe { sync_actions(session, &sync_info) }
+    };
+    let grab = |subaction_path| {
+        let state = fake_runtime::boolean_state(session, action, subaction_path);
+        (state.is_active, state.current_state, state.last_change_time)
+    };
 
-    xr::Result::SUCCESS
+    set_triggers(true, 1);
+    assert_eq!(sync(xr::Path::NULL), xr::Result::SUCCESS);
+    let right_pressed = grab(right);
+    assert_eq!((right_pressed.0, right_pressed.1), (true, true));
+    assert!(grab(left).1);
+
+    //Only the left hand is synced, the right hand keeps what it had rather than going inactive
+    set_triggers(false, 2);
+    assert_eq!(sync(left), xr::Result::SUCCESS);
+    assert_eq!((grab(left).0, grab(left).1), (true, false));
+    assert_eq!(grab(right), right_pressed);
+    assert_eq!((grab(xr::Path::NULL).0, grab(xr::Path::NULL).1), (true, true));
+
+    assert_eq!(sync(right), xr::Result::SUCCESS);
+    assert_eq!((grab(right).0, grab(right).1), (true, false));
+    assert!(!grab(xr::Path::NULL).1);
+
+    //A path none of the set's actions were created with can't be synced
+    let head = instance.string_to_path("/user/head").unwrap();
+    assert_eq!(sync(head), xr::Result::ERROR_PATH_UNSUPPORTED);
+}
+
+#[test]
+fn test_absent_source_fallback() {
+    use common::config::BindingConfigBuilder;
+
+    use crate::fake_runtime;
+    use crate::path::TopLevelUserPath;
+
+    //Both actions read the Index trackpad, only scroll says what to use on a controller without one
+    let index_name = "/interaction_profiles/valve/index_controller";
+    let config = BindingConfigBuilder::new()
+        .bind(index_name, "gameplay/scroll", "/user/hand/right/input/trackpad")
+        .with_fallback("/user/hand/right/input/thumbstick")
+        .bind(index_name, "gameplay/pan", "/user/hand/right/input/trackpad")
+        .build()
+        .unwrap();
+
+    let instance = fake_runtime::create_instance_with_bindings(config);
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[("scroll", xr::ActionType::VECTOR2F_INPUT), ("pan", xr::ActionType::VECTOR2F_INPUT)],
+    );
+    let (scroll, pan) = (actions[0], actions[1]);
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let set_source = |profile_name: &str, source: &str, x: f32, is_active: bool| {
+        let profile = instance.string_to_path(profile_name).unwrap();
+        let source = instance.string_to_path(source).unwrap();
+        fake_runtime::set_input_state(
+            session,
+            wrapper.god_states()[&profile][&source].action.handle,
+            god_actions::GodActionStateEnum::Vector2f(openxr::ActionState::<openxr::Vector2f> {
+                current_state: openxr::Vector2f { x, y: 0f32 },
+                changed_since_last_sync: true,
+                last_change_time: xr::Time::from_nanos(1),
+                is_active,
+            }),
+        );
+    };
+    let state = |action| {
+        let state = fake_runtime::vector2f_state(session, action, xr::Path::NULL);
+        (state.is_active, state.current_state.x)
+    };
+
+    //A Touch controller is in use on the right hand, it has a thumbstick but no trackpad
+    let touch_name = "/interaction_profiles/oculus/touch_controller";
+    set_source(touch_name, "/user/hand/right/input/thumbstick", 0.5, true);
+    assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+    let touch = instance.string_to_path(touch_name).unwrap();
+    assert_eq!(wrapper.active_profile(TopLevelUserPath(instance.string_to_path(openxr::USER_HAND_RIGHT).unwrap())).0, touch);
+    assert_eq!(state(scroll), (true, 0.5));
+    assert!(!state(pan).0);
+
+    //Back on the Index both read the trackpad
+    set_source(touch_name, "/user/hand/right/input/thumbstick", 0.5, false);
+    set_source(index_name, "/user/hand/right/input/trackpad", -0.25, true);
+    assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+    assert_eq!(state(scroll), (true, -0.25));
+    assert_eq!(state(pan), (true, -0.25));
+}
+
+#[test]
+fn test_smoothing() {
+    use std::sync::atomic::Ordering;
+
+    use common::config::BindingConfigBuilder;
+
+    use crate::fake_runtime;
+
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let trigger_path = "/user/hand/right/input/trigger/value";
+    let config = BindingConfigBuilder::new()
+        .bind(profile_name, "gameplay/throttle", trigger_path)
+        .with_smoothing(100)
+        .build()
+        .unwrap();
+
+    let instance = fake_runtime::create_instance_with_bindings(config);
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("throttle", xr::ActionType::FLOAT_INPUT)]);
+    let throttle = actions[0];
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let profile = instance.string_to_path(profile_name).unwrap();
+    let trigger = wrapper.god_states()[&profile][&instance.string_to_path(trigger_path).unwrap()].action.handle;
+    let set_trigger = |value: f32, is_active: bool| {
+        fake_runtime::set_input_state(
+            session,
+            trigger,
+            god_actions::GodActionStateEnum::Float(openxr::ActionState::<f32> {
+                current_state: value,
+                changed_since_last_sync: true,
+                last_change_time: xr::Time::from_nanos(1),
+                is_active,
+            }),
+        );
+    };
+    //Every sync is for a frame 20ms after the last one
+    let sync_at_ms = |ms: i64| {
+        wrapper.predicted_display_time.store(ms * 1_000_000, Ordering::Relaxed);
+        assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+        fake_runtime::float_state(session, throttle, xr::Path::NULL).current_state
+    };
+
+    set_trigger(0f32, true);
+    assert_eq!(sync_at_ms(1000), 0f32);
+
+    //A step to fully pulled is eased in, about two thirds of the way after one time constant
+    set_trigger(1f32, true);
+    let eased = (1..=20).map(|frame| sync_at_ms(1000 + frame * 20)).collect::<Vec<_>>();
+    assert!(eased.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", eased);
+    assert!(eased[0] > 0f32 && eased[0] < 0.5, "{:?}", eased);
+    assert!((eased[4] - (1f32 - (-1f32).exp())).abs() < 0.001, "{:?}", eased);
+    assert!(eased[19] > 0.98 && eased[19] < 1f32, "{:?}", eased);
+
+    //Syncing again for the same frame doesn't move it on
+    assert_eq!(sync_at_ms(1400), eased[19]);
+
+    //Letting go of the controller starts over, the next value isn't eased from the old one
+    set_trigger(0f32, false);
+    sync_at_ms(1420);
+    set_trigger(0.5, true);
+    assert_eq!(sync_at_ms(1440), 0.5);
+}
+
+#[test]
+fn test_unknown_action_type_passthrough() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance);
+    let action_set = fake_runtime::new_action_set(&instance, "gameplay");
+    //A type from an extension the layer predates
+    let action = fake_runtime::new_action(action_set, "squish", xr::ActionType::from_raw(1_000_999_000), &[]);
+    assert!(action.get_wrapper().unwrap().is_passthrough());
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    //Nothing is cached for it, the state is whatever the runtime reports
+    let wrapper = session.get_wrapper().unwrap().clone();
+    assert!(wrapper.attached_action_state(action).is_err());
+    assert!(wrapper.output_bindings.get().unwrap().get(&action).is_none());
+    fake_runtime::set_input_state(
+        session,
+        action,
+        god_actions::GodActionStateEnum::Float(openxr::ActionState::<f32> {
+            current_state: 0.75,
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(5),
+            is_active: true,
+        }),
+    );
+    let state = fake_runtime::float_state(session, action, xr::Path::NULL);
+    assert_eq!(state.current_state, 0.75);
+    assert_eq!(state.last_change_time.as_nanos(), 5);
+    assert!(state.is_active);
+
+    //The runtime is also the one to say it was read as the wrong type
+    let get_info = xr::ActionStateGetInfo {
+        ty: xr::ActionStateGetInfo::TYPE,
+        next: ptr::null(),
+        action,
+        subaction_path: xr::Path::NULL,
+    };
+    let mut bool_state = xr::ActionStateBoolean {
+        ty: xr::ActionStateBoolean::TYPE,
+        next: ptr::null_mut(),
+        current_state: false.into(),
+        changed_since_last_sync: false.into(),
+        last_change_time: xr::Time::from_nanos(0),
+        is_active: false.into(),
+    };
+    assert_eq!(unsafe { get_action_state_boolean(session, &get_info, &mut bool_state) }, xr::Result::ERROR_ACTION_TYPE_MISMATCH);
+
+    //Its bound sources are the runtime's to enumerate as well, it has none as nothing was suggested for it
+    let enumerate_info = xr::BoundSourcesForActionEnumerateInfo {
+        ty: xr::BoundSourcesForActionEnumerateInfo::TYPE,
+        next: ptr::null(),
+        action,
+    };
+    let mut count = 1;
+    assert_eq!(
+        unsafe { enumerate_bound_sources_for_action(session, &enumerate_info, 0, &mut count, ptr::null_mut()) },
+        xr::Result::SUCCESS
+    );
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn test_modifier_condition() {
+    use common::config::BindingConfigBuilder;
+
+    use crate::fake_runtime;
+
+    //The trigger jumps while the grip is held and fires otherwise
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let trigger_path = "/user/hand/right/input/trigger/click";
+    let squeeze_path = "/user/hand/right/input/squeeze/value";
+    let config = BindingConfigBuilder::new()
+        .bind(profile_name, "gameplay/jump", trigger_path)
+        .with_condition(squeeze_path, false)
+        .bind(profile_name, "gameplay/fire", trigger_path)
+        .with_condition(squeeze_path, true)
+        .build()
+        .unwrap();
+
+    let instance = fake_runtime::create_instance_with_bindings(config);
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[("jump", xr::ActionType::BOOLEAN_INPUT), ("fire", xr::ActionType::BOOLEAN_INPUT)],
+    );
+    let (jump, fire) = (actions[0], actions[1]);
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let profile = instance.string_to_path(profile_name).unwrap();
+    let god_action = |source: &str| wrapper.god_states()[&profile][&instance.string_to_path(source).unwrap()].action.handle;
+    fake_runtime::set_input_state(
+        session,
+        god_action(trigger_path),
+        god_actions::GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+            current_state: true,
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(1),
+            is_active: true,
+        }),
+    );
+    let set_squeeze = |value: f32| {
+        fake_runtime::set_input_state(
+            session,
+            god_action(squeeze_path),
+            god_actions::GodActionStateEnum::Float(openxr::ActionState::<f32> {
+                current_state: value,
+                changed_since_last_sync: true,
+                last_change_time: xr::Time::from_nanos(1),
+                is_active: true,
+            }),
+        );
+    };
+    let state = |action| {
+        let state = fake_runtime::boolean_state(session, action, xr::Path::NULL);
+        (state.is_active, state.current_state)
+    };
+
+    //With no other binding a gated off action is inactive
+    set_squeeze(0.1);
+    assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+    assert_eq!(state(jump), (false, false));
+    assert_eq!(state(fire), (true, true));
+
+    set_squeeze(0.9);
+    assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+    assert_eq!(state(jump), (true, true));
+    assert_eq!(state(fire), (false, false));
+
+    let effective = wrapper.effective_bindings();
+    let jump_binding = &effective.profiles[profile_name].action_sets["gameplay"].actions["jump"].bindings[0];
+    assert_eq!(jump_binding.when.as_ref().map(|when| (when.path.as_str(), when.released)), Some((squeeze_path, false)));
+}
+
+#[test]
+fn test_unmanaged_user_paths() {
+    use common::config::Settings;
+
+    use crate::fake_runtime;
+
+    let settings = Settings {
+        managed_user_paths: vec![String::from("/user/hand/left"), String::from("/user/hand/right")],
+        ..Default::default()
+    };
+    let instance = fake_runtime::create_instance_with_settings(settings);
+    let xbox_name = "/interaction_profiles/microsoft/xbox_controller";
+    let index_name = "/interaction_profiles/valve/index_controller";
+    let xbox = instance.string_to_path(xbox_name).unwrap();
+    let index = instance.string_to_path(index_name).unwrap();
+    assert!(!instance.god_action_sets().contains_key(&xbox));
+    assert!(instance.god_action_sets().contains_key(&index));
+
+    let session = fake_runtime::new_session(&instance);
+    let (driving, driving_actions) = fake_runtime::new_actions(&instance, "driving", &[("honk", xr::ActionType::BOOLEAN_INPUT)]);
+    let (hands, hand_actions) = fake_runtime::new_actions(&instance, "hands", &[("grab", xr::ActionType::BOOLEAN_INPUT)]);
+    let (honk, grab) = (driving_actions[0], hand_actions[0]);
+    assert_eq!(fake_runtime::suggest(&instance, xbox_name, &[(honk, "/user/gamepad/input/a/click")]), xr::Result::SUCCESS);
+    assert_eq!(fake_runtime::suggest(&instance, index_name, &[(grab, "/user/hand/right/input/trigger/click")]), xr::Result::SUCCESS);
+
+    //The gamepad suggestion reaches the runtime untouched and isn't taken for an unknown profile, the hand one stays in the layer
+    assert!(fake_runtime::suggested_actions(instance.handle, xbox).contains(&honk));
+    assert!(!fake_runtime::suggested_actions(instance.handle, index).contains(&grab));
+    assert!(instance.unknown_profiles.read().unwrap().is_empty());
+
+    assert_eq!(fake_runtime::attach(session, &[driving, hands]), xr::Result::SUCCESS);
+    let attached = fake_runtime::attached_sets(session);
+    assert!(attached.contains(&driving));
+    assert!(!attached.contains(&hands));
+    assert!(attached.contains(&instance.god_action_sets()[&index].handle));
+
+    let wrapper = session.get_wrapper().unwrap().clone();
+    assert!(wrapper.leaves_to_runtime(honk));
+    assert!(!wrapper.leaves_to_runtime(grab));
+    let pressed = god_actions::GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+        current_state: true,
+        changed_since_last_sync: true,
+        last_change_time: xr::Time::from_nanos(3),
+        is_active: true,
+    });
+    fake_runtime::set_input_state(session, honk, pressed);
+    let trigger = instance.string_to_path("/user/hand/right/input/trigger/click").unwrap();
+    fake_runtime::set_input_state(session, wrapper.god_states()[&index][&trigger].action.handle, pressed);
+    assert_eq!(fake_runtime::sync(session, &[driving, hands]), xr::Result::SUCCESS);
+
+    //The runtime reads the gamepad action itself, the hand action is remapped through its god action
+    let state = |action| {
+        let state = fake_runtime::boolean_state(session, action, xr::Path::NULL);
+        (state.is_active, state.current_state)
+    };
+    assert_eq!(state(honk), (true, true));
+    assert!(wrapper.attached_action_state(honk).is_err());
+    assert_eq!(state(grab), (true, true));
+    assert!(wrapper.attached_action_state(grab).is_ok());
+
+    //The runtime bound the gamepad action, so it enumerates and names its sources too
+    let enumerate_info = xr::BoundSourcesForActionEnumerateInfo {
+        ty: xr::BoundSourcesForActionEnumerateInfo::TYPE,
+        next: ptr::null(),
+        action: honk,
+    };
+    let mut sources = [xr::Path::NULL; 4];
+    let mut count = 0;
+    let result = unsafe { enumerate_bound_sources_for_action(session, &enumerate_info, 4, &mut count, sources.as_mut_ptr()) };
+    assert_eq!(result, xr::Result::SUCCESS);
+    let a_click = instance.string_to_path("/user/gamepad/input/a/click").unwrap();
+    assert_eq!(&sources[..count as usize], &[a_click]);
+
+    let get_info = xr::InputSourceLocalizedNameGetInfo {
+        ty: xr::InputSourceLocalizedNameGetInfo::TYPE,
+        next: ptr::null(),
+        source_path: a_click,
+        which_components: xr::InputSourceLocalizedNameFlags::COMPONENT,
+    };
+    assert_eq!(unsafe { get_input_source_localized_name(session, &get_info, 0, &mut count, ptr::null_mut()) }, xr::Result::SUCCESS);
+    let mut buffer = vec![0 as c_char; count as usize];
+    assert_eq!(
+        unsafe { get_input_source_localized_name(session, &get_info, count, &mut count, buffer.as_mut_ptr()) },
+        xr::Result::SUCCESS
+    );
+    assert_eq!(
+        crate::util::char_arr_to_string_lossy(&buffer),
+        format!("{} /user/gamepad/input/a/click", fake_runtime::RUNTIME_NAME)
+    );
+}
+
+#[test]
+fn test_action_limit_reached() {
+    use common::config::{BindingConfigBuilder, Settings};
+
+    use crate::fake_runtime;
+
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let config = BindingConfigBuilder::new()
+        .bind(profile_name, "gameplay/jump", "/user/hand/right/input/trigger/click")
+        .build()
+        .unwrap();
+    let instance = fake_runtime::create_configured_instance(config, Settings { defer_god_sets: true, ..Default::default() });
+    let session = fake_runtime::new_session(&instance);
+    let (gameplay, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+    assert_eq!(fake_runtime::suggest(&instance, profile_name, &[(actions[0], "/user/hand/right/input/trigger/click")]), xr::Result::SUCCESS);
+    fake_runtime::limit_actions(instance.handle, 5);
+    assert_eq!(fake_runtime::attach(session, &[gameplay]), xr::Result::SUCCESS);
+
+    //The set is cut short at the limit rather than failing, and what was made is attached and read
+    let profile = instance.string_to_path(profile_name).unwrap();
+    let god_set = &instance.god_action_sets()[&profile];
+    assert_eq!(god_set.god_actions().count(), 5);
+    assert_eq!(fake_runtime::allocated(god_set.handle.into_raw(), xr::ObjectType::ACTION).len(), 5);
+    let attached = fake_runtime::attached_sets(session);
+    assert!(attached.contains(&god_set.handle));
+    assert!(!attached.contains(&gameplay));
+    let wrapper = session.get_wrapper().unwrap().clone();
+    assert!(!wrapper.god_states()[&profile].is_empty());
 }
@@ -1,5 +1,6 @@
 use std::sync::Weak;
 
+use crate::ffi::catch_panic;
 use crate::wrappers::*;
 
 use openxr::sys as xr;
@@ -10,26 +11,27 @@ pub unsafe extern "system" fn locate_space(
     time: xr::Time,
     location: *mut xr::SpaceLocation,
 ) -> xr::Result {
-    let location = &mut *location;
-    let (space, base_space) = match (space.get_wrapper(), base_space.get_wrapper()) {
-        (Some(space), Some(base_space)) => (space, base_space),
-        _ => return xr::Result::ERROR_HANDLE_INVALID,
-    };
+    catch_panic("xrLocateSpace", || {
+        let location = &mut *location;
+        let (space, base_space) = match (space.get_wrapper(), base_space.get_wrapper()) {
+            (Some(space), Some(base_space)) => (space, base_space),
+            _ => return xr::Result::ERROR_HANDLE_INVALID,
+        };
 
-    if !Weak::ptr_eq(&space.session, &base_space.session) {
-        return xr::Result::ERROR_VALIDATION_FAILURE;
-    }
-
-    let (space_handle, base_space_handle) = match (space.get_handle(), base_space.get_handle()) {
-        (Some(space), Some(base_space)) => (space, base_space),
-        _ => {
-            location.location_flags = xr::SpaceLocationFlags::EMPTY;
-            location.pose = Default::default();
-            location.pose.orientation.w = 1.;
-            return xr::Result::SUCCESS;
+        if !Weak::ptr_eq(&space.session, &base_space.session) {
+            return xr::Result::ERROR_VALIDATION_FAILURE;
         }
-    };
 
-    let result = (space.session().instance().core.locate_space)(space_handle, base_space_handle, time, location);
-    result
+        let (space_handle, base_space_handle) = match (space.get_handle(), base_space.get_handle()) {
+            (Some(space), Some(base_space)) => (space, base_space),
+            _ => {
+                location.location_flags = xr::SpaceLocationFlags::EMPTY;
+                location.pose = Default::default();
+                location.pose.orientation.w = 1.;
+                return xr::Result::SUCCESS;
+            }
+        };
+
+        (space.session().instance().core.locate_space)(space_handle, base_space_handle, time, location)
+    })
 }
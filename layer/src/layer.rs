@@ -0,0 +1,523 @@
+//! The entry points of the remapping layer, everything the loader calls once negotiation is done
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::RwLock;
+
+use common::config::{self, BindingConfig};
+use common::serial::{CONFIG_DIR, get_uuid};
+use once_cell::sync::{Lazy, OnceCell};
+use openxr::sys as xr;
+use openxr::sys::pfn as pfn;
+
+use crate::emulated;
+#[cfg(test)]
+use crate::fake_runtime;
+use crate::ffi::*;
+use crate::god_actions;
+use crate::injections;
+use crate::loader_interfaces::*;
+use crate::selftest;
+use crate::trace;
+use crate::util::{self, *};
+use crate::wrappers::{self, *};
+
+pub unsafe extern "system" fn create_api_layer_instance(
+    instance_info: *const xr::InstanceCreateInfo, 
+    layer_info: *const ApiLayerCreateInfo, 
+    instance: *mut xr::Instance
+) -> xr::Result 
+{
+    catch_panic("xrCreateApiLayerInstance", || {
+        let next_info = &*(*layer_info).next_info;
+
+        assert_eq!(LAYER_NAME, CStr::from_ptr(next_info.layer_name.as_ptr().cast()).to_str().unwrap());
+
+        //Get the xrGetInstanceProcAddr func of the layer bellow us
+        let get_instance_proc_addr_next: pfn::GetInstanceProcAddr = next_info.next_get_instance_proc_addr; 
+
+        //Initialize the layer bellow us
+        let result = {
+            let mut my_create_info = *layer_info;
+            my_create_info.next_info = next_info.next;
+
+            (next_info.next_create_api_layer_instance)(instance_info, &my_create_info, instance)
+        };
+
+        if result.into_raw() < 0 { return result; }
+
+        let application_info = &(*instance_info).application_info;
+        let application_name = i8_arr_to_owned(&application_info.application_name);
+        let engine_name = i8_arr_to_owned(&application_info.engine_name);
+
+        let activation = config::load_activation(Path::new(config::ACTIVATION_FILE)).unwrap_or_else(|why| {
+            println!("{}: {}, remapping every application", config::ACTIVATION_FILE, why);
+            Default::default()
+        });
+        let passthrough = !activation.is_active(&application_name, &engine_name);
+        if passthrough {
+            println!("{} isn't activated, passing it straight through to the runtime", application_name);
+        }
+
+        let entry = match openxr::Entry::from_proc_addr(get_instance_proc_addr_next) {
+            Ok(caller) => caller,
+            Err(result) => return result,
+        };
+
+        let core = match openxr::raw::Instance::load(&entry, *instance) {
+            Ok(caller) => {
+                if trace::enabled(trace::NEXT_LAYER) {
+                    println!("{}", trace::FunctionTable::probe(get_instance_proc_addr_next, *instance).describe(true));
+                }
+                caller
+            },
+            Err(result) => {
+                //Loading stops at the first missing function, the table says which ones it was missing
+                println!("{}", trace::FunctionTable::probe(get_instance_proc_addr_next, *instance).describe(false));
+                return result;
+            },
+        };
+
+        let enabled_extensions = if (*instance_info).enabled_extension_count == 0 {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(
+                (*instance_info).enabled_extension_names,
+                (*instance_info).enabled_extension_count as usize,
+            )
+            .iter()
+            .map(|ptr| CStr::from_ptr(*ptr).to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+        };
+
+        let enabled_ext = enabled_extensions
+            .iter()
+            .filter_map(|name| {
+                //The names come from the application so an oversized one is skipped rather than trusted
+                let mut extension_name = [0; xr::MAX_EXTENSION_NAME_SIZE];
+                if let Err(err) = util::try_place_cstr(&mut extension_name, name) {
+                    println!("ignoring extension {} as it needs {} bytes", name, err.required);
+                    return None;
+                }
+                Some(xr::ExtensionProperties {
+                    ty: xr::ExtensionProperties::TYPE,
+                    next: std::ptr::null_mut(),
+                    extension_name,
+                    extension_version: 0,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let exts = match openxr::InstanceExtensions::load(&entry, *instance, &openxr::ExtensionSet::from_properties(&enabled_ext)) {
+            Ok(caller) => caller,
+            Err(result) => return result,
+        };
+
+        let mut wrapper = wrappers::InstanceWrapper {
+            handle: *instance,
+            sessions: RwLock::new(Vec::new()),
+            action_sets: RwLock::new(Vec::new()),
+
+            god_action_sets: Default::default(),
+            emulated_set: None,
+            host_input: Default::default(),
+
+            custom_bindings: if passthrough {
+                Default::default()
+            } else {
+                load_custom_bindings(&application_name)
+            },
+
+            unknown_profiles: Default::default(),
+            forwarded_suggestions: Default::default(),
+
+            settings: config::load_settings(Path::new(config::SETTINGS_FILE)).unwrap_or_else(|why| {
+                println!("{}: {}, using the default settings", config::SETTINGS_FILE, why);
+                Default::default()
+            }),
+
+            enabled_extensions,
+
+            application_name,
+            application_version: application_info.application_version,
+            engine_name,
+            engine_version: application_info.engine_version,
+
+            core,
+            exts,
+
+            passthrough,
+
+            get_instance_proc_addr_next,
+        };
+
+        match wrapper.instance_properties() {
+            Ok((runtime_name, runtime_version)) => println!("Runtime: {} {}", runtime_name, runtime_version),
+            Err(result) => println!("couldn't get the runtime's properties: {}", result),
+        }
+
+        if !passthrough {
+            //Deferred god action sets are created in xrAttachSessionActionSets for just the profiles in use
+            if !wrapper.settings.defer_god_sets {
+                match god_actions::create_god_action_sets(&wrapper) {
+                    Ok(god_action_sets) => {
+                        wrapper.god_action_sets = OnceCell::from(god_action_sets);
+                    },
+                    Err(result) => {
+                        println!("failed to create god action sets");
+                        wrapper.destroy_instance();
+                        *instance = xr::Instance::NULL;
+                        return result;      
+                    },
+                }
+            }
+
+            if emulated::enabled() {
+                match emulated::profile() {
+                    Some(profile_info) => {
+                        match god_actions::GodActionSet::create_emulated_set(&wrapper, profile_info) {
+                            Ok(emulated_set) => wrapper.emulated_set = Some(emulated_set),
+                            Err(result) => println!("failed to create the emulated input profile: {}", result),
+                        }
+                        #[cfg(target_os = "linux")]
+                        match emulated::Joystick::open_configured() {
+                            Ok(joystick) => *wrapper.host_input.get_mut().unwrap() = Some(Box::new(joystick)),
+                            Err(why) => println!("couldn't open a joystick for emulated input: {}", why),
+                        }
+                    }
+                    None => println!("input is not emulated as the catalog has no {} for its layout", emulated::LAYOUT_PROFILE),
+                }
+            }
+        }
+
+        let selftest_mode = selftest::mode();
+        if !passthrough && selftest_mode != selftest::Mode::Off {
+            //Injected bindings never came from a file, only the ones read from disk can be checked again
+            let config_files = if INJECTED_BINDINGS.lock().unwrap().is_some() {
+                Vec::new()
+            } else {
+                custom_binding_files(&wrapper.application_name).to_vec()
+            };
+            let report = selftest::run(&wrapper, &config_files);
+            selftest::publish(&wrapper, &report);
+            if !report.passed() && selftest_mode == selftest::Mode::Abort {
+                wrapper.destroy_instance();
+                *instance = xr::Instance::NULL;
+                return xr::Result::ERROR_INITIALIZATION_FAILED;
+            }
+        }
+
+        //Add this instance to the wrapper map
+        if let Some(warning) = register_handle(instances(), *instance, Arc::new(wrapper)) {
+            println!("{}", warning);
+        }
+
+        result
+    })
+}
+
+static INJECTED_BINDINGS: Lazy<Mutex<Option<BindingConfig>>> = Lazy::new(|| Mutex::new(None));
+
+///Makes instances created from now on use `bindings` instead of reading custom_bindings.json, `None` goes back to the file
+///
+///For embedders that build their config with [config::BindingConfigBuilder] rather than writing one out
+pub fn inject_custom_bindings(bindings: Option<BindingConfig>) {
+    *INJECTED_BINDINGS.lock().unwrap() = bindings;
+}
+
+///The custom binding files of an application, the shared one first and then the per machine one layered over it
+fn custom_binding_files(application_name: &str) -> [PathBuf; 2] {
+    let uuid = get_uuid(application_name);
+    let path = |stem: &str| {
+        let toml = PathBuf::from(format!("{}{}/{}.toml", CONFIG_DIR, uuid, stem));
+        if toml.exists() {
+            toml
+        } else {
+            toml.with_extension("json")
+        }
+    };
+    [path("custom_bindings"), path("custom_bindings.local")]
+}
+
+///The injected bindings if there are any, otherwise the application's custom_bindings.toml or custom_bindings.json
+///
+///custom_bindings.local.toml or custom_bindings.local.json, when there is one, is laid over it for settings that only apply to this machine
+pub(crate) fn load_custom_bindings(application_name: &str) -> BindingConfig {
+    custom_bindings_or_files(INJECTED_BINDINGS.lock().unwrap().clone(), application_name)
+}
+
+///[load_custom_bindings] with `injected` in place of the injected bindings
+fn custom_bindings_or_files(injected: Option<BindingConfig>, application_name: &str) -> BindingConfig {
+    if let Some(bindings) = injected {
+        return bindings;
+    }
+    let paths = custom_binding_files(application_name);
+    config::load_layered(&paths).unwrap_or_else(|why| {
+        println!("{}: {}, using the application's bindings", paths[0].display(), why);
+        Default::default()
+    })
+}
+
+///Functions the layer deliberately leaves to the runtime
+///
+///Remapping poses means wrapping the spaces made from actions, and xrCreateReferenceSpace is wrapped only so xrLocateSpace can tell the two kinds apart.
+///Everything else about reference spaces is the runtime's business, these are listed so they are never picked up by an injection by accident
+const PASSTHROUGH_FUNCTIONS: &[&str] = &["xrEnumerateReferenceSpaces", "xrGetReferenceSpaceBoundsRect"];
+
+pub unsafe extern "system" fn instance_proc_addr(instance: xr::Instance, name: *const c_char, function: *mut Option<pfn::VoidFunction>) -> xr::Result {
+    catch_panic("xrGetInstanceProcAddr", || {
+        let instance = InstanceWrapper::from_handle_panic(instance);
+        let result = (instance.get_instance_proc_addr_next)(instance.handle, name, function);
+
+        if result.into_raw() < 0 { return result; }
+
+        let name = if let Ok(slice) = CStr::from_ptr(name).to_str() { slice } else { return xr::Result::ERROR_VALIDATION_FAILURE };
+        println!("instance_proc_addr: {}", name);
+
+        //The wrapper still has to be cleaned up, everything else goes straight to the runtime
+        if instance.passthrough && name != "xrDestroyInstance" {
+            return result;
+        }
+        if PASSTHROUGH_FUNCTIONS.contains(&name) {
+            return result;
+        }
+
+        (*function) = Some(
+            match name {
+                //Constructors
+                "xrCreateSession" => std::mem::transmute::<pfn::CreateSession, pfn::VoidFunction>(injections::create_session),
+                "xrCreateActionSet" => std::mem::transmute::<pfn::CreateActionSet, pfn::VoidFunction>(injections::create_action_set),
+                "xrCreateAction" => std::mem::transmute::<pfn::CreateAction, pfn::VoidFunction>(injections::create_action),
+                "xrCreateActionSpace" => std::mem::transmute::<pfn::CreateActionSpace, pfn::VoidFunction>(injections::create_action_space),
+                "xrCreateReferenceSpace" => std::mem::transmute::<pfn::CreateReferenceSpace, pfn::VoidFunction>(injections::create_reference_space),
+
+                //Destructors
+                "xrDestroyInstance" => std::mem::transmute::<pfn::DestroyInstance, pfn::VoidFunction>(injections::destroy_instance),
+                "xrDestroySession" => std::mem::transmute::<pfn::DestroySession, pfn::VoidFunction>(injections::destroy_session),
+                "xrDestroyActionSet" => std::mem::transmute::<pfn::DestroyActionSet, pfn::VoidFunction>(injections::destroy_action_set),
+                "xrDestroyAction" => std::mem::transmute::<pfn::DestroyAction, pfn::VoidFunction>(injections::destroy_action),
+                "xrDestroySpace" => std::mem::transmute::<pfn::DestroySpace, pfn::VoidFunction>(injections::destroy_space),
+
+                //Instance methods
+                "xrSuggestInteractionProfileBindings" => std::mem::transmute::<pfn::SuggestInteractionProfileBindings, pfn::VoidFunction>(injections::instance::suggest_interaction_profile_bindings),
+                "xrPollEvent" => std::mem::transmute::<pfn::PollEvent, pfn::VoidFunction>(injections::instance::poll_event),
+
+                //Session methods
+                "xrAttachSessionActionSets" => std::mem::transmute::<pfn::AttachSessionActionSets, pfn::VoidFunction>(injections::session::attach_session_action_sets),
+                "xrWaitFrame" => std::mem::transmute::<pfn::WaitFrame, pfn::VoidFunction>(injections::session::wait_frame),
+                "xrSyncActions" => std::mem::transmute::<pfn::SyncActions, pfn::VoidFunction>(injections::session::sync_actions),
+                "xrGetActionStateBoolean" => std::mem::transmute::<pfn::GetActionStateBoolean, pfn::VoidFunction>(injections::session::get_action_state_boolean),
+                "xrGetActionStateFloat" => std::mem::transmute::<pfn::GetActionStateFloat, pfn::VoidFunction>(injections::session::get_action_state_float),
+                "xrGetActionStateVector2f" => std::mem::transmute::<pfn::GetActionStateVector2f, pfn::VoidFunction>(injections::session::get_action_state_vector2f),
+                "xrGetActionStatePose" => std::mem::transmute::<pfn::GetActionStatePose, pfn::VoidFunction>(injections::session::get_action_state_pose),
+                "xrLocateViews" => std::mem::transmute::<pfn::LocateViews, pfn::VoidFunction>(injections::session::locate_views),
+                "xrApplyHapticFeedback" => std::mem::transmute::<pfn::ApplyHapticFeedback, pfn::VoidFunction>(injections::session::apply_haptic_feedback),
+                "xrStopHapticFeedback" => std::mem::transmute::<pfn::StopHapticFeedback, pfn::VoidFunction>(injections::session::stop_haptic_feedback),
+                "xrEnumerateBoundSourcesForAction" => std::mem::transmute::<pfn::EnumerateBoundSourcesForAction, pfn::VoidFunction>(injections::session::enumerate_bound_sources_for_action),
+                "xrGetInputSourceLocalizedName" => std::mem::transmute::<pfn::GetInputSourceLocalizedName, pfn::VoidFunction>(injections::session::get_input_source_localized_name),
+
+                //Space methods
+                "xrLocateSpace" => std::mem::transmute::<pfn::LocateSpace, pfn::VoidFunction>(injections::space::locate_space),
+
+                //Whatever the runtime gave us, a runtime may report success for a name it doesn't know and leave the function null
+                _ => return result,
+            }
+        );
+
+        result
+    })
+}
+
+#[test]
+fn test_unknown_function() {
+    unsafe extern "system" fn null_proc_addr(
+        _instance: xr::Instance,
+        _name: *const c_char,
+        function: *mut Option<pfn::VoidFunction>,
+    ) -> xr::Result {
+        *function = None;
+        xr::Result::SUCCESS
+    }
+
+    let instance = fake_runtime::create_instance_with_proc_addr(null_proc_addr);
+
+    let mut function = None;
+    let result = unsafe { instance_proc_addr(instance.handle, b"xrNonexistentFunction\0".as_ptr() as _, &mut function) };
+    assert_eq!(result, xr::Result::SUCCESS);
+    assert!(function.is_none());
+
+    //Functions the layer intercepts are still handed out
+    let result = unsafe { instance_proc_addr(instance.handle, b"xrSyncActions\0".as_ptr() as _, &mut function) };
+    assert_eq!(result, xr::Result::SUCCESS);
+    assert!(function.is_some());
+}
+
+#[test]
+fn test_passthrough() {
+    let active = fake_runtime::create_instance();
+    let passthrough = fake_runtime::create_passthrough_instance();
+    assert!(passthrough.god_action_sets().is_empty());
+
+    let proc_addr = |instance: &InstanceWrapper, name: &[u8]| {
+        let mut function = None;
+        let result = unsafe { instance_proc_addr(instance.handle, name.as_ptr() as _, &mut function) };
+        assert_eq!(result, xr::Result::SUCCESS);
+        function.unwrap() as usize
+    };
+    let next_proc_addr = |instance: &InstanceWrapper, name: &[u8]| {
+        let mut function = None;
+        unsafe { (instance.get_instance_proc_addr_next)(instance.handle, name.as_ptr() as _, &mut function) };
+        function.unwrap() as usize
+    };
+
+    let sync_actions = injections::session::sync_actions as pfn::SyncActions as usize;
+    assert_eq!(proc_addr(&active, b"xrSyncActions\0"), sync_actions);
+    assert_eq!(proc_addr(&passthrough, b"xrSyncActions\0"), next_proc_addr(&passthrough, b"xrSyncActions\0"));
+    assert_ne!(proc_addr(&passthrough, b"xrSyncActions\0"), sync_actions);
+
+    let destroy_instance = injections::destroy_instance as pfn::DestroyInstance as usize;
+    assert_eq!(proc_addr(&passthrough, b"xrDestroyInstance\0"), destroy_instance);
+}
+
+#[test]
+fn test_passthrough_functions() {
+    let instance = fake_runtime::create_instance();
+    let proc_addr = |get_instance_proc_addr: pfn::GetInstanceProcAddr, name: &str| {
+        let name = std::ffi::CString::new(name).unwrap();
+        let mut function = None;
+        assert_eq!(unsafe { get_instance_proc_addr(instance.handle, name.as_ptr(), &mut function) }, xr::Result::SUCCESS);
+        function.unwrap() as usize
+    };
+
+    for name in PASSTHROUGH_FUNCTIONS {
+        assert_eq!(proc_addr(instance_proc_addr, name), proc_addr(instance.get_instance_proc_addr_next, name), "{}", name);
+    }
+    //Reference spaces are still created through the layer so they can be told apart from action spaces
+    let create_reference_space = injections::create_reference_space as pfn::CreateReferenceSpace as usize;
+    assert_eq!(proc_addr(instance_proc_addr, "xrCreateReferenceSpace"), create_reference_space);
+}
+
+#[test]
+fn test_negotiate_twice() {
+    let instance = fake_runtime::create_instance();
+
+    let layer_name = std::ffi::CString::new(LAYER_NAME).unwrap();
+    let negotiate = || {
+        let mut request: XrNegotiateApiLayerRequest = unsafe { std::mem::zeroed() };
+        let result = unsafe { crate::xrNegotiateLoaderApiLayerInterface(std::ptr::null(), layer_name.as_ptr(), &mut request) };
+        assert_eq!(result, xr::Result::SUCCESS);
+        assert!(request.get_instance_proc_addr.is_some());
+    };
+    negotiate();
+    negotiate();
+
+    //Negotiating again mustn't start the layer over, the instance made before it is still known
+    assert!(InstanceWrapper::from_handle(instance.handle).is_some());
+}
+
+#[test]
+fn test_exported_create_session() {
+    let instance = fake_runtime::create_instance();
+
+    //What the loader is handed is the remapping layer's own dispatch, not a separate one
+    let layer_name = std::ffi::CString::new(LAYER_NAME).unwrap();
+    let mut request: XrNegotiateApiLayerRequest = unsafe { std::mem::zeroed() };
+    let result = unsafe { crate::xrNegotiateLoaderApiLayerInterface(std::ptr::null(), layer_name.as_ptr(), &mut request) };
+    assert_eq!(result, xr::Result::SUCCESS);
+    assert_eq!(request.get_instance_proc_addr.unwrap() as usize, instance_proc_addr as pfn::GetInstanceProcAddr as usize);
+
+    let mut function = None;
+    let result = unsafe { (request.get_instance_proc_addr.unwrap())(instance.handle, b"xrCreateSession\0".as_ptr() as _, &mut function) };
+    assert_eq!(result, xr::Result::SUCCESS);
+    assert_eq!(function.unwrap() as usize, injections::create_session as pfn::CreateSession as usize);
+
+    //Sessions made through it get their god action sets attached
+    let create_session = unsafe { std::mem::transmute::<pfn::VoidFunction, pfn::CreateSession>(function.unwrap()) };
+    let create_info = xr::SessionCreateInfo {
+        ty: xr::SessionCreateInfo::TYPE,
+        next: std::ptr::null(),
+        create_flags: xr::SessionCreateFlags::EMPTY,
+        system_id: xr::SystemId::from_raw(1),
+    };
+    let mut session = xr::Session::NULL;
+    assert_eq!(unsafe { create_session(instance.handle, &create_info, &mut session) }, xr::Result::SUCCESS);
+    assert!(!session.get_wrapper().unwrap().god_states().is_empty());
+}
+
+#[test]
+fn test_built_config() {
+    use common::config::BindingConfigBuilder;
+
+    use crate::god_actions::GodActionStateEnum;
+
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let trigger = "/user/hand/right/input/trigger/value";
+    let squeeze = "/user/hand/right/input/squeeze/value";
+    let trigger_click = "/user/hand/right/input/trigger/click";
+    let a_click = "/user/hand/right/input/a/click";
+    let config = BindingConfigBuilder::new()
+        .bind_vector(profile_name, "gameplay/steer", trigger, squeeze)
+        .with_dead_zone(0.2)
+        //A chord, boosting takes A with the trigger pulled
+        .bind(profile_name, "gameplay/boost", a_click)
+        .with_condition(trigger_click, false)
+        .build()
+        .unwrap();
+
+    //Injected bindings stand in for custom_bindings.json
+    let injected = custom_bindings_or_files(Some(config.clone()), "fake_application");
+    assert!(injected.profiles.contains_key(profile_name));
+
+    let instance = fake_runtime::create_instance_with_bindings(injected);
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[("steer", xr::ActionType::VECTOR2F_INPUT), ("boost", xr::ActionType::BOOLEAN_INPUT)],
+    );
+    let (steer, boost) = (actions[0], actions[1]);
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let profile = instance.string_to_path(profile_name).unwrap();
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let god_action = |path: &str| wrapper.god_states()[&profile][&instance.string_to_path(path).unwrap()].action.handle;
+    let sync = |trigger_value: f32, a: bool| {
+        let float = |current_state| {
+            GodActionStateEnum::Float(openxr::ActionState::<f32> {
+                current_state,
+                changed_since_last_sync: true,
+                last_change_time: xr::Time::from_nanos(10),
+                is_active: true,
+            })
+        };
+        let boolean = |current_state| {
+            GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+                current_state,
+                changed_since_last_sync: true,
+                last_change_time: xr::Time::from_nanos(10),
+                is_active: true,
+            })
+        };
+        fake_runtime::set_input_state(session, god_action(trigger), float(trigger_value));
+        fake_runtime::set_input_state(session, god_action(squeeze), float(0.0));
+        fake_runtime::set_input_state(session, god_action(trigger_click), boolean(trigger_value == 1.0));
+        fake_runtime::set_input_state(session, god_action(a_click), boolean(a));
+        assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+        (
+            fake_runtime::vector2f_state(session, steer, xr::Path::NULL).current_state,
+            fake_runtime::boolean_state(session, boost, xr::Path::NULL).current_state,
+        )
+    };
+
+    let (steered, boosted) = sync(0.6, true);
+    assert!((steered.x - 0.5).abs() < 1e-6);
+    assert_eq!(steered.y, 0f32);
+    assert!(!boosted);
+    let (_, boosted) = sync(1.0, true);
+    assert!(boosted);
+    let (_, boosted) = sync(1.0, false);
+    assert!(!boosted);
+}
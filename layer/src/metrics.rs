@@ -0,0 +1,185 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(any(test, feature = "sync_timing"))]
+use std::sync::Mutex;
+#[cfg(any(test, feature = "sync_timing"))]
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+
+///Every function the layer intercepts, named as in the spec
+const FUNCTIONS: &[&str] = &[
+    "xrNegotiateLoaderApiLayerInterface",
+    "xrCreateApiLayerInstance",
+    "xrGetInstanceProcAddr",
+    "xrCreateSession",
+    "xrCreateActionSet",
+    "xrCreateAction",
+    "xrCreateActionSpace",
+    "xrCreateReferenceSpace",
+    "xrDestroyInstance",
+    "xrDestroySession",
+    "xrDestroyActionSet",
+    "xrDestroyAction",
+    "xrDestroySpace",
+    "xrSuggestInteractionProfileBindings",
+    "xrPollEvent",
+    "xrAttachSessionActionSets",
+    "xrWaitFrame",
+    "xrSyncActions",
+    "xrGetActionStateBoolean",
+    "xrGetActionStateFloat",
+    "xrGetActionStateVector2f",
+    "xrGetActionStatePose",
+    "xrLocateViews",
+    "xrApplyHapticFeedback",
+    "xrStopHapticFeedback",
+    "xrEnumerateBoundSourcesForAction",
+    "xrGetInputSourceLocalizedName",
+    "xrLocateSpace",
+];
+
+//Never written to after creation so counting only touches the one atomic
+static COUNTERS: Lazy<HashMap<&'static str, AtomicU64>> = Lazy::new(|| {
+    FUNCTIONS
+        .iter()
+        .map(|name| (*name, AtomicU64::new(0)))
+        .collect()
+});
+
+///Counts a call to an intercepted function, names the layer doesn't intercept are ignored
+pub fn count(name: &str) {
+    if let Some(counter) = COUNTERS.get(name) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+///How many times each intercepted function has been called since the layer was loaded, across every instance
+pub fn metrics() -> BTreeMap<&'static str, u64> {
+    COUNTERS
+        .iter()
+        .map(|(name, counter)| (*name, counter.load(Ordering::Relaxed)))
+        .collect()
+}
+
+///Prints every function that has been called at least once, busiest first
+pub fn log() {
+    let mut metrics = metrics()
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .collect::<Vec<_>>();
+    metrics.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    for (name, count) in metrics {
+        println!("metrics {}: {}", name, count);
+    }
+    #[cfg(any(test, feature = "sync_timing"))]
+    {
+        let timing = sync_timing();
+        if timing.count > 0 {
+            println!(
+                "metrics xrSyncActions remap pass: min {:?}, max {:?}, avg {:?} over {} syncs",
+                timing.min,
+                timing.max,
+                timing.average(),
+                timing.count
+            );
+        }
+    }
+    //Whatever outlives the destroyed instance, e.g. the other instances of an application that creates several
+    crate::wrappers::for_each_instance(|instance| {
+        let god_actions = instance.describe_god_actions().values().map(Vec::len).sum::<usize>();
+        println!("metrics {}: {} god actions", instance, god_actions);
+    });
+    crate::wrappers::for_each_session(|session| {
+        println!("metrics {}: {} syncs", session, session.sync_idx.read().unwrap());
+        #[cfg(any(test, feature = "sync_timing"))]
+        {
+            let timing = *session.sync_timing.lock().unwrap();
+            if timing.count > 0 {
+                println!(
+                    "metrics {} remap pass: min {:?}, max {:?}, avg {:?}",
+                    session,
+                    timing.min,
+                    timing.max,
+                    timing.average()
+                );
+            }
+        }
+    });
+}
+
+///How long the layer's own work in xrSyncActions took, from the runtime syncing the god sets to the application's actions being updated
+///
+///Only compiled in with the `sync_timing` feature
+#[cfg(any(test, feature = "sync_timing"))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncTiming {
+    pub count: u64,
+    pub min: Duration,
+    pub max: Duration,
+    pub total: Duration,
+}
+
+#[cfg(any(test, feature = "sync_timing"))]
+impl SyncTiming {
+    pub fn average(&self) -> Duration {
+        if self.count == 0 {
+            Duration::default()
+        } else {
+            self.total / self.count as u32
+        }
+    }
+
+    fn record(&mut self, elapsed: Duration) {
+        if self.count == 0 || elapsed < self.min {
+            self.min = elapsed;
+        }
+        self.max = self.max.max(elapsed);
+        self.total += elapsed;
+        self.count += 1;
+    }
+}
+
+#[cfg(any(test, feature = "sync_timing"))]
+static SYNC_TIMING: Lazy<Mutex<SyncTiming>> = Lazy::new(Default::default);
+
+///The remap pass timings of every xrSyncActions since the layer was loaded, across every instance
+#[cfg(any(test, feature = "sync_timing"))]
+pub fn sync_timing() -> SyncTiming {
+    *SYNC_TIMING.lock().unwrap()
+}
+
+//Tests read the fake runtime's clock so the timings they check don't depend on how fast the machine is
+#[cfg(all(feature = "sync_timing", not(test)))]
+fn now() -> Instant {
+    Instant::now()
+}
+
+#[cfg(test)]
+use crate::fake_runtime::now;
+
+///Records the time from its creation until it is dropped as one remap pass, in the process wide timings and the session's own
+#[cfg(any(test, feature = "sync_timing"))]
+pub struct SyncTimer<'a> {
+    start: Instant,
+    session_timing: &'a Mutex<SyncTiming>,
+}
+
+#[cfg(any(test, feature = "sync_timing"))]
+impl<'a> SyncTimer<'a> {
+    pub fn start(session_timing: &'a Mutex<SyncTiming>) -> Self {
+        SyncTimer {
+            start: now(),
+            session_timing,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "sync_timing"))]
+impl Drop for SyncTimer<'_> {
+    fn drop(&mut self) {
+        let elapsed = now().saturating_duration_since(self.start);
+        SYNC_TIMING.lock().unwrap().record(elapsed);
+        self.session_timing.lock().unwrap().record(elapsed);
+    }
+}
@@ -0,0 +1,167 @@
+//! The layer built without the remap feature, every call goes straight to the runtime
+//!
+//! Only the instance's xrGetInstanceProcAddr from the layer below us is kept, in a plain `Mutex<HashMap>` rather than the wrapper maps
+//! the full layer uses, so the build needs neither the config nor dashmap or serde
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use openxr::sys as xr;
+use openxr::sys::pfn;
+
+use crate::loader_interfaces::*;
+use crate::ffi::{catch_panic, get_func, LAYER_NAME};
+
+static NEXT_PROC_ADDRS: Lazy<Mutex<HashMap<xr::Instance, pfn::GetInstanceProcAddr>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn next_proc_addr(instance: xr::Instance) -> Option<pfn::GetInstanceProcAddr> {
+    NEXT_PROC_ADDRS.lock().unwrap().get(&instance).copied()
+}
+
+pub unsafe extern "system" fn create_api_layer_instance(
+    instance_info: *const xr::InstanceCreateInfo,
+    layer_info: *const ApiLayerCreateInfo,
+    instance: *mut xr::Instance,
+) -> xr::Result {
+    catch_panic("xrCreateApiLayerInstance", || {
+        let next_info = &*(*layer_info).next_info;
+
+        assert_eq!(LAYER_NAME, CStr::from_ptr(next_info.layer_name.as_ptr() as *const c_char).to_str().unwrap());
+
+        let result = {
+            let mut my_create_info = *layer_info;
+            my_create_info.next_info = next_info.next;
+
+            (next_info.next_create_api_layer_instance)(instance_info, &my_create_info, instance)
+        };
+
+        if result.into_raw() < 0 {
+            return result;
+        }
+
+        NEXT_PROC_ADDRS.lock().unwrap().insert(*instance, next_info.next_get_instance_proc_addr);
+        result
+    })
+}
+
+pub unsafe extern "system" fn instance_proc_addr(instance: xr::Instance, name: *const c_char, function: *mut Option<pfn::VoidFunction>) -> xr::Result {
+    catch_panic("xrGetInstanceProcAddr", || {
+        let get_instance_proc_addr_next = match next_proc_addr(instance) {
+            Some(get_instance_proc_addr_next) => get_instance_proc_addr_next,
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
+        let result = get_instance_proc_addr_next(instance, name, function);
+
+        if result.into_raw() < 0 {
+            return result;
+        }
+
+        //The instance is forgotten once it is destroyed, everything else is the runtime's own function
+        if CStr::from_ptr(name).to_bytes() == b"xrDestroyInstance" && (*function).is_some() {
+            *function = Some(std::mem::transmute::<pfn::DestroyInstance, pfn::VoidFunction>(destroy_instance));
+        }
+
+        result
+    })
+}
+
+unsafe extern "system" fn destroy_instance(instance: xr::Instance) -> xr::Result {
+    catch_panic("xrDestroyInstance", || {
+        let destroy_instance_next = match next_proc_addr(instance).and_then(|next| get_func(next, instance, "xrDestroyInstance")) {
+            Some(destroy_instance_next) => std::mem::transmute::<pfn::VoidFunction, pfn::DestroyInstance>(destroy_instance_next),
+            None => return xr::Result::ERROR_HANDLE_INVALID,
+        };
+
+        let result = destroy_instance_next(instance);
+        if result.into_raw() >= 0 {
+            NEXT_PROC_ADDRS.lock().unwrap().remove(&instance);
+        }
+        result
+    })
+}
+
+#[cfg(test)]
+unsafe extern "system" fn next_create_api_layer_instance(
+    _instance_info: *const xr::InstanceCreateInfo,
+    _layer_info: *const ApiLayerCreateInfo,
+    instance: *mut xr::Instance,
+) -> xr::Result {
+    *instance = xr::Instance::from_raw(42);
+    xr::Result::SUCCESS
+}
+
+#[cfg(test)]
+unsafe extern "system" fn next_create_session(
+    _instance: xr::Instance,
+    _create_info: *const xr::SessionCreateInfo,
+    session: *mut xr::Session,
+) -> xr::Result {
+    *session = xr::Session::from_raw(7);
+    xr::Result::SUCCESS
+}
+
+#[cfg(test)]
+unsafe extern "system" fn next_get_instance_proc_addr(
+    _instance: xr::Instance,
+    name: *const c_char,
+    function: *mut Option<pfn::VoidFunction>,
+) -> xr::Result {
+    *function = match CStr::from_ptr(name).to_bytes() {
+        b"xrCreateSession" => Some(std::mem::transmute::<pfn::CreateSession, pfn::VoidFunction>(next_create_session)),
+        _ => None,
+    };
+    if (*function).is_some() {
+        xr::Result::SUCCESS
+    } else {
+        xr::Result::ERROR_FUNCTION_UNSUPPORTED
+    }
+}
+
+#[test]
+fn test_forwards_create_session() {
+    use std::ptr;
+
+    let layer_name = std::ffi::CString::new(LAYER_NAME).unwrap();
+    let mut request: XrNegotiateApiLayerRequest = unsafe { std::mem::zeroed() };
+    let result = unsafe { crate::xrNegotiateLoaderApiLayerInterface(ptr::null(), layer_name.as_ptr(), &mut request) };
+    assert_eq!(result, xr::Result::SUCCESS);
+
+    let mut next_info = XrApiLayerNextInfo {
+        ty: xr::StructureType::from_raw(0),
+        struct_version: 1,
+        struct_size: std::mem::size_of::<XrApiLayerNextInfo>(),
+        layer_name: [0; xr::MAX_API_LAYER_NAME_SIZE],
+        next_get_instance_proc_addr,
+        next_create_api_layer_instance,
+        next: ptr::null_mut(),
+    };
+    for (name, byte) in next_info.layer_name.iter_mut().zip(LAYER_NAME.bytes()) {
+        *name = byte as _;
+    }
+    let layer_info = ApiLayerCreateInfo {
+        ty: xr::StructureType::from_raw(0),
+        struct_version: 1,
+        struct_size: std::mem::size_of::<ApiLayerCreateInfo>(),
+        loader_instance: ptr::null(),
+        settings_file_location: [0; XR_API_LAYER_MAX_SETTINGS_PATH_SIZE],
+        next_info: &mut next_info,
+    };
+    let mut instance = xr::Instance::NULL;
+    let result = unsafe { (request.create_api_layer_instance.unwrap())(ptr::null(), &layer_info, &mut instance) };
+    assert_eq!(result, xr::Result::SUCCESS);
+    assert_eq!(instance, xr::Instance::from_raw(42));
+
+    //xrCreateSession is the runtime's own function, not a wrapper
+    let mut function = None;
+    let result = unsafe { (request.get_instance_proc_addr.unwrap())(instance, b"xrCreateSession\0".as_ptr() as _, &mut function) };
+    assert_eq!(result, xr::Result::SUCCESS);
+    assert_eq!(function.unwrap() as usize, next_create_session as pfn::CreateSession as usize);
+
+    let create_session = unsafe { std::mem::transmute::<pfn::VoidFunction, pfn::CreateSession>(function.unwrap()) };
+    let mut session = xr::Session::NULL;
+    assert_eq!(unsafe { create_session(instance, ptr::null(), &mut session) }, xr::Result::SUCCESS);
+    assert_eq!(session, xr::Session::from_raw(7));
+}
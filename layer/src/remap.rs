@@ -0,0 +1,1710 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use common::application_bindings;
+use common::application_bindings::{AnalogThreshold, Axis, AxisRange, Gesture, GestureKind, Sector, Transform};
+use common::config;
+use common::interaction_profiles;
+use common::xrapplication_info::ActionType;
+use openxr::sys as xr;
+
+use crate::god_actions::{Binding, GodActionStateEnum, InputBinding, OutputBinding};
+use crate::wrappers::{ActionWrapper, InstanceWrapper, SessionWrapper};
+
+///A god input bound to an application action along with the transform the user configured for it
+pub struct RemappedBinding {
+    pub source: Arc<InputBinding>,
+    ///Drives the y axis when two scalar sources are composed into a vector, `source` then drives x
+    pub y_source: Option<Arc<InputBinding>>,
+    pub transform: Transform,
+    ///The instance's [Settings::analog_scale](common::config::Settings::analog_scale), applied ahead of `transform`
+    pub analog_scale: f32,
+    ///Set when the user configured a [fallback](application_bindings::Binding::fallback)
+    pub fallback: Option<Fallback>,
+    ///Set when the user gated the binding on another source, see [application_bindings::Binding::when]
+    pub condition: Option<Condition>,
+    memory: Mutex<TransformMemory>,
+}
+
+///The god input a gated binding waits on
+pub struct Condition {
+    pub source: Arc<InputBinding>,
+    ///Met while the source isn't held rather than while it is
+    pub released: bool,
+}
+
+impl Condition {
+    ///See [is_held]
+    pub fn is_met(&self) -> bool {
+        is_held(&self.source) != self.released
+    }
+}
+
+///Buttons are held while pressed, analog sources from the default [AnalogThreshold]'s `on_threshold`
+pub fn is_held(source: &InputBinding) -> bool {
+    let state = *source.action_state.read().unwrap();
+    let state = state.get_inner();
+    state.is_active() && matches!(state.get_scalar(), Ok(value) if value >= AnalogThreshold::default().on_threshold)
+}
+
+///What a binding reads while another profile than its own is active on its hand
+pub struct Fallback {
+    ///The configured fallback path
+    pub path: String,
+    ///For every other profile, its own source at the binding's path if it has one and its fallback source if not
+    pub sources: HashMap<xr::Path /* interactionProfile */, Arc<InputBinding>>,
+}
+
+///What a transform remembers from one sync to the next
+#[derive(Default)]
+pub struct TransformMemory {
+    pub pressed: bool,
+    ///Whether the source last pointed into the binding's [Sector] rather than another one
+    pub sector_selected: bool,
+    pub gesture: GestureMemory,
+    ///What a `smoothing_ms` binding last handed on and the predicted display time it was for, floats only use `x`
+    pub smoothed: Option<(openxr::Vector2f, i64)>,
+}
+
+///Where a source is in the press sequence a [Gesture] is watching for, times are in nanoseconds
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GestureMemory {
+    #[default]
+    Idle,
+    Pressed { since: i64 },
+    ///Held long enough to be a long press
+    Held,
+    ///Released before the hold time, waiting to see if a second press follows
+    Released { at: i64 },
+    ///The second press of a double tap, ignored until it is released
+    SecondPress,
+}
+
+impl RemappedBinding {
+    pub fn new(
+        source: Arc<InputBinding>,
+        y_source: Option<Arc<InputBinding>>,
+        transform: Transform,
+    ) -> Self {
+        Self {
+            source,
+            y_source,
+            transform,
+            analog_scale: 1f32,
+            fallback: None,
+            condition: None,
+            memory: Default::default(),
+        }
+    }
+
+    pub fn with_fallback(mut self, fallback: Fallback) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    ///The source read while `active_profiles` (top level user path to profile) are in use, see [Fallback]
+    ///
+    ///Without a fallback, or while no profile or the binding's own is active, that is always `source`
+    pub fn active_source(&self, active_profiles: &HashMap<xr::Path, xr::Path>) -> &Arc<InputBinding> {
+        let active_profile = active_profiles.get(&self.source.subaction_path).copied().unwrap_or(xr::Path::NULL);
+        match &self.fallback {
+            Some(fallback) if active_profile != xr::Path::NULL && active_profile != self.source.action.profile_name => {
+                fallback.sources.get(&active_profile).unwrap_or(&self.source)
+            }
+            _ => &self.source,
+        }
+    }
+
+    pub fn with_analog_scale(mut self, analog_scale: f32) -> Self {
+        self.analog_scale = analog_scale;
+        self
+    }
+
+    ///Reads the current god state and runs it through this binding's transform
+    ///
+    ///Should be called once per sync as some transforms depend on the previous result, `predicted_display_time` is what timed transforms measure against.
+    ///`active_profiles` picks the source read when there is a [Fallback], see [RemappedBinding::active_source]
+    ///
+    ///Composed vectors are scaled as a whole, then the transform sees the scaled state. A binding whose [Condition] isn't met reads as inactive,
+    ///so the transform lets go of anything it latched
+    pub fn sample(&self, active_profiles: &HashMap<xr::Path, xr::Path>, predicted_display_time: xr::Time) -> GodActionStateEnum {
+        let state = *self.active_source(active_profiles).action_state.read().unwrap();
+        let state = match &self.y_source {
+            Some(y_source) => compose_vector(state, *y_source.action_state.read().unwrap()),
+            None => state,
+        };
+        let mut state = apply_analog_scale(state, self.analog_scale);
+        if matches!(&self.condition, Some(condition) if !condition.is_met()) {
+            match &mut state {
+                GodActionStateEnum::Boolean(state) => state.is_active = false,
+                GodActionStateEnum::Float(state) => state.is_active = false,
+                GodActionStateEnum::Vector2f(state) => state.is_active = false,
+                GodActionStateEnum::Pose(state) => state.is_active = false,
+            }
+        }
+        apply_transform(&self.transform, state, &mut self.memory.lock().unwrap(), predicted_display_time)
+    }
+
+    ///Every god input this binding may read: its source and y source, the sources its [Fallback] stands in with and its [Condition]'s
+    pub fn sources(&self) -> impl Iterator<Item = &Arc<InputBinding>> {
+        std::iter::once(&self.source)
+            .chain(self.y_source.iter())
+            .chain(self.fallback.iter().flat_map(|fallback| fallback.sources.values()))
+            .chain(self.condition.iter().map(|condition| &condition.source))
+    }
+
+    ///Forgets anything the transform latched (e.g. a threshold held past its off point) so the next sample starts from rest
+    pub fn reset(&self) {
+        *self.memory.lock().unwrap() = Default::default();
+    }
+}
+
+impl Deref for RemappedBinding {
+    type Target = InputBinding;
+
+    fn deref(&self) -> &Self::Target {
+        &self.source
+    }
+}
+
+impl Binding for RemappedBinding {
+    fn is_active(&self, session: &SessionWrapper) -> bool {
+        match &self.y_source {
+            Some(y_source) => self.source.is_active(session) && y_source.is_active(session),
+            None => self.source.is_active(session),
+        }
+    }
+
+    fn subaction_path(&self) -> xr::Path {
+        self.source.subaction_path
+    }
+}
+
+///A god output bound to an application haptic action, with the amplitude scale the user configured for it
+pub struct RemappedOutput {
+    pub output: Arc<OutputBinding>,
+    ///Multiplies the amplitude of every vibration sent through this binding
+    pub amplitude: f32,
+}
+
+impl Deref for RemappedOutput {
+    type Target = OutputBinding;
+
+    fn deref(&self) -> &Self::Target {
+        &self.output
+    }
+}
+
+impl Binding for RemappedOutput {
+    fn is_active(&self, session: &SessionWrapper) -> bool {
+        self.output.is_active(session)
+    }
+
+    fn subaction_path(&self) -> xr::Path {
+        self.output.subaction_path
+    }
+}
+
+///Combines two scalar states into a vector state whose length is clamped to 1
+///
+///The result is only active while both sources are
+pub fn compose_vector(x: GodActionStateEnum, y: GodActionStateEnum) -> GodActionStateEnum {
+    let x_inner = x.get_inner();
+    let y_inner = y.get_inner();
+
+    let mut vec = openxr::Vector2f {
+        x: x_inner.get_scalar().unwrap_or(0f32),
+        y: y_inner.get_scalar().unwrap_or(0f32),
+    };
+    let len = (vec.x * vec.x + vec.y * vec.y).sqrt();
+    if len > 1f32 {
+        vec.x /= len;
+        vec.y /= len;
+    }
+
+    let last_change_time = |state: &GodActionStateEnum| {
+        state
+            .get_inner()
+            .last_change_time()
+            .map_or(0, |time| time.as_nanos())
+    };
+
+    GodActionStateEnum::Vector2f(openxr::ActionState::<openxr::Vector2f> {
+        current_state: vec,
+        changed_since_last_sync: changed_since_last_sync(&x) || changed_since_last_sync(&y),
+        last_change_time: xr::Time::from_nanos(cmp::max(last_change_time(&x), last_change_time(&y))),
+        is_active: x_inner.is_active() && y_inner.is_active(),
+    })
+}
+
+///Multiplies a float or vector state by `scale`, clamping floats to -1..1 and vectors to a length of 1
+pub fn apply_analog_scale(mut state: GodActionStateEnum, scale: f32) -> GodActionStateEnum {
+    if scale == 1f32 {
+        return state;
+    }
+    match &mut state {
+        GodActionStateEnum::Float(state) => state.current_state = (state.current_state * scale).clamp(-1f32, 1f32),
+        GodActionStateEnum::Vector2f(state) => {
+            let vec = &mut state.current_state;
+            let len = (vec.x * vec.x + vec.y * vec.y).sqrt() * scale;
+            let scale = if len > 1f32 { scale / len } else { scale };
+            vec.x *= scale;
+            vec.y *= scale;
+        }
+        _ => {}
+    }
+    state
+}
+
+fn changed_since_last_sync(state: &GodActionStateEnum) -> bool {
+    match state {
+        GodActionStateEnum::Boolean(state) => state.changed_since_last_sync,
+        GodActionStateEnum::Float(state) => state.changed_since_last_sync,
+        GodActionStateEnum::Vector2f(state) => state.changed_since_last_sync,
+        GodActionStateEnum::Pose(_) => false,
+    }
+}
+
+///Runs a god state through a binding's transform, see [Transform] for the order the steps are applied in
+pub fn apply_transform(
+    transform: &Transform,
+    mut state: GodActionStateEnum,
+    memory: &mut TransformMemory,
+    now: xr::Time,
+) -> GodActionStateEnum {
+    if transform.invert {
+        match &mut state {
+            GodActionStateEnum::Boolean(state) => state.current_state = !state.current_state,
+            GodActionStateEnum::Float(state) => state.current_state = -state.current_state,
+            GodActionStateEnum::Vector2f(state) => {
+                state.current_state.x = -state.current_state.x;
+                state.current_state.y = -state.current_state.y;
+            }
+            GodActionStateEnum::Pose(_) => {}
+        }
+    }
+
+    if transform.dead_zone > 0f32 {
+        match &mut state {
+            GodActionStateEnum::Float(state) => {
+                state.current_state = apply_dead_zone(state.current_state, transform.dead_zone)
+            }
+            GodActionStateEnum::Vector2f(state) => {
+                let vec = &mut state.current_state;
+                let len = (vec.x * vec.x + vec.y * vec.y).sqrt();
+                if len > 0f32 {
+                    let scale = apply_dead_zone(len, transform.dead_zone) / len;
+                    vec.x *= scale;
+                    vec.y *= scale;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(curve) = transform.curve {
+        match &mut state {
+            GodActionStateEnum::Float(state) => state.current_state = apply_curve(state.current_state, curve),
+            GodActionStateEnum::Vector2f(state) => {
+                let vec = &mut state.current_state;
+                let len = (vec.x * vec.x + vec.y * vec.y).sqrt();
+                if len > 0f32 {
+                    let scale = apply_curve(len, curve) / len;
+                    vec.x *= scale;
+                    vec.y *= scale;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(smoothing_ms) = transform.smoothing_ms {
+        match &mut state {
+            GodActionStateEnum::Float(state) => {
+                let mut value = openxr::Vector2f { x: state.current_state, y: 0f32 };
+                let eased = apply_smoothing(smoothing_ms, &mut value, state.is_active, &mut memory.smoothed, now);
+                if eased {
+                    state.current_state = value.x;
+                    state.changed_since_last_sync = true;
+                    state.last_change_time = xr::Time::from_nanos(cmp::max(now.as_nanos(), state.last_change_time.as_nanos()));
+                }
+            }
+            GodActionStateEnum::Vector2f(state) => {
+                let eased = apply_smoothing(smoothing_ms, &mut state.current_state, state.is_active, &mut memory.smoothed, now);
+                if eased {
+                    state.changed_since_last_sync = true;
+                    state.last_change_time = xr::Time::from_nanos(cmp::max(now.as_nanos(), state.last_change_time.as_nanos()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let (Some(sector), GodActionStateEnum::Vector2f(vec_state)) = (transform.sector, state) {
+        let was_selected = memory.sector_selected;
+        let selected = apply_sector(&sector, &vec_state, &mut memory.sector_selected);
+        state = GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+            current_state: selected,
+            changed_since_last_sync: selected != was_selected,
+            last_change_time: vec_state.last_change_time,
+            is_active: vec_state.is_active,
+        });
+    }
+
+    if let (Some(axis), GodActionStateEnum::Vector2f(vec_state)) = (transform.axis, state) {
+        state = GodActionStateEnum::Float(openxr::ActionState::<f32> {
+            current_state: match axis {
+                Axis::X => vec_state.current_state.x,
+                Axis::Y => vec_state.current_state.y,
+            },
+            changed_since_last_sync: vec_state.changed_since_last_sync,
+            last_change_time: vec_state.last_change_time,
+            is_active: vec_state.is_active,
+        });
+    }
+
+    if let (AxisRange::Unsigned, GodActionStateEnum::Float(state)) = (transform.range, &mut state) {
+        state.current_state = (state.current_state + 1f32) / 2f32;
+    }
+
+    if let (Some(threshold), GodActionStateEnum::Float(float_state)) = (transform.threshold, state) {
+        memory.pressed = float_state.is_active
+            && if memory.pressed {
+                float_state.current_state > threshold.off_threshold
+            } else {
+                float_state.current_state >= threshold.on_threshold
+            };
+        state = GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+            current_state: memory.pressed,
+            changed_since_last_sync: float_state.changed_since_last_sync,
+            last_change_time: float_state.last_change_time,
+            is_active: float_state.is_active,
+        });
+    }
+
+    if let (Some(gesture), GodActionStateEnum::Boolean(bool_state)) = (transform.gesture, &mut state) {
+        let fired = apply_gesture(&gesture, bool_state, &mut memory.gesture, now);
+        if fired != bool_state.current_state {
+            bool_state.current_state = fired;
+            bool_state.changed_since_last_sync = true;
+            bool_state.last_change_time = xr::Time::from_nanos(cmp::max(now.as_nanos(), bool_state.last_change_time.as_nanos()));
+        }
+    }
+
+    state
+}
+
+///Moves `value` part of the way from the last smoothed value towards the source, true if that left it somewhere other than the source
+///
+///The share is `1 - e^(-dt / smoothing_ms)` of the time since the last sync, so the result doesn't depend on the frame rate.
+///An inactive source forgets the last value, as does an application that hasn't called xrWaitFrame yet and so has no display time to measure against
+fn apply_smoothing(
+    smoothing_ms: u32,
+    value: &mut openxr::Vector2f,
+    is_active: bool,
+    smoothed: &mut Option<(openxr::Vector2f, i64)>,
+    now: xr::Time,
+) -> bool {
+    if !is_active || now.as_nanos() == 0 {
+        *smoothed = None;
+        return false;
+    }
+    let (last, last_time) = match *smoothed {
+        Some(last) => last,
+        None => {
+            *smoothed = Some((*value, now.as_nanos()));
+            return false;
+        }
+    };
+    //Syncing twice for one frame moves nothing
+    let elapsed_ms = cmp::max(now.as_nanos() - last_time, 0) as f32 / 1_000_000f32;
+    let share = 1f32 - (-elapsed_ms / smoothing_ms as f32).exp();
+    let next = openxr::Vector2f {
+        x: last.x + (value.x - last.x) * share,
+        y: last.y + (value.y - last.y) * share,
+    };
+    *smoothed = Some((next, cmp::max(now.as_nanos(), last_time)));
+    let eased = next.x != value.x || next.y != value.y;
+    *value = next;
+    eased
+}
+
+///Whether a [Sector] binding is pressed, `selected` is the latch kept between syncs
+///
+///A source at its center leaves the latch alone, so the sector chosen last stays pressed after the finger is lifted
+fn apply_sector(sector: &Sector, source: &openxr::ActionState<openxr::Vector2f>, selected: &mut bool) -> bool {
+    if !source.is_active {
+        *selected = false;
+        return false;
+    }
+    let vec = source.current_state;
+    if vec.x != 0f32 || vec.y != 0f32 {
+        *selected = sector_of(vec, sector.count) == sector.index;
+    }
+    *selected
+}
+
+///The sector of `count` a vector points into, 0 is centered straight up and the rest follow clockwise
+fn sector_of(vec: openxr::Vector2f, count: u32) -> u32 {
+    //Clockwise from straight up as a fraction of a turn, shifted by half a sector so sector 0 straddles up
+    let turn = vec.x.atan2(vec.y) / std::f32::consts::TAU + 0.5 / count as f32;
+    cmp::min((turn.rem_euclid(1f32) * count as f32) as u32, count - 1)
+}
+
+///Steps a gesture's state machine with the latest sample of its source, returning whether the gesture's action is pressed
+///
+///Edges are timed with the source's last change time, `now` only decides when a hold or the double tap window runs out.
+///An application that hasn't called xrWaitFrame has no time to measure with so the source's own time stands in
+fn apply_gesture(
+    gesture: &Gesture,
+    source: &openxr::ActionState<bool>,
+    memory: &mut GestureMemory,
+    now: xr::Time,
+) -> bool {
+    if !source.is_active {
+        *memory = GestureMemory::Idle;
+        return false;
+    }
+
+    let hold = gesture.hold_ms as i64 * 1_000_000;
+    let double_tap = gesture.double_tap_ms as i64 * 1_000_000;
+    //Runtimes that don't stamp their states leave the time at zero, the edge is then taken to be now
+    let changed_at = match source.last_change_time.as_nanos() {
+        0 => now.as_nanos(),
+        changed_at => changed_at,
+    };
+    let now = cmp::max(now.as_nanos(), changed_at);
+    let down = source.current_state;
+
+    let mut tapped = false;
+    let mut double_tapped = false;
+
+    *memory = match (*memory, down) {
+        (GestureMemory::Idle, true) => GestureMemory::Pressed { since: changed_at },
+        (GestureMemory::Idle, false) => GestureMemory::Idle,
+        (GestureMemory::Pressed { since }, true) => GestureMemory::Pressed { since },
+        //Released before the hold time, even if only just
+        (GestureMemory::Pressed { .. }, false) => GestureMemory::Released { at: changed_at },
+        (GestureMemory::Held, true) => GestureMemory::Held,
+        (GestureMemory::Held, false) => GestureMemory::Idle,
+        (GestureMemory::Released { at }, true) if changed_at - at <= double_tap => {
+            double_tapped = true;
+            GestureMemory::SecondPress
+        }
+        //The window ran out between syncs, the tap is late but still a tap and this is a fresh press
+        (GestureMemory::Released { .. }, true) => {
+            tapped = true;
+            GestureMemory::Pressed { since: changed_at }
+        }
+        (GestureMemory::Released { at }, false) if now - at > double_tap => {
+            tapped = true;
+            GestureMemory::Idle
+        }
+        (GestureMemory::Released { at }, false) => GestureMemory::Released { at },
+        (GestureMemory::SecondPress, true) => GestureMemory::SecondPress,
+        (GestureMemory::SecondPress, false) => GestureMemory::Idle,
+    };
+
+    if let GestureMemory::Pressed { since } = *memory {
+        if now - since >= hold {
+            *memory = GestureMemory::Held;
+        }
+    }
+
+    match gesture.kind {
+        GestureKind::Tap => tapped,
+        GestureKind::LongPress => *memory == GestureMemory::Held,
+        GestureKind::DoubleTap => double_tapped,
+    }
+}
+
+///Fills in what the binding's transform leaves unset, first from the interaction profile's defaults for the source and then from the hard-coded defaults
+pub fn with_defaults(
+    mut transform: Transform,
+    profile_defaults: Option<&Transform>,
+    action_type: ActionType,
+    source_type: ActionType,
+) -> Transform {
+    let profile_defaults = profile_defaults.cloned().unwrap_or_default();
+
+    if transform.dead_zone == 0f32 {
+        transform.dead_zone = profile_defaults.dead_zone;
+    }
+
+    //Only boolean actions get a threshold, a float action bound to a trigger wants the raw value
+    if action_type == ActionType::BooleanInput
+        && source_type == ActionType::FloatInput
+        && transform.threshold.is_none()
+    {
+        transform.threshold = Some(profile_defaults.threshold.unwrap_or_default());
+    }
+
+    transform
+}
+
+fn apply_curve(value: f32, curve: f32) -> f32 {
+    value.signum() * value.abs().powf(curve)
+}
+
+fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        0f32
+    } else {
+        value.signum() * (value.abs() - dead_zone) / (1f32 - dead_zone)
+    }
+}
+
+///Whether the application bound this action only on user paths the layer doesn't manage (see [Settings::managed_user_paths]) and no
+///custom binding remaps it, the runtime then reads it as it would without the layer
+///
+///[Settings::managed_user_paths]: common::config::Settings::managed_user_paths
+pub fn is_left_to_runtime(instance: &InstanceWrapper, action: &ActionWrapper) -> bool {
+    !instance.settings.manages_every_user_path()
+        && binding_profiles(instance, action).is_empty()
+        && instance
+            .forwarded_suggestions
+            .read()
+            .unwrap()
+            .values()
+            .flatten()
+            .any(|suggested_binding| suggested_binding.action == action.handle)
+}
+
+///Whether the user's custom bindings change what an action is bound to compared to the application's suggestions
+///
+///Only bindings touching `subaction_path` are compared when it is given, an unbound action counts as remapped for every subaction path
+pub fn is_remapped(instance: &InstanceWrapper, action: &ActionWrapper, subaction_path: Option<&str>) -> bool {
+    let touches_subaction = |binding: &&application_bindings::Binding| match subaction_path {
+        Some(subaction_path) => std::iter::once(&binding.path)
+            .chain(binding.y.iter())
+            .any(|path| path == subaction_path || path.starts_with(&format!("{}/", subaction_path))),
+        None => true,
+    };
+
+    let action_set_name = &action.action_set().name;
+    let suggested_bindings = action.bindings.read().unwrap();
+    for (profile_name, profile_bindings) in &instance.custom_bindings.profiles {
+        let action_bindings = match profile_bindings
+            .action_sets
+            .get(action_set_name)
+            .and_then(|set| set.actions.get(&action.name))
+        {
+            Some(action_bindings) => action_bindings,
+            None => continue,
+        };
+
+        if action_bindings.unbound {
+            return true;
+        }
+
+        let suggested = instance
+            .string_to_path(profile_name)
+            .ok()
+            .and_then(|profile| suggested_bindings.get(&profile))
+            .map(|bindings| {
+                bindings
+                    .iter()
+                    .map(|binding| {
+                        application_bindings::Binding::new(instance.path_to_string(*binding).unwrap())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let suggested = suggested.iter().filter(touches_subaction).collect::<Vec<_>>();
+        let custom = action_bindings.bindings.iter().filter(touches_subaction).collect::<Vec<_>>();
+        if custom.len() != suggested.len() || custom.iter().any(|binding| !suggested.contains(binding)) {
+            return true;
+        }
+    }
+
+    false
+}
+
+///Components some runtimes keep for themselves and refuse to bind, e.g. the button that opens the runtime's dashboard
+const RESERVED_COMPONENTS: &[&str] = &["/input/system"];
+
+pub fn is_reserved(path: &str) -> bool {
+    RESERVED_COMPONENTS.iter().any(|component| {
+        path.ends_with(component) || path.contains(&format!("{}/", component))
+    })
+}
+
+///Explains why a set of custom bindings can't be used because it binds a reserved component
+fn reserved_binding_warning(
+    action_name: &str,
+    profile_name: &str,
+    bindings: &[application_bindings::Binding],
+) -> Option<String> {
+    let reserved = bindings
+        .iter()
+        .flat_map(|binding| std::iter::once(&binding.path).chain(binding.y.iter()))
+        .find(|path| is_reserved(path))?;
+    Some(format!(
+        "custom bindings: {} is a reserved path the runtime may not allow binding, keeping the suggested bindings of {} for {}",
+        reserved, profile_name, action_name
+    ))
+}
+
+///The extension `binding` needs when it names a subpath or feature of `profile` that was left out because the application didn't enable it
+fn disabled_extension(instance: &InstanceWrapper, profile: xr::Path, binding: &str) -> Option<String> {
+    let profile_info = interaction_profiles::profile(&instance.path_to_string(profile).ok()?)?;
+    profile_info
+        .subaction_paths
+        .iter()
+        .filter_map(|user_path| binding.strip_prefix(user_path.as_str()))
+        .flat_map(|rest| {
+            profile_info
+                .subpaths
+                .iter()
+                .filter(move |(subpath, _)| rest == subpath.as_str() || rest.starts_with(&format!("{}/", subpath)))
+                .map(move |(subpath, subpath_info)| (&rest[subpath.len()..], subpath_info))
+        })
+        .find_map(|(component, subpath_info)| {
+            if !subpath_info.is_enabled(&instance.enabled_extensions) {
+                return subpath_info.extension.clone();
+            }
+            subpath_info
+                .disabled_feature_extension(component.strip_prefix('/')?, &instance.enabled_extensions)
+                .cloned()
+        })
+}
+
+///Whether the application attached its actions without suggesting a single binding, leaving them all to the runtime's defaults
+pub fn suggested_nothing(instance: &InstanceWrapper) -> bool {
+    instance.unknown_profiles.read().unwrap().is_empty()
+        && instance.action_sets.read().unwrap().iter().all(|action_set| {
+            action_set
+                .actions
+                .read()
+                .unwrap()
+                .iter()
+                .all(|action| action.bindings.read().unwrap().is_empty())
+        })
+}
+
+///Stands in for the suggestions of an application that never made any, binding each action to the catalog sources its name matches
+///
+///An action matches a subpath by its identifier or localized name (e.g. trigger or Trigger) and takes the component of its own type, or names the component too (e.g. trigger_click or grip_pose).
+///The runtime's state then passes through untouched. Actions matching nothing stay inactive until the user binds them
+pub fn derive_identity_bindings(instance: &InstanceWrapper) {
+    for profile_name in interaction_profiles::catalog().profiles.keys() {
+        let (profile, profile_info) = match instance
+            .string_to_path(profile_name)
+            .ok()
+            .and_then(|profile| Some((profile, instance.profile_info(profile)?)))
+        {
+            Some(profile) => profile,
+            None => continue,
+        };
+
+        for action_set in instance.action_sets.read().unwrap().iter() {
+            for action in action_set.actions.read().unwrap().iter() {
+                let subaction_paths = action
+                    .subaction_paths
+                    .iter()
+                    .filter_map(|path| instance.path_to_nonempty_string(*path).ok())
+                    .collect::<Vec<_>>();
+                let bindings = profile_info
+                    .subaction_paths
+                    .iter()
+                    .filter(|user_path| subaction_paths.is_empty() || subaction_paths.contains(user_path))
+                    .flat_map(|user_path| {
+                        identity_components(&profile_info, user_path, &action.name, action.action_type)
+                            .into_iter()
+                            .map(move |component| format!("{}{}", user_path, component))
+                    })
+                    .filter_map(|binding| instance.string_to_path(&binding).ok())
+                    .collect::<Vec<_>>();
+                if !bindings.is_empty() {
+                    action.bindings.write().unwrap().insert(profile, bindings);
+                }
+            }
+        }
+    }
+}
+
+///The components under `user_path` an action called `action_name` would be suggested for if it were named after them
+fn identity_components(
+    profile_info: &interaction_profiles::InteractionProfile,
+    user_path: &str,
+    action_name: &str,
+    action_type: ActionType,
+) -> Vec<String> {
+    let normalize = |name: &str| name.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_ascii_lowercase();
+    let action_name = normalize(action_name);
+
+    profile_info
+        .subpaths
+        .iter()
+        .filter(|(_, subpath_info)| subpath_info.exists_for(user_path))
+        .filter_map(|(subpath, subpath_info)| {
+            let identifier = normalize(subpath.rsplit('/').next().unwrap_or(subpath));
+            let names_subpath = action_name == identifier || action_name == normalize(&subpath_info.localized_name);
+            let feature = subpath_info.features.iter().find(|feature| {
+                feature.get_type() == action_type
+                    && (names_subpath || action_name == format!("{}{}", identifier, normalize(feature.to_str())))
+            })?;
+            interaction_profiles::binding_path(subpath, feature, action_type).into_iter().next()
+        })
+        .collect()
+}
+
+///The bindings of an action under each profile, the application's suggestions with any profile the user has custom bindings for replaced by those
+///
+///A profile the user maps the action to null on is left with no bindings, which silences the action on that profile only
+fn binding_profiles(instance: &InstanceWrapper, action: &ActionWrapper) -> HashMap<xr::Path, Vec<application_bindings::Binding>> {
+    let mut profiles = HashMap::new();
+
+    for (profile, bindings) in action.bindings.read().unwrap().iter() {
+        profiles.insert(
+            *profile,
+            bindings
+                .iter()
+                .map(|binding| {
+                    application_bindings::Binding::new(instance.path_to_string(*binding).unwrap())
+                })
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    let action_set_name = &action.action_set().name;
+    for (profile_name, profile_bindings) in &instance.custom_bindings.profiles {
+        let action_bindings = match profile_bindings
+            .action_sets
+            .get(action_set_name)
+            .and_then(|set| set.actions.get(&action.name))
+        {
+            Some(action_bindings) => action_bindings,
+            None => continue,
+        };
+
+        if let Some(warning) = reserved_binding_warning(&action.name, profile_name, &action_bindings.bindings) {
+            println!("{}", warning);
+            continue;
+        }
+
+        match instance.string_to_path(profile_name) {
+            Ok(profile) => {
+                profiles.insert(profile, action_bindings.bindings.clone());
+            }
+            Err(_) => println!("custom bindings: invalid profile path {}", profile_name),
+        }
+    }
+
+    profiles
+}
+
+///Resolves the bindings used for an input action, preferring the user's custom bindings over the application's suggestions
+///
+///Custom bindings replace the suggested bindings of a profile wholesale, unless they bind a reserved component (see [is_reserved]) in which case the suggestions pass through
+pub fn resolve_input_bindings(
+    instance: &InstanceWrapper,
+    action: &ActionWrapper,
+    god_states: &HashMap<xr::Path, HashMap<xr::Path, Arc<InputBinding>>>,
+) -> Vec<Arc<RemappedBinding>> {
+    let profiles = binding_profiles(instance, action);
+
+    let mut remapped = Vec::new();
+
+    for (profile, bindings) in profiles {
+        let defaults = instance
+            .god_action_sets()
+            .get(&profile)
+            .map(|god_set| &god_set.profile_info.defaults);
+        let states = match god_states.get(&profile) {
+            Some(states) => states,
+            None => {
+                println!(
+                    "custom bindings: unknown profile {} for {}",
+                    instance.path_to_string(profile).unwrap(),
+                    action.name
+                );
+                continue;
+            }
+        };
+
+        let find_source = |path: &str| {
+            let source = instance
+                .string_to_path(path)
+                .ok()
+                .and_then(|path| states.get(&path))
+                .cloned();
+            if source.is_none() {
+                match disabled_extension(instance, profile, path) {
+                    Some(extension) => println!(
+                        "custom bindings: unknown source {} for {}, it needs {} which the application didn't enable",
+                        path, action.name, extension
+                    ),
+                    None => println!("custom bindings: unknown source {} for {}", path, action.name),
+                }
+            }
+            source
+        };
+
+        for binding in bindings {
+            let source = match find_source(&binding.path) {
+                Some(source) => source,
+                None => continue,
+            };
+            let y_source = match &binding.y {
+                Some(y) => match find_source(y) {
+                    Some(y_source) => Some(y_source),
+                    None => continue,
+                },
+                None => None,
+            };
+
+            let transform = with_defaults(
+                binding.transform,
+                defaults.and_then(|defaults| defaults.get(&source.action.name)),
+                action.action_type,
+                source.action.action_type,
+            );
+
+            let output_type = config::binding_output_type(
+                source.action.action_type,
+                y_source.as_ref().map(|y_source| y_source.action.action_type),
+                &transform,
+            );
+            match output_type.map(|output_type| (output_type, config::coercion(output_type, action.action_type))) {
+                Ok((_, Some(coercion))) if coercion.is_implicit() => {}
+                Ok((output_type, _)) => {
+                    println!(
+                        "custom bindings: {} gives {:?} which can't drive {}, a {:?} action",
+                        binding.path, output_type, action.name, action.action_type
+                    );
+                    continue;
+                }
+                Err(why) => {
+                    println!("custom bindings: {} for {}: {}", binding.path, action.name, why);
+                    continue;
+                }
+            }
+
+            let fallback = match &binding.fallback {
+                Some(fallback_path) if y_source.is_none() => Some(Fallback {
+                    path: fallback_path.clone(),
+                    sources: fallback_sources(instance, action, god_states, profile, &binding.path, fallback_path, &transform),
+                }),
+                _ => None,
+            };
+
+            //The condition is read on the binding's own profile, a modifier it doesn't have would never let the binding through
+            let condition = match &binding.when {
+                Some(when) => match find_source(&when.path) {
+                    Some(condition_source) => Some(Condition {
+                        source: condition_source,
+                        released: when.released,
+                    }),
+                    None => {
+                        println!(
+                            "custom bindings: dropping {} for {} as the source it waits on isn't there",
+                            binding.path, action.name
+                        );
+                        continue;
+                    }
+                },
+                None => None,
+            };
+
+            let mut remapped_binding =
+                RemappedBinding::new(source, y_source, transform).with_analog_scale(instance.settings.analog_scale);
+            if let Some(fallback) = fallback {
+                remapped_binding = remapped_binding.with_fallback(fallback);
+            }
+            if let Some(condition) = condition {
+                remapped_binding = remapped_binding.with_condition(condition);
+            }
+            remapped.push(Arc::new(remapped_binding));
+        }
+    }
+
+    remapped
+}
+
+///The sources every profile other than `profile` stands in with for `path`, see [Fallback::sources]
+///
+///Profiles with neither source, or only ones that can't drive the action through `transform`, are left out so the binding goes inactive on them
+fn fallback_sources(
+    instance: &InstanceWrapper,
+    action: &ActionWrapper,
+    god_states: &HashMap<xr::Path, HashMap<xr::Path, Arc<InputBinding>>>,
+    profile: xr::Path,
+    path: &str,
+    fallback_path: &str,
+    transform: &Transform,
+) -> HashMap<xr::Path, Arc<InputBinding>> {
+    let path = instance.string_to_path(path).ok();
+    let fallback_path = instance.string_to_path(fallback_path).ok();
+    god_states
+        .iter()
+        .filter(|(other_profile, _)| **other_profile != profile)
+        .filter_map(|(other_profile, states)| {
+            let source = path
+                .and_then(|path| states.get(&path))
+                .or_else(|| fallback_path.and_then(|fallback_path| states.get(&fallback_path)))?;
+            let output_type = config::binding_output_type(source.action.action_type, None, transform);
+            match output_type.map(|output_type| config::coercion(output_type, action.action_type)) {
+                Ok(Some(coercion)) if coercion.is_implicit() => Some((*other_profile, source.clone())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+///The god outputs an application haptic action vibrates, resolved from its suggestions and custom bindings like [resolve_input_bindings]
+pub fn resolve_output_bindings(
+    instance: &InstanceWrapper,
+    action: &ActionWrapper,
+    god_outputs: &HashMap<xr::Path, HashMap<xr::Path, Arc<OutputBinding>>>,
+) -> Vec<Arc<RemappedOutput>> {
+    let mut remapped = Vec::new();
+
+    for (profile, bindings) in binding_profiles(instance, action) {
+        let outputs = match god_outputs.get(&profile) {
+            Some(outputs) => outputs,
+            None => {
+                println!(
+                    "custom bindings: unknown profile {} for {}",
+                    instance.path_to_string(profile).unwrap(),
+                    action.name
+                );
+                continue;
+            }
+        };
+
+        for binding in bindings {
+            let output = match instance.string_to_path(&binding.path).ok().and_then(|path| outputs.get(&path)) {
+                Some(output) => output.clone(),
+                None => {
+                    println!("custom bindings: unknown output {} for {}", binding.path, action.name);
+                    continue;
+                }
+            };
+            remapped.push(Arc::new(RemappedOutput {
+                output,
+                amplitude: binding.transform.amplitude.unwrap_or(1f32),
+            }));
+        }
+    }
+
+    remapped
+}
+
+///The bindings attaching `action` to `session` resolved for it
+#[cfg(test)]
+fn attached_bindings(session: xr::Session, action: xr::Action) -> Vec<Arc<RemappedBinding>> {
+    use crate::wrappers::WrappedHandle;
+
+    let session = session.get_wrapper().unwrap().clone();
+    let action = action.get_wrapper().unwrap().clone();
+    let subaction_bindings = session
+        .attached_input_bindings(action.action_set().handle, action.handle)
+        .unwrap()
+        .read()
+        .unwrap();
+    let bindings = subaction_bindings.get_matching(xr::Path::NULL).unwrap().into_iter().cloned().collect();
+    bindings
+}
+
+///An active god input state that changed at 10ns
+#[cfg(test)]
+fn changed_input<T>(current_state: T) -> openxr::ActionState<T> {
+    openxr::ActionState::<T> {
+        current_state,
+        changed_since_last_sync: true,
+        last_change_time: xr::Time::from_nanos(10),
+        is_active: true,
+    }
+}
+
+///What an inactive app action reads after a sync at 20ns in which `binding`'s source is in `state`
+#[cfg(test)]
+fn sample_into_app<T: Default>(binding: &RemappedBinding, state: GodActionStateEnum) -> T
+where
+    openxr::ActionState<T>: crate::god_actions::OxideActionState,
+{
+    use crate::god_actions::OxideActionState;
+
+    *binding.source.action_state.write().unwrap() = state;
+    let mut app_state = openxr::ActionState::<T> {
+        current_state: T::default(),
+        changed_since_last_sync: false,
+        last_change_time: xr::Time::from_nanos(0),
+        is_active: false,
+    };
+    app_state
+        .sync_from_god_states(std::iter::once(binding.sample(&HashMap::new(), xr::Time::from_nanos(20))), xr::Time::from_nanos(20))
+        .unwrap();
+    app_state.current_state
+}
+
+#[test]
+fn test_invert() {
+    use crate::god_actions::OxideActionState;
+
+    let released = GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+        current_state: false,
+        changed_since_last_sync: false,
+        last_change_time: xr::Time::from_nanos(10),
+        is_active: true,
+    });
+
+    let transform = Transform {
+        invert: true,
+        ..Default::default()
+    };
+
+    let mut app_state = openxr::ActionState::<bool> {
+        current_state: false,
+        changed_since_last_sync: false,
+        last_change_time: xr::Time::from_nanos(0),
+        is_active: false,
+    };
+    app_state
+        .sync_from_god_states(std::iter::once(apply_transform(
+            &transform,
+            released,
+            &mut TransformMemory::default(),
+            xr::Time::from_nanos(0),
+        )), xr::Time::from_nanos(0))
+        .unwrap();
+
+    assert!(app_state.current_state);
+    assert!(app_state.changed_since_last_sync);
+}
+
+#[test]
+fn test_axis_split() {
+    let stick = GodActionStateEnum::Vector2f(openxr::ActionState::<openxr::Vector2f> {
+        current_state: openxr::Vector2f { x: 0.5, y: -0.5 },
+        changed_since_last_sync: true,
+        last_change_time: xr::Time::from_nanos(10),
+        is_active: true,
+    });
+
+    let split = |axis, range| {
+        let transform = Transform {
+            axis: Some(axis),
+            range,
+            ..Default::default()
+        };
+        match apply_transform(&transform, stick, &mut TransformMemory::default(), xr::Time::from_nanos(0)) {
+            GodActionStateEnum::Float(state) => state.current_state,
+            _ => panic!("split did not produce a float"),
+        }
+    };
+
+    assert_eq!(split(Axis::X, AxisRange::Signed), 0.5);
+    assert_eq!(split(Axis::Y, AxisRange::Signed), -0.5);
+    assert_eq!(split(Axis::X, AxisRange::Unsigned), 0.75);
+    assert_eq!(split(Axis::Y, AxisRange::Unsigned), 0.25);
+}
+
+#[test]
+fn test_compose_vector() {
+    let scalar = |value, is_active| {
+        GodActionStateEnum::Float(openxr::ActionState::<f32> {
+            current_state: value,
+            changed_since_last_sync: false,
+            last_change_time: xr::Time::from_nanos(10),
+            is_active,
+        })
+    };
+
+    match compose_vector(scalar(0.3, true), scalar(0.4, true)) {
+        GodActionStateEnum::Vector2f(state) => {
+            assert_eq!(state.current_state.x, 0.3);
+            assert_eq!(state.current_state.y, 0.4);
+            assert!(state.is_active);
+        }
+        _ => panic!("composition did not produce a vector"),
+    }
+
+    match compose_vector(scalar(0.3, true), scalar(0.4, false)) {
+        GodActionStateEnum::Vector2f(state) => assert!(!state.is_active),
+        _ => panic!("composition did not produce a vector"),
+    }
+}
+
+#[test]
+fn test_unbound_action() {
+    use common::config::BindingConfigBuilder;
+    use crate::fake_runtime;
+    use crate::wrappers::WrappedHandle;
+
+    let profile_name = "/interaction_profiles/khr/simple_controller";
+    let source_name = "/user/hand/left/input/select/click";
+    let config = BindingConfigBuilder::new().unbind(profile_name, "gameplay/jump").build().unwrap();
+    let instance = fake_runtime::create_instance_with_bindings(config);
+
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[("jump", xr::ActionType::BOOLEAN_INPUT), ("crouch", xr::ActionType::BOOLEAN_INPUT)],
+    );
+    let (jump, crouch) = (actions[0], actions[1]);
+    assert_eq!(
+        fake_runtime::suggest(&instance, profile_name, &[(jump, source_name), (crouch, source_name)]),
+        xr::Result::SUCCESS
+    );
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+    assert!(attached_bindings(session, jump).is_empty());
+
+    let profile = instance.string_to_path(profile_name).unwrap();
+    let source_path = instance.string_to_path(source_name).unwrap();
+    let wrapper = session.get_wrapper().unwrap().clone();
+    fake_runtime::set_input_state(
+        session,
+        wrapper.god_states()[&profile][&source_path].action.handle,
+        GodActionStateEnum::Boolean(changed_input(true)),
+    );
+    assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+
+    //Only the unbound action is silenced, the rest of its set still reads the source
+    assert!(!fake_runtime::boolean_state(session, jump, xr::Path::NULL).is_active);
+    assert!(fake_runtime::boolean_state(session, crouch, xr::Path::NULL).current_state);
+}
+
+#[test]
+fn test_unbound_on_one_profile() {
+    use common::config::BindingConfigBuilder;
+    use serde_json::json;
+
+    use crate::fake_runtime;
+    use crate::wrappers::WrappedHandle;
+
+    let simple = "/interaction_profiles/khr/simple_controller";
+    let index = "/interaction_profiles/valve/index_controller";
+    let config = BindingConfigBuilder::new().unbind(simple, "gameplay/jump").build().unwrap();
+    let instance = fake_runtime::create_instance_with_bindings(config);
+
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+    let jump = actions[0];
+    assert_eq!(fake_runtime::suggest(&instance, simple, &[(jump, "/user/hand/right/input/select/click")]), xr::Result::SUCCESS);
+    assert_eq!(fake_runtime::suggest(&instance, index, &[(jump, "/user/hand/right/input/a/click")]), xr::Result::SUCCESS);
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    //Nulling jump on the simple controller leaves the Index's suggestion alone
+    let wrapper = session.get_wrapper().unwrap().clone();
+    assert_eq!(
+        wrapper.effective_config_json(),
+        json!({
+            simple: { "gameplay": { "jump": null } },
+            index: { "gameplay": { "jump": { "bindings": ["/user/hand/right/input/a/click"] } } }
+        })
+    );
+
+    let index_path = instance.string_to_path(index).unwrap();
+    let a_click = instance.string_to_path("/user/hand/right/input/a/click").unwrap();
+    fake_runtime::set_input_state(
+        session,
+        wrapper.god_states()[&index_path][&a_click].action.handle,
+        GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+            current_state: true,
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(1),
+            is_active: true,
+        }),
+    );
+    assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+    let state = fake_runtime::boolean_state(session, jump, xr::Path::NULL);
+    assert!(state.is_active);
+    assert!(state.current_state);
+}
+
+#[test]
+fn test_gestures() {
+    let gestures = [GestureKind::Tap, GestureKind::LongPress, GestureKind::DoubleTap]
+        .iter()
+        .map(|kind| Transform {
+            gesture: Some(Gesture::new(*kind)),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+
+    //Feeds the same button to a tap, a long press and a double tap binding, each step is (pressed, changed at ms, now ms)
+    let run = |steps: &[(bool, i64, i64)]| {
+        let mut memories = gestures.iter().map(|_| TransformMemory::default()).collect::<Vec<_>>();
+        steps
+            .iter()
+            .map(|(pressed, changed_at, now)| {
+                let button = GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+                    current_state: *pressed,
+                    changed_since_last_sync: false,
+                    last_change_time: xr::Time::from_nanos(changed_at * 1_000_000),
+                    is_active: true,
+                });
+                let mut fired = [false; 3];
+                for (idx, (transform, memory)) in gestures.iter().zip(memories.iter_mut()).enumerate() {
+                    fired[idx] = apply_transform(transform, button, memory, xr::Time::from_nanos(now * 1_000_000))
+                        .get_inner()
+                        .get_bool()
+                        .unwrap();
+                }
+                fired
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let none = [false, false, false];
+    let tap = [true, false, false];
+    let long_press = [false, true, false];
+    let double_tap = [false, false, true];
+
+    //A tap only fires once the double tap window has passed, and only for one sync
+    assert_eq!(
+        run(&[(true, 1000, 1000), (false, 1100, 1100), (false, 1100, 1300), (false, 1100, 1450), (false, 1100, 1500)]),
+        vec![none, none, none, tap, none]
+    );
+
+    //A hold fires as soon as the hold time is reached and lasts until release
+    assert_eq!(
+        run(&[(true, 1000, 1000), (true, 1000, 1400), (true, 1000, 1500), (true, 1000, 2000), (false, 2100, 2100), (false, 2100, 3000)]),
+        vec![none, none, long_press, long_press, none, none]
+    );
+
+    //Released just before the hold time is still a tap
+    assert_eq!(
+        run(&[(true, 1000, 1000), (true, 1000, 1490), (false, 1499, 1500), (false, 1499, 1900)]),
+        vec![none, none, none, tap]
+    );
+
+    //The second press of a double tap is never a long press or a tap, however long it is held
+    assert_eq!(
+        run(&[(true, 1000, 1000), (false, 1100, 1100), (true, 1200, 1200), (true, 1200, 2000), (false, 2100, 2100), (false, 2100, 3000)]),
+        vec![none, none, double_tap, none, none, none]
+    );
+
+    //A second press after the window missed between syncs confirms the first tap and starts over
+    assert_eq!(
+        run(&[(true, 1000, 1000), (false, 1100, 1100), (true, 1500, 1500), (false, 1600, 1600), (false, 1600, 2000)]),
+        vec![none, none, tap, none, tap]
+    );
+}
+
+#[test]
+fn test_sectors() {
+    let sectors = (0..4)
+        .map(|index| Transform {
+            dead_zone: 0.2,
+            sector: Some(Sector { count: 4, index }),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    let mut memories = sectors.iter().map(|_| TransformMemory::default()).collect::<Vec<_>>();
+
+    //Which of the four sectors are pressed for a trackpad at (x, y)
+    let mut touch = |x: f32, y: f32, is_active: bool| {
+        let trackpad = GodActionStateEnum::Vector2f(openxr::ActionState::<openxr::Vector2f> {
+            current_state: openxr::Vector2f { x, y },
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(1),
+            is_active,
+        });
+        sectors
+            .iter()
+            .zip(memories.iter_mut())
+            .map(|(transform, memory)| {
+                apply_transform(transform, trackpad, memory, xr::Time::from_nanos(1))
+                    .get_inner()
+                    .get_bool()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(touch(0f32, 0f32, true), vec![false, false, false, false]);
+    //Sector 0 is up, the rest follow clockwise
+    assert_eq!(touch(0.1, 0.9, true), vec![true, false, false, false]);
+    assert_eq!(touch(0.8, -0.2, true), vec![false, true, false, false]);
+    //Lifting the finger, or resting it inside the dead zone, keeps the last sector
+    assert_eq!(touch(0f32, 0f32, true), vec![false, true, false, false]);
+    assert_eq!(touch(-0.1, 0.1, true), vec![false, true, false, false]);
+    assert_eq!(touch(-0.3, -0.9, true), vec![false, false, true, false]);
+    assert_eq!(touch(-1f32, 0f32, true), vec![false, false, false, true]);
+    assert_eq!(touch(0f32, 0f32, true), vec![false, false, false, true]);
+    //Until the trackpad goes away altogether
+    assert_eq!(touch(0f32, 0f32, false), vec![false, false, false, false]);
+    assert_eq!(touch(0f32, 0f32, true), vec![false, false, false, false]);
+
+    assert_eq!(sector_of(openxr::Vector2f { x: -0.1, y: 1f32 }, 8), 0);
+    assert_eq!(sector_of(openxr::Vector2f { x: 1f32, y: 1f32 }, 8), 1);
+    assert_eq!(sector_of(openxr::Vector2f { x: -1f32, y: 0.1 }, 8), 6);
+    assert_eq!(sector_of(openxr::Vector2f { x: -1f32, y: 0.9 }, 8), 7);
+}
+
+#[test]
+fn test_profile_default_threshold() {
+    use common::application_bindings::AnalogThreshold;
+
+    let index = &common::interaction_profiles::generate().profiles["/interaction_profiles/valve/index_controller"];
+    let trigger_defaults = index.defaults.get("/input/trigger/value");
+
+    let transform = with_defaults(
+        Transform::default(),
+        trigger_defaults,
+        ActionType::BooleanInput,
+        ActionType::FloatInput,
+    );
+    let threshold = transform.threshold.unwrap();
+    assert_eq!(Some(threshold), trigger_defaults.unwrap().threshold);
+
+    //Float actions still see the raw trigger
+    assert_eq!(
+        with_defaults(Transform::default(), trigger_defaults, ActionType::FloatInput, ActionType::FloatInput).threshold,
+        None
+    );
+
+    let mut memory = TransformMemory::default();
+    let mut pull = |value: f32| {
+        let trigger = GodActionStateEnum::Float(openxr::ActionState::<f32> {
+            current_state: value,
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(10),
+            is_active: true,
+        });
+        apply_transform(&transform, trigger, &mut memory, xr::Time::from_nanos(0)).get_inner().get_bool().unwrap()
+    };
+
+    //Below the profile's on threshold but above the hard-coded one
+    assert!(!pull((threshold.on_threshold + AnalogThreshold::default().on_threshold) / 2f32));
+    assert!(pull(threshold.on_threshold));
+    //Hysteresis keeps it pressed until the off threshold
+    assert!(pull((threshold.on_threshold + threshold.off_threshold) / 2f32));
+    assert!(!pull(threshold.off_threshold));
+}
+
+#[test]
+fn test_is_remapped() {
+    use common::application_bindings::{AnalogThreshold, ApplicationBindings};
+    use crate::fake_runtime;
+    use crate::wrappers::WrappedHandle;
+
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let trigger = "/user/hand/right/input/trigger/value";
+    let mut custom_bindings = ApplicationBindings::default();
+    let actions = &mut custom_bindings
+        .profiles
+        .entry(profile_name.to_owned())
+        .or_default()
+        .action_sets
+        .entry("gameplay".to_owned())
+        .or_default()
+        .actions;
+    actions.entry("use".to_owned()).or_default().bindings = vec![application_bindings::Binding::new(trigger.to_owned())];
+    actions.entry("fire".to_owned()).or_default().bindings = vec![application_bindings::Binding {
+        transform: Transform {
+            threshold: Some(AnalogThreshold {
+                on_threshold: 0.9,
+                off_threshold: 0.8,
+            }),
+            ..Default::default()
+        },
+        ..application_bindings::Binding::new(trigger.to_owned())
+    }];
+    let instance = fake_runtime::create_instance_with_bindings(custom_bindings);
+
+    let (_, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[
+            ("use", xr::ActionType::BOOLEAN_INPUT),
+            ("crouch", xr::ActionType::BOOLEAN_INPUT),
+            ("fire", xr::ActionType::BOOLEAN_INPUT),
+        ],
+    );
+    let suggested = actions.iter().map(|action| (*action, trigger)).collect::<Vec<_>>();
+    assert_eq!(fake_runtime::suggest(&instance, profile_name, &suggested), xr::Result::SUCCESS);
+    let action = |idx: usize| actions[idx].get_wrapper().unwrap().clone();
+
+    assert!(!is_remapped(&instance, &action(0), None));
+    assert!(!is_remapped(&instance, &action(1), None));
+    assert!(is_remapped(&instance, &action(2), None));
+    assert!(is_remapped(&instance, &action(2), Some("/user/hand/right")));
+    assert!(!is_remapped(&instance, &action(2), Some("/user/hand/left")));
+}
+
+#[test]
+fn test_remap_menu() {
+    use common::application_bindings::ApplicationBindings;
+    use crate::fake_runtime;
+
+    let profile_name = "/interaction_profiles/oculus/touch_controller";
+    let menu = "/user/hand/left/input/menu/click";
+    let a = "/user/hand/right/input/a/click";
+    let system = "/user/hand/right/input/system/click";
+
+    let resolve = |custom_source: &str| {
+        let mut custom_bindings = ApplicationBindings::default();
+        custom_bindings
+            .profiles
+            .entry(profile_name.to_owned())
+            .or_default()
+            .action_sets
+            .entry("gameplay".to_owned())
+            .or_default()
+            .actions
+            .entry("menu".to_owned())
+            .or_default()
+            .bindings = vec![application_bindings::Binding::new(custom_source.to_owned())];
+        let instance = fake_runtime::create_instance_with_bindings(custom_bindings);
+
+        let session = fake_runtime::new_session(&instance);
+        let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("menu", xr::ActionType::BOOLEAN_INPUT)]);
+        assert_eq!(fake_runtime::suggest(&instance, profile_name, &[(actions[0], menu)]), xr::Result::SUCCESS);
+        assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+        attached_bindings(session, actions[0])
+            .iter()
+            .map(|binding| binding.binding_str.clone())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(resolve(a), vec![a]);
+
+    assert!(is_reserved(system));
+    assert!(reserved_binding_warning("menu", profile_name, &[application_bindings::Binding::new(system.to_owned())])
+        .unwrap()
+        .contains("reserved"));
+    assert_eq!(resolve(system), vec![menu]);
+}
+
+#[test]
+fn test_remap_palm_pose() {
+    use common::application_bindings::ApplicationBindings;
+    use common::interaction_profiles::PALM_POSE_EXTENSION;
+    use crate::fake_runtime;
+
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let grip = "/user/hand/left/input/grip/pose";
+    let grip_surface = "/user/hand/left/input/grip_surface/pose";
+
+    let resolve = |enabled_extensions: Vec<String>| {
+        let mut custom_bindings = ApplicationBindings::default();
+        custom_bindings
+            .profiles
+            .entry(profile_name.to_owned())
+            .or_default()
+            .action_sets
+            .entry("gameplay".to_owned())
+            .or_default()
+            .actions
+            .entry("palm".to_owned())
+            .or_default()
+            .bindings = vec![application_bindings::Binding::new(grip_surface.to_owned())];
+        let instance = fake_runtime::create_instance_with_extensions(custom_bindings, enabled_extensions);
+
+        let session = fake_runtime::new_session(&instance);
+        let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("palm", xr::ActionType::POSE_INPUT)]);
+        assert_eq!(fake_runtime::suggest(&instance, profile_name, &[(actions[0], grip)]), xr::Result::SUCCESS);
+        assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+        let profile = instance.string_to_path(profile_name).unwrap();
+        let remapped = attached_bindings(session, actions[0])
+            .iter()
+            .map(|binding| binding.binding_str.clone())
+            .collect::<Vec<_>>();
+        (remapped, disabled_extension(&instance, profile, grip_surface))
+    };
+
+    assert_eq!(resolve(vec![PALM_POSE_EXTENSION.to_owned()]), (vec![grip_surface.to_owned()], None));
+    assert_eq!(resolve(Vec::new()), (Vec::new(), Some(PALM_POSE_EXTENSION.to_owned())));
+}
+
+#[test]
+fn test_analog_scale() {
+    use crate::fake_runtime;
+
+    let trigger = fake_runtime::god_input("/user/hand/left/input/trigger/value", ActionType::FloatInput);
+
+    let reaching_app = |analog_scale| {
+        let binding = RemappedBinding::new(trigger.clone(), None, Transform::default()).with_analog_scale(analog_scale);
+        sample_into_app::<f32>(&binding, GodActionStateEnum::Float(changed_input(0.6)))
+    };
+
+    assert!((reaching_app(0.5) - 0.3).abs() < f32::EPSILON);
+    assert_eq!(reaching_app(2f32), 1f32);
+    assert_eq!(reaching_app(1f32), 0.6);
+
+    let stick = GodActionStateEnum::Vector2f(changed_input(openxr::Vector2f { x: 0.6, y: 0.0 }));
+    match apply_analog_scale(stick, 2f32) {
+        GodActionStateEnum::Vector2f(state) => assert_eq!(state.current_state.x, 1f32),
+        _ => panic!("scaling changed the state type"),
+    }
+}
+
+#[test]
+fn test_remap_proximity() {
+    use common::config::BindingConfigBuilder;
+    use common::interaction_profiles::TOUCH_PROXIMITY_EXTENSION;
+    use crate::fake_runtime;
+
+    let profile_name = "/interaction_profiles/oculus/touch_controller";
+    let proximity = "/user/hand/right/input/trigger/proximity_fb";
+
+    let config = BindingConfigBuilder::new()
+        .bind(profile_name, "gameplay/ready", proximity)
+        .with_threshold(0.7, 0.6)
+        .build()
+        .unwrap();
+    let instance = fake_runtime::create_instance_with_extensions(config.clone(), vec![TOUCH_PROXIMITY_EXTENSION.to_owned()]);
+
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("ready", xr::ActionType::BOOLEAN_INPUT)]);
+    assert_eq!(fake_runtime::suggest(&instance, profile_name, &[(actions[0], proximity)]), xr::Result::SUCCESS);
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let remapped = attached_bindings(session, actions[0]);
+    assert_eq!(remapped.len(), 1);
+    assert_eq!(remapped[0].source.action.action_type, ActionType::FloatInput);
+
+    let reaching_app = |distance: f32| sample_into_app::<bool>(&remapped[0], GodActionStateEnum::Float(changed_input(distance)));
+    assert!(!reaching_app(0.5));
+    assert!(reaching_app(0.8));
+    //Hysteresis keeps it pressed until the finger backs off past the off threshold
+    assert!(reaching_app(0.65));
+    assert!(!reaching_app(0.3));
+
+    //Without the extension the runtime has no proximity sources to read
+    let without = fake_runtime::create_instance_with_extensions(config, Vec::new());
+    assert_eq!(
+        disabled_extension(&without, without.string_to_path(profile_name).unwrap(), proximity),
+        Some(TOUCH_PROXIMITY_EXTENSION.to_owned())
+    );
+    assert_eq!(disabled_extension(&without, without.string_to_path(profile_name).unwrap(), "/user/hand/right/input/trigger/value"), None);
+}
+
+#[test]
+fn test_remap_twist() {
+    use common::config::BindingConfigBuilder;
+    use common::interaction_profiles::FLIGHT_STICK_PROFILE;
+    use crate::fake_runtime;
+
+    let config = BindingConfigBuilder::new()
+        .bind(FLIGHT_STICK_PROFILE, "flight/yaw", "/user/gamepad/input/joystick/twist")
+        .with_invert()
+        .with_curve(2f32)
+        .build()
+        .unwrap();
+    let instance = fake_runtime::create_instance_with_bindings(config);
+
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "flight", &[("yaw", xr::ActionType::FLOAT_INPUT)]);
+    assert_eq!(
+        fake_runtime::suggest(&instance, FLIGHT_STICK_PROFILE, &[(actions[0], "/user/gamepad/input/joystick/twist")]),
+        xr::Result::SUCCESS
+    );
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let remapped = attached_bindings(session, actions[0]);
+    assert_eq!(remapped.len(), 1);
+
+    let reaching_app = |twist: f32| sample_into_app::<f32>(&remapped[0], GodActionStateEnum::Float(changed_input(twist)));
+    //Twisting right yaws left, and the curve keeps small twists small
+    assert_eq!(reaching_app(0.5), -0.25);
+    assert_eq!(reaching_app(-1f32), 1f32);
+    assert_eq!(reaching_app(0f32), 0f32);
+}
+
+#[test]
+fn test_remap_squeeze_force() {
+    use common::config::BindingConfigBuilder;
+    use crate::fake_runtime;
+
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let config = BindingConfigBuilder::new()
+        .bind(profile_name, "gameplay/grab", "/user/hand/right/input/squeeze/force")
+        .with_threshold(0.8, 0.6)
+        .bind(profile_name, "gameplay/grip", "/user/hand/right/input/squeeze/value")
+        .build()
+        .unwrap();
+    let instance = fake_runtime::create_instance_with_bindings(config);
+
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[("grab", xr::ActionType::BOOLEAN_INPUT), ("grip", xr::ActionType::FLOAT_INPUT)],
+    );
+    //Suggested on the squeeze the application knows, the custom bindings move grab onto the force sensor
+    let squeeze_value = "/user/hand/right/input/squeeze/value";
+    assert_eq!(
+        fake_runtime::suggest(&instance, profile_name, &[(actions[0], squeeze_value), (actions[1], squeeze_value)]),
+        xr::Result::SUCCESS
+    );
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let grab = attached_bindings(session, actions[0]);
+    let grip = attached_bindings(session, actions[1]);
+    assert_eq!(grab.len(), 1);
+    assert_eq!(grab[0].binding_str, "/user/hand/right/input/squeeze/force");
+    assert_eq!(grip.len(), 1);
+    assert_eq!(grip[0].binding_str, "/user/hand/right/input/squeeze/value");
+
+    let squeeze = |value: f32, force: f32| {
+        (
+            sample_into_app::<bool>(&grab[0], GodActionStateEnum::Float(changed_input(force))),
+            sample_into_app::<f32>(&grip[0], GodActionStateEnum::Float(changed_input(value))),
+        )
+    };
+    //Closing the hand moves the squeeze all the way without grabbing, only pressing hard does
+    assert_eq!(squeeze(1f32, 0.2), (false, 1f32));
+    assert_eq!(squeeze(1f32, 0.9), (true, 1f32));
+    assert_eq!(squeeze(0.5, 0f32), (false, 0.5));
+}
+
+#[test]
+fn test_remap_trigger_touch() {
+    use common::config::BindingConfigBuilder;
+    use crate::fake_runtime;
+
+    let profile_name = "/interaction_profiles/valve/index_controller";
+    let config = BindingConfigBuilder::new()
+        .bind(profile_name, "gameplay/aim_assist", "/user/hand/right/input/trigger/touch")
+        .bind(profile_name, "gameplay/fire", "/user/hand/right/input/trigger/click")
+        .build()
+        .unwrap();
+    let instance = fake_runtime::create_instance_with_bindings(config);
+
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[("aim_assist", xr::ActionType::BOOLEAN_INPUT), ("fire", xr::ActionType::BOOLEAN_INPUT)],
+    );
+    //Both start out on the trigger's click, the custom bindings move aim_assist onto its touch sensor
+    let click = "/user/hand/right/input/trigger/click";
+    assert_eq!(
+        fake_runtime::suggest(&instance, profile_name, &[(actions[0], click), (actions[1], click)]),
+        xr::Result::SUCCESS
+    );
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let aim_assist = attached_bindings(session, actions[0]);
+    let fire = attached_bindings(session, actions[1]);
+    assert_eq!(aim_assist.len(), 1);
+    assert_eq!(aim_assist[0].binding_str, "/user/hand/right/input/trigger/touch");
+    assert_eq!(fire.len(), 1);
+    assert_eq!(fire[0].binding_str, "/user/hand/right/input/trigger/click");
+    assert!(!Arc::ptr_eq(&aim_assist[0].source, &fire[0].source));
+
+    let trigger = |touch: bool, click: bool| {
+        (
+            sample_into_app::<bool>(&aim_assist[0], GodActionStateEnum::Boolean(changed_input(touch))),
+            sample_into_app::<bool>(&fire[0], GodActionStateEnum::Boolean(changed_input(click))),
+        )
+    };
+    //Resting a finger on the trigger doesn't fire
+    assert_eq!(trigger(true, false), (true, false));
+    assert_eq!(trigger(true, true), (true, true));
+    assert_eq!(trigger(false, false), (false, false));
+}
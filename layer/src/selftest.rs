@@ -0,0 +1,230 @@
+//! A check of the whole setup run as an instance is created, so users can tell a broken install or config from a broken application
+//!
+//! The catalog, the custom bindings and the god action sets built against the runtime are each checked, every failure is collected into a
+//! [Report] which is printed and written to selftest.json next to actions.json
+
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use common::config;
+use common::interaction_profiles;
+use common::serial::{get_uuid, read_json, write_json_atomic, CONFIG_DIR};
+use common::xrapplication_info::XrApplicationInfo;
+
+use crate::emulated;
+use crate::wrappers::InstanceWrapper;
+
+///Set to run the self test on every activated application, `abort` also fails instance creation when it doesn't pass and `0` turns it off
+pub const SELFTEST_VAR: &str = "OXIDEXR_SELFTEST";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Off,
+    Report,
+    Abort,
+}
+
+pub fn mode() -> Mode {
+    parse_mode(env::var(SELFTEST_VAR).ok().as_deref())
+}
+
+fn parse_mode(value: Option<&str>) -> Mode {
+    match value.map(str::trim) {
+        Some("abort") => Mode::Abort,
+        Some("0") | None => Mode::Off,
+        Some(_) => Mode::Report,
+    }
+}
+
+///What went wrong in each check, checks that passed have no failures
+#[derive(Debug, Default)]
+pub struct Report {
+    pub checks: BTreeMap<&'static str, Vec<String>>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.checks.values().all(Vec::is_empty)
+    }
+
+    fn describe(&self) -> String {
+        let mut description = format!("self test {}", if self.passed() { "passed" } else { "failed" });
+        for (check, failures) in &self.checks {
+            description += &format!("\n  {}: {}", check, if failures.is_empty() { "ok" } else { "failed" });
+            for failure in failures {
+                description += &format!("\n    {}", failure);
+            }
+        }
+        description
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "passed": self.passed(),
+            "checks": self.checks,
+        })
+    }
+}
+
+///Runs every check against a freshly created instance, `config_files` are the custom binding files it was loaded from
+///
+///The layer drops a config that doesn't load, so the files are read again here to say why
+pub fn run(instance: &InstanceWrapper, config_files: &[PathBuf]) -> Report {
+    let mut report = Report::default();
+
+    report.checks.insert(
+        "catalog",
+        interaction_profiles::catalog().validate().err().map(|why| why.to_string()).into_iter().collect(),
+    );
+
+    let application_info = read_json::<XrApplicationInfo>(&format!("{}{}/actions.json", CONFIG_DIR, get_uuid(&instance.application_name)));
+    //Bindings for the emulated profile are checked like any other while it is in use
+    let mut catalog = (*interaction_profiles::catalog()).clone();
+    if let Some(profile_info) = instance.emulated_set.as_ref().and_then(|_| emulated::profile()) {
+        catalog.profiles.insert(emulated::EMULATED_PROFILE.to_owned(), Arc::new(profile_info));
+    }
+    let mut config_failures = Vec::new();
+    for path in config_files.iter().filter(|path| path.exists()) {
+        if let Err(errors) = config::load_file_diagnosed(path, application_info.as_ref(), &catalog) {
+            config_failures.extend(errors.iter().map(|error| format!("{}: {}", path.display(), error)));
+        }
+    }
+    report.checks.insert("config", config_failures);
+
+    let mut god_set_failures = Vec::new();
+    for god_set in instance.god_action_sets_in_order() {
+        if god_set.suggestion_result.into_raw() < 0 {
+            god_set_failures.push(format!("the runtime rejected the suggested bindings of {} with {}", god_set.name, god_set.suggestion_result));
+        }
+    }
+    //Deferred god sets are only made at the first attach, until then there is nothing to check them against
+    if instance.god_action_sets.get().is_some() {
+        for profile_name in instance.custom_bindings.profiles.keys() {
+            //Profiles used only on unmanaged user paths are left to the runtime and have no god set either
+            let profile_info = match interaction_profiles::profile(profile_name) {
+                Some(profile_info) if profile_name != emulated::EMULATED_PROFILE => profile_info,
+                _ => continue,
+            };
+            if !profile_info.subaction_paths.iter().any(|user_path| instance.settings.manages(user_path)) {
+                continue;
+            }
+            let has_god_set = matches!(instance.string_to_path(profile_name), Ok(profile) if instance.god_action_sets().contains_key(&profile));
+            if !has_god_set {
+                god_set_failures.push(format!("{} is bound but has no god action set", profile_name));
+            }
+        }
+    }
+    report.checks.insert("god_action_sets", god_set_failures);
+
+    report
+}
+
+///Prints the report and writes it to selftest.json next to actions.json
+pub fn publish(instance: &InstanceWrapper, report: &Report) {
+    println!("{}", report.describe());
+    let path = Path::new(CONFIG_DIR).join(get_uuid(&instance.application_name)).join("selftest.json");
+    if let Err(why) = write_json_atomic(&report.to_json(), &path) {
+        println!("couldn't write {}: {}", path.display(), why);
+    }
+}
+
+#[test]
+fn test_bad_config() {
+    use crate::fake_runtime;
+
+    let dir = env::temp_dir().join(format!("oxidexr_selftest_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("custom_bindings.json");
+    std::fs::write(
+        &path,
+        r#"{
+    "/interaction_profiles/acme/wand": {
+        "gameplay": { "fire": { "bindings": [{ "path": "/user/hand/right/input/trigger" }] } }
+    },
+    "/interaction_profiles/valve/index_controller": {
+        "gameplay": { "move": { "bindings": [{ "path": "/user/hand/left/input/thumbstick", "dead_zone": 1.5 }] } }
+    }
+}"#,
+    )
+    .unwrap();
+
+    let instance = fake_runtime::create_instance();
+    let report = run(&instance, &[path, dir.join("custom_bindings.local.json")]);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!report.passed());
+    assert!(report.checks["catalog"].is_empty());
+    assert!(report.checks["god_action_sets"].is_empty());
+    let failures = &report.checks["config"];
+    assert_eq!(failures.len(), 2, "{:?}", failures);
+    assert!(failures[0].contains("line 2") && failures[0].contains("/interaction_profiles/acme/wand"), "{}", failures[0]);
+    assert!(failures[1].contains("line 6") && failures[1].contains("dead_zone 1.5"), "{}", failures[1]);
+    assert!(report.describe().starts_with("self test failed"));
+
+    let report = run(&instance, &[]);
+    assert!(report.passed(), "{}", report.describe());
+}
+
+#[test]
+fn test_mode() {
+    assert_eq!(parse_mode(None), Mode::Off);
+    assert_eq!(parse_mode(Some("0")), Mode::Off);
+    assert_eq!(parse_mode(Some("1")), Mode::Report);
+    assert_eq!(parse_mode(Some("")), Mode::Report);
+    assert_eq!(parse_mode(Some(" abort ")), Mode::Abort);
+}
+
+#[test]
+fn test_emulated_config() {
+    use crate::fake_runtime;
+
+    let dir = env::temp_dir().join(format!("oxidexr_selftest_emulated_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("custom_bindings.json");
+    std::fs::write(
+        &path,
+        r#"{
+    "/interaction_profiles/oxidexr/emulated_input": {
+        "gameplay": {
+            "jump": { "bindings": [{ "path": "/user/gamepad/input/a/click" }] },
+            "move": { "bindings": [{ "path": "/user/gamepad/input/thumbstick_left", "dead_zone": 1.5 }] }
+        }
+    }
+}"#,
+    )
+    .unwrap();
+
+    //With emulated input on its profile is checked like a catalog one, so only the real problem is reported
+    let report = run(&fake_runtime::create_emulated_instance(Default::default()), std::slice::from_ref(&path));
+    let failures = &report.checks["config"];
+    assert_eq!(failures.len(), 1, "{:?}", failures);
+    assert!(failures[0].contains("line 5") && failures[0].contains("dead_zone 1.5"), "{}", failures[0]);
+
+    let report = run(&fake_runtime::create_instance(), &[path]);
+    std::fs::remove_dir_all(&dir).unwrap();
+    let failures = &report.checks["config"];
+    assert_eq!(failures.len(), 2, "{:?}", failures);
+    assert!(failures[0].contains("not in the interaction profile catalog"), "{}", failures[0]);
+}
+
+#[test]
+fn test_rejected_suggestions() {
+    use common::config::Settings;
+
+    use crate::fake_runtime;
+    use crate::god_actions;
+
+    let instance = fake_runtime::create_instance_with_settings(Settings { defer_god_sets: true, ..Default::default() });
+    assert!(run(&instance, &[]).passed());
+
+    fake_runtime::fail_suggestions(instance.handle);
+    let index = instance.string_to_path("/interaction_profiles/valve/index_controller").unwrap();
+    let god_sets = god_actions::create_god_action_sets_for(&instance, Some(&[index])).unwrap();
+    assert!(instance.god_action_sets.set(god_sets).is_ok());
+    let report = run(&instance, &[]);
+    let failures = &report.checks["god_action_sets"];
+    assert_eq!(failures.len(), 1, "{:?}", failures);
+    assert!(failures[0].starts_with("the runtime rejected the suggested bindings of /interaction_profiles/valve/index_controller"), "{}", failures[0]);
+}
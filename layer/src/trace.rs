@@ -0,0 +1,280 @@
+use std::env;
+
+use once_cell::sync::OnceCell;
+use openxr::sys as xr;
+use openxr::sys::pfn;
+
+use crate::god_actions::{GodActionStateEnum, SubactionBindings};
+use crate::remap::RemappedBinding;
+use crate::ffi::get_func;
+
+///Comma separated list of trace targets to print, e.g. OXIDEXR_TRACE=remap
+pub const TRACE_VAR: &str = "OXIDEXR_TRACE";
+
+///Logs how every application action was resolved on each xrSyncActions
+pub const REMAP: &str = "remap";
+
+///Logs how many times each intercepted function was called when an instance is destroyed
+pub const METRICS: &str = "metrics";
+
+///Writes the bindings each session resolved to effective_bindings.json next to actions.json when its action sets are attached
+pub const EFFECTIVE_CONFIG: &str = "effective_config";
+
+///Writes the same bindings as a Graphviz graph to binding_graph.dot next to actions.json, render it with e.g. `dot -Tsvg`
+pub const BINDING_GRAPH: &str = "binding_graph";
+
+///Logs which core functions the layer below us resolved when an instance is created
+pub const NEXT_LAYER: &str = "next_layer";
+
+static TARGETS: OnceCell<Vec<String>> = OnceCell::new();
+
+fn parse_targets(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|target| !target.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+pub fn enabled(target: &str) -> bool {
+    TARGETS
+        .get_or_init(|| env::var(TRACE_VAR).map(|value| parse_targets(&value)).unwrap_or_default())
+        .iter()
+        .any(|enabled| enabled == target)
+}
+
+///Every core function an instance can resolve, the ones the loader handles before there is an instance are left out
+const CORE_FUNCTIONS: &[&str] = &[
+    "xrDestroyInstance",
+    "xrGetInstanceProperties",
+    "xrPollEvent",
+    "xrResultToString",
+    "xrStructureTypeToString",
+    "xrGetSystem",
+    "xrGetSystemProperties",
+    "xrEnumerateEnvironmentBlendModes",
+    "xrCreateSession",
+    "xrDestroySession",
+    "xrEnumerateReferenceSpaces",
+    "xrCreateReferenceSpace",
+    "xrGetReferenceSpaceBoundsRect",
+    "xrCreateActionSpace",
+    "xrLocateSpace",
+    "xrDestroySpace",
+    "xrEnumerateViewConfigurations",
+    "xrGetViewConfigurationProperties",
+    "xrEnumerateViewConfigurationViews",
+    "xrEnumerateSwapchainFormats",
+    "xrCreateSwapchain",
+    "xrDestroySwapchain",
+    "xrEnumerateSwapchainImages",
+    "xrAcquireSwapchainImage",
+    "xrWaitSwapchainImage",
+    "xrReleaseSwapchainImage",
+    "xrBeginSession",
+    "xrEndSession",
+    "xrRequestExitSession",
+    "xrWaitFrame",
+    "xrBeginFrame",
+    "xrEndFrame",
+    "xrLocateViews",
+    "xrStringToPath",
+    "xrPathToString",
+    "xrCreateActionSet",
+    "xrDestroyActionSet",
+    "xrCreateAction",
+    "xrDestroyAction",
+    "xrSuggestInteractionProfileBindings",
+    "xrAttachSessionActionSets",
+    "xrGetCurrentInteractionProfile",
+    "xrGetActionStateBoolean",
+    "xrGetActionStateFloat",
+    "xrGetActionStateVector2f",
+    "xrGetActionStatePose",
+    "xrSyncActions",
+    "xrEnumerateBoundSourcesForAction",
+    "xrGetInputSourceLocalizedName",
+    "xrApplyHapticFeedback",
+    "xrStopHapticFeedback",
+];
+
+///Whether the layer below us resolves each core function
+pub struct FunctionTable {
+    pub functions: Vec<(&'static str, bool)>,
+}
+
+impl FunctionTable {
+    pub unsafe fn probe(get_instance_proc_addr: pfn::GetInstanceProcAddr, instance: xr::Instance) -> Self {
+        Self {
+            functions: CORE_FUNCTIONS
+                .iter()
+                .map(|name| (*name, get_func(get_instance_proc_addr, instance, name).is_some()))
+                .collect(),
+        }
+    }
+
+    pub fn missing(&self) -> Vec<&'static str> {
+        self.functions.iter().filter(|(_, present)| !present).map(|(name, _)| *name).collect()
+    }
+
+    ///A summary line followed by one line per function if `all`, otherwise only the missing ones
+    pub fn describe(&self, all: bool) -> String {
+        let missing = self.missing();
+        let mut description = format!(
+            "next layer resolved {} of {} core functions",
+            self.functions.len() - missing.len(),
+            self.functions.len()
+        );
+        for (name, present) in &self.functions {
+            if all || !present {
+                description += &format!("\n  {}: {}", name, if *present { "present" } else { "missing" });
+            }
+        }
+        description
+    }
+}
+
+///Collects one line per application action during a sync and prints them once the sync is done
+pub struct RemapTrace {
+    records: Vec<String>,
+}
+
+impl RemapTrace {
+    ///Returns None unless the remap target is enabled so the regular sync path does no extra work
+    pub fn begin() -> Option<Self> {
+        if enabled(REMAP) {
+            Some(Self::new())
+        } else {
+            None
+        }
+    }
+
+    fn new() -> Self {
+        Self { records: Vec::new() }
+    }
+
+    pub fn record(
+        &mut self,
+        action_name: &str,
+        subaction_bindings: &SubactionBindings<RemappedBinding>,
+        main_state: &GodActionStateEnum,
+    ) {
+        let sources = subaction_bindings
+            .get_matching(xr::Path::NULL)
+            .unwrap()
+            .iter()
+            .map(|binding| {
+                let mut source = format!(
+                    "{}={}",
+                    binding.binding_str,
+                    describe_state(&binding.action_state.read().unwrap())
+                );
+                if let Some(y_source) = &binding.y_source {
+                    source += &format!(
+                        " + {}={}",
+                        y_source.binding_str,
+                        describe_state(&y_source.action_state.read().unwrap())
+                    );
+                }
+                if !binding.transform.is_identity() {
+                    source += &format!(" {:?}", binding.transform);
+                }
+                source
+            })
+            .collect::<Vec<_>>();
+
+        self.records.push(format!(
+            "{}: [{}] -> {}",
+            action_name,
+            sources.join(", "),
+            describe_state(main_state)
+        ));
+    }
+
+    pub fn finish(self, sync_idx: u64) {
+        for record in &self.records {
+            println!("trace remap sync {}: {}", sync_idx, record);
+        }
+    }
+}
+
+fn describe_state(state: &GodActionStateEnum) -> String {
+    let (value, is_active) = match state {
+        GodActionStateEnum::Boolean(state) => (state.current_state.to_string(), state.is_active),
+        GodActionStateEnum::Float(state) => (format!("{:.3}", state.current_state), state.is_active),
+        GodActionStateEnum::Vector2f(state) => (
+            format!("({:.3}, {:.3})", state.current_state.x, state.current_state.y),
+            state.is_active,
+        ),
+        GodActionStateEnum::Pose(state) => (String::from("pose"), state.is_active),
+    };
+
+    if is_active {
+        value
+    } else {
+        format!("{} (inactive)", value)
+    }
+}
+
+#[test]
+fn test_remap_trace() {
+    use common::application_bindings::Transform;
+    use common::xrapplication_info::ActionType;
+    use std::sync::Arc;
+
+    use crate::fake_runtime;
+    use crate::god_actions::CachedActionStatesEnum;
+
+    assert_eq!(parse_targets("remap, other,"), vec!["remap", "other"]);
+
+    let binding = Arc::new(RemappedBinding::new(
+        fake_runtime::god_input("/user/hand/left/input/select/click", ActionType::BooleanInput),
+        None,
+        Transform {
+            invert: true,
+            ..Default::default()
+        },
+    ));
+    let bindings = SubactionBindings::Singleton(vec![binding]);
+
+    let mut trace = RemapTrace::new();
+    for action_name in &["main/jump", "main/crouch"] {
+        let mut states = CachedActionStatesEnum::new(ActionType::BooleanInput, &Vec::new());
+        states.sync(&bindings, xr::Time::from_nanos(0)).unwrap();
+        trace.record(action_name, &bindings, &states.main_state());
+    }
+
+    assert_eq!(trace.records.len(), 2);
+    assert!(trace.records[0].starts_with("main/jump: [/user/hand/left/input/select/click="));
+}
+
+#[test]
+fn test_function_table() {
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    use crate::fake_runtime;
+
+    unsafe extern "system" fn without_pose(
+        instance: xr::Instance,
+        name: *const c_char,
+        function: *mut Option<pfn::VoidFunction>,
+    ) -> xr::Result {
+        if CStr::from_ptr(name).to_bytes() == b"xrGetActionStatePose" {
+            *function = None;
+            return xr::Result::ERROR_FUNCTION_UNSUPPORTED;
+        }
+        fake_runtime::get_instance_proc_addr(instance, name, function)
+    }
+
+    let complete = unsafe { FunctionTable::probe(fake_runtime::get_instance_proc_addr, xr::Instance::from_raw(1)) };
+    assert!(complete.missing().is_empty());
+    assert_eq!(complete.describe(false).lines().count(), 1);
+
+    let table = unsafe { FunctionTable::probe(without_pose, xr::Instance::from_raw(1)) };
+    assert_eq!(table.missing(), vec!["xrGetActionStatePose"]);
+    let description = table.describe(false);
+    assert_eq!(description.lines().skip(1).collect::<Vec<_>>(), vec!["  xrGetActionStatePose: missing"]);
+    assert_eq!(table.describe(true).lines().count(), CORE_FUNCTIONS.len() + 1);
+}
@@ -3,25 +3,78 @@ use std::ffi::CStr;
 use openxr::Result;
 use openxr::sys as xr;
 
-pub const LAYER_NAME: &'static str = "XR_APILAYER_BULLCH_oxidexr";
-pub const LAYER_VERSION: u32 = 1;
-
 pub unsafe fn i8_arr_to_owned(arr: &[i8]) -> String {
-    String::from(CStr::from_ptr(std::mem::transmute(arr.as_ptr())).to_str().unwrap())
+    String::from(CStr::from_ptr(arr.as_ptr().cast()).to_str().unwrap())
 }
 
-pub fn place_cstr(out: &mut [std::os::raw::c_char], s: &str) {
+///Reads a fixed size name array from the application without trusting it
+///
+///The string ends at the first null or at the end of the array, whichever comes first, and invalid UTF-8 is replaced
+pub fn char_arr_to_string_lossy(arr: &[std::os::raw::c_char]) -> String {
+    let bytes = arr
+        .iter()
+        .take_while(|c| **c != 0)
+        .map(|c| *c as u8)
+        .collect::<Vec<_>>();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+///Returned by [try_place_cstr] when a string and its trailing null don't fit in the output array
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TooLong {
+    pub required: usize,
+    pub available: usize,
+}
+
+///Copies a string into a fixed size, null terminated output array, leaving the array untouched if it doesn't fit
+pub fn try_place_cstr(out: &mut [std::os::raw::c_char], s: &str) -> std::result::Result<(), TooLong> {
     if s.len() + 1 > out.len() {
-        panic!(
-            "string requires {} > {} bytes (including trailing null)",
-            s.len(),
-            out.len()
-        );
+        return Err(TooLong {
+            required: s.len() + 1,
+            available: out.len(),
+        });
     }
     for (i, o) in s.bytes().zip(out.iter_mut()) {
         *o = i as std::os::raw::c_char;
     }
     out[s.len()] = 0;
+    Ok(())
+}
+
+///Like [try_place_cstr] but panics if the string doesn't fit, only use this for strings known to fit
+#[cfg(test)]
+pub fn place_cstr(out: &mut [std::os::raw::c_char], s: &str) {
+    if let Err(err) = try_place_cstr(out, s) {
+        panic!(
+            "string requires {} > {} bytes (including trailing null)",
+            err.required,
+            err.available
+        );
+    }
+}
+
+///Hands `items` out through the count/fill two-call convention of OpenXR's enumerate functions
+///
+///The count is written on every call. A capacity of 0 only asks for it, a smaller one than the count is ERROR_SIZE_INSUFFICIENT and leaves the output alone
+pub unsafe fn fill_output_array<T: Copy>(items_out: *mut T, capacity_input: u32, count_output: *mut u32, items: &[T]) -> xr::Result {
+    *count_output = items.len() as u32;
+    if capacity_input == 0 {
+        return xr::Result::SUCCESS;
+    }
+    if (capacity_input as usize) < items.len() {
+        return xr::Result::ERROR_SIZE_INSUFFICIENT;
+    }
+    if items_out.is_null() {
+        return xr::Result::ERROR_VALIDATION_FAILURE;
+    }
+    std::slice::from_raw_parts_mut(items_out, items.len()).copy_from_slice(items);
+    xr::Result::SUCCESS
+}
+
+///[fill_output_array] for a string, the count includes the null terminator
+pub unsafe fn fill_output_string(buffer: *mut std::os::raw::c_char, capacity_input: u32, count_output: *mut u32, s: &str) -> xr::Result {
+    let chars = s.bytes().map(|byte| byte as std::os::raw::c_char).chain(std::iter::once(0)).collect::<Vec<_>>();
+    fill_output_array(buffer, capacity_input, count_output, &chars)
 }
 
 pub fn check(result: xr::Result) -> Result<xr::Result> {
@@ -38,4 +91,48 @@ pub fn check2<T>(result: xr::Result, out: T) -> Result<T> {
     } else {
         Ok(out)
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_char_arr_to_string_lossy() {
+    assert_eq!(char_arr_to_string_lossy(&[b'a' as _, b'b' as _, 0, b'c' as _]), "ab");
+    assert_eq!(char_arr_to_string_lossy(&[b'a' as _, b'b' as _]), "ab");
+    assert_eq!(char_arr_to_string_lossy(&[b'a' as _, 0xff_u8 as _, 0]), "a\u{fffd}");
+}
+
+#[test]
+fn test_try_place_cstr() {
+    let mut out = [1 as std::os::raw::c_char; 4];
+
+    assert_eq!(try_place_cstr(&mut out, "ab"), Ok(()));
+    assert_eq!(out[..3], [b'a' as _, b'b' as _, 0]);
+
+    assert_eq!(try_place_cstr(&mut out, "abc"), Ok(()));
+    assert_eq!(out, [b'a' as _, b'b' as _, b'c' as _, 0]);
+
+    assert_eq!(try_place_cstr(&mut out, "abcd"), Err(TooLong { required: 5, available: 4 }));
+    assert_eq!(out, [b'a' as _, b'b' as _, b'c' as _, 0]);
+}
+
+#[test]
+fn test_fill_output_string() {
+    let mut count = 0;
+    let mut out = [1 as std::os::raw::c_char; 4];
+
+    //Asking for the size writes nothing else, even with no buffer
+    assert_eq!(unsafe { fill_output_string(std::ptr::null_mut(), 0, &mut count, "abc") }, xr::Result::SUCCESS);
+    assert_eq!(count, 4);
+
+    assert_eq!(unsafe { fill_output_string(out.as_mut_ptr(), 4, &mut count, "abc") }, xr::Result::SUCCESS);
+    assert_eq!(count, 4);
+    assert_eq!(out, [b'a' as _, b'b' as _, b'c' as _, 0]);
+
+    //Too small still reports what is needed but leaves the buffer alone
+    assert_eq!(unsafe { fill_output_string(out.as_mut_ptr(), 4, &mut count, "wxyz") }, xr::Result::ERROR_SIZE_INSUFFICIENT);
+    assert_eq!(count, 5);
+    assert_eq!(out, [b'a' as _, b'b' as _, b'c' as _, 0]);
+
+    assert_eq!(unsafe { fill_output_string(out.as_mut_ptr(), 4, &mut count, "") }, xr::Result::SUCCESS);
+    assert_eq!(count, 1);
+    assert_eq!(out[0], 0);
+}
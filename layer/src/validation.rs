@@ -1,5 +1,3 @@
-use std::ptr;
-
 use openxr::sys as xr;
 use openxr::Result;
 
@@ -15,13 +13,13 @@ impl Validate for xr::ActionStateGetInfo {
         if self.ty != xr::ActionStateGetInfo::TYPE {
             return Err(xr::Result::ERROR_VALIDATION_FAILURE);
         }
-        if self.next != ptr::null() {
+        if !self.next.is_null() {
             return Err(xr::Result::ERROR_VALIDATION_FAILURE);
         }
         if self.action.get_wrapper().is_none() {
             return Err(xr::Result::ERROR_HANDLE_INVALID);
         }
-        return Ok(());
+        Ok(())
     }
 }
 
@@ -30,10 +28,10 @@ impl Validate for xr::ActionStateBoolean {
         if self.ty != xr::ActionStateBoolean::TYPE {
             return Err(xr::Result::ERROR_VALIDATION_FAILURE);
         }
-        if self.next != ptr::null_mut() {
+        if !self.next.is_null() {
             return Err(xr::Result::ERROR_VALIDATION_FAILURE);
         }
-        return Ok(());
+        Ok(())
     }
 }
 
@@ -42,10 +40,10 @@ impl Validate for xr::ActionStateFloat {
         if self.ty != xr::ActionStateFloat::TYPE {
             return Err(xr::Result::ERROR_VALIDATION_FAILURE);
         }
-        if self.next != ptr::null_mut() {
+        if !self.next.is_null() {
             return Err(xr::Result::ERROR_VALIDATION_FAILURE);
         }
-        return Ok(());
+        Ok(())
     }
 }
 
@@ -54,10 +52,10 @@ impl Validate for xr::ActionStateVector2f {
         if self.ty != xr::ActionStateVector2f::TYPE {
             return Err(xr::Result::ERROR_VALIDATION_FAILURE);
         }
-        if self.next != ptr::null_mut() {
+        if !self.next.is_null() {
             return Err(xr::Result::ERROR_VALIDATION_FAILURE);
         }
-        return Ok(());
+        Ok(())
     }
 }
 
@@ -66,9 +64,9 @@ impl Validate for xr::ActionStatePose {
         if self.ty != xr::ActionStatePose::TYPE {
             return Err(xr::Result::ERROR_VALIDATION_FAILURE);
         }
-        if self.next != ptr::null_mut() {
+        if !self.next.is_null() {
             return Err(xr::Result::ERROR_VALIDATION_FAILURE);
         }
-        return Ok(());
+        Ok(())
     }
 }
\ No newline at end of file
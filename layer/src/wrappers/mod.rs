@@ -1,25 +1,37 @@
 pub mod space;
 pub mod session;
 
+use common::application_bindings::ApplicationBindings;
+use common::config::Settings;
+use common::interaction_profiles::{self, InteractionProfile};
 use common::xrapplication_info::ActionType;
 use dashmap::DashMap;
-use once_cell::sync::OnceCell;
+use dashmap::mapref::entry::Entry;
+use once_cell::sync::{Lazy, OnceCell};
 use openxr::Result;
 use openxr::sys as xr;
 use openxr::sys::pfn as pfn;
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::ffi::CString;
+use std::fmt::Debug;
+use std::hash::Hash;
 use std::ops::Add;
 use std::ptr;
+use std::sync::Mutex;
+use std::sync::Once;
 use std::sync::RwLock;
 use std::sync::Weak;
 use std::sync::Arc;
 
 use crate::god_actions::CachedActionStatesEnum;
+use crate::god_actions::GodAction;
+use crate::god_actions::GodActionSet;
 use crate::god_actions::OutputBinding;
 use crate::god_actions::InputBinding;
 use crate::god_actions::SubactionBindings;
+use crate::remap::{RemappedBinding, RemappedOutput};
 use crate::util;
 
 pub use self::space::*;
@@ -34,8 +46,10 @@ static ACTIONS:     OnceCell<HandleMap<xr::Action, ActionWrapper>> = OnceCell::n
 static ACTION_SETS: OnceCell<HandleMap<xr::ActionSet, ActionSetWrapper>> = OnceCell::new();
 static SPACES:      OnceCell<HandleMap<xr::Space, SpaceWrapper>> = OnceCell::new();
 
-pub unsafe fn static_init() {
-    if INSTANCES.get().is_none() {
+///Sets up the wrapper maps, only the first call does anything so loaders that negotiate more than once keep every live handle
+pub fn static_init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
         #[cfg(feature = "vscode_dbg")]
         if let Some(vscode) = option_env!("VSCODE_GIT_ASKPASS_NODE") {
             let url = format!("vscode://vadimcn.vscode-lldb/launch/config?{{'request':'attach','pid':{}}}", std::process::id());
@@ -43,12 +57,12 @@ pub unsafe fn static_init() {
             std::thread::sleep(std::time::Duration::from_millis(2000)); // Wait for debugger to attach
         }
 
-        INSTANCES.set(DashMap::new()).unwrap();
-        SESSIONS.set(DashMap::new()).map_err(|_| {}).unwrap();
-        ACTIONS.set(DashMap::new()).unwrap();
-        ACTION_SETS.set(DashMap::new()).unwrap();
-        SPACES.set(DashMap::new()).map_err(|_| {}).unwrap();
-    }
+        INSTANCES.get_or_init(DashMap::new);
+        SESSIONS.get_or_init(DashMap::new);
+        ACTIONS.get_or_init(DashMap::new);
+        ACTION_SETS.get_or_init(DashMap::new);
+        SPACES.get_or_init(DashMap::new);
+    });
 }
 
 #[allow(invalid_value)]
@@ -61,6 +75,26 @@ unsafe fn _assert_thread_safe() {
     let _: &T = &std::mem::zeroed::<SpaceWrapper>();
 }
 
+///Adds a freshly created handle to its wrapper map
+///
+///Handles are unique, so one that is already registered means the runtime handed it out twice. The runtime will only answer for the newer object
+///so its wrapper replaces the old one, the returned warning says so
+pub fn register_handle<H, T>(map: &HandleMap<H, T>, handle: H, wrapper: Arc<T>) -> Option<String>
+where
+    H: Copy + Eq + Hash + Debug,
+{
+    match map.entry(handle) {
+        Entry::Occupied(mut entry) => {
+            entry.insert(wrapper);
+            Some(format!("the runtime reused {:?} while it was still alive, replacing its wrapper", handle))
+        }
+        Entry::Vacant(entry) => {
+            entry.insert(wrapper);
+            None
+        }
+    }
+}
+
 pub fn instances() -> &'static HandleMap<xr::Instance, InstanceWrapper> {
     INSTANCES.get().unwrap()
 }
@@ -86,7 +120,28 @@ pub struct InstanceWrapper {
     pub sessions: RwLock<Vec<Arc<SessionWrapper>>>,
     pub action_sets: RwLock<Vec<Arc<ActionSetWrapper>>>,
 
-    pub god_action_sets: HashMap<xr::Path, crate::god_actions::GodActionSet>,
+    ///Set when the instance is created, or at the first attach when [Settings::defer_god_sets] is on, read through [InstanceWrapper::god_action_sets]
+    pub god_action_sets: OnceCell<HashMap<xr::Path, GodActionSet>>,
+    ///The set for the [emulated](crate::emulated) profile when OXIDEXR_EMULATE_INPUT is set, kept out of `god_action_sets` as the runtime never sees it
+    pub emulated_set: Option<crate::god_actions::GodActionSet>,
+    ///This instance's own source for the emulated set, read when none was installed with [crate::set_host_input]
+    pub host_input: Mutex<Option<Box<dyn crate::emulated::HostInput>>>,
+
+    ///The user's custom bindings for this application, these override the application's suggested bindings
+    pub custom_bindings: ApplicationBindings,
+
+    ///Profiles the application suggested bindings for that aren't in the catalog, with how many bindings were passed through to the runtime
+    pub unknown_profiles: RwLock<BTreeMap<String, usize>>,
+
+    ///The application's suggested bindings on paths outside [Settings::managed_user_paths] (and on managed ones when
+    ///[InstanceWrapper::forwards_suggestions]), by profile, these go to the runtime as they are
+    pub forwarded_suggestions: RwLock<HashMap<xr::Path, Vec<xr::ActionSuggestedBinding>>>,
+
+    ///Layer wide settings, read once when the instance is created
+    pub settings: Settings,
+
+    ///The instance extensions the application enabled, these decide which optional subpaths (e.g. /input/grip_surface) exist
+    pub enabled_extensions: Vec<String>,
 
     pub application_name: String,
     pub application_version: u32,
@@ -94,8 +149,12 @@ pub struct InstanceWrapper {
     pub engine_version: u32,
 
     pub core: openxr::raw::Instance,
+    #[allow(dead_code)] //Loaded with the core functions, nothing calls an extension function yet
     pub exts: openxr::InstanceExtensions,
 
+    ///Set when the application isn't activated (see [common::config::Activation]), the layer then forwards every call untouched
+    pub passthrough: bool,
+
     pub get_instance_proc_addr_next: pfn::GetInstanceProcAddr,
 }
 
@@ -133,7 +192,122 @@ impl std::fmt::Debug for InstanceWrapper {
     }
 }
 
+///Short enough for one log line, the handle and everything else are in [Debug]
+impl std::fmt::Display for InstanceWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Instance({:?} v{} on {:?})", self.application_name, self.application_version, self.engine_name)
+    }
+}
+
+impl std::fmt::Display for ActionSetWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ActionSet({:?})", self.name)
+    }
+}
+
+///Named `action_set/action` as in custom bindings
+impl std::fmt::Display for ActionWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.action_set.upgrade() {
+            Some(action_set) => write!(f, "Action({:?}: {:?})", format!("{}/{}", action_set.name, self.name), self.action_type),
+            None => write!(f, "Action({:?}: {:?})", self.name, self.action_type),
+        }
+    }
+}
+
+static NO_GOD_ACTION_SETS: Lazy<HashMap<xr::Path, GodActionSet>> = Lazy::new(HashMap::new);
+
 impl InstanceWrapper {
+    ///The god action set of every profile, empty until they are created
+    pub fn god_action_sets(&self) -> &HashMap<xr::Path, GodActionSet> {
+        self.god_action_sets.get().unwrap_or(&NO_GOD_ACTION_SETS)
+    }
+
+    ///The god action sets sorted by profile, the order they are attached and synced in so runtimes and logs always see them the same way
+    pub fn god_action_sets_in_order(&self) -> Vec<&GodActionSet> {
+        let mut god_sets = self.god_action_sets().values().collect::<Vec<_>>();
+        god_sets.sort_by(|a, b| a.name.cmp(&b.name));
+        god_sets
+    }
+
+    ///The god action with this runtime handle, from any god set
+    pub fn god_action(&self, handle: xr::Action) -> Option<Arc<GodAction>> {
+        self.god_action_sets()
+            .values()
+            .flat_map(|god_set| god_set.input_actions.values().chain(god_set.output_actions.values()))
+            .find(|god_action| god_action.handle == handle)
+            .cloned()
+    }
+
+    ///[GodActionSet::describe] for every god set, the emulated one included, keyed by profile
+    pub fn describe_god_actions(&self) -> BTreeMap<String, Vec<(String, ActionType, Vec<String>)>> {
+        self.god_action_sets()
+            .values()
+            .chain(self.emulated_set.iter())
+            .map(|god_set| (god_set.name.clone(), god_set.describe(self)))
+            .collect()
+    }
+
+    ///The catalog's description of `profile` as the god sets see it, `None` for profiles the catalog doesn't have
+    ///
+    ///Before deferred god sets are created this comes straight from the catalog
+    pub fn profile_info(&self, profile: xr::Path) -> Option<Arc<InteractionProfile>> {
+        if let Some(god_set) = self.god_action_sets().get(&profile) {
+            return Some(god_set.profile_info.clone());
+        }
+        if self.god_action_sets.get().is_some() || !self.settings.defer_god_sets {
+            return None;
+        }
+        let profile_info = interaction_profiles::profile(&self.path_to_string(profile).ok()?)?;
+        Some(Arc::new(profile_info.with_extensions(&self.enabled_extensions)))
+    }
+
+    ///The catalog profiles the application suggested bindings for
+    pub fn suggested_profiles(&self) -> Vec<xr::Path> {
+        let mut suggested = self
+            .action_sets
+            .read()
+            .unwrap()
+            .iter()
+            .flat_map(|action_set| {
+                action_set
+                    .actions
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .flat_map(|action| action.bindings.read().unwrap().keys().copied().collect::<Vec<_>>())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        suggested.sort_by_key(|profile| profile.into_raw());
+        suggested.dedup();
+        suggested
+    }
+
+    ///The catalog profiles the application suggested bindings for or the user has custom bindings for, what deferred god sets are created for
+    pub fn used_profiles(&self) -> Vec<xr::Path> {
+        let catalog = interaction_profiles::catalog();
+        let mut used = self
+            .suggested_profiles()
+            .into_iter()
+            .chain(
+                self.custom_bindings
+                    .profiles
+                    .keys()
+                    .filter(|profile_name| catalog.profiles.contains_key(profile_name.as_str()))
+                    .filter_map(|profile_name| self.string_to_path(profile_name).ok()),
+            )
+            .collect::<Vec<_>>();
+        used.sort_by_key(|profile| profile.into_raw());
+        used.dedup();
+        used
+    }
+
+    ///Whether the application's suggested bindings reach the runtime as well as the layer, see [Settings::forward_suggestions](common::config::Settings::forward_suggestions)
+    pub fn forwards_suggestions(&self) -> bool {
+        self.settings.forward_suggestions.unwrap_or(self.passthrough)
+    }
+
     #[inline]
     pub fn create_session(
         &self,
@@ -171,8 +345,9 @@ impl InstanceWrapper {
         &self,
         path_string: &str,
     ) -> openxr::Result<xr::Path> {
+        //Strings from corrupt config files can hold interior NULs which can't be handed to the runtime
+        let str = CString::new(path_string).map_err(|_| xr::Result::ERROR_PATH_FORMAT_INVALID)?;
         unsafe {
-            let str = CString::new(path_string).unwrap();
             let mut path = xr::Path::NULL;
             let result = (self.core.string_to_path)(self.handle, str.as_ptr(), &mut path);
             if result.into_raw() < 0 {
@@ -232,24 +407,38 @@ impl InstanceWrapper {
         })
     }
 
+    ///The runtime's name and version
+    pub fn instance_properties(&self) -> Result<(String, xr::Version)> {
+        let mut properties = xr::InstanceProperties::out(ptr::null_mut());
+        let result = unsafe { (self.core.get_instance_properties)(self.handle, properties.as_mut_ptr()) };
+        //XrInstanceProperties is a fixed size struct so a single call fills it
+        let properties = unsafe { util::check2(result, properties)?.assume_init() };
+        Ok((util::char_arr_to_string_lossy(&properties.runtime_name), properties.runtime_version))
+    }
+
+    ///The string of `path`, empty for [xr::Path::NULL] or when the runtime reports a zero length
     pub fn path_to_string(
         &self, 
         path: xr::Path,
     ) -> Result<String, xr::Result> {
+        if path == xr::Path::NULL {
+            return Ok(String::new());
+        }
         unsafe {
             let mut string = String::new();
 
             let mut len = 0;
             let result = (self.core.path_to_string)(self.handle, path, 0, &mut len, std::ptr::null_mut());
             if result.into_raw() < 0 { return Err(result); }
+            //The length counts the terminator, a runtime reporting none at all has nothing to give
+            if len == 0 { return Ok(string); }
             
-            let mut buffer = Vec::<i8>::with_capacity(len as usize);
-            buffer.set_len(len as usize);
-    
+            let mut buffer = vec![0i8; len as usize];
+
             let result = (self.core.path_to_string)(self.handle, path, len, &mut len, buffer.as_mut_ptr());
             if result.into_raw() < 0 { return Err(result); }
 
-            let slice = std::str::from_utf8(std::mem::transmute(&buffer[..len as usize - 1])).unwrap();
+            let slice = std::str::from_utf8(std::mem::transmute::<&[i8], &[u8]>(&buffer[..(len as usize).saturating_sub(1)])).unwrap();
             string.clear();
             string.reserve(slice.len());
             string.insert_str(0, slice);
@@ -258,6 +447,14 @@ impl InstanceWrapper {
         }
     }
 
+    ///[InstanceWrapper::path_to_string] for paths that other paths or names are built from, where an empty string is as good as invalid
+    pub fn path_to_nonempty_string(&self, path: xr::Path) -> Result<String, xr::Result> {
+        match self.path_to_string(path)? {
+            string if string.is_empty() => Err(xr::Result::ERROR_PATH_INVALID),
+            string => Ok(string),
+        }
+    }
+
     pub fn from_handle_panic<'a>(handle: xr::Instance) -> HandleRef<'a, xr::Instance, InstanceWrapper> {
         INSTANCES.get().unwrap().get(&handle).unwrap()
     }
@@ -280,6 +477,19 @@ impl ActionSetWrapper {
         self.instance.upgrade().unwrap().clone()
     }
 
+    ///Whether the application has attached this set to any of its sessions
+    ///
+    ///The runtime only ever sees the god action sets attached so it can't be relied on to enforce this
+    pub fn is_attached(&self) -> bool {
+        self.instance().sessions.read().unwrap().iter().any(|session| {
+            session
+                .input_bindings
+                .get()
+                .filter(|input_bindings| input_bindings.contains_key(&self.handle))
+                .is_some()
+        })
+    }
+
     pub fn from_handle_panic<'a>(handle: xr::ActionSet) -> HandleRef<'a, xr::ActionSet, ActionSetWrapper> {
         ACTION_SETS.get().unwrap().get(&handle).unwrap()
     }
@@ -294,6 +504,11 @@ impl ActionWrapper {
     pub fn from_handle_panic<'a>(handle: xr::Action) -> HandleRef<'a, xr::Action, ActionWrapper> {
         ACTIONS.get().unwrap().get(&handle).unwrap()
     }
+
+    ///Actions of a type the layer doesn't know (e.g. one added by an extension newer than it) have nothing to remap, calls on them go straight to the runtime
+    pub fn is_passthrough(&self) -> bool {
+        self.action_type == ActionType::Unknown
+    }
 }
 
 pub trait HandleWrapper {
@@ -304,6 +519,23 @@ pub trait HandleWrapper {
     fn from_handle<'a>(handle: Self::HandleType) -> Option<HandleRef<'a, Self::HandleType, Self>> where Self: 'static {
         HandleWrapper::all_handles().get(&handle)
     }
+
+    ///Every live wrapper of this type, cloned out of the map so none of its shards stay locked while they are used
+    fn all_wrappers() -> Vec<Arc<Self>> where Self: Sized + 'static {
+        Self::all_handles().iter().map(|entry| entry.value().clone()).collect()
+    }
+}
+
+///Calls `f` with every live instance, see [HandleWrapper::all_wrappers]
+///
+///`f` may create or destroy handles, instances created meanwhile are not visited
+pub fn for_each_instance<F: FnMut(&Arc<InstanceWrapper>)>(f: F) {
+    InstanceWrapper::all_wrappers().iter().for_each(f)
+}
+
+///Calls `f` with every live session, see [for_each_instance]
+pub fn for_each_session<F: FnMut(&Arc<SessionWrapper>)>(f: F) {
+    SessionWrapper::all_wrappers().iter().for_each(f)
 }
 
 impl HandleWrapper for InstanceWrapper {
@@ -380,4 +612,262 @@ impl WrappedHandle for xr::Action {
 
 impl WrappedHandle for xr::Space {
     type Wrapper = SpaceWrapper;
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+const ROUND_TRIP_PATHS: &[&str] = &[
+    "/user/hand/left",
+    "/user/hand/right",
+    "/user/hand/left/input/trigger/value",
+    "/user/hand/right/input/trigger/value",
+    "/user/hand/left/input/thumbstick",
+    "/user/hand/right/input/a/click",
+    "/user/hand/left/input/grip/pose",
+    "/user/hand/right/output/haptic",
+    "/interaction_profiles/khr/simple_controller",
+    "/interaction_profiles/valve/index_controller",
+];
+
+///Goes straight to the runtime, what any caching in front of `string_to_path` and `path_to_string` has to agree with
+#[cfg(test)]
+fn uncached_string_to_path(instance: &InstanceWrapper, path_string: &str) -> xr::Path {
+    let path_string = CString::new(path_string).unwrap();
+    let mut path = xr::Path::NULL;
+    assert_eq!(
+        unsafe { (instance.core.string_to_path)(instance.handle, path_string.as_ptr(), &mut path) },
+        xr::Result::SUCCESS
+    );
+    path
+}
+
+#[test]
+fn test_path_round_trip() {
+    let instance = crate::fake_runtime::create_instance();
+
+    for path_string in ROUND_TRIP_PATHS {
+        let path = instance.string_to_path(path_string).unwrap();
+        assert_eq!(path, uncached_string_to_path(&instance, path_string));
+        assert_eq!(&instance.path_to_string(path).unwrap(), path_string);
+        assert_eq!(instance.string_to_path(path_string).unwrap(), path);
+    }
+
+    //New paths are interned while earlier ones are still being looked up
+    let mut seen = Vec::new();
+    for (i, path_string) in ROUND_TRIP_PATHS.iter().enumerate() {
+        let interleaved = format!("{}/round_trip_{}", path_string, i);
+        seen.push((instance.string_to_path(&interleaved).unwrap(), interleaved));
+        for (path, path_string) in &seen {
+            assert_eq!(&instance.path_to_string(*path).unwrap(), path_string);
+            assert_eq!(instance.string_to_path(path_string).unwrap(), *path);
+        }
+    }
+
+    assert_eq!(instance.path_to_string(xr::Path::from_raw(u64::MAX)), Err(xr::Result::ERROR_PATH_INVALID));
+}
+
+#[test]
+fn test_zero_length_path() {
+    use crate::fake_runtime;
+    use crate::injections::instance::suggest_interaction_profile_bindings;
+
+    let instance = fake_runtime::create_instance();
+    assert_eq!(instance.path_to_string(fake_runtime::zero_length_path()), Ok(String::new()));
+    assert_eq!(instance.path_to_string(xr::Path::NULL), Ok(String::new()));
+    assert_eq!(instance.path_to_nonempty_string(fake_runtime::zero_length_path()), Err(xr::Result::ERROR_PATH_INVALID));
+    assert_eq!(instance.path_to_nonempty_string(xr::Path::NULL), Err(xr::Result::ERROR_PATH_INVALID));
+
+    //Suggesting for a profile without a name is rejected rather than treated as an unknown profile
+    let bindings: [xr::ActionSuggestedBinding; 0] = [];
+    let suggested_bindings = xr::InteractionProfileSuggestedBinding {
+        ty: xr::InteractionProfileSuggestedBinding::TYPE,
+        next: ptr::null(),
+        interaction_profile: fake_runtime::zero_length_path(),
+        count_suggested_bindings: 0,
+        suggested_bindings: bindings.as_ptr(),
+    };
+    assert_eq!(
+        unsafe { suggest_interaction_profile_bindings(instance.handle, &suggested_bindings) },
+        xr::Result::ERROR_PATH_INVALID
+    );
+    assert!(instance.unknown_profiles.read().unwrap().is_empty());
+}
+
+#[test]
+fn test_interior_nul_path() {
+    use common::application_bindings::Binding;
+
+    use crate::fake_runtime;
+    use crate::remap;
+
+    let instance = fake_runtime::create_instance();
+    assert_eq!(
+        instance.string_to_path("/user/hand/left\0/input/select/click"),
+        Err(xr::Result::ERROR_PATH_FORMAT_INVALID)
+    );
+
+    //A corrupt custom binding is skipped, the rest of the action's bindings still apply
+    let mut custom_bindings = ApplicationBindings::default();
+    custom_bindings
+        .profiles
+        .entry("/interaction_profiles/khr/simple_controller".to_owned())
+        .or_default()
+        .action_sets
+        .entry("gameplay".to_owned())
+        .or_default()
+        .actions
+        .entry("jump".to_owned())
+        .or_default()
+        .bindings = vec![
+        Binding::new("/user/hand/left/input/select\0/click".to_owned()),
+        Binding::new("/user/hand/right/input/select/click".to_owned()),
+    ];
+    let instance = fake_runtime::create_instance_with_bindings(custom_bindings);
+    let session = fake_runtime::new_session(&instance).get_wrapper().unwrap().clone();
+    let (_, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("jump", xr::ActionType::BOOLEAN_INPUT)]);
+
+    let remapped = remap::resolve_input_bindings(&instance, &actions[0].get_wrapper().unwrap(), session.god_states());
+    assert_eq!(remapped.len(), 1);
+    assert_eq!(remapped[0].binding_str, "/user/hand/right/input/select/click");
+}
+
+#[test]
+fn test_path_round_trip_threads() {
+    let instance = crate::fake_runtime::create_instance();
+    let expected = ROUND_TRIP_PATHS
+        .iter()
+        .map(|path_string| (instance.string_to_path(path_string).unwrap(), path_string.to_string()))
+        .collect::<Vec<_>>();
+
+    let threads = (0..8)
+        .map(|thread| {
+            let instance = instance.clone();
+            let expected = expected.clone();
+            std::thread::spawn(move || {
+                for i in 0..200 {
+                    let (path, path_string) = &expected[(thread + i) % expected.len()];
+                    assert_eq!(instance.string_to_path(path_string).unwrap(), *path);
+                    assert_eq!(&instance.path_to_string(*path).unwrap(), path_string);
+
+                    //Every thread interns the same new paths so they race to be first
+                    let shared = format!("/user/hand/left/input/thread_race_{}", i % 16);
+                    let shared_path = instance.string_to_path(&shared).unwrap();
+                    assert_eq!(instance.path_to_string(shared_path).unwrap(), shared);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    for (path, path_string) in &expected {
+        assert_eq!(&instance.path_to_string(*path).unwrap(), path_string);
+    }
+    for i in 0..16 {
+        let shared = format!("/user/hand/left/input/thread_race_{}", i);
+        assert_eq!(instance.string_to_path(&shared).unwrap(), uncached_string_to_path(&instance, &shared));
+    }
+}
+
+#[test]
+fn test_instance_properties() {
+    let instance = crate::fake_runtime::create_instance();
+    let (runtime_name, runtime_version) = instance.instance_properties().unwrap();
+    assert_eq!(runtime_name, crate::fake_runtime::RUNTIME_NAME);
+    assert_eq!(runtime_version, crate::fake_runtime::RUNTIME_VERSION);
+}
+
+#[test]
+fn test_register_duplicate_handle() {
+    let instance = crate::fake_runtime::create_instance();
+    let action_set = |name: &str| {
+        Arc::new(ActionSetWrapper {
+            handle: xr::ActionSet::from_raw(143),
+            instance: Arc::downgrade(&instance),
+            actions: RwLock::new(Vec::new()),
+            name: String::from(name),
+            localized_name: String::from(name),
+            priority: 0,
+        })
+    };
+
+    assert_eq!(register_handle(action_sets(), xr::ActionSet::from_raw(143), action_set("first")), None);
+    let warning = register_handle(action_sets(), xr::ActionSet::from_raw(143), action_set("second")).unwrap();
+    assert!(warning.contains(&format!("{:?}", xr::ActionSet::from_raw(143))));
+    //The runtime only knows the handle as the newer object
+    assert_eq!(xr::ActionSet::from_raw(143).get_wrapper().unwrap().name, "second");
+    action_sets().remove(&xr::ActionSet::from_raw(143));
+}
+
+#[test]
+fn test_for_each_instance() {
+    use crate::fake_runtime;
+
+    let first = fake_runtime::create_instance();
+    let second = fake_runtime::create_instance();
+
+    //Other tests create instances alongside this one, only ours are checked
+    let mut visited = Vec::new();
+    for_each_instance(|instance| {
+        //Nothing is locked while visiting, so looking handles up again can't deadlock
+        assert!(instance.handle.get_wrapper().is_some());
+        visited.push(instance.handle);
+    });
+    assert_eq!(visited.iter().filter(|handle| **handle == first.handle).count(), 1);
+    assert_eq!(visited.iter().filter(|handle| **handle == second.handle).count(), 1);
+
+    let session = fake_runtime::new_session(&first);
+    let mut sessions = 0;
+    for_each_session(|wrapper| {
+        if wrapper.handle == session {
+            sessions += 1;
+            //Destroying from the callback is fine too, the wrapper stays alive until it returns
+            assert_eq!(unsafe { crate::injections::destroy_session(session) }, xr::Result::SUCCESS);
+        }
+    });
+    assert_eq!(sessions, 1);
+    assert!(session.get_wrapper().is_none());
+}
+
+#[test]
+fn test_display() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    assert_eq!(instance.to_string(), r#"Instance("fake_application" v0 on "fake_engine")"#);
+
+    let session = fake_runtime::new_session(&instance);
+    let left = instance.string_to_path("/user/hand/left").unwrap();
+    let action_set = fake_runtime::new_action_set(&instance, "gameplay");
+    let action = fake_runtime::new_action(action_set, "hand", xr::ActionType::POSE_INPUT, &[left]);
+
+    let session = session.get_wrapper().unwrap().clone();
+    assert_eq!(session.to_string(), format!(r#"Session({:#x} of "fake_application")"#, session.handle.into_raw()));
+    assert_eq!(action_set.get_wrapper().unwrap().to_string(), r#"ActionSet("gameplay")"#);
+    let action = action.get_wrapper().unwrap().clone();
+    assert_eq!(action.to_string(), r#"Action("gameplay/hand": PoseInput)"#);
+
+    let space = |ty, handle| SpaceWrapper {
+        unchecked_handle: xr::Space::from_raw(handle),
+        session: Arc::downgrade(&session),
+        ty,
+    };
+    let action_space = |subaction_path| {
+        SpaceType::ACTION(Arc::new(ActionSpace {
+            action: action.clone(),
+            subaction_path,
+            pose_in_action_space: xr::Posef {
+                orientation: xr::Quaternionf { x: 0f32, y: 0f32, z: 0f32, w: 1f32 },
+                position: xr::Vector3f { x: 0f32, y: 0f32, z: 0f32 },
+            },
+            sync_idx: RwLock::new(0),
+            cur_binding: RwLock::new(None),
+        }))
+    };
+    assert_eq!(space(SpaceType::REFERENCE, 0x2a).to_string(), "Space(reference 0x2a)");
+    assert_eq!(space(action_space(left), 0x2b).to_string(), r#"Space(action "gameplay/hand" /user/hand/left)"#);
+    assert_eq!(space(action_space(xr::Path::NULL), 0x2c).to_string(), r#"Space(action "gameplay/hand")"#);
+
+    //Debug keeps the full detail
+    assert!(format!("{:?}", instance).contains(&format!("{:?}", instance.handle)));
+}
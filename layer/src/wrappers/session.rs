@@ -1,37 +1,52 @@
+use std::collections::HashSet;
 use std::sync::Weak;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 
 use openxr::sys as xr;
 
 use crate::god_actions;
+use crate::god_actions::{Binding, GodActionStateEnum};
 use crate::path::*;
+use crate::remap;
+use common::application_bindings::{self, ActionBindings, SourceCondition, Transform};
+use common::interaction_profiles;
+use common::xrapplication_info::BindingInfo;
 
 use super::*;
 
+///Locks held together are always taken in this order, never while holding a later one:
+///1. a [SubactionBindings] of `input_bindings`
+///2. a god state (an `action_state` of `god_states`) or the transform memory of a [RemappedBinding], each held only while it is read or written
+///3. a state of `cached_action_states`
+///
+///xrSyncActions writes every god state before any cached state is locked and samples an action's god states before taking its cached state,
+///so nothing waits on a god state while holding a cached state an xrGetActionState* call is waiting on
 #[derive(Default)]
 pub struct SessionWrapper {
     pub handle: xr::Session,
     pub instance: Weak<InstanceWrapper>,
     pub spaces: RwLock<Vec<Arc<SpaceWrapper>>>,
 
-    ///Every input binding and its cached state (updated every sync call)
-    pub god_states: HashMap<
-        xr::Path, /* interactionProfile */
-        HashMap<xr::Path /* binding */, Arc<InputBinding>>,
-    >,
+    ///Every input binding and its cached state (updated every sync call), set once the god action sets are attached
+    pub god_states: OnceCell<GodStates>,
 
     ///Every output binding (is this needed?)
-    pub god_outputs: HashMap<
-        xr::Path, /* interactionProfile */
-        HashMap<xr::Path /* binding */, Arc<OutputBinding>>,
-    >,
+    pub god_outputs: OnceCell<GodOutputs>,
+
+    ///The god inputs at [Settings::reset_source](common::config::Settings::reset_source) on every profile that has one, set along with `god_states`
+    pub reset_sources: OnceCell<Vec<Arc<InputBinding>>>,
+
+    ///Whether a reset source was held at the last sync, so holding one resets only once
+    pub reset_held: AtomicBool,
 
     ///The bindings for each attached input action
-    pub input_bindings: OnceCell<
-        HashMap<xr::ActionSet, HashMap<xr::Action, RwLock<SubactionBindings<InputBinding>>>>,
-    >,
+    pub input_bindings: OnceCell<AttachedActions>,
 
     ///The bindings for each attached output action
-    pub output_bindings: OnceCell<HashMap<xr::Action, RwLock<SubactionBindings<OutputBinding>>>>,
+    pub output_bindings: OnceCell<HashMap<xr::Action, RwLock<SubactionBindings<RemappedOutput>>>>,
+
+    ///The attached application actions the runtime reads itself by action set, see [remap::is_left_to_runtime]
+    pub runtime_actions: OnceCell<HashMap<xr::ActionSet, HashSet<xr::Action>>>,
 
     ///The cached state of the attached application actions (updated every sync call)
     pub cached_action_states: OnceCell<HashMap<xr::Action, RwLock<CachedActionStatesEnum>>>,
@@ -42,6 +57,41 @@ pub struct SessionWrapper {
     pub active_profiles: HashMap<TopLevelUserPath, RwLock<InteractionProfilePath>>,
 
     pub sync_idx: RwLock<u64>,
+
+    ///The predictedDisplayTime returned by the latest xrWaitFrame, zero until the application first calls it
+    pub predicted_display_time: AtomicI64,
+
+    ///The remap pass timings of this session's xrSyncActions calls, see [crate::metrics::sync_timing] for every session together
+    #[cfg(any(test, feature = "sync_timing"))]
+    pub sync_timing: std::sync::Mutex<crate::metrics::SyncTiming>,
+}
+
+pub type GodStates = HashMap<
+    xr::Path, /* interactionProfile */
+    HashMap<xr::Path /* binding */, Arc<InputBinding>>,
+>;
+
+pub type GodOutputs = HashMap<
+    xr::Path, /* interactionProfile */
+    HashMap<xr::Path /* binding */, Arc<OutputBinding>>,
+>;
+
+pub type AttachedActions = HashMap<
+    xr::ActionSet,
+    HashMap<xr::Action, RwLock<SubactionBindings<RemappedBinding>>>,
+>;
+
+static NO_GOD_STATES: Lazy<GodStates> = Lazy::new(HashMap::new);
+static NO_GOD_OUTPUTS: Lazy<GodOutputs> = Lazy::new(HashMap::new);
+
+///Sessions have no name, so the handle tells them apart
+impl std::fmt::Display for SessionWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.instance.upgrade() {
+            Some(instance) => write!(f, "Session({:#x} of {:?})", self.handle.into_raw(), instance.application_name),
+            None => write!(f, "Session({:#x})", self.handle.into_raw()),
+        }
+    }
 }
 
 impl SessionWrapper {
@@ -71,69 +121,102 @@ impl SessionWrapper {
             );
         }
 
-        //Create session specific input / output states for each god action
-        for (profile_name, god_action_set) in &instance.god_action_sets {
-            let states = match wrapper.god_states.get_mut(profile_name) {
-                Some(states) => states,
-                None => {
-                    wrapper.god_states.insert(*profile_name, HashMap::new());
-                    wrapper.god_states.get_mut(profile_name).unwrap()
-                }
-            };
-            let outputs = match wrapper.god_outputs.get_mut(profile_name) {
-                Some(states) => states,
-                None => {
-                    wrapper.god_outputs.insert(*profile_name, HashMap::new());
-                    wrapper.god_outputs.get_mut(profile_name).unwrap()
+        //Deferred god sets, and god sets sharing the runtime with application actions on unmanaged paths, are attached along with the application's sets instead
+        if !instance.settings.defer_god_sets && instance.settings.manages_every_user_path() {
+            wrapper.attach_god_action_sets(instance, &[])?;
+        }
+
+        Ok(wrapper)
+    }
+
+    ///Every input binding of the god actions, empty until the god action sets are attached
+    pub fn god_states(&self) -> &GodStates {
+        self.god_states.get().unwrap_or(&NO_GOD_STATES)
+    }
+
+    pub fn god_outputs(&self) -> &GodOutputs {
+        self.god_outputs.get().unwrap_or(&NO_GOD_OUTPUTS)
+    }
+
+    ///Whether the runtime reads this application action itself, see [remap::is_left_to_runtime]
+    pub fn leaves_to_runtime(&self, action: xr::Action) -> bool {
+        self.runtime_actions.get().into_iter().flat_map(HashMap::values).any(|actions| actions.contains(&action))
+    }
+
+    ///Builds the god states for the instance's god action sets and attaches the sets to this session, this can only succeed once
+    ///
+    ///`runtime_sets` are application sets with actions left to the runtime, they are attached in the same call as a session only gets one
+    pub fn attach_god_action_sets(&self, instance: &InstanceWrapper, runtime_sets: &[xr::ActionSet]) -> Result<()> {
+        if self.god_states.get().is_some() {
+            return Err(xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED);
+        }
+
+        let mut god_states = GodStates::new();
+        let mut god_outputs = GodOutputs::new();
+
+        let emulated_set = match &instance.emulated_set {
+            Some(emulated_set) => Some((instance.string_to_path(&emulated_set.name)?, emulated_set)),
+            None => None,
+        };
+
+        //Create session specific input / output states for each god action, emulated ones included so custom bindings can use them
+        for (profile_name, god_action_set) in instance
+            .god_action_sets()
+            .iter()
+            .map(|(profile_name, god_action_set)| (*profile_name, god_action_set))
+            .chain(emulated_set)
+        {
+            let states = god_states.entry(profile_name).or_default();
+            for god_action in god_action_set.input_actions.values() {
+                for subaction_path in &god_action.subaction_paths {
+                    let name = instance
+                        .path_to_nonempty_string(*subaction_path)?
+                        .add(&god_action.name);
+                    #[cfg(any(test, feature = "strict"))]
+                    println!("{}", &name);
+
+                    states.insert(
+                        instance.string_to_path(&name)?,
+                        Arc::new(god_actions::InputBinding {
+                            action: god_action.clone(),
+                            binding_str: name,
+                            subaction_path: *subaction_path,
+                            action_state: RwLock::new(
+                                god_actions::GodActionStateEnum::new(god_action.action_type)
+                                    .unwrap(),
+                            ),
+                        }),
+                    );
                 }
-            };
+            }
 
-            for god_action in god_action_set.god_actions.values() {
-                if god_action.action_type.is_input() {
-                    for subaction_path in &god_action.subaction_paths {
-                        let name = instance
-                            .path_to_string(*subaction_path)?
-                            .add(&god_action.name);
-                        println!("{}", &name);
-
-                        states.insert(
-                            instance.string_to_path(&name)?,
-                            Arc::new(god_actions::InputBinding {
-                                action: god_action.clone(),
-                                binding_str: name,
-                                subaction_path: *subaction_path,
-                                action_state: RwLock::new(
-                                    god_actions::GodActionStateEnum::new(god_action.action_type)
-                                        .unwrap(),
-                                ),
-                            }),
-                        );
-                    }
-                } else {
-                    for subaction_path in &god_action.subaction_paths {
-                        let name = instance
-                            .path_to_string(*subaction_path)?
-                            .add(&god_action.name);
-                        println!("{}", &name);
-
-                        outputs.insert(
-                            instance.string_to_path(&name)?,
-                            Arc::new(god_actions::OutputBinding {
-                                action: god_action.clone(),
-                                binding_str: name,
-                                subaction_path: *subaction_path,
-                            }),
-                        );
-                    }
+            let outputs = god_outputs.entry(profile_name).or_default();
+            for god_action in god_action_set.output_actions.values() {
+                for subaction_path in &god_action.subaction_paths {
+                    let name = instance
+                        .path_to_nonempty_string(*subaction_path)?
+                        .add(&god_action.name);
+                    #[cfg(any(test, feature = "strict"))]
+                    println!("{}", &name);
+
+                    outputs.insert(
+                        instance.string_to_path(&name)?,
+                        Arc::new(god_actions::OutputBinding {
+                            action: god_action.clone(),
+                            binding_str: name,
+                            subaction_path: *subaction_path,
+                        }),
+                    );
                 }
             }
         }
 
         //Attach the god action sets to the session
         let god_action_sets = instance
-            .god_action_sets
-            .values()
+            .god_action_sets_in_order()
+            .into_iter()
             .map(|container| container.handle)
+            .chain(runtime_sets.iter().copied())
             .collect::<Vec<_>>();
 
         let attach_info = xr::SessionActionSetsAttachInfo {
@@ -143,14 +226,27 @@ impl SessionWrapper {
             action_sets: god_action_sets.as_ptr(),
         };
 
-        let result = wrapper.attach_session_action_sets(&attach_info);
+        let result = self.attach_session_action_sets(&attach_info);
 
         if result.into_raw() < 0 {
             println!("attach_session_action_sets {}", result);
             return Err(result);
         }
 
-        Ok(wrapper)
+        let mut reset_sources = Vec::new();
+        if let Some(reset_source) = &instance.settings.reset_source {
+            let path = instance.string_to_path(reset_source)?;
+            reset_sources.extend(god_states.values().filter_map(|states| states.get(&path)).cloned());
+            if reset_sources.is_empty() {
+                println!("reset_source {} is an input of none of the profiles", reset_source);
+            }
+        }
+
+        //A racing attach can only have been refused by the runtime, so these are still unset
+        let _ = self.reset_sources.set(reset_sources);
+        let _ = self.god_states.set(god_states);
+        let _ = self.god_outputs.set(god_outputs);
+        Ok(())
     }
 
     pub fn is_device_active(
@@ -158,19 +254,616 @@ impl SessionWrapper {
         interaction_profile: InteractionProfilePath,
         top_level_user_path: TopLevelUserPath,
     ) -> bool {
-        interaction_profile
-            == *self
-                .active_profiles
-                .get(&top_level_user_path)
-                .unwrap()
-                .read()
-                .unwrap()
+        interaction_profile == self.active_profile(top_level_user_path)
+    }
+
+    ///The name xrGetInputSourceLocalizedName gives a bound source, `None` for sources no god action reads
+    ///
+    ///Bound sources are what the god actions read, so this names what the user actually bound rather than what the application suggested.
+    ///The profile in use on the source's user path is preferred when several have the source
+    pub fn localized_source_name(
+        &self,
+        instance: &InstanceWrapper,
+        source: xr::Path,
+        components: xr::InputSourceLocalizedNameFlags,
+    ) -> Result<Option<String>> {
+        let source_str = instance.path_to_string(source)?;
+        let user_path = match interaction_profiles::TOP_LEVEL_USER_PATHS
+            .iter()
+            .find(|user_path| source_str.starts_with(&format!("{}/", user_path)))
+        {
+            Some(user_path) => *user_path,
+            None => return Ok(None),
+        };
+        //Sources on user paths the layer doesn't manage only ever come from actions left to the runtime
+        if !instance.settings.manages(user_path) {
+            return Ok(None);
+        }
+        let has_source = |profile: &xr::Path| {
+            matches!(self.god_states().get(profile), Some(states) if states.contains_key(&source))
+                || matches!(self.god_outputs().get(profile), Some(outputs) if outputs.contains_key(&source))
+        };
+        let active = self.active_profile(TopLevelUserPath(instance.string_to_path(user_path)?)).0;
+        let god_set = match instance.god_action_sets().get(&active).filter(|_| has_source(&active)) {
+            Some(god_set) => god_set,
+            None => match instance
+                .god_action_sets_in_order()
+                .into_iter()
+                .find(|god_set| matches!(instance.string_to_path(&god_set.name), Ok(profile) if has_source(&profile)))
+            {
+                Some(god_set) => god_set,
+                None => return Ok(None),
+            },
+        };
+        let component = match god_set.profile_info.localized_name_for_binding(&source_str) {
+            Some(component) => component,
+            None => return Ok(None),
+        };
+
+        let mut parts = Vec::new();
+        if components.contains(xr::InputSourceLocalizedNameFlags::USER_PATH) {
+            parts.extend(interaction_profiles::localized_user_path(user_path));
+        }
+        if components.contains(xr::InputSourceLocalizedNameFlags::INTERACTION_PROFILE) {
+            parts.push(god_set.profile_info.title.as_str());
+        }
+        if components.contains(xr::InputSourceLocalizedNameFlags::COMPONENT) {
+            parts.push(component);
+        }
+        Ok(Some(parts.join(" ")))
+    }
+
+    pub fn active_profile(&self, top_level_user_path: TopLevelUserPath) -> InteractionProfilePath {
+        match self.active_profiles.get(&top_level_user_path) {
+            Some(active_profile) => *active_profile.read().unwrap(),
+            None => InteractionProfilePath(xr::Path::NULL),
+        }
+    }
+
+    ///What the runtime reports to xrGetCurrentInteractionProfile for `top_level_user_path`, NULL if it reports nothing or fails
+    fn reported_profile(&self, instance: &InstanceWrapper, top_level_user_path: TopLevelUserPath) -> InteractionProfilePath {
+        let mut profile_state = xr::InteractionProfileState {
+            ty: xr::InteractionProfileState::TYPE,
+            next: ptr::null_mut(),
+            interaction_profile: xr::Path::NULL,
+        };
+        let result = unsafe { (instance.core.get_current_interaction_profile)(self.handle, top_level_user_path.0, &mut profile_state) };
+        if result.into_raw() < 0 {
+            InteractionProfilePath(xr::Path::NULL)
+        } else {
+            InteractionProfilePath(profile_state.interaction_profile)
+        }
+    }
+
+    ///The profile in use on every top level user path, [xr::Path::NULL] where there is none
+    pub fn active_profile_snapshot(&self) -> HashMap<xr::Path, xr::Path> {
+        self.active_profiles
+            .iter()
+            .map(|(user_path, active_profile)| (user_path.0, active_profile.read().unwrap().0))
+            .collect()
+    }
+
+    ///Updates the active profile of every top level user path from what the runtime reports, see [SessionWrapper::update_active_profile]
+    pub fn refresh_active_profiles(&self, instance: &InstanceWrapper) {
+        for user_path in self.active_profiles.keys() {
+            self.update_active_profile(*user_path, self.reported_profile(instance, *user_path));
+        }
+    }
+
+    ///Handles an XrEventDataInteractionProfileChanged for this session before the application sees it
+    ///
+    ///A user path the runtime no longer reports a profile on had its controller turned off, so its god states are marked inactive rather than left
+    ///holding the controller's last values. The application's action states only follow at its next xrSyncActions, as the spec requires
+    pub fn interaction_profile_changed(&self, instance: &InstanceWrapper) {
+        for user_path in self.active_profiles.keys() {
+            let reported = self.reported_profile(instance, *user_path);
+            if reported.0 == xr::Path::NULL {
+                for state in self.god_states().values().flat_map(|states| states.values()) {
+                    if state.subaction_path == user_path.0 {
+                        *state.action_state.write().unwrap() = GodActionStateEnum::new(state.action.action_type).unwrap();
+                    }
+                }
+            }
+            self.update_active_profile(*user_path, reported);
+        }
+    }
+
+    ///Decides which profile is in use for a top level user path, each path is tracked on its own so the hands can be on different controllers
+    ///
+    ///The profile the runtime reports wins while its god states on that path are active. Otherwise it is inferred from whichever profile has active god states there, so a controller turning off (or a runtime that doesn't report one) doesn't leave a stale profile behind
+    pub fn update_active_profile(&self, top_level_user_path: TopLevelUserPath, reported: InteractionProfilePath) {
+        let active_profile = match self.active_profiles.get(&top_level_user_path) {
+            Some(active_profile) => active_profile,
+            None => return,
+        };
+
+        let has_active_states = |profile: &xr::Path| {
+            self.god_states()
+                .get(profile)
+                .into_iter()
+                .flat_map(|states| states.values())
+                .any(|state| state.subaction_path == top_level_user_path.0 && state.is_active(self))
+        };
+
+        let profile = if has_active_states(&reported.0) {
+            reported.0
+        } else {
+            let mut profiles = self.god_states().keys().copied().collect::<Vec<_>>();
+            profiles.sort_by_key(|profile| profile.into_raw());
+            profiles
+                .into_iter()
+                .find(|profile| has_active_states(profile))
+                .unwrap_or(xr::Path::NULL)
+        };
+
+        let previous = std::mem::replace(&mut *active_profile.write().unwrap(), InteractionProfilePath(profile));
+        if let Some(instance) = self.instance.upgrade().filter(|instance| instance.settings.warn_profile_mismatch) {
+            if previous.0 != profile {
+                if let Some(warning) = self.profile_mismatch_warning(&instance, top_level_user_path, reported) {
+                    println!("{}", warning);
+                }
+            }
+        }
+    }
+
+    ///Explains how the application could be wrong about the controller in use on `top_level_user_path`, see [Settings::warn_profile_mismatch](common::config::Settings::warn_profile_mismatch)
+    ///
+    ///Either the runtime reports a different profile to the application's xrGetCurrentInteractionProfile than the one the layer routes through,
+    ///or the active profile is one the application never suggested bindings for, so it is only driven through custom bindings and the application may act as if another controller were in use
+    pub fn profile_mismatch_warning(
+        &self,
+        instance: &InstanceWrapper,
+        top_level_user_path: TopLevelUserPath,
+        reported: InteractionProfilePath,
+    ) -> Option<String> {
+        let active = self.active_profile(top_level_user_path).0;
+        if active == xr::Path::NULL {
+            return None;
+        }
+        let name = |path: xr::Path| instance.path_to_string(path).unwrap_or_default();
+        let user_path = name(top_level_user_path.0);
+
+        if reported.0 != xr::Path::NULL && reported.0 != active {
+            return Some(format!(
+                "warning: {} is active on {} but the runtime reports {} to the application",
+                name(active),
+                user_path,
+                name(reported.0)
+            ));
+        }
+
+        let suggested = instance.suggested_profiles();
+        if suggested.is_empty() || suggested.contains(&active) {
+            return None;
+        }
+        Some(format!(
+            "warning: {} is active on {} but the application only suggested bindings for {}, it may behave as if one of those were in use",
+            name(active),
+            user_path,
+            suggested.into_iter().map(name).collect::<Vec<_>>().join(", ")
+        ))
+    }
+
+    ///The cached state of an action, as long as its set is attached to this session rather than to another session or to none
+    pub fn attached_action_state(&self, action: xr::Action) -> Result<&RwLock<CachedActionStatesEnum>> {
+        self.cached_action_states
+            .get()
+            .and_then(|cached_action_states| cached_action_states.get(&action))
+            .ok_or(xr::Result::ERROR_ACTIONSET_NOT_ATTACHED)
+    }
+
+    ///The bindings of an input action in `action_set`, ERROR_ACTIONSET_NOT_ATTACHED unless the set is attached to this session
+    pub fn attached_input_bindings(
+        &self,
+        action_set: xr::ActionSet,
+        action: xr::Action,
+    ) -> Result<&RwLock<SubactionBindings<RemappedBinding>>> {
+        self.input_bindings
+            .get()
+            .and_then(|input_bindings| input_bindings.get(&action_set))
+            .and_then(|actions| actions.get(&action))
+            .ok_or(xr::Result::ERROR_ACTIONSET_NOT_ATTACHED)
+    }
+
+    ///The bindings of an output action, see [SessionWrapper::attached_input_bindings]
+    pub fn attached_output_bindings(&self, action: xr::Action) -> Result<&RwLock<SubactionBindings<RemappedOutput>>> {
+        self.output_bindings
+            .get()
+            .and_then(|output_bindings| output_bindings.get(&action))
+            .ok_or(xr::Result::ERROR_ACTIONSET_NOT_ATTACHED)
+    }
+
+    pub fn predicted_display_time(&self) -> xr::Time {
+        xr::Time::from_nanos(self.predicted_display_time.load(Ordering::Relaxed))
     }
 
     #[inline]
     pub fn instance(&self) -> Arc<InstanceWrapper> {
         self.instance.upgrade().unwrap()
     }
+
+    ///Snapshots every attached input action along with its last synced value and the sources feeding it, sorted by action set and name
+    pub fn attached_action_summary(&self) -> Vec<ActionSummary> {
+        let (input_bindings, cached_action_states) =
+            match (self.input_bindings.get(), self.cached_action_states.get()) {
+                (Some(input_bindings), Some(cached_action_states)) => {
+                    (input_bindings, cached_action_states)
+                }
+                _ => return Vec::new(),
+            };
+
+        let instance = self.instance();
+        let mut summary = Vec::new();
+        for actions in input_bindings.values() {
+            for (action_handle, subaction_bindings) in actions {
+                let action = match action_handle.get_wrapper() {
+                    Some(action) => action.clone(),
+                    None => continue,
+                };
+
+                //Looked up before the bindings are locked as it takes their lock itself
+                let god_actions = self
+                    .god_actions_for(*action_handle, xr::Path::NULL)
+                    .into_iter()
+                    .filter_map(|god_action| instance.god_action(god_action))
+                    .collect();
+
+                //Same lock order as xrSyncActions
+                let subaction_bindings = subaction_bindings.read().unwrap();
+                let value = cached_action_states
+                    .get(action_handle)
+                    .unwrap()
+                    .read()
+                    .unwrap()
+                    .main_state();
+
+                let mut sources = Vec::new();
+                for binding in subaction_bindings.get_matching(xr::Path::NULL).unwrap() {
+                    sources.push(bound_source(&binding.source));
+                    if let Some(y_source) = &binding.y_source {
+                        sources.push(bound_source(y_source));
+                    }
+                }
+
+                summary.push(ActionSummary {
+                    action_set_name: action.action_set().name.clone(),
+                    name: action.name.clone(),
+                    action_type: action.action_type,
+                    value,
+                    sources,
+                    remapped: self.is_remapped(*action_handle, xr::Path::NULL),
+                    god_actions,
+                });
+            }
+        }
+
+        summary.sort_by(|a, b| (&a.action_set_name, &a.name).cmp(&(&b.action_set_name, &b.name)));
+        summary
+    }
+
+    ///Returns every latched transform to rest, for when a user gets stuck in a state the physical input can't release
+    ///
+    ///Actions whose value drops as a result report the change on the next xrSyncActions
+    pub fn reset_transform_states(&self) {
+        let input_bindings = match self.input_bindings.get() {
+            Some(input_bindings) => input_bindings,
+            None => return,
+        };
+
+        for subaction_bindings in input_bindings.values().flat_map(HashMap::values) {
+            //A sync samples under a read lock, holding the write lock keeps it from seeing half of an action reset
+            for binding in subaction_bindings.write().unwrap().get_matching(xr::Path::NULL).unwrap() {
+                binding.reset();
+            }
+        }
+    }
+
+    ///Resets the latched transforms when a [reset source](SessionWrapper::reset_sources) is pressed, called by xrSyncActions once the god states are fresh
+    pub fn poll_reset_sources(&self) {
+        let held = self.reset_sources.get().into_iter().flatten().any(|source| remap::is_held(source));
+        let was_held = self.reset_held.swap(held, Ordering::Relaxed);
+        if held && !was_held {
+            println!("{}: reset source pressed, releasing every latched transform", self);
+            self.reset_transform_states();
+        }
+    }
+
+    ///Whether the user's custom bindings alter an action on this subaction path (or on any with [xr::Path::NULL]), rather than passing the application's suggestions through
+    pub fn is_remapped(&self, action: xr::Action, subaction_path: xr::Path) -> bool {
+        let action = match action.get_wrapper() {
+            Some(action) => action.clone(),
+            None => return false,
+        };
+        let instance = self.instance();
+
+        let subaction_path = if subaction_path == xr::Path::NULL {
+            None
+        } else {
+            match instance.path_to_nonempty_string(subaction_path) {
+                Ok(subaction_path) => Some(subaction_path),
+                Err(_) => return false,
+            }
+        };
+
+        remap::is_remapped(&instance, &action, subaction_path.as_deref())
+    }
+
+    ///The god actions feeding an attached application action on a subaction path (or on every one with [xr::Path::NULL])
+    ///
+    ///Every source a binding may read contributes, see [RemappedBinding::sources]. Emulated sources have no runtime action and are left out.
+    ///A god action covers every hand of its profile, so the left and right A buttons are the same action
+    pub fn god_actions_for(&self, app_action: xr::Action, subaction_path: xr::Path) -> Vec<xr::Action> {
+        let mut god_actions = Vec::new();
+        let mut push = |god_action: xr::Action| {
+            if god_action != xr::Action::NULL && !god_actions.contains(&god_action) {
+                god_actions.push(god_action);
+            }
+        };
+
+        let input_bindings = self
+            .input_bindings
+            .get()
+            .and_then(|input_bindings| input_bindings.values().find_map(|actions| actions.get(&app_action)));
+        if let Some(subaction_bindings) = input_bindings {
+            for binding in subaction_bindings.read().unwrap().get_matching(subaction_path).unwrap_or_default() {
+                binding.sources().for_each(|source| push(source.action.handle));
+            }
+        }
+
+        let output_bindings = self.output_bindings.get().and_then(|output_bindings| output_bindings.get(&app_action));
+        if let Some(subaction_bindings) = output_bindings {
+            for binding in subaction_bindings.read().unwrap().get_matching(subaction_path).unwrap_or_default() {
+                push(binding.action.handle);
+            }
+        }
+
+        god_actions
+    }
+
+    ///Every attached application action a god action feeds, the reverse of [SessionWrapper::god_actions_for]
+    pub fn app_actions_for(&self, god_action: xr::Action) -> Vec<xr::Action> {
+        let input_actions = self
+            .input_bindings
+            .get()
+            .into_iter()
+            .flat_map(|input_bindings| input_bindings.values().flat_map(|actions| actions.keys()));
+        let output_actions = self.output_bindings.get().into_iter().flat_map(|output_bindings| output_bindings.keys());
+        input_actions
+            .chain(output_actions)
+            .copied()
+            .filter(|app_action| self.god_actions_for(*app_action, xr::Path::NULL).contains(&god_action))
+            .collect()
+    }
+}
+
+///What [SessionWrapper::effective_config_json] shows in place of bindings for a profile missing from the catalog
+pub const UNKNOWN_PROFILE_NOTE: &str = "passthrough (unknown profile)";
+
+impl SessionWrapper {
+    ///The bindings this session actually ended up with for every attached action, in the same layout as custom_bindings.json
+    ///
+    ///Unlike the config files this is the resolved result, custom bindings have replaced suggestions and profile defaults are filled into the transforms
+    ///
+    ///Profiles missing from the catalog are listed as "passthrough (unknown profile)" since their suggestions went straight to the runtime
+    pub fn effective_config_json(&self) -> serde_json::Value {
+        let mut effective = serde_json::to_value(self.effective_bindings()).unwrap();
+        for profile_name in self.instance().unknown_profiles.read().unwrap().keys() {
+            effective[profile_name] = serde_json::Value::from(UNKNOWN_PROFILE_NOTE);
+        }
+        effective
+    }
+
+    ///The resolved bindings behind [SessionWrapper::effective_config_json], without the notes for unknown profiles
+    pub fn effective_bindings(&self) -> ApplicationBindings {
+        let instance = self.instance();
+        let mut effective = ApplicationBindings::default();
+
+        let mut attached_actions = Vec::new();
+
+        if let Some(input_bindings) = self.input_bindings.get() {
+            for (action_handle, subaction_bindings) in input_bindings.values().flatten() {
+                let action = match action_handle.get_wrapper() {
+                    Some(action) => action.clone(),
+                    None => continue,
+                };
+                for binding in subaction_bindings.read().unwrap().get_matching(xr::Path::NULL).unwrap() {
+                    action_bindings_mut(&mut effective, &binding.action.profile_name_str, &action)
+                        .bindings
+                        .push(application_bindings::Binding {
+                        path: binding.binding_str.clone(),
+                        y: binding.y_source.as_ref().map(|y_source| y_source.binding_str.clone()),
+                        transform: binding.transform.clone(),
+                        fallback: binding.fallback.as_ref().map(|fallback| fallback.path.clone()),
+                        when: binding.condition.as_ref().map(|condition| SourceCondition {
+                            path: condition.source.binding_str.clone(),
+                            released: condition.released,
+                        }),
+                        note: None,
+                    });
+                }
+                attached_actions.push(action);
+            }
+        }
+
+        if let Some(output_bindings) = self.output_bindings.get() {
+            for (action_handle, subaction_bindings) in output_bindings {
+                let action = match action_handle.get_wrapper() {
+                    Some(action) => action.clone(),
+                    None => continue,
+                };
+                for binding in subaction_bindings.read().unwrap().get_matching(xr::Path::NULL).unwrap() {
+                    let amplitude = Some(binding.amplitude).filter(|amplitude| *amplitude != 1f32);
+                    action_bindings_mut(&mut effective, &binding.action.profile_name_str, &action)
+                        .bindings
+                        .push(application_bindings::Binding {
+                            transform: Transform {
+                                amplitude,
+                                ..Default::default()
+                            },
+                            ..application_bindings::Binding::new(binding.binding_str.clone())
+                        });
+                }
+                attached_actions.push(action);
+            }
+        }
+
+        //Subaction bindings come out in hash order, sorting keeps snapshots of the same application comparable
+        for profile_bindings in effective.profiles.values_mut() {
+            for action_set_bindings in profile_bindings.action_sets.values_mut() {
+                for action_bindings in action_set_bindings.actions.values_mut() {
+                    action_bindings.bindings.sort_by(|a, b| a.path.cmp(&b.path));
+                }
+            }
+        }
+
+        //Profiles an action is unbound on have no bindings left to show for it, so they are listed the way they were configured
+        for action in attached_actions {
+            for (profile_name, profile_bindings) in &instance.custom_bindings.profiles {
+                let unbound = profile_bindings
+                    .action_sets
+                    .get(&action.action_set().name)
+                    .and_then(|set| set.actions.get(&action.name))
+                    .filter(|action_bindings| action_bindings.unbound)
+                    .is_some();
+                if unbound {
+                    *action_bindings_mut(&mut effective, profile_name, &action) = ActionBindings::unbound();
+                }
+            }
+        }
+
+        effective
+    }
+}
+
+impl SessionWrapper {
+    ///Everything needed to reproduce how this session was remapped: the application, its extensions, the catalog as resolved for those extensions and the effective bindings
+    ///
+    ///Along with them come the god actions the layer created and the attached actions as of the snapshot, which show what it made of all that
+    pub fn snapshot_json(&self) -> serde_json::Value {
+        let instance = self.instance();
+        let catalog = interaction_profiles::Root {
+            profiles: instance
+                .god_action_sets()
+                .values()
+                .chain(instance.emulated_set.iter())
+                .map(|god_set| (god_set.name.clone(), god_set.profile_info.clone()))
+                .collect(),
+        };
+        let runtime = match instance.instance_properties() {
+            Ok((name, version)) => serde_json::json!({ "name": name, "version": version.to_string() }),
+            Err(result) => serde_json::Value::from(format!("unknown ({})", result)),
+        };
+        //What each god action feeds, the emulated ones have no handle to look their bindings up by
+        let feeds = |profile_name: &str, name: &str| {
+            instance
+                .god_action_sets()
+                .values()
+                .filter(|god_set| god_set.name == profile_name)
+                .flat_map(|god_set| god_set.input_actions.values().chain(god_set.output_actions.values()))
+                .filter(|god_action| god_action.name == name)
+                .flat_map(|god_action| self.app_actions_for(god_action.handle))
+                .filter_map(|app_action| app_action.get_wrapper().map(|action| format!("{}/{}", action.action_set().name, action.name)))
+                .collect::<Vec<_>>()
+        };
+        let god_actions = instance
+            .describe_god_actions()
+            .into_iter()
+            .map(|(profile_name, god_actions)| {
+                let god_actions = god_actions
+                    .into_iter()
+                    .map(|(name, action_type, subaction_paths)| {
+                        serde_json::json!({
+                            "name": name,
+                            "type": action_type,
+                            "subaction_paths": subaction_paths,
+                            "feeds": feeds(&profile_name, &name),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                (profile_name, serde_json::Value::from(god_actions))
+            })
+            .collect::<serde_json::Map<_, _>>();
+        serde_json::json!({
+            "runtime": runtime,
+            "application": {
+                "name": instance.application_name,
+                "version": instance.application_version,
+                "engine_name": instance.engine_name,
+                "engine_version": instance.engine_version,
+            },
+            "enabled_extensions": instance.enabled_extensions,
+            "catalog": catalog,
+            "god_actions": god_actions,
+            "effective_config": self.effective_config_json(),
+            "attached_actions": self.attached_action_summary().iter().map(ActionSummary::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+fn action_bindings_mut<'a>(
+    bindings: &'a mut ApplicationBindings,
+    profile_name: &str,
+    action: &ActionWrapper,
+) -> &'a mut ActionBindings {
+    bindings
+        .profiles
+        .entry(profile_name.to_owned())
+        .or_default()
+        .action_sets
+        .entry(action.action_set().name.clone())
+        .or_default()
+        .actions
+        .entry(action.name.clone())
+        .or_default()
+}
+
+///The live state of an attached application action, see [SessionWrapper::attached_action_summary]
+pub struct ActionSummary {
+    pub action_set_name: String,
+    pub name: String,
+    pub action_type: ActionType,
+    pub value: GodActionStateEnum,
+    ///The god input paths feeding this action, each with what the catalog calls it
+    pub sources: Vec<BindingInfo>,
+    ///See [SessionWrapper::is_remapped]
+    pub remapped: bool,
+    ///See [SessionWrapper::god_actions_for]
+    pub god_actions: Vec<Arc<god_actions::GodAction>>,
+}
+
+impl ActionSummary {
+    ///How [SessionWrapper::snapshot_json] lists the action, poses have no value besides whether they are active
+    pub fn to_json(&self) -> serde_json::Value {
+        let value = match self.value {
+            GodActionStateEnum::Boolean(state) => serde_json::json!(state.current_state),
+            GodActionStateEnum::Float(state) => serde_json::json!(state.current_state),
+            GodActionStateEnum::Vector2f(state) => serde_json::json!([state.current_state.x, state.current_state.y]),
+            GodActionStateEnum::Pose(_) => serde_json::Value::Null,
+        };
+        serde_json::json!({
+            "action_set": self.action_set_name,
+            "name": self.name,
+            "type": self.action_type,
+            "active": self.value.get_inner().is_active(),
+            "value": value,
+            "sources": self.sources,
+            "remapped": self.remapped,
+            "god_actions": self
+                .god_actions
+                .iter()
+                .map(|god_action| serde_json::json!({ "profile": god_action.profile_name_str, "name": god_action.name }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+///A god input's path paired with the catalog's name for it, the same pairing actions.json uses for suggested bindings
+fn bound_source(source: &InputBinding) -> BindingInfo {
+    BindingInfo {
+        path: source.binding_str.clone(),
+        localized_name: interaction_profiles::profile(&source.action.profile_name_str)
+            .and_then(|profile_info| profile_info.localized_name_for_binding(&source.binding_str).map(str::to_owned)),
+    }
 }
 
 impl SessionWrapper {
@@ -182,6 +875,15 @@ impl SessionWrapper {
         unsafe { (self.instance().core.attach_session_action_sets)(self.handle, attach_info) }
     }
 
+    #[inline]
+    pub fn wait_frame(
+        &self,
+        frame_wait_info: *const xr::FrameWaitInfo,
+        frame_state: *mut xr::FrameState,
+    ) -> xr::Result {
+        unsafe { (self.instance().core.wait_frame)(self.handle, frame_wait_info, frame_state) }
+    }
+
     #[inline]
     pub fn sync_actions(&self, sync_info: *const xr::ActionsSyncInfo) -> xr::Result {
         unsafe { (self.instance().core.sync_actions)(self.handle, sync_info) }
@@ -262,3 +964,376 @@ impl SessionWrapper {
         })
     }
 }
+
+#[test]
+fn test_attached_action_summary() {
+    use common::config::BindingConfigBuilder;
+
+    use crate::fake_runtime;
+
+    let profile = "/interaction_profiles/khr/simple_controller";
+    let config = BindingConfigBuilder::new()
+        .bind(profile, "gameplay/fire", "/user/hand/right/input/select/click")
+        .with_invert()
+        .build()
+        .unwrap();
+    let instance = fake_runtime::create_instance_with_bindings(config);
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[("use", xr::ActionType::FLOAT_INPUT), ("fire", xr::ActionType::BOOLEAN_INPUT)],
+    );
+    let suggested = [(actions[0], "/user/hand/left/input/select/click"), (actions[1], "/user/hand/right/input/select/click")];
+    assert_eq!(fake_runtime::suggest(&instance, profile, &suggested), xr::Result::SUCCESS);
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let select = instance.string_to_path("/user/hand/left/input/select/click").unwrap();
+    let god_action = session.get_wrapper().unwrap().god_states()[&instance.string_to_path(profile).unwrap()][&select].action.handle;
+    fake_runtime::set_input_state(
+        session,
+        god_action,
+        GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+            current_state: true,
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(10),
+            is_active: true,
+        }),
+    );
+    assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+
+    let summary = session.get_wrapper().unwrap().attached_action_summary();
+    assert_eq!(summary.iter().map(|action| action.name.as_str()).collect::<Vec<_>>(), vec!["fire", "use"]);
+    assert_eq!(
+        summary[1].sources,
+        vec![BindingInfo {
+            path: String::from("/user/hand/left/input/select/click"),
+            localized_name: Some(String::from("Select")),
+        }]
+    );
+    match summary[1].value {
+        GodActionStateEnum::Float(state) => assert_eq!(state.current_state, 1.0),
+        _ => panic!("summary value has the wrong type"),
+    }
+    //Only the custom binding changes what the application suggested
+    assert!(summary[0].remapped);
+    assert!(!summary[1].remapped);
+}
+
+#[test]
+fn test_active_profile_per_user_path() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance).get_wrapper().unwrap().clone();
+    let left = TopLevelUserPath(instance.string_to_path(openxr::USER_HAND_LEFT).unwrap());
+    let right = TopLevelUserPath(instance.string_to_path(openxr::USER_HAND_RIGHT).unwrap());
+    let simple = instance.string_to_path("/interaction_profiles/khr/simple_controller").unwrap();
+    let index = instance.string_to_path("/interaction_profiles/valve/index_controller").unwrap();
+
+    let set_active = |profile: xr::Path, source: &str, is_active| {
+        *session.god_states()[&profile][&instance.string_to_path(source).unwrap()].action_state.write().unwrap() =
+            GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+                current_state: false,
+                changed_since_last_sync: false,
+                last_change_time: xr::Time::from_nanos(0),
+                is_active,
+            });
+    };
+    set_active(simple, "/user/hand/left/input/select/click", true);
+    set_active(index, "/user/hand/right/input/a/click", true);
+
+    //The runtime doesn't report anything so the profiles are inferred per hand
+    session.update_active_profile(left, InteractionProfilePath(xr::Path::NULL));
+    session.update_active_profile(right, InteractionProfilePath(xr::Path::NULL));
+    assert_eq!(session.active_profile(left), InteractionProfilePath(simple));
+    assert_eq!(session.active_profile(right), InteractionProfilePath(index));
+
+    //A reported profile without any active state on that hand is stale
+    session.update_active_profile(left, InteractionProfilePath(index));
+    assert_eq!(session.active_profile(left), InteractionProfilePath(simple));
+
+    set_active(simple, "/user/hand/left/input/select/click", false);
+    session.update_active_profile(left, InteractionProfilePath(simple));
+    assert_eq!(session.active_profile(left), InteractionProfilePath(xr::Path::NULL));
+    assert_eq!(session.active_profile(right), InteractionProfilePath(index));
+}
+
+#[test]
+fn test_profile_mismatch_warning() {
+    use common::config::{BindingConfigBuilder, Settings};
+
+    use crate::fake_runtime;
+
+    //The application only knows the simple controller, custom bindings let an Index drive it
+    let config = BindingConfigBuilder::new()
+        .bind("/interaction_profiles/valve/index_controller", "gameplay/select", "/user/hand/right/input/a/click")
+        .build()
+        .unwrap();
+    let settings = Settings {
+        warn_profile_mismatch: true,
+        ..Default::default()
+    };
+    let instance = fake_runtime::create_configured_instance(config, settings);
+    let session = fake_runtime::new_session(&instance);
+    let (_, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("select", xr::ActionType::BOOLEAN_INPUT)]);
+    let action = actions[0];
+    assert_eq!(
+        fake_runtime::suggest(&instance, "/interaction_profiles/khr/simple_controller", &[(action, "/user/hand/right/input/select/click")]),
+        xr::Result::SUCCESS
+    );
+    let simple = instance.string_to_path("/interaction_profiles/khr/simple_controller").unwrap();
+    let index = instance.string_to_path("/interaction_profiles/valve/index_controller").unwrap();
+
+    let session = session.get_wrapper().unwrap().clone();
+    let right = TopLevelUserPath(instance.string_to_path(openxr::USER_HAND_RIGHT).unwrap());
+    let a_click = instance.string_to_path("/user/hand/right/input/a/click").unwrap();
+    *session.god_states()[&index][&a_click].action_state.write().unwrap() = GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+        current_state: false,
+        changed_since_last_sync: false,
+        last_change_time: xr::Time::from_nanos(0),
+        is_active: true,
+    });
+
+    session.update_active_profile(right, InteractionProfilePath(index));
+    assert_eq!(session.active_profile(right), InteractionProfilePath(index));
+    let warning = session.profile_mismatch_warning(&instance, right, InteractionProfilePath(index)).unwrap();
+    assert!(warning.contains("/interaction_profiles/valve/index_controller is active on /user/hand/right"), "{}", warning);
+    assert!(warning.contains("/interaction_profiles/khr/simple_controller"), "{}", warning);
+
+    //The runtime telling the application about a controller other than the one the layer routes through
+    session.update_active_profile(right, InteractionProfilePath(simple));
+    assert_eq!(session.active_profile(right), InteractionProfilePath(index));
+    let warning = session.profile_mismatch_warning(&instance, right, InteractionProfilePath(simple)).unwrap();
+    assert!(warning.contains("the runtime reports /interaction_profiles/khr/simple_controller"), "{}", warning);
+
+    //Nothing to warn about once the application suggests bindings for the controller in use
+    assert_eq!(
+        fake_runtime::suggest(&instance, "/interaction_profiles/valve/index_controller", &[(action, "/user/hand/right/input/a/click")]),
+        xr::Result::SUCCESS
+    );
+    assert_eq!(session.profile_mismatch_warning(&instance, right, InteractionProfilePath(index)), None);
+}
+
+#[test]
+fn test_sided_god_states() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance();
+    let session = fake_runtime::new_session(&instance).get_wrapper().unwrap().clone();
+
+    let touch = instance.string_to_path("/interaction_profiles/oculus/touch_controller").unwrap();
+    let has_state = |binding: &str| session.god_states()[&touch].contains_key(&instance.string_to_path(binding).unwrap());
+
+    assert!(has_state("/user/hand/left/input/x/click"));
+    assert!(!has_state("/user/hand/right/input/x/click"));
+    assert!(has_state("/user/hand/right/input/a/click"));
+    assert!(!has_state("/user/hand/left/input/a/click"));
+    assert!(has_state("/user/hand/left/input/trigger/value"));
+    assert!(has_state("/user/hand/right/input/trigger/value"));
+}
+
+#[test]
+fn test_reset_transform_states() {
+    use common::config::{BindingConfigBuilder, Settings};
+
+    use crate::fake_runtime;
+
+    let profile = "/interaction_profiles/valve/index_controller";
+    let trigger_path = "/user/hand/right/input/trigger/value";
+    let config = BindingConfigBuilder::new()
+        .bind(profile, "gameplay/fire", trigger_path)
+        .with_threshold(0.8, 0.7)
+        .build()
+        .unwrap();
+    let settings = Settings {
+        reset_source: Some(String::from("/user/hand/left/input/b/click")),
+        ..Default::default()
+    };
+    let instance = fake_runtime::create_configured_instance(config, settings);
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("fire", xr::ActionType::BOOLEAN_INPUT)]);
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let wrapper = session.get_wrapper().unwrap().clone();
+    let god_states = &wrapper.god_states()[&instance.string_to_path(profile).unwrap()];
+    let god_action = |path: &str| god_states[&instance.string_to_path(path).unwrap()].action.handle;
+    let (trigger, b) = (god_action(trigger_path), god_action("/user/hand/left/input/b/click"));
+    let sync = |trigger_value: f32, b_pressed: bool| {
+        fake_runtime::set_input_state(
+            session,
+            trigger,
+            GodActionStateEnum::Float(openxr::ActionState::<f32> {
+                current_state: trigger_value,
+                changed_since_last_sync: true,
+                last_change_time: xr::Time::from_nanos(1),
+                is_active: true,
+            }),
+        );
+        fake_runtime::set_input_state(
+            session,
+            b,
+            GodActionStateEnum::Boolean(openxr::ActionState::<bool> {
+                current_state: b_pressed,
+                changed_since_last_sync: true,
+                last_change_time: xr::Time::from_nanos(1),
+                is_active: true,
+            }),
+        );
+        assert_eq!(fake_runtime::sync(session, &[action_set]), xr::Result::SUCCESS);
+        let state = fake_runtime::boolean_state(session, actions[0], xr::Path::NULL);
+        (state.current_state, state.changed_since_last_sync)
+    };
+
+    assert_eq!(sync(0.9, false), (true, true));
+    //Between the thresholds the latch holds
+    assert_eq!(sync(0.75, false), (true, false));
+
+    assert_eq!(sync(0.75, true), (false, true));
+    //Holding the reset source only resets once
+    assert_eq!(sync(0.9, true), (true, true));
+    assert_eq!(sync(0.75, false), (true, false));
+    assert_eq!(sync(0.75, true), (false, true));
+}
+
+#[test]
+fn test_snapshot() {
+    use crate::fake_runtime;
+
+    let instance = fake_runtime::create_instance_with_extensions(
+        Default::default(),
+        vec![String::from(interaction_profiles::PALM_POSE_EXTENSION)],
+    );
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(&instance, "gameplay", &[("use", xr::ActionType::FLOAT_INPUT)]);
+    let index = "/interaction_profiles/valve/index_controller";
+    assert_eq!(fake_runtime::suggest(&instance, index, &[(actions[0], "/user/hand/left/input/trigger/value")]), xr::Result::SUCCESS);
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let snapshot = session.get_wrapper().unwrap().snapshot_json();
+    assert_eq!(snapshot["application"]["name"], "fake_application");
+    assert_eq!(snapshot["runtime"]["name"], fake_runtime::RUNTIME_NAME);
+    assert_eq!(snapshot["enabled_extensions"], serde_json::json!([interaction_profiles::PALM_POSE_EXTENSION]));
+    //The catalog is the one resolved for the enabled extensions
+    assert!(snapshot["catalog"]["profiles"][index]["subpaths"].get("/input/grip_surface").is_some());
+    assert!(snapshot["effective_config"].is_object());
+    let trigger = snapshot["god_actions"][index]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|god_action| god_action["name"] == "/input/trigger/value")
+        .unwrap();
+    assert_eq!(trigger["type"], "FloatInput");
+    assert_eq!(trigger["subaction_paths"], serde_json::json!(["/user/hand/left", "/user/hand/right"]));
+    assert_eq!(trigger["feeds"], serde_json::json!(["gameplay/use"]));
+    //Nothing is synced yet so the action is listed at rest
+    assert_eq!(
+        snapshot["attached_actions"],
+        serde_json::json!([{
+            "action_set": "gameplay",
+            "name": "use",
+            "type": "FloatInput",
+            "active": false,
+            "value": 0.0,
+            "sources": [{ "path": "/user/hand/left/input/trigger/value", "localized_name": "Trigger" }],
+            "remapped": false,
+            "god_actions": [{ "profile": index, "name": "/input/trigger/value" }],
+        }])
+    );
+}
+
+#[test]
+fn test_god_actions_for() {
+    use common::config::BindingConfigBuilder;
+
+    use crate::fake_runtime;
+
+    let index = "/interaction_profiles/valve/index_controller";
+    let simple = "/interaction_profiles/khr/simple_controller";
+    let config = BindingConfigBuilder::new()
+        .bind(index, "gameplay/jump", "/user/hand/right/input/a/click")
+        .bind_vector(index, "gameplay/steer", "/user/hand/right/input/trigger/value", "/user/hand/right/input/squeeze/value")
+        .bind(index, "gameplay/crouch", "/user/hand/left/input/thumbstick/click")
+        .with_condition("/user/hand/left/input/trigger/click", false)
+        .bind(index, "gameplay/pause", "/user/hand/left/input/b/click")
+        .with_fallback("/user/hand/left/input/menu/click")
+        .build()
+        .unwrap();
+    let instance = fake_runtime::create_instance_with_bindings(config);
+    let session = fake_runtime::new_session(&instance);
+    let (action_set, actions) = fake_runtime::new_actions(
+        &instance,
+        "gameplay",
+        &[
+            ("jump", xr::ActionType::BOOLEAN_INPUT),
+            ("steer", xr::ActionType::VECTOR2F_INPUT),
+            ("crouch", xr::ActionType::BOOLEAN_INPUT),
+            ("pause", xr::ActionType::BOOLEAN_INPUT),
+        ],
+    );
+    let (jump, steer, crouch, pause) = (actions[0], actions[1], actions[2], actions[3]);
+    assert_eq!(fake_runtime::attach(session, &[action_set]), xr::Result::SUCCESS);
+
+    let session = session.get_wrapper().unwrap().clone();
+    let god_action = |profile: &str, source: &str| {
+        let profile = instance.string_to_path(profile).unwrap();
+        let source = instance.string_to_path(source).unwrap();
+        session.god_states()[&profile][&source].action.handle
+    };
+
+    //One to one
+    let a_click = god_action(index, "/user/hand/right/input/a/click");
+    assert_eq!(session.god_actions_for(jump, xr::Path::NULL), vec![a_click]);
+    assert_eq!(session.app_actions_for(a_click), vec![jump]);
+
+    //A chord of two god actions feeding one application action
+    let trigger = god_action(index, "/user/hand/right/input/trigger/value");
+    let squeeze = god_action(index, "/user/hand/right/input/squeeze/value");
+    assert_eq!(session.god_actions_for(steer, xr::Path::NULL), vec![trigger, squeeze]);
+    assert_eq!(session.app_actions_for(trigger), vec![steer]);
+    assert_eq!(session.app_actions_for(squeeze), vec![steer]);
+
+    //The source a binding waits on feeds it too
+    let thumbstick_click = god_action(index, "/user/hand/left/input/thumbstick/click");
+    let trigger_click = god_action(index, "/user/hand/left/input/trigger/click");
+    assert_eq!(session.god_actions_for(crouch, xr::Path::NULL), vec![thumbstick_click, trigger_click]);
+
+    //As do the sources other profiles stand in with
+    let pause_god_actions = session.god_actions_for(pause, xr::Path::NULL);
+    assert_eq!(pause_god_actions[0], god_action(index, "/user/hand/left/input/b/click"));
+    assert!(pause_god_actions.contains(&god_action(simple, "/user/hand/left/input/menu/click")));
+    assert_eq!(session.app_actions_for(god_action(simple, "/user/hand/left/input/menu/click")), vec![pause]);
+
+    assert!(session.app_actions_for(god_action(index, "/user/hand/right/input/thumbstick/touch")).is_empty());
+    assert!(session.god_actions_for(xr::Action::from_raw(999), xr::Path::NULL).is_empty());
+}
+
+#[test]
+fn test_god_set_order() {
+    use crate::fake_runtime;
+
+    let orders = || {
+        let instance = fake_runtime::create_instance();
+        let session = fake_runtime::new_session(&instance);
+        let name_of = |action_set: &xr::ActionSet| {
+            instance.god_action_sets().values().find(|god_set| god_set.handle == *action_set).unwrap().name.clone()
+        };
+        let attached = fake_runtime::attached_sets(session).iter().map(name_of).collect::<Vec<_>>();
+        let suggested = fake_runtime::suggestion_order(instance.handle)
+            .into_iter()
+            .map(|profile| instance.path_to_string(profile).unwrap())
+            .collect::<Vec<_>>();
+        (attached, suggested)
+    };
+
+    let (attached, suggested) = orders();
+    let mut sorted = attached.clone();
+    sorted.sort();
+    assert!(attached.len() > 1);
+    assert_eq!(attached, sorted);
+    assert_eq!(suggested, sorted);
+    //Every instance hashes its paths differently, the order mustn't follow
+    for _ in 0..4 {
+        assert_eq!(orders(), (attached.clone(), suggested.clone()));
+    }
+}
@@ -13,6 +13,7 @@ pub struct SpaceWrapper {
     pub ty: SpaceType,
 }
 
+#[allow(clippy::upper_case_acronyms)]
 pub enum SpaceType {
     ACTION(Arc<ActionSpace>),
     REFERENCE,
@@ -33,6 +34,28 @@ pub struct ActionSpaceBinding {
     pub binding: Arc<InputBinding>,
 }
 
+impl std::fmt::Display for SpaceWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.ty {
+            SpaceType::REFERENCE => write!(f, "Space(reference {:#x})", self.unchecked_handle.into_raw()),
+            SpaceType::ACTION(action_space) => {
+                let action_set = action_space.action.action_set.upgrade();
+                let action = match &action_set {
+                    Some(action_set) => format!("{}/{}", action_set.name, action_space.action.name),
+                    None => action_space.action.name.clone(),
+                };
+                let subaction_path = action_set
+                    .and_then(|action_set| action_set.instance.upgrade())
+                    .and_then(|instance| instance.path_to_nonempty_string(action_space.subaction_path).ok());
+                match subaction_path {
+                    Some(subaction_path) => write!(f, "Space(action {:?} {})", action, subaction_path),
+                    None => write!(f, "Space(action {:?})", action),
+                }
+            }
+        }
+    }
+}
+
 impl SpaceWrapper {
     pub fn get_handle(&self) -> Option<xr::Space> {
         match &self.ty {
@@ -65,7 +88,7 @@ impl ActionSpace {
         &self,
         session: &SessionWrapper,
         sync_idx: u64,
-        subaction_bindings: &SubactionBindings<InputBinding>,
+        subaction_bindings: &SubactionBindings<RemappedBinding>,
     ) -> Result<()> {
         let instance = session.instance();
 
@@ -105,7 +128,7 @@ impl ActionSpace {
                     subaction_path: self.subaction_path,
                     pose_in_action_space: self.pose_in_action_space,
                 })?,
-                binding: (*binding).clone(),
+                binding: binding.source.clone(),
             })
         } else {
             *cur_binding = None